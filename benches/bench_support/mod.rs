@@ -0,0 +1,153 @@
+use std::{
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+const WORDS: [&str; 16] = [
+    "src", "lib", "docs", "assets", "build", "config", "tests", "vendor", "Projects", "notes",
+    "images", "backup", "2024", "release", "module", "utils",
+];
+const EXTENSIONS: [&str; 8] = ["rs", "md", "txt", "json", "png", "toml", "log", "JPG"];
+
+// a directory under the system temp dir, removed again when dropped
+pub struct TempTree {
+    pub root: PathBuf,
+}
+
+impl TempTree {
+    pub fn new(name: &str) -> TempTree {
+        let root = std::env::temp_dir().join(format!("ff-bench-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).expect("could not create the bench directory");
+        TempTree { root }
+    }
+
+    pub fn path(&self) -> String {
+        self.root.to_string_lossy().to_string()
+    }
+}
+
+impl Drop for TempTree {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+// xorshift, the generated trees are the same on every run
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng(seed.max(1))
+    }
+
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+fn entry_name(rng: &mut Rng, index: usize) -> String {
+    let word = WORDS[rng.below(WORDS.len())];
+    match rng.below(3) {
+        0 => format!("{}{}", word, index),
+        1 => format!(
+            "{}_{}.{}",
+            word,
+            index,
+            EXTENSIONS[rng.below(EXTENSIONS.len())]
+        ),
+        _ => format!(".{}{}", word, index),
+    }
+}
+
+// one directory of `entries` entries, every tenth a directory and the files
+// of a few different sizes, like a crowded downloads folder
+pub fn flat_tree(name: &str, entries: usize) -> TempTree {
+    let tree = TempTree::new(name);
+    let mut rng = Rng::new(entries as u64);
+    for index in 0..entries {
+        let path = tree.root.join(entry_name(&mut rng, index));
+        if index % 10 == 0 {
+            fs::create_dir(&path).unwrap();
+        } else {
+            fs::write(&path, vec![0u8; rng.below(4096)]).unwrap();
+        }
+    }
+    tree
+}
+
+// `dirs` directories of `files` files of `file_size` bytes, plus a few large
+// files at the top where reflinks and copy_file_range make a difference
+pub fn nested_tree(name: &str, dirs: usize, files: usize, file_size: usize) -> TempTree {
+    let tree = TempTree::new(name);
+    let mut rng = Rng::new((dirs * files) as u64);
+    let content: Vec<u8> = (0..file_size).map(|_| rng.next() as u8).collect();
+    for dir in 0..dirs {
+        let dir_path = tree
+            .root
+            .join(format!("{}{}", WORDS[dir % WORDS.len()], dir));
+        fs::create_dir_all(&dir_path).unwrap();
+        for file in 0..files {
+            fs::write(dir_path.join(format!("file{}.bin", file)), &content).unwrap();
+        }
+    }
+    let large: Vec<u8> = (0..8 * 1024 * 1024).map(|_| rng.next() as u8).collect();
+    for index in 0..4 {
+        fs::write(tree.root.join(format!("large{}.bin", index)), &large).unwrap();
+    }
+    tree
+}
+
+// `count` paths shaped like a home directory, (is_dir, path) as the walk of
+// the directory store reports them
+pub fn synthetic_paths(count: usize) -> Vec<(bool, String)> {
+    let mut rng = Rng::new(count as u64);
+    let mut paths = Vec::with_capacity(count);
+    let mut dirs = vec!["/home/user".to_string()];
+    while paths.len() < count {
+        let parent = dirs[rng.below(dirs.len())].clone();
+        let index = paths.len();
+        // deep trees have far more files than directories
+        if rng.below(8) == 0 && parent.matches('/').count() < 10 {
+            let dir = format!("{}/{}{}", parent, WORDS[rng.below(WORDS.len())], index);
+            dirs.push(dir.clone());
+            paths.push((true, dir));
+        } else {
+            paths.push((false, format!("{}/{}", parent, entry_name(&mut rng, index))));
+        }
+    }
+    paths
+}
+
+// what a copy costs without the reflink and copy_file_range fast paths, the
+// contents go through a buffer like the copy on other platforms. fs::copy
+// can't be the baseline, it uses copy_file_range itself on linux
+pub fn plain_copy(src: &Path, dest: &Path) -> io::Result<()> {
+    if src.is_file() {
+        let mut input = File::open(src)?;
+        let mut output = File::create(dest)?;
+        let mut buffer = vec![0u8; 1024 * 1024];
+        loop {
+            let read = input.read(&mut buffer)?;
+            if read == 0 {
+                return Ok(());
+            }
+            output.write_all(&buffer[..read])?;
+        }
+    }
+    fs::create_dir_all(dest)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        plain_copy(&entry.path(), &dest.join(entry.file_name()))?;
+    }
+    Ok(())
+}
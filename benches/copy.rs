@@ -0,0 +1,65 @@
+// the binary has no library target, the modules the copy goes through are
+// compiled in from src under the same paths
+#![allow(dead_code)]
+
+use std::{fs, path::PathBuf, sync::mpsc};
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+
+mod bench_support;
+#[path = "../src/events.rs"]
+mod events;
+#[path = "../src/jobs.rs"]
+mod jobs;
+#[path = "../src/utils"]
+mod utils {
+    pub mod format;
+}
+#[path = "../src/operations"]
+mod operations {
+    pub mod copy;
+}
+
+use jobs::JobControl;
+use operations::copy::copy_dir_file_with_progress;
+
+// the copy of the file manager against a plain buffered copy of the same
+// tree. where the filesystem supports reflinks (btrfs, xfs) the fast path
+// clones instead of copying, elsewhere it falls back to copy_file_range
+fn copy(c: &mut Criterion) {
+    let tree = bench_support::nested_tree("copy", 50, 40, 16 * 1024);
+    let target = bench_support::TempTree::new("copy-target");
+    let dest = target.root.join("copy");
+    // a fresh destination for every iteration, removing it isn't measured
+    let fresh = || -> PathBuf {
+        let _ = fs::remove_dir_all(&dest);
+        dest.clone()
+    };
+
+    let mut group = c.benchmark_group("copy tree");
+    group.sample_size(10);
+    group.bench_function("fast path", |b| {
+        b.iter_batched(
+            fresh,
+            |dest| {
+                // nobody listens for progress, sends to a dropped receiver
+                // are ignored like they are when the ui is gone
+                let (sender, _) = mpsc::channel();
+                copy_dir_file_with_progress(&tree.root, &dest, &JobControl::default(), &sender)
+                    .unwrap()
+            },
+            BatchSize::PerIteration,
+        )
+    });
+    group.bench_function("buffered", |b| {
+        b.iter_batched(
+            fresh,
+            |dest| bench_support::plain_copy(&tree.root, &dest).unwrap(),
+            BatchSize::PerIteration,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, copy);
+criterion_main!(benches);
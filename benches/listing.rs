@@ -0,0 +1,104 @@
+// the binary has no library target, the modules the listing goes through are
+// compiled in from src under the same paths
+#![allow(dead_code)]
+
+use std::{fs, io, path::PathBuf};
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+
+mod bench_support;
+#[path = "../src/entry_meta.rs"]
+mod entry_meta;
+#[path = "../src/events.rs"]
+mod events;
+#[path = "../src/ignore_rules.rs"]
+mod ignore_rules;
+#[path = "../src/utils/mod.rs"]
+mod utils;
+#[path = "../src/operations"]
+mod operations {
+    pub mod permissions;
+}
+
+use utils::files::{get_file_path_data, sort_entries_by_type, SortBy, SortOptions, SortType};
+
+const ENTRIES: usize = 50_000;
+
+fn sort_keys() -> [(&'static str, SortBy); 4] {
+    [
+        ("default", SortBy::Default),
+        ("name", SortBy::Name),
+        ("size", SortBy::Size),
+        ("date", SortBy::DateAdded),
+    ]
+}
+
+// reading, sorting and filtering a whole directory, what entering it costs
+fn listing(c: &mut Criterion) {
+    let tree = bench_support::flat_tree("listing", ENTRIES);
+    let options = SortOptions::default();
+
+    let mut group = c.benchmark_group("listing 50k");
+    group.sample_size(10);
+    for (label, sort_by) in sort_keys() {
+        group.bench_function(label, |b| {
+            b.iter(|| {
+                get_file_path_data(
+                    tree.path(),
+                    true,
+                    None,
+                    sort_by.clone(),
+                    &SortType::ASC,
+                    &options,
+                )
+                .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+// only the sort, on metadata that was already read
+fn sorting(c: &mut Criterion) {
+    let tree = bench_support::flat_tree("sorting", ENTRIES);
+    let paths = fs::read_dir(&tree.root)
+        .unwrap()
+        .map(|res| res.map(|e| e.path()))
+        .collect::<Result<Vec<PathBuf>, io::Error>>()
+        .unwrap();
+    let entries = entry_meta::read_all(paths);
+    let natural = SortOptions {
+        natural: true,
+        directories_first: true,
+        ..SortOptions::default()
+    };
+
+    let mut group = c.benchmark_group("sorting 50k");
+    for (label, sort_by) in sort_keys() {
+        group.bench_function(label, |b| {
+            b.iter_batched(
+                || entries.clone(),
+                |entries| {
+                    sort_entries_by_type(
+                        sort_by.clone(),
+                        SortType::ASC,
+                        &SortOptions::default(),
+                        entries,
+                    )
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.bench_function("name natural", |b| {
+        b.iter_batched(
+            || entries.clone(),
+            |entries| sort_entries_by_type(SortBy::Name, SortType::ASC, &natural, entries),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, listing, sorting);
+criterion_main!(benches);
@@ -0,0 +1,68 @@
+// the binary has no library target, the modules the search goes through are
+// compiled in from src under the same paths
+#![allow(dead_code)]
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+mod bench_support;
+#[path = "../src/directory_store.rs"]
+mod directory_store;
+#[path = "../src/frecency.rs"]
+mod frecency;
+#[path = "../src/ignore_rules.rs"]
+mod ignore_rules;
+#[path = "../src/search_query.rs"]
+mod search_query;
+
+use directory_store::DirectoryStore;
+use frecency::FrecencyScores;
+use search_query::SearchQuery;
+
+const PATHS: usize = 500_000;
+const LIMIT: usize = 100;
+
+fn store() -> DirectoryStore {
+    let mut store = DirectoryStore::new();
+    for (is_dir, path) in bench_support::synthetic_paths(PATHS) {
+        match is_dir {
+            true => store.insert(&path),
+            false => store.insert_file(&path),
+        }
+    }
+    store.build_index();
+    store
+}
+
+fn index(c: &mut Criterion) {
+    let mut store = store();
+    let mut group = c.benchmark_group("search 500k");
+    group.sample_size(10);
+    group.bench_function("build index", |b| b.iter(|| store.build_index()));
+    group.finish();
+}
+
+// queries the search box gets, from a short prefix that can't use the
+// trigram index to terms that narrow it down a lot
+fn search(c: &mut Criterion) {
+    let store = store();
+    let queries = [
+        ("short", "sr"),
+        ("word", "config"),
+        ("rare", "release12"),
+        ("several terms", "docs notes .md$"),
+        ("exclude", "src !vendor"),
+        ("exact", "\"Projects\""),
+        ("regex", "re:module\\d+/.*\\.rs$"),
+    ];
+
+    let frecency = FrecencyScores::default();
+    let mut group = c.benchmark_group("search 500k");
+    for (label, text) in queries {
+        let query = SearchQuery::parse(text).unwrap();
+        group.bench_function(label, |b| b.iter(|| store.search(&query, &frecency, LIMIT)));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, index, search);
+criterion_main!(benches);
@@ -1,12 +1,56 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    sync::Arc,
+    time::Instant,
+};
+
+use anyhow::anyhow;
+
+use crate::compare::Comparison;
+use crate::configuration::Configuration;
+use crate::content_search::ContentMatch;
+use crate::converters::{ConversionResult, Converter};
+use crate::custom_commands::CustomCommand;
 use crate::directory_store::DirectoryStore;
+use crate::editor::Editor;
+use crate::errors;
+use crate::filter::Filter;
+use crate::frecency::{FrecencyScores, FrecencyStore};
+use crate::ignore_rules::IgnorePatterns;
+use crate::nav::Dir;
+use crate::operations::bulk_rename::{self, RenamePattern, RenamePlan};
+use crate::operations::file_ops::LinkKind;
+use crate::operations::permissions::Properties;
+use crate::operations::sync::SyncPlan;
+use crate::search_history::SearchKind;
+use crate::search_query::SearchQuery;
+use crate::search_worker::SearchWorker;
+use crate::system::volumes::Volume;
+use crate::theme::{Theme, ThemeColors};
+use crate::utils::files::SortOptions;
+use crate::{SortBy, SortType};
 
 extern crate copypasta;
 
+pub const GLOBAL_SEARCH_LIMIT: usize = 500;
+
+// shared by the search input and `ff query`, see SearchQuery for the syntax
+pub fn global_search(
+    store: &DirectoryStore,
+    frecency: &FrecencyStore,
+    query: &str,
+    limit: usize,
+) -> Result<Vec<String>, String> {
+    let query = SearchQuery::parse(query)?;
+    Ok(store.search(&query, &frecency.scores(), limit))
+}
+
+// a search whose results the next query narrows down
 #[derive(Debug, Clone)]
-pub enum IDE {
-    NVIM,
-    VSCODE,
-    ZED,
+pub struct PinnedSearch {
+    pub query: String,
+    pub results: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -16,9 +60,65 @@ pub enum InputMode {
     WatchDelete,
     WatchCreate,
     WatchRename,
+    WatchCopyName,
     WatchSort,
     WatchKeyBinding,
     WatchCopy,
+    WatchBulkRename,
+    WatchPatternRename,
+    WatchCompare,
+    WatchArchive,
+    ContentSearch,
+    Filter,
+    WatchProperties,
+    WatchJobs,
+    WatchCancelCopy,
+    WatchTransfer,
+    WatchSync,
+    WatchHash,
+    WatchJump,
+    WatchEditor,
+    WatchCommands,
+    WatchConverters,
+    WatchConversions,
+    WatchTree,
+    WatchGoto,
+    WatchSaveSearch,
+    WatchSavedSearches,
+    WatchVolumes,
+    WatchThemes,
+    WatchIgnore,
+    CacheLoading,
+}
+
+impl InputMode {
+    // modes with a prompt the user types into, pasted text is only typed
+    // there so it can't fire the keys of a popup
+    pub fn takes_text(&self) -> bool {
+        matches!(
+            self,
+            InputMode::Editing
+                | InputMode::WatchCreate
+                | InputMode::WatchRename
+                | InputMode::WatchCopyName
+                | InputMode::WatchPatternRename
+                | InputMode::ContentSearch
+                | InputMode::Filter
+                | InputMode::WatchTransfer
+                | InputMode::WatchJump
+                | InputMode::WatchGoto
+                | InputMode::WatchSaveSearch
+                | InputMode::WatchIgnore
+        )
+    }
+}
+
+// a message shown over the listing for a few seconds
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub message: String,
+    pub error: bool,
+    pub shown: Instant,
 }
 
 #[derive(Debug, Clone)]
@@ -26,14 +126,30 @@ pub struct App {
     pub input: String,
     pub character_index: usize,
     pub input_mode: InputMode,
-    pub message: Vec<String>,
     pub files: Vec<String>,
     pub read_only_files: Vec<String>,
-    pub selected_id: Option<IDE>,
+    pub editors: Vec<Editor>,
+    pub selected_editor: Option<Editor>,
+    // print the selected paths and exit instead of opening them
+    pub print_on_select: bool,
+    pub commands: Vec<CustomCommand>,
+    pub converters: Vec<Converter>,
+    // what the last conversion job did to every file
+    pub conversion_results: Vec<ConversionResult>,
+    pub conversion_scroll: u16,
     pub render_popup: bool,
-    pub prev_dir: String,
+    // directory the listing belongs to, the listing itself may be filtered,
+    // empty or showing search results
+    pub current_dir: PathBuf,
 
     pub show_hidden_files: bool,
+    pub respect_gitignore: bool,
+    // ignore_directories of settings.json, listings hide them together
+    // with the gitignored entries
+    pub ignore_patterns: IgnorePatterns,
+    pub sort_options: SortOptions,
+    // key the listing is sorted by, kept when the listing is refreshed
+    pub sort_by: SortBy,
     // create and edit file name
     pub create_edit_file_name: String,
     pub char_index: usize,
@@ -52,7 +168,109 @@ pub struct App {
     pub copy_move_read_only_files_prev: String,
 
     pub preview_files: Vec<String>,
-    pub preview_file_content: String,
+    // bytes of the selected file the text preview reads, grows with "v"
+    pub preview_limit: u64,
+    // first table column shown in the csv preview
+    pub preview_column: usize,
+    // json, yaml and toml files are previewed as a foldable tree
+    pub structured_view: bool,
+    // keeps the text preview scrolled to the end, like tail -f
+    pub follow_preview: bool,
+    pub preview_max_bytes: u64,
+    // why the selected directory couldn't be previewed
+    pub preview_error: Option<String>,
+    // lines scrolled in the text preview
+    pub preview_scroll: u16,
+    // the preview was clicked, moving scrolls it instead of the listing
+    pub preview_focused: bool,
+    // table with size, date and permission columns instead of the list
+    pub detail_view: bool,
+    // `g` was pressed and the next key completes the sequence
+    pub pending_goto: bool,
+    // `y` was pressed and the next key picks what is copied
+    pub pending_yank: bool,
+
+    pub marked_files: Vec<String>,
+    pub locked_dirs: HashSet<String>,
+    // bumped whenever the directory is read again
+    pub listing_generation: u64,
+    pub bulk_rename_plan: Option<RenamePlan>,
+    // expression typed in the pattern rename prompt, the entries it renames
+    // and why it can't be used
+    pub pattern_rename_input: String,
+    pub pattern_rename_targets: Vec<String>,
+    pub pattern_rename_error: Option<String>,
+    // diff of two marked entries shown in the preview pane
+    pub comparison: Option<Comparison>,
+    pub comparison_unified: bool,
+    pub comparison_scroll: u16,
+
+    pub content_search_root: String,
+    pub content_search_query: String,
+    pub content_search_last_query: String,
+    pub content_search_results: Vec<ContentMatch>,
+    pub content_search_status: String,
+
+    pub filter_input: String,
+    pub filter_error: Option<String>,
+
+    // recursive sizes calculated on demand, keyed by full path
+    pub dir_sizes: HashMap<String, u64>,
+
+    pub properties: Option<Properties>,
+    // edited mode and the rwx bit under the cursor in the properties popup
+    pub pending_mode: u32,
+    pub permission_cursor: usize,
+
+    // copy to / move to / link destination prompt
+    pub transfer_input: String,
+    pub transfer_is_move: bool,
+    pub transfer_sources: Vec<String>,
+    pub transfer_candidates: Vec<String>,
+    pub transfer_error: Option<String>,
+    // set when the prompt creates a link instead of copying or moving
+    pub transfer_link: Option<LinkKind>,
+    pub link_relative: bool,
+    // set when the prompt picks the directory a sync mirrors into
+    pub transfer_sync: bool,
+
+    // dry run of a sync waiting for confirmation, extraneous entries in the
+    // destination are only deleted when `sync_delete` is on
+    pub sync_plan: Option<SyncPlan>,
+    pub sync_delete: bool,
+    pub sync_scroll: u16,
+
+    pub hash_target: String,
+    pub hash_result: Option<String>,
+
+    pub jump_input: String,
+    pub jump_results: Vec<String>,
+    // search or filter query waiting for a name in the save prompt
+    pub search_to_save: Option<(SearchKind, String)>,
+    pub search_error: Option<String>,
+    // query the listing holds the results of, its matches are highlighted
+    pub search_query: Option<SearchQuery>,
+    pub search_stack: Vec<PinnedSearch>,
+    // frecency when the search was opened, ranks its results
+    pub search_frecency: Arc<FrecencyScores>,
+
+    pub volumes: Vec<Volume>,
+
+    pub theme: Theme,
+    // the terminal is light, the theme picker changes the light theme
+    pub light_mode: bool,
+    // rgb theme colors are reduced to 256 colors without it
+    pub true_color: bool,
+    // listed in the theme picker
+    pub themes: Vec<Theme>,
+
+    pub goto_input: String,
+    pub goto_candidates: Vec<String>,
+    pub goto_error: Option<String>,
+
+    pub toast: Option<Toast>,
+    // entries found by the cache build running in the background
+    pub cache_build: Option<usize>,
 }
 
 impl App {
@@ -62,14 +280,23 @@ impl App {
         Self {
             input: String::new(),
             input_mode: InputMode::Normal,
-            message: Vec::new(),
             files,
             read_only_files: files_clone,
             character_index: 0,
-            selected_id: None,
+            editors: Vec::new(),
+            selected_editor: None,
+            print_on_select: false,
+            commands: Vec::new(),
+            converters: Vec::new(),
+            conversion_results: Vec::new(),
+            conversion_scroll: 0,
             render_popup: false,
-            prev_dir: String::new(),
+            current_dir: PathBuf::new(),
             show_hidden_files: false,
+            respect_gitignore: false,
+            ignore_patterns: IgnorePatterns::default(),
+            sort_options: SortOptions::default(),
+            sort_by: SortBy::Default,
             create_edit_file_name: String::new(),
             char_index: 0,
             is_create_edit_error: false,
@@ -84,7 +311,77 @@ impl App {
             copy_move_read_only_files_prev: String::new(),
 
             preview_files: Vec::new(),
-            preview_file_content: String::new(),
+            preview_error: None,
+            preview_limit: 0,
+            preview_column: 0,
+            structured_view: true,
+            follow_preview: false,
+            preview_max_bytes: 0,
+            preview_scroll: 0,
+            preview_focused: false,
+            detail_view: false,
+            pending_goto: false,
+            pending_yank: false,
+
+            marked_files: Vec::new(),
+            locked_dirs: HashSet::new(),
+            listing_generation: 0,
+            bulk_rename_plan: None,
+            pattern_rename_input: String::new(),
+            pattern_rename_targets: Vec::new(),
+            pattern_rename_error: None,
+            comparison: None,
+            comparison_unified: false,
+            comparison_scroll: 0,
+
+            content_search_root: String::new(),
+            content_search_query: String::new(),
+            content_search_last_query: String::new(),
+            content_search_results: Vec::new(),
+            content_search_status: String::new(),
+
+            filter_input: String::new(),
+            filter_error: None,
+
+            dir_sizes: HashMap::new(),
+
+            properties: None,
+            pending_mode: 0,
+            permission_cursor: 0,
+
+            transfer_input: String::new(),
+            transfer_is_move: false,
+            transfer_sources: Vec::new(),
+            transfer_candidates: Vec::new(),
+            transfer_error: None,
+            transfer_link: None,
+            link_relative: true,
+            transfer_sync: false,
+            sync_plan: None,
+            sync_delete: false,
+            sync_scroll: 0,
+
+            hash_target: String::new(),
+            hash_result: None,
+
+            jump_input: String::new(),
+            jump_results: Vec::new(),
+            search_to_save: None,
+            search_error: None,
+            search_query: None,
+            search_stack: Vec::new(),
+            search_frecency: Arc::default(),
+
+            volumes: Vec::new(),
+            theme: Theme::onedark(),
+            light_mode: false,
+            true_color: true,
+            themes: Vec::new(),
+            goto_input: String::new(),
+            goto_candidates: Vec::new(),
+            goto_error: None,
+            toast: None,
+            cache_build: None,
         }
     }
 
@@ -98,25 +395,90 @@ impl App {
         self.character_index = self.clamp_cursor(cursor_moved_right);
     }
 
-    pub fn enter_char(&mut self, new_char: char, store: DirectoryStore) {
+    pub fn enter_char(&mut self, new_char: char, search: &mut SearchWorker) {
         let index = self.byte_index();
         self.input.insert(index, new_char);
-        self.filter_files(self.input.clone(), store);
+        self.perform_global_search(search);
         self.move_cursor_right();
     }
 
-    pub fn filter_files(&mut self, input: String, store: DirectoryStore) {
-        let mut new_files: Vec<String> = Vec::new();
+    // replaces the search input, e.g. with a query from the history
+    pub fn set_input(&mut self, query: String, search: &mut SearchWorker) {
+        self.input = query;
+        self.character_index = self.input.chars().count();
+        self.perform_global_search(search);
+    }
 
-        let r = store.search(&input);
-        for file in self.read_only_files.iter() {
-            if file.contains(&input) {
-                new_files.push(file.clone());
+    // results arrive through `show_search_results` while the worker scans,
+    // a pinned search is narrowed right away
+    pub fn perform_global_search(&mut self, search: &mut SearchWorker) {
+        if let Some(pinned) = self.search_stack.last() {
+            search.cancel();
+            match SearchQuery::parse(&self.input) {
+                Ok(query) => {
+                    self.files = pinned
+                        .results
+                        .iter()
+                        .filter(|path| query.is_empty() || query.matches(path))
+                        .cloned()
+                        .collect();
+                    self.search_error = None;
+                    self.search_query = Some(query);
+                }
+                Err(e) => self.search_error = Some(e),
             }
+            return;
         }
+        // an invalid query keeps the last results and only reports the error
+        match search.start(&self.input, self.search_frecency.clone()) {
+            Ok(query) => {
+                self.search_error = None;
+                self.search_query = Some(query);
+            }
+            Err(e) => self.search_error = Some(e),
+        }
+    }
 
-        //self.files = new_files;
-        self.files = r;
+    // keeps the current results and starts a query that narrows them
+    pub fn pin_search(&mut self) {
+        if self.input.trim().is_empty() || self.search_error.is_some() {
+            return;
+        }
+        self.search_stack.push(PinnedSearch {
+            query: std::mem::take(&mut self.input),
+            results: self.files.clone(),
+        });
+        self.reset_cursor();
+        self.search_query = None;
+    }
+
+    // drops the last pinned search and puts its query back into the input
+    pub fn pop_search(&mut self, search: &mut SearchWorker) -> bool {
+        match self.search_stack.pop() {
+            Some(pinned) => {
+                self.set_input(pinned.query, search);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // the pinned queries, e.g. `proj › src`
+    pub fn search_breadcrumb(&self) -> String {
+        self.search_stack
+            .iter()
+            .map(|pinned| pinned.query.as_str())
+            .collect::<Vec<_>>()
+            .join(" › ")
+    }
+
+    pub fn show_search_results(&mut self, results: Vec<String>) {
+        self.files = results;
+    }
+
+    pub fn open_search(&mut self, frecency: &FrecencyStore) {
+        self.search_frecency = Arc::new(frecency.scores());
+        self.input_mode = InputMode::Editing;
     }
 
     pub fn byte_index(&mut self) -> usize {
@@ -127,7 +489,7 @@ impl App {
             .unwrap_or(self.input.len())
     }
 
-    pub fn delete_char(&mut self, store: DirectoryStore) {
+    pub fn delete_char(&mut self, search: &mut SearchWorker) {
         let is_not_cursor_leftmost = self.character_index != 0;
         if is_not_cursor_leftmost {
             let current_index = self.character_index;
@@ -138,7 +500,7 @@ impl App {
             let after_char_to_delete = self.input.chars().skip(current_index);
 
             self.input = before_char_to_delete.chain(after_char_to_delete).collect();
-            self.filter_files(self.input.clone(), store);
+            self.perform_global_search(search);
             self.move_cursor_left();
         }
     }
@@ -160,21 +522,170 @@ impl App {
         self.error_message = String::new();
     }
 
-    pub fn submit_message(&mut self) {
-        self.message.push(self.input.clone());
-        self.input.clear();
-        self.reset_cursor();
+    // narrows the current listing, an invalid expression keeps the last
+    // valid result on screen and only reports the error
+    pub fn apply_filter(&mut self) {
+        match Filter::parse(&self.filter_input) {
+            Ok(filter) => {
+                self.files = if filter.is_empty() {
+                    self.read_only_files.clone()
+                } else {
+                    filter.apply(&self.read_only_files)
+                };
+                self.filter_error = None;
+            }
+            Err(e) => {
+                self.filter_error = Some(e);
+            }
+        }
+    }
+
+    pub fn clear_filter(&mut self) {
+        self.filter_input.clear();
+        self.filter_error = None;
+        self.files = self.read_only_files.clone();
     }
 
-    pub fn validate_user_input(&self, input: &str) -> Option<IDE> {
-        match input {
-            "nvim" => Some(IDE::NVIM),
-            "vscode" => Some(IDE::VSCODE),
-            "zed" => Some(IDE::ZED),
-            _ => None,
+    pub fn toggle_mark(&mut self, path: &str) {
+        if let Some(index) = self.marked_files.iter().position(|p| p == path) {
+            self.marked_files.remove(index);
+        } else {
+            self.marked_files.push(path.to_string());
         }
     }
 
+    pub fn is_marked(&self, path: &str) -> bool {
+        self.marked_files.iter().any(|p| p == path)
+    }
+
+    pub fn theme_colors(&self) -> ThemeColors {
+        self.theme.colors.with_depth(self.true_color)
+    }
+
+    pub fn listing_ignore(&self) -> Option<&IgnorePatterns> {
+        self.respect_gitignore.then_some(&self.ignore_patterns)
+    }
+
+    // the names the typed expression gives the targets, the plan is what
+    // enter applies
+    pub fn preview_pattern_rename(&mut self) {
+        self.bulk_rename_plan = None;
+        self.pattern_rename_error = None;
+        if self.pattern_rename_input.is_empty() {
+            return;
+        }
+        match RenamePattern::parse(&self.pattern_rename_input) {
+            Ok(pattern) => {
+                let names = pattern.new_names(&self.pattern_rename_targets);
+                self.bulk_rename_plan = Some(bulk_rename::build_plan(
+                    &self.pattern_rename_targets,
+                    &names,
+                ));
+            }
+            Err(e) => self.pattern_rename_error = Some(e),
+        }
+    }
+
+    // enter hands the picked files to the editor, or prints them with
+    // --print-on-select
+    pub fn can_open(&self) -> bool {
+        self.print_on_select || self.selected_editor.is_some()
+    }
+
+    pub fn show_toast(&mut self, message: String, error: bool) {
+        self.toast = Some(Toast {
+            message,
+            error,
+            shown: Instant::now(),
+        });
+    }
+
+    // switches to `theme` and saves it in settings.json
+    pub fn update_theme(&mut self, theme: Theme, config: &mut Configuration) -> anyhow::Result<()> {
+        let name = theme.name.clone();
+        self.theme = theme;
+        let light = self.light_mode;
+        let set = move |settings: &mut Configuration| match light {
+            true => settings.light_theme = name.clone(),
+            false => settings.theme = name.clone(),
+        };
+        set(config);
+        config.update_settings_file(set)
+    }
+
+    // shows `dir` as the new listing
+    pub fn enter(&mut self, dir: Dir) {
+        self.current_dir = dir.path;
+        self.search_query = None;
+        self.search_stack.clear();
+        self.read_only_files = dir.entries.clone();
+        self.files = dir.entries;
+        self.locked_dirs = dir.locked;
+        self.listing_generation += 1;
+    }
+
+    // rereads the current directory with the active hidden, gitignore, sort
+    // and filter settings. returns the index of `select`, else of the entry
+    // at `selected`, else the same position when that entry is gone
+    pub fn refresh_file_list(
+        &mut self,
+        sort_type: &SortType,
+        selected: Option<usize>,
+        select: Option<String>,
+    ) -> Option<usize> {
+        let select = select.or_else(|| selected.and_then(|index| self.files.get(index).cloned()));
+        match Dir::read(
+            self.current_dir.clone(),
+            self.show_hidden_files,
+            self.listing_ignore(),
+            self.sort_by.clone(),
+            sort_type,
+            &self.sort_options,
+            &self.dir_sizes,
+        ) {
+            Ok(dir) => {
+                self.enter(dir);
+                if !self.filter_input.is_empty() {
+                    self.apply_filter();
+                }
+            }
+            // a directory that can't be read anymore keeps its old listing
+            Err(e) => self.curr_stats = errors::status(&self.current_dir_string(), e),
+        }
+
+        if self.files.is_empty() {
+            return None;
+        }
+        let index = select
+            .and_then(|select| self.files.iter().position(|file| *file == select))
+            .unwrap_or_else(|| selected.unwrap_or(0).min(self.files.len() - 1));
+        Some(index)
+    }
+
+    pub fn current_dir_string(&self) -> String {
+        self.current_dir.to_string_lossy().to_string()
+    }
+
+    pub fn open_goto(&mut self) {
+        self.goto_input = format!("{}{}", self.current_dir_string(), std::path::MAIN_SEPARATOR);
+        self.goto_candidates.clear();
+        self.goto_error = None;
+        self.input_mode = InputMode::WatchGoto;
+    }
+
+    pub fn validate_user_input(&self, input: &str) -> Option<Editor> {
+        self.editors
+            .iter()
+            .find(|editor| editor.name == input)
+            .cloned()
+    }
+
+    // "helix, nvim, vscode", for errors about a missing or broken editor
+    pub fn editor_names(&self) -> String {
+        let names: Vec<&str> = self.editors.iter().map(|e| e.name.as_str()).collect();
+        names.join(", ")
+    }
+
     // TODO: could we combine search, create, edit input field methods?
     // there is a lot of duplication here
     //
@@ -227,32 +738,26 @@ impl App {
         new_cursor_pos.clamp(0, self.create_edit_file_name.chars().count())
     }
 
-    pub fn handle_arguments(&mut self, args: Vec<String>) {
-        if args.len() > 1 {
-            let ide = &args[1];
+    // `ff nvim` or `ff --editor nvim`
+    pub fn handle_arguments(&mut self, args: Vec<String>) -> anyhow::Result<()> {
+        let name = match args.get(1).map(|arg| arg.as_str()) {
+            Some("--editor") => args.get(2),
+            Some(_) => args.get(1),
+            None => None,
+        };
 
-            let validated_ide = self.validate_user_input(ide);
+        if let Some(name) = name {
+            let validated_editor = self.validate_user_input(name);
 
-            if let Some(selection) = validated_ide {
-                //
-                self.selected_id = Some(selection);
+            if let Some(selection) = validated_editor {
+                self.selected_editor = Some(selection);
             } else {
-                panic!(
-                    "Invalid IDE selection, Please select from the following: nvim, vscode, zed"
-                );
-            }
-        }
-    }
-
-    pub fn get_selected_ide(&self) -> Option<String> {
-        if let Some(selection) = &self.selected_id {
-            match selection {
-                IDE::NVIM => Some("nvim".to_string()),
-                IDE::VSCODE => Some("vscode".to_string()),
-                IDE::ZED => Some("zed".to_string()),
+                return Err(anyhow!(
+                    "Invalid editor selection, Please select from the following: {}",
+                    self.editor_names()
+                ));
             }
-        } else {
-            None
         }
+        Ok(())
     }
 }
@@ -0,0 +1,127 @@
+use std::{
+    collections::BTreeMap,
+    io,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use crate::custom_commands::{commands_from_config, CommandSpec, CustomCommand};
+
+// what enter does with a file
+#[derive(Debug, Clone)]
+pub enum Association {
+    Editor,
+    // the default application of the system
+    Open,
+    // enter does nothing, the preview is all there is
+    PreviewOnly,
+    Command(CustomCommand),
+}
+
+// "associations": { "png": "open", "image/*": "open", "log": "preview-only",
+// "mp4": { "command": "mpv {file}", "mode": "silent" } }. keys are
+// extensions or mime types, anything without a match opens in the editor
+#[derive(Default)]
+pub struct Associations {
+    rules: Vec<(String, Association)>,
+}
+
+impl Associations {
+    pub fn from_config(associations: &BTreeMap<String, CommandSpec>) -> Associations {
+        let rules = commands_from_config(associations)
+            .into_iter()
+            .map(|command| {
+                let key = command.name.trim_start_matches('.').to_lowercase();
+                let association = match command.template.as_str() {
+                    "editor" => Association::Editor,
+                    "open" => Association::Open,
+                    "preview-only" => Association::PreviewOnly,
+                    _ => Association::Command(command),
+                };
+                (key, association)
+            })
+            .collect();
+        Associations { rules }
+    }
+
+    // the extension wins over the exact mime type, which wins over `type/*`
+    pub fn resolve(&self, path: &str) -> Association {
+        let extension = Path::new(path)
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let mime = mime_type(&extension);
+        let wildcard = mime.map(|mime| format!("{}/*", mime.split('/').next().unwrap_or("")));
+
+        let candidates = [Some(extension.as_str()), mime, wildcard.as_deref()];
+        candidates
+            .iter()
+            .flatten()
+            .filter(|key| !key.is_empty())
+            .find_map(|key| {
+                self.rules
+                    .iter()
+                    .find(|(rule, _)| rule == key)
+                    .map(|(_, association)| association.clone())
+            })
+            .unwrap_or(Association::Editor)
+    }
+}
+
+// the common types by extension, enough to match rules like `image/*`
+pub fn mime_type(extension: &str) -> Option<&'static str> {
+    let mime = match extension {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "tiff" | "tif" => "image/tiff",
+        "mp4" | "m4v" => "video/mp4",
+        "mkv" => "video/x-matroska",
+        "webm" => "video/webm",
+        "mov" => "video/quicktime",
+        "avi" => "video/x-msvideo",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "flac" => "audio/flac",
+        "ogg" => "audio/ogg",
+        "m4a" => "audio/mp4",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" | "tgz" => "application/gzip",
+        "tar" => "application/x-tar",
+        "json" => "application/json",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "xlsx" => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "csv" => "text/csv",
+        "md" => "text/markdown",
+        "txt" | "log" => "text/plain",
+        _ => return None,
+    };
+    Some(mime)
+}
+
+// hands the file to the default application and returns right away
+pub fn open_with_system(path: &str) -> io::Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    command
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    Ok(())
+}
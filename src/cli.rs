@@ -0,0 +1,461 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+    sync::mpsc,
+    thread,
+    time::UNIX_EPOCH,
+};
+
+use anyhow::anyhow;
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::Serialize;
+
+use crate::app::{global_search, GLOBAL_SEARCH_LIMIT};
+use crate::config_check;
+use crate::configuration::Configuration;
+use crate::directory_store::load_or_build_store;
+use crate::filter::Filter;
+use crate::frecency::FrecencyStore;
+use crate::git_status::{repo_statuses, status_of};
+use crate::jobs::{JobControl, JobMessage};
+use crate::operations::transfer::{self, expand_home};
+use crate::picker;
+use crate::theme;
+use crate::{get_file_path_data, SortBy, SortType};
+
+const USAGE: &str = "usage:
+  ff query <pattern> [--limit N] [--json]
+  ff ls [path] [--json] [--hidden] [--sort name|size|modified] [--desc] [--filter <terms>]
+  ff copy <source>... <destination>
+  ff move <source>... <destination>
+  ff trash <path>...
+  ff config check|path|edit
+  <command> | ff --pick
+  ff --print-on-select prints the paths picked with enter instead of opening them, the tui is drawn on stderr
+  any of them, or the tui, with --profile <name> to use ~/.config/ff/profiles/<name>.toml";
+
+pub enum Command {
+    Query {
+        pattern: String,
+        limit: usize,
+        json: bool,
+    },
+    Ls {
+        path: String,
+        json: bool,
+        show_hidden: bool,
+        sort_by: SortBy,
+        sort_type: SortType,
+        filter: Filter,
+    },
+    Copy {
+        sources: Vec<String>,
+        dest: String,
+    },
+    Move {
+        sources: Vec<String>,
+        dest: String,
+    },
+    Trash {
+        paths: Vec<String>,
+    },
+    Pick,
+    Config(ConfigCommand),
+}
+
+pub enum ConfigCommand {
+    // reports the problems of settings.json, the profiles and the themes
+    Check,
+    Path,
+    // opens settings.json in $VISUAL or $EDITOR and checks it afterwards
+    Edit,
+}
+
+#[derive(Serialize)]
+struct QueryResult {
+    path: String,
+    #[serde(rename = "type")]
+    kind: &'static str,
+}
+
+#[derive(Serialize)]
+struct ListEntry {
+    path: String,
+    name: String,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    size: u64,
+    // seconds since the unix epoch
+    modified: Option<u64>,
+    git: Option<&'static str>,
+}
+
+// removes `flag` from the arguments, true when it was there
+pub fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    let before = args.len();
+    args.retain(|arg| arg != flag);
+    args.len() != before
+}
+
+// None when the first argument isn't a subcommand, e.g. `ff nvim` still
+// starts the tui with an editor selected
+pub fn parse_command(args: &[String]) -> Option<anyhow::Result<Command>> {
+    match args.get(1).map(|arg| arg.as_str()) {
+        Some("query") => Some(parse_query(&args[2..])),
+        Some("ls") => Some(parse_ls(&args[2..])),
+        Some("copy") => {
+            Some(parse_transfer(&args[2..]).map(|(sources, dest)| Command::Copy { sources, dest }))
+        }
+        Some("move") => {
+            Some(parse_transfer(&args[2..]).map(|(sources, dest)| Command::Move { sources, dest }))
+        }
+        Some("trash") => Some(parse_trash(&args[2..])),
+        Some("--pick") => Some(Ok(Command::Pick)),
+        Some("config") => Some(parse_config(&args[2..])),
+        _ => None,
+    }
+}
+
+fn parse_ls(args: &[String]) -> anyhow::Result<Command> {
+    let mut path: Option<String> = None;
+    let mut json = false;
+    let mut show_hidden = false;
+    let mut sort_by = SortBy::Default;
+    let mut sort_type = SortType::ASC;
+    let mut filter = Filter::default();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--hidden" => show_hidden = true,
+            "--desc" => sort_type = SortType::DESC,
+            "--sort" => {
+                sort_by = match args.next().map(|value| value.as_str()) {
+                    Some("name") => SortBy::Name,
+                    Some("size") => SortBy::Size,
+                    Some("modified") => SortBy::DateAdded,
+                    _ => return Err(anyhow!("--sort needs name, size or modified\n{}", USAGE)),
+                }
+            }
+            "--filter" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| anyhow!("--filter needs a value\n{}", USAGE))?;
+                filter = Filter::parse(value).map_err(|e| anyhow!(e))?;
+            }
+            flag if flag.starts_with("--") => {
+                return Err(anyhow!("unknown option '{}'\n{}", flag, USAGE))
+            }
+            value if path.is_none() => path = Some(expand_home(value)),
+            value => return Err(anyhow!("unexpected argument '{}'\n{}", value, USAGE)),
+        }
+    }
+
+    let path = match path {
+        Some(path) => path,
+        None => std::env::current_dir()?.to_string_lossy().to_string(),
+    };
+    Ok(Command::Ls {
+        path,
+        json,
+        show_hidden,
+        sort_by,
+        sort_type,
+        filter,
+    })
+}
+
+// the last argument is the destination, like cp and mv
+fn parse_transfer(args: &[String]) -> anyhow::Result<(Vec<String>, String)> {
+    if args.len() < 2 {
+        return Err(anyhow!("missing source or destination\n{}", USAGE));
+    }
+    let sources = args[..args.len() - 1]
+        .iter()
+        .map(|source| expand_home(source))
+        .collect();
+    Ok((sources, expand_home(&args[args.len() - 1])))
+}
+
+fn parse_trash(args: &[String]) -> anyhow::Result<Command> {
+    if args.is_empty() {
+        return Err(anyhow!("missing path\n{}", USAGE));
+    }
+    Ok(Command::Trash {
+        paths: args.iter().map(|path| expand_home(path)).collect(),
+    })
+}
+
+fn parse_config(args: &[String]) -> anyhow::Result<Command> {
+    let command = match args.first().map(|arg| arg.as_str()) {
+        Some("check") => ConfigCommand::Check,
+        Some("path") => ConfigCommand::Path,
+        Some("edit") => ConfigCommand::Edit,
+        Some(other) => return Err(anyhow!("unknown config command '{}'\n{}", other, USAGE)),
+        None => return Err(anyhow!("missing config command\n{}", USAGE)),
+    };
+    if let Some(arg) = args.get(1) {
+        return Err(anyhow!("unexpected argument '{}'\n{}", arg, USAGE));
+    }
+    Ok(Command::Config(command))
+}
+
+fn parse_query(args: &[String]) -> anyhow::Result<Command> {
+    let mut pattern: Option<String> = None;
+    let mut limit = GLOBAL_SEARCH_LIMIT;
+    let mut json = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--limit" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| anyhow!("--limit needs a value\n{}", USAGE))?;
+                limit = value
+                    .parse()
+                    .map_err(|_| anyhow!("invalid limit '{}'\n{}", value, USAGE))?;
+            }
+            flag if flag.starts_with("--") => {
+                return Err(anyhow!("unknown option '{}'\n{}", flag, USAGE))
+            }
+            value => match &mut pattern {
+                // `ff query foo bar` searches for "foo bar"
+                Some(pattern) => {
+                    pattern.push(' ');
+                    pattern.push_str(value);
+                }
+                None => pattern = Some(value.to_string()),
+            },
+        }
+    }
+
+    let pattern = pattern.ok_or_else(|| anyhow!("missing pattern\n{}", USAGE))?;
+    Ok(Command::Query {
+        pattern,
+        limit,
+        json,
+    })
+}
+
+pub fn run(command: Command, config: &Configuration) -> anyhow::Result<()> {
+    match command {
+        Command::Query {
+            pattern,
+            limit,
+            json,
+        } => {
+            let store = load_or_build_store(&config.cache_header(), &config.cache_directory)?;
+            let frecency = FrecencyStore::load(&config.frecency_path());
+            let results =
+                global_search(&store, &frecency, &pattern, limit).map_err(anyhow::Error::msg)?;
+
+            if json {
+                let results: Vec<QueryResult> = results
+                    .into_iter()
+                    .map(|path| QueryResult {
+                        kind: if Path::new(&path).is_dir() {
+                            "dir"
+                        } else {
+                            "file"
+                        },
+                        path,
+                    })
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&results)?);
+            } else {
+                for path in results {
+                    println!("{}", path);
+                }
+            }
+        }
+        Command::Ls {
+            path,
+            json,
+            show_hidden,
+            sort_by,
+            sort_type,
+            filter,
+        } => {
+            // same listing the tui shows for this directory
+            let ignore = config.ignore_patterns();
+            let files = get_file_path_data(
+                path.clone(),
+                show_hidden,
+                config.respect_gitignore.then_some(&ignore),
+                sort_by,
+                &sort_type,
+                &config.sort,
+            )?;
+            let files = filter.apply(&files);
+
+            if json {
+                let entries = list_entries(&path, files);
+                println!("{}", serde_json::to_string_pretty(&entries)?);
+            } else {
+                for file in files {
+                    println!("{}", file);
+                }
+            }
+        }
+        Command::Copy { sources, dest } => {
+            let targets = resolve_targets(&sources, &dest)?;
+            run_with_progress(move |control, sender| transfer::copy_to(&targets, control, sender))?;
+        }
+        Command::Move { sources, dest } => {
+            let targets = resolve_targets(&sources, &dest)?;
+            run_with_progress(move |control, sender| transfer::move_to(&targets, control, sender))?;
+        }
+        Command::Trash { paths } => {
+            for path in paths.iter() {
+                if std::fs::symlink_metadata(path).is_err() {
+                    return Err(anyhow!("{} does not exist", path));
+                }
+            }
+            trash::delete_all(&paths)?;
+        }
+        Command::Pick => {
+            let candidates = picker::read_candidates()?;
+            let (theme, _) = theme::configured(config);
+            let colors = theme
+                .colors
+                .with_depth(theme::true_color(&config.color_depth));
+            match picker::pick(candidates, colors)? {
+                Some(selection) => println!("{}", selection),
+                // same exit code as fzf when nothing was chosen
+                None => std::process::exit(130),
+            }
+        }
+        Command::Config(ConfigCommand::Path) => println!("{}", config.settings_path),
+        Command::Config(ConfigCommand::Check) => check_config(config)?,
+        Command::Config(ConfigCommand::Edit) => {
+            let editor = env::var("VISUAL")
+                .or_else(|_| env::var("EDITOR"))
+                .unwrap_or_else(|_| String::from("vi"));
+            let mut editor_parts = editor.split_whitespace();
+            let editor_cmd = editor_parts.next().unwrap_or("vi");
+            let status = process::Command::new(editor_cmd)
+                .args(editor_parts)
+                .arg(&config.settings_path)
+                .status()?;
+            if !status.success() {
+                return Err(anyhow!("{} exited with an error", editor_cmd));
+            }
+            check_config(config)?;
+        }
+    }
+    Ok(())
+}
+
+fn check_config(config: &Configuration) -> anyhow::Result<()> {
+    let diagnostics = config_check::check(config);
+    for diagnostic in &diagnostics {
+        println!("{}", diagnostic);
+    }
+    let errors = diagnostics
+        .iter()
+        .filter(|diagnostic| !diagnostic.warning)
+        .count();
+    let warnings = diagnostics.len() - errors;
+    if errors > 0 {
+        return Err(anyhow!("{} errors, {} warnings", errors, warnings));
+    }
+    match warnings {
+        0 => println!("no problems found"),
+        _ => println!("{} warnings", warnings),
+    }
+    Ok(())
+}
+
+fn list_entries(dir: &str, files: Vec<String>) -> Vec<ListEntry> {
+    let statuses = repo_statuses(Path::new(dir));
+    // git reports resolved paths, so look entries up through the resolved dir
+    let resolved_dir = fs::canonicalize(dir).unwrap_or_else(|_| PathBuf::from(dir));
+
+    files
+        .into_iter()
+        .map(|file| {
+            let path = Path::new(&file);
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let metadata = fs::symlink_metadata(path).ok();
+            let kind = match &metadata {
+                Some(meta) if meta.file_type().is_symlink() => "symlink",
+                Some(meta) if meta.is_dir() => "dir",
+                _ => "file",
+            };
+            let modified = metadata
+                .as_ref()
+                .and_then(|meta| meta.modified().ok())
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|duration| duration.as_secs());
+            let git = status_of(&statuses, &resolved_dir.join(&name)).map(|status| status.label());
+
+            ListEntry {
+                size: metadata.map(|meta| meta.len()).unwrap_or(0),
+                path: file,
+                name,
+                kind,
+                modified,
+                git,
+            }
+        })
+        .collect()
+}
+
+// copying into an existing directory keeps the source names, a single source
+// can also be copied to a new path
+fn resolve_targets(sources: &[String], dest: &str) -> anyhow::Result<Vec<(PathBuf, PathBuf)>> {
+    let dest = Path::new(dest);
+    if dest.is_dir() {
+        return transfer::plan_targets(sources, dest);
+    }
+    if sources.len() > 1 {
+        return Err(anyhow!("{} is not a directory", dest.display()));
+    }
+    if dest.exists() {
+        return Err(anyhow!("{} already exists", dest.display()));
+    }
+    Ok(vec![(PathBuf::from(&sources[0]), dest.to_path_buf())])
+}
+
+// runs the same workers the tui uses as jobs and draws their byte counters on
+// the terminal
+fn run_with_progress<F>(work: F) -> anyhow::Result<()>
+where
+    F: FnOnce(&JobControl, &mpsc::Sender<JobMessage>) -> anyhow::Result<()> + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel();
+    let control = JobControl::default();
+    let worker = thread::spawn(move || work(&control, &sender));
+
+    let bar = ProgressBar::new(0);
+    bar.set_style(
+        ProgressStyle::with_template(
+            "{bar:40} {bytes}/{total_bytes} {bytes_per_sec} eta {eta} {wide_msg}",
+        )?
+        .progress_chars("=> "),
+    );
+
+    // the sender is dropped when the worker returns
+    for message in receiver {
+        match message {
+            JobMessage::Bytes { copied, total } => {
+                bar.set_length(total);
+                bar.set_position(copied);
+            }
+            JobMessage::Progress(message) => bar.set_message(message),
+            _ => {}
+        }
+    }
+    bar.finish_and_clear();
+
+    worker
+        .join()
+        .map_err(|_| anyhow!("worker thread panicked"))?
+}
@@ -0,0 +1,371 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use anyhow::anyhow;
+use ratatui::{
+    style::Style,
+    text::{Line, Span},
+};
+use similar::{ChangeTag, TextDiff};
+use walkdir::WalkDir;
+
+use crate::theme::ThemeColors;
+
+// bigger files are only compared byte by byte
+const MAX_DIFF_BYTES: u64 = 4 * 1024 * 1024;
+// unchanged lines kept around every change
+const CONTEXT_LINES: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RowKind {
+    Same,
+    Removed,
+    Added,
+    // removed on the left and replaced by the line on the right
+    Changed,
+}
+
+// one row of the side by side view, a side is empty where the other one has
+// lines that were added or removed
+#[derive(Debug, Clone)]
+pub struct DiffRow {
+    pub kind: RowKind,
+    pub left: Option<(usize, String)>,
+    pub right: Option<(usize, String)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Hunk {
+    pub header: String,
+    pub rows: Vec<DiffRow>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DirSummary {
+    pub only_left: Vec<String>,
+    pub only_right: Vec<String>,
+    pub different: Vec<String>,
+    pub identical: usize,
+}
+
+#[derive(Debug, Clone)]
+pub enum CompareResult {
+    Text(Vec<Hunk>),
+    // binary or too large to diff line by line
+    Bytes { equal: bool },
+    Dirs(DirSummary),
+}
+
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    pub left: PathBuf,
+    pub right: PathBuf,
+    pub result: CompareResult,
+}
+
+impl Comparison {
+    // two files are diffed, two directories summarized
+    pub fn new(left: &Path, right: &Path) -> anyhow::Result<Comparison> {
+        let result = match (left.is_dir(), right.is_dir()) {
+            (true, true) => CompareResult::Dirs(compare_dirs(left, right)),
+            (false, false) => compare_files(left, right)?,
+            _ => return Err(anyhow!("can't compare a file with a directory")),
+        };
+        Ok(Comparison {
+            left: left.to_path_buf(),
+            right: right.to_path_buf(),
+            result,
+        })
+    }
+
+    pub fn title(&self) -> String {
+        format!("{} <-> {}", self.left.display(), self.right.display())
+    }
+
+    // `width` is the inner width of the pane, the side by side view splits it
+    pub fn lines(&self, width: u16, unified: bool, colors: &ThemeColors) -> Vec<Line<'static>> {
+        match &self.result {
+            CompareResult::Text(hunks) if hunks.is_empty() => {
+                vec![Line::styled(
+                    "Files are identical",
+                    Style::default().fg(colors.success),
+                )]
+            }
+            CompareResult::Text(hunks) if unified => unified_lines(hunks, colors),
+            CompareResult::Text(hunks) => side_by_side_lines(hunks, width, colors),
+            CompareResult::Bytes { equal: true } => {
+                vec![Line::styled(
+                    "Files are identical",
+                    Style::default().fg(colors.success),
+                )]
+            }
+            CompareResult::Bytes { equal: false } => {
+                vec![Line::styled(
+                    "Binary files differ",
+                    Style::default().fg(colors.warning),
+                )]
+            }
+            CompareResult::Dirs(summary) => summary_lines(summary, colors),
+        }
+    }
+}
+
+fn compare_files(left: &Path, right: &Path) -> anyhow::Result<CompareResult> {
+    let too_large =
+        fs::metadata(left)?.len() > MAX_DIFF_BYTES || fs::metadata(right)?.len() > MAX_DIFF_BYTES;
+    if too_large {
+        return Ok(CompareResult::Bytes {
+            equal: same_contents(left, right)?,
+        });
+    }
+
+    let (old, new) = (fs::read(left)?, fs::read(right)?);
+    let (Ok(old), Ok(new)) = (String::from_utf8(old), String::from_utf8(new)) else {
+        return Ok(CompareResult::Bytes {
+            equal: same_contents(left, right)?,
+        });
+    };
+    if old.contains('\0') || new.contains('\0') {
+        return Ok(CompareResult::Bytes { equal: old == new });
+    }
+    Ok(CompareResult::Text(hunks(&old, &new)))
+}
+
+fn hunks(old: &str, new: &str) -> Vec<Hunk> {
+    let diff = TextDiff::from_lines(old, new);
+    let mut hunks = Vec::new();
+    for hunk in diff
+        .unified_diff()
+        .context_radius(CONTEXT_LINES)
+        .iter_hunks()
+    {
+        let mut rows: Vec<DiffRow> = Vec::new();
+        // removed lines wait for the added lines that replace them so both
+        // end up on the same rows
+        let mut removed: Vec<(usize, String)> = Vec::new();
+        let mut added: Vec<(usize, String)> = Vec::new();
+        for change in hunk.iter_changes() {
+            let text = change.value().trim_end_matches(['\n', '\r']).to_string();
+            match change.tag() {
+                ChangeTag::Delete => removed.push((change.old_index().unwrap_or(0) + 1, text)),
+                ChangeTag::Insert => added.push((change.new_index().unwrap_or(0) + 1, text)),
+                ChangeTag::Equal => {
+                    pair_changes(&mut rows, &mut removed, &mut added);
+                    rows.push(DiffRow {
+                        kind: RowKind::Same,
+                        left: Some((change.old_index().unwrap_or(0) + 1, text.clone())),
+                        right: Some((change.new_index().unwrap_or(0) + 1, text)),
+                    });
+                }
+            }
+        }
+        pair_changes(&mut rows, &mut removed, &mut added);
+        hunks.push(Hunk {
+            header: hunk.header().to_string(),
+            rows,
+        });
+    }
+    hunks
+}
+
+fn pair_changes(
+    rows: &mut Vec<DiffRow>,
+    removed: &mut Vec<(usize, String)>,
+    added: &mut Vec<(usize, String)>,
+) {
+    let count = removed.len().max(added.len());
+    let mut removed = removed.drain(..);
+    let mut added = added.drain(..);
+    for _ in 0..count {
+        let (left, right) = (removed.next(), added.next());
+        let kind = match (&left, &right) {
+            (Some(_), None) => RowKind::Removed,
+            (None, Some(_)) => RowKind::Added,
+            _ => RowKind::Changed,
+        };
+        rows.push(DiffRow { kind, left, right });
+    }
+}
+
+fn same_contents(left: &Path, right: &Path) -> io::Result<bool> {
+    if fs::metadata(left)?.len() != fs::metadata(right)?.len() {
+        return Ok(false);
+    }
+    let (mut left, mut right) = (File::open(left)?, File::open(right)?);
+    let mut left_buffer = vec![0u8; 64 * 1024];
+    let mut right_buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = left.read(&mut left_buffer)?;
+        if read == 0 {
+            return Ok(true);
+        }
+        right.read_exact(&mut right_buffer[..read])?;
+        if left_buffer[..read] != right_buffer[..read] {
+            return Ok(false);
+        }
+    }
+}
+
+// relative path -> whether it is a directory, for everything below `root`
+fn entries(root: &Path) -> BTreeMap<String, bool> {
+    WalkDir::new(root)
+        .min_depth(1)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let relative = entry.path().strip_prefix(root).ok()?;
+            Some((
+                relative.to_string_lossy().to_string(),
+                entry.file_type().is_dir(),
+            ))
+        })
+        .collect()
+}
+
+fn compare_dirs(left: &Path, right: &Path) -> DirSummary {
+    let (left_entries, right_entries) = (entries(left), entries(right));
+    let mut summary = DirSummary::default();
+
+    for (path, is_dir) in left_entries.iter() {
+        match right_entries.get(path) {
+            None => summary.only_left.push(path.clone()),
+            Some(other_is_dir) if other_is_dir != is_dir => summary.different.push(path.clone()),
+            Some(true) => {}
+            Some(false) => match same_contents(&left.join(path), &right.join(path)) {
+                Ok(true) => summary.identical += 1,
+                _ => summary.different.push(path.clone()),
+            },
+        }
+    }
+    summary.only_right = right_entries
+        .keys()
+        .filter(|path| !left_entries.contains_key(*path))
+        .cloned()
+        .collect();
+
+    // a directory missing on one side stands for everything inside it
+    for list in [&mut summary.only_left, &mut summary.only_right] {
+        let all: HashSet<String> = list.iter().cloned().collect();
+        list.retain(|path| {
+            !Path::new(path)
+                .ancestors()
+                .skip(1)
+                .any(|parent| all.contains(&*parent.to_string_lossy()))
+        });
+    }
+    summary
+}
+
+fn line_number(entry: &Option<(usize, String)>) -> String {
+    match entry {
+        Some((number, _)) => format!("{:>5} ", number),
+        None => String::from("      "),
+    }
+}
+
+fn unified_lines(hunks: &[Hunk], colors: &ThemeColors) -> Vec<Line<'static>> {
+    let removed = Style::default().fg(colors.error);
+    let added = Style::default().fg(colors.success);
+    let mut lines = Vec::new();
+    for hunk in hunks {
+        lines.push(Line::styled(
+            hunk.header.clone(),
+            Style::default().fg(colors.accent),
+        ));
+        for row in &hunk.rows {
+            match (row.kind, &row.left, &row.right) {
+                (RowKind::Same, Some((number, text)), _) => {
+                    lines.push(Line::styled(
+                        format!("{:>5}   {}", number, text),
+                        Style::default().fg(colors.muted),
+                    ));
+                }
+                (_, left, right) => {
+                    if let Some((number, text)) = left {
+                        lines.push(Line::styled(format!("{:>5} - {}", number, text), removed));
+                    }
+                    if let Some((number, text)) = right {
+                        lines.push(Line::styled(format!("{:>5} + {}", number, text), added));
+                    }
+                }
+            }
+        }
+    }
+    lines
+}
+
+// cuts or pads `text` to exactly `width` characters
+fn fit(text: &str, width: usize) -> String {
+    let mut fitted: String = text.chars().take(width).collect();
+    let length = fitted.chars().count();
+    fitted.push_str(&" ".repeat(width - length));
+    fitted
+}
+
+fn side_by_side_lines(hunks: &[Hunk], width: u16, colors: &ThemeColors) -> Vec<Line<'static>> {
+    // a line number column on both sides and a separator in the middle
+    let half = (width as usize).saturating_sub(3) / 2;
+    let text_width = half.saturating_sub(6);
+    let separator = Span::styled(" | ", Style::default().fg(colors.dim));
+    let mut lines = Vec::new();
+    for hunk in hunks {
+        lines.push(Line::styled(
+            hunk.header.clone(),
+            Style::default().fg(colors.accent),
+        ));
+        for row in &hunk.rows {
+            let (left_style, right_style) = match row.kind {
+                RowKind::Removed => (colors.error, colors.dim),
+                RowKind::Added => (colors.dim, colors.success),
+                RowKind::Changed => (colors.error, colors.success),
+                RowKind::Same => (colors.muted, colors.muted),
+            };
+            let side = |entry: &Option<(usize, String)>| {
+                let text = entry.as_ref().map(|(_, text)| text.as_str()).unwrap_or("");
+                format!("{}{}", line_number(entry), fit(text, text_width))
+            };
+            lines.push(Line::from(vec![
+                Span::styled(side(&row.left), Style::default().fg(left_style)),
+                separator.clone(),
+                Span::styled(side(&row.right), Style::default().fg(right_style)),
+            ]));
+        }
+    }
+    lines
+}
+
+fn summary_lines(summary: &DirSummary, colors: &ThemeColors) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::from(format!(
+        "{} only in left, {} only in right, {} different, {} identical",
+        summary.only_left.len(),
+        summary.only_right.len(),
+        summary.different.len(),
+        summary.identical
+    ))];
+    let sections = [
+        ("Only in left", "-", &summary.only_left, colors.error),
+        ("Only in right", "+", &summary.only_right, colors.success),
+        ("Different", "~", &summary.different, colors.warning),
+    ];
+    for (title, marker, paths, color) in sections {
+        if paths.is_empty() {
+            continue;
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::styled(
+            title.to_string(),
+            Style::default().fg(colors.accent),
+        ));
+        for path in paths {
+            lines.push(Line::styled(
+                format!("{} {}", marker, path),
+                Style::default().fg(color),
+            ));
+        }
+    }
+    lines
+}
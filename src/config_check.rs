@@ -0,0 +1,381 @@
+use std::{
+    env, fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use serde_json::Value;
+use syntect::highlighting::ThemeSet;
+
+use crate::configuration::Configuration;
+use crate::converters::converters_from_config;
+use crate::editor::{default_editors, split_template};
+use crate::ignore_rules::invalid_patterns;
+use crate::keymap::KeyMap;
+use crate::operations::transfer::expand_home;
+use crate::profile::Profile;
+use crate::theme;
+
+pub struct Diagnostic {
+    pub path: String,
+    // line and column, both starting at 1
+    pub position: Option<(usize, usize)>,
+    pub message: String,
+    // warnings don't stop ff from starting, errors make it fall back to
+    // the defaults
+    pub warning: bool,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path)?;
+        if let Some((line, column)) = self.position {
+            write!(f, ":{}:{}", line, column)?;
+        }
+        let level = match self.warning {
+            true => "warning",
+            false => "error",
+        };
+        write!(f, ": {}: {}", level, self.message)
+    }
+}
+
+// what's wrong with settings.json, the profiles and the theme files
+pub fn check(config: &Configuration) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let editors = check_settings(&config.settings_path, &mut diagnostics);
+    check_profiles(config, &editors, &mut diagnostics);
+    for path in theme::theme_files() {
+        check_theme(&path, &mut diagnostics);
+    }
+    diagnostics
+}
+
+// returns the names of the editors that can be picked
+fn check_settings(path: &str, diagnostics: &mut Vec<Diagnostic>) -> Vec<String> {
+    let mut editors: Vec<String> = default_editors().into_keys().collect();
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => {
+            diagnostics.push(diagnostic(
+                path,
+                None,
+                format!("unable to read: {}", e),
+                true,
+            ));
+            return editors;
+        }
+    };
+
+    let value: Value = match serde_json::from_str(&content) {
+        Ok(value) => value,
+        Err(e) => {
+            diagnostics.push(json_error(path, &e));
+            return editors;
+        }
+    };
+    let known = serde_json::to_value(Configuration::new()).unwrap_or(Value::Null);
+    if let (Some(keys), Some(known)) = (value.as_object(), known.as_object()) {
+        for key in keys.keys().filter(|key| !known.contains_key(*key)) {
+            diagnostics.push(diagnostic(
+                path,
+                key_position(&content, key),
+                format!("unknown setting \"{}\"", key),
+                false,
+            ));
+        }
+    }
+
+    let settings: Configuration = match serde_json::from_str(&content) {
+        Ok(settings) => settings,
+        Err(e) => {
+            diagnostics.push(json_error(path, &e));
+            return editors;
+        }
+    };
+    let mut error = |key: &str, message: String, warning: bool| {
+        diagnostics.push(diagnostic(
+            path,
+            key_position(&content, key),
+            message,
+            warning,
+        ));
+    };
+
+    if !Path::new(&settings.start_path).is_dir() {
+        error(
+            "start_path",
+            format!("start_path {} is not a directory", settings.start_path),
+            false,
+        );
+    }
+    if let Err(e) = KeyMap::new(&settings.keybindings) {
+        error("keybindings", format!("{}", e), false);
+    }
+    for invalid in invalid_patterns(&settings.ignore_directories) {
+        error(
+            "ignore_directories",
+            format!("invalid ignore pattern {}", invalid),
+            false,
+        );
+    }
+    for (key, name) in [
+        ("theme", &settings.theme),
+        ("light_theme", &settings.light_theme),
+    ] {
+        let file = theme::themes_dir().join(format!("{}.toml", name));
+        if !theme::is_builtin(name) && !file.exists() {
+            error(
+                key,
+                format!(
+                    "unknown theme \"{}\", {} doesn't exist",
+                    name,
+                    file.display()
+                ),
+                false,
+            );
+        }
+    }
+    let choices: [(&str, &str, &[&str]); 4] = [
+        (
+            "color_mode",
+            &settings.color_mode,
+            &["dark", "light", "auto"],
+        ),
+        (
+            "color_depth",
+            &settings.color_depth,
+            &["auto", "truecolor", "24bit", "256"],
+        ),
+        ("layout", &settings.layout, &["default", "miller"]),
+        (
+            "image_protocol",
+            &settings.image_protocol,
+            &["auto", "kitty", "iterm2", "sixel", "halfblocks"],
+        ),
+    ];
+    for (key, value, allowed) in choices {
+        if !allowed.contains(&value) {
+            error(
+                key,
+                format!(
+                    "{} is \"{}\", expected one of {}",
+                    key,
+                    value,
+                    allowed.join(", ")
+                ),
+                false,
+            );
+        }
+    }
+    if !ThemeSet::load_defaults()
+        .themes
+        .contains_key(&settings.syntax_theme)
+    {
+        error(
+            "syntax_theme",
+            format!("unknown syntax_theme \"{}\"", settings.syntax_theme),
+            false,
+        );
+    }
+
+    // the built in editors are only there to be picked, they don't all
+    // have to be installed
+    let defaults = default_editors();
+    for (name, template) in &settings.editors {
+        if defaults.get(name) != Some(template) {
+            if let Some(program) = missing_program(template) {
+                error(
+                    "editors",
+                    format!("editor \"{}\": {} is not in $PATH", name, program),
+                    true,
+                );
+            }
+        }
+    }
+    for converter in converters_from_config(&settings.converters) {
+        if converter.from.is_empty() || converter.to.is_empty() {
+            error(
+                "converters",
+                format!(
+                    "converter \"{}\" needs \"from\" and \"to\" extensions",
+                    converter.name
+                ),
+                false,
+            );
+        }
+        for program in converter
+            .steps
+            .iter()
+            .map(String::as_str)
+            .filter_map(missing_program)
+        {
+            error(
+                "converters",
+                format!(
+                    "converter \"{}\": {} is not in $PATH",
+                    converter.name, program
+                ),
+                true,
+            );
+        }
+    }
+    if let Some(program) = settings
+        .preview
+        .command
+        .as_deref()
+        .and_then(missing_program)
+    {
+        error(
+            "preview",
+            format!("previewer {} is not in $PATH", program),
+            true,
+        );
+    }
+
+    editors.extend(settings.editors.into_keys());
+    editors
+}
+
+fn check_profiles(config: &Configuration, editors: &[String], diagnostics: &mut Vec<Diagnostic>) {
+    let mut paths: Vec<PathBuf> = fs::read_dir(config.profiles_path())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "toml")
+        })
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        let display = path.display().to_string();
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let profile: Profile = match toml::from_str(&content) {
+            Ok(profile) => profile,
+            Err(e) => {
+                diagnostics.push(toml_error(&display, &content, &e));
+                continue;
+            }
+        };
+        let position = |key: &str| {
+            content
+                .lines()
+                .position(|line| line.trim_start().starts_with(key))
+                .map(|line| (line + 1, 1))
+        };
+        if let Some(editor) = profile.editor.filter(|editor| !editors.contains(editor)) {
+            diagnostics.push(diagnostic(
+                &display,
+                position("editor"),
+                format!(
+                    "unknown editor \"{}\", expected one of {}",
+                    editor,
+                    editors.join(", ")
+                ),
+                false,
+            ));
+        }
+        if let Some(start_path) = profile.start_path {
+            if !Path::new(&expand_home(&start_path)).is_dir() {
+                diagnostics.push(diagnostic(
+                    &display,
+                    position("start_path"),
+                    format!("start_path {} is not a directory", start_path),
+                    false,
+                ));
+            }
+        }
+        if let Err(e) = KeyMap::new(&profile.keybindings) {
+            diagnostics.push(diagnostic(
+                &display,
+                position("[keybindings]"),
+                format!("{}", e),
+                false,
+            ));
+        }
+    }
+}
+
+fn check_theme(path: &Path, diagnostics: &mut Vec<Diagnostic>) {
+    let display = path.display().to_string();
+    match fs::read_to_string(path) {
+        Ok(content) => {
+            if let Err(e) = theme::validate(&content) {
+                diagnostics.push(toml_error(&display, &content, &e));
+            }
+        }
+        Err(e) => diagnostics.push(diagnostic(
+            &display,
+            None,
+            format!("unable to read: {}", e),
+            false,
+        )),
+    }
+}
+
+fn diagnostic(
+    path: &str,
+    position: Option<(usize, usize)>,
+    message: String,
+    warning: bool,
+) -> Diagnostic {
+    Diagnostic {
+        path: path.to_string(),
+        position,
+        message,
+        warning,
+    }
+}
+
+fn json_error(path: &str, error: &serde_json::Error) -> Diagnostic {
+    // serde_json puts the position at the end of the message too
+    let message = error.to_string();
+    let message = match message.rfind(" at line ") {
+        Some(index) => message[..index].to_string(),
+        None => message,
+    };
+    diagnostic(path, Some((error.line(), error.column())), message, false)
+}
+
+fn toml_error(path: &str, content: &str, error: &toml::de::Error) -> Diagnostic {
+    let position = error
+        .span()
+        .map(|span| offset_position(content, span.start));
+    diagnostic(path, position, error.message().to_string(), false)
+}
+
+// where `"key":` is in the json, settings.json is often a single line
+fn key_position(content: &str, key: &str) -> Option<(usize, usize)> {
+    let quoted = format!("\"{}\"", key);
+    content
+        .match_indices(&quoted)
+        .find(|(index, _)| {
+            content[index + quoted.len()..]
+                .trim_start()
+                .starts_with(':')
+        })
+        .map(|(index, _)| offset_position(content, index))
+}
+
+fn offset_position(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |index| index + 1) + 1;
+    (line, column)
+}
+
+// the program of a command template when it can't be found
+fn missing_program(template: &str) -> Option<String> {
+    let program = split_template(template).into_iter().next()?;
+    let found = if program.contains(std::path::MAIN_SEPARATOR) {
+        Path::new(&program).is_file()
+    } else {
+        env::var_os("PATH")
+            .is_some_and(|paths| env::split_paths(&paths).any(|dir| dir.join(&program).is_file()))
+    };
+    (!found).then_some(program)
+}
@@ -1,12 +1,23 @@
 use std::{
+    collections::{BTreeMap, HashMap},
     fs::{self, File},
     io::{BufReader, BufWriter},
-    path::Path,
+    path::{Path, PathBuf},
 };
 
+use anyhow::Context;
 use dirs::home_dir;
 use serde::{Deserialize, Serialize};
 
+use crate::converters::ConverterSpec;
+use crate::custom_commands::CommandSpec;
+use crate::directory_store::CacheHeader;
+use crate::editor::default_editors;
+use crate::ignore_rules::IgnorePatterns;
+use crate::keymap::KeySpec;
+use crate::theme::{DEFAULT_LIGHT_THEME, DEFAULT_THEME};
+use crate::utils::files::SortOptions;
+
 #[derive(Serialize, Deserialize, Default, Clone)]
 
 pub struct Configuration {
@@ -15,6 +26,170 @@ pub struct Configuration {
     pub root_dir: String,
     pub cache_directory: String,
     pub settings_path: String,
+    #[serde(default)]
+    pub keybindings: HashMap<String, KeySpec>,
+    // auto, kitty, iterm2, sixel or halfblocks
+    #[serde(default = "default_image_protocol")]
+    pub image_protocol: String,
+    // skip paths matched by .gitignore/.ffignore in listings, cache and search
+    #[serde(default)]
+    pub respect_gitignore: bool,
+    // also put files in the directory cache so global search can find them
+    #[serde(default)]
+    pub index_files: bool,
+    // name -> command template, merged over the built-in editors
+    #[serde(default)]
+    pub editors: BTreeMap<String, String>,
+    // user commands run on the selection from the command popup
+    #[serde(default)]
+    pub commands: BTreeMap<String, CommandSpec>,
+    // name -> extensions it takes, the one it produces and the command (or
+    // list of commands) that converts a file
+    #[serde(default)]
+    pub converters: BTreeMap<String, ConverterSpec>,
+    // extension or mime type -> "editor", "open", "preview-only" or a command
+    // run on the file when pressing enter
+    #[serde(default)]
+    pub associations: BTreeMap<String, CommandSpec>,
+    // on_enter_directory, on_select, on_delete or before_open -> command,
+    // the event is passed as json on stdin
+    #[serde(default)]
+    pub hooks: HashMap<String, String>,
+    // "default" or "miller" for parent / current / preview columns
+    #[serde(default = "default_layout")]
+    pub layout: String,
+    // name comparison used when sorting by name
+    #[serde(default)]
+    pub sort: SortOptions,
+    // text previews read at most this many bytes, "v" loads the next chunk
+    #[serde(default = "default_preview_max_bytes")]
+    pub preview_max_bytes: u64,
+
+    #[serde(default)]
+    pub preview: PreviewOptions,
+
+    // how many levels of subdirectories are watched for changes besides the
+    // current directory
+    #[serde(default)]
+    pub watch_depth: usize,
+
+    // syntect theme used to highlight text previews
+    #[serde(default = "default_syntax_theme")]
+    pub syntax_theme: String,
+    // name of a file in the themes directory, or the built in onedark
+    #[serde(default = "default_theme")]
+    pub theme: String,
+    // used instead of `theme` on a light background
+    #[serde(default = "default_light_theme")]
+    pub light_theme: String,
+    // "dark", "light" or "auto" to pick by the background of the terminal
+    #[serde(default = "default_color_mode")]
+    pub color_mode: String,
+    // "auto" to check $COLORTERM and $TERM, "truecolor" or "256"
+    #[serde(default = "default_color_depth")]
+    pub color_depth: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PreviewOptions {
+    // e.g. "bat --color=always {file}", its output replaces the text preview
+    #[serde(default)]
+    pub command: Option<String>,
+}
+
+fn default_image_protocol() -> String {
+    String::from("auto")
+}
+
+fn default_layout() -> String {
+    String::from("default")
+}
+
+fn default_theme() -> String {
+    String::from(DEFAULT_THEME)
+}
+
+fn default_light_theme() -> String {
+    String::from(DEFAULT_LIGHT_THEME)
+}
+
+fn default_color_mode() -> String {
+    String::from("dark")
+}
+
+fn default_color_depth() -> String {
+    String::from("auto")
+}
+
+fn default_syntax_theme() -> String {
+    String::from("base16-ocean.dark")
+}
+
+fn default_preview_max_bytes() -> u64 {
+    256 * 1024
+}
+
+// $XDG_CONFIG_HOME/ff, %APPDATA%\ff on windows, ~/.config/ff otherwise
+pub fn config_dir() -> PathBuf {
+    base_dir("XDG_CONFIG_HOME", dirs::config_dir(), ".config").join("ff")
+}
+
+// $XDG_CACHE_HOME/ff, %LOCALAPPDATA%\ff on windows, ~/.cache/ff otherwise
+pub fn cache_dir() -> PathBuf {
+    base_dir("XDG_CACHE_HOME", dirs::cache_dir(), ".cache").join("ff")
+}
+
+// macos has its own folders too, but ~/.config is where people look for the
+// settings of a terminal tool
+fn base_dir(variable: &str, platform_dir: Option<PathBuf>, fallback: &str) -> PathBuf {
+    if let Some(dir) = std::env::var_os(variable).filter(|dir| !dir.is_empty()) {
+        return PathBuf::from(dir);
+    }
+    match platform_dir {
+        Some(dir) if cfg!(windows) => dir,
+        _ => home_dir().unwrap_or_default().join(fallback),
+    }
+}
+
+// where everything was kept before config_dir / cache_dir
+fn legacy_config_dir() -> PathBuf {
+    home_dir().unwrap_or_default().join(".config").join("ff")
+}
+
+// moves ~/.config/ff to the config directory when that is somewhere else,
+// and the directory cache from it to the cache directory
+fn migrate_legacy_files() {
+    let legacy = legacy_config_dir();
+    let config = config_dir();
+    if legacy != config && legacy.is_dir() && !config.exists() {
+        if let Some(parent) = config.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        if let Err(error) = fs::rename(&legacy, &config) {
+            eprintln!(
+                "unable to move {} to {}: {}",
+                legacy.display(),
+                config.display(),
+                error
+            );
+        }
+    }
+
+    let cache = cache_dir();
+    for entry in fs::read_dir(&config).into_iter().flatten().flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !name.starts_with("cache_directory") {
+            continue;
+        }
+        let target = cache.join(&name);
+        if target.exists() || fs::create_dir_all(&cache).is_err() {
+            continue;
+        }
+        // the cache directory may be on another file system
+        if fs::rename(entry.path(), &target).is_err() && fs::copy(entry.path(), &target).is_ok() {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
 }
 
 impl Configuration {
@@ -25,14 +200,35 @@ impl Configuration {
             root_dir: String::from("."),
             cache_directory: String::from(""),
             settings_path: String::from(""),
+            keybindings: HashMap::new(),
+            image_protocol: default_image_protocol(),
+            respect_gitignore: false,
+            index_files: false,
+            editors: default_editors(),
+            commands: BTreeMap::new(),
+            converters: BTreeMap::new(),
+            associations: BTreeMap::new(),
+            hooks: HashMap::new(),
+            layout: default_layout(),
+            sort: SortOptions::default(),
+            preview_max_bytes: default_preview_max_bytes(),
+            preview: PreviewOptions::default(),
+            watch_depth: 0,
+            syntax_theme: default_syntax_theme(),
+            theme: default_theme(),
+            light_theme: default_light_theme(),
+            color_mode: default_color_mode(),
+            color_depth: default_color_depth(),
         };
 
         config.set_default_ignore_directories();
         let home_dir = home_dir().unwrap();
-        let append_config_to_cache =
-            format!("{}/.config/ff/cache_directory.json", home_dir.display());
-        let append_config_to_settings = format!("{}/.config/ff/settings.json", home_dir.display());
-        let append_to_start_path = format!("{}/Desktop", home_dir.display());
+        let append_config_to_cache = cache_dir()
+            .join("cache_directory.bin")
+            .display()
+            .to_string();
+        let append_config_to_settings = config_dir().join("settings.json").display().to_string();
+        let append_to_start_path = home_dir.join("Desktop").display().to_string();
         //let append_to_start_path = format!("{}/Desktop", home_dir.display());
 
         config.start_path = append_to_start_path;
@@ -42,6 +238,47 @@ impl Configuration {
         config
     }
 
+    // everything the directory cache for these settings depends on
+    pub fn cache_header(&self) -> CacheHeader {
+        CacheHeader::new(
+            &self.start_path,
+            self.ignore_directories.clone(),
+            self.respect_gitignore,
+            self.index_files,
+        )
+    }
+
+    // patterns are relative to the start path, like the directory cache
+    pub fn ignore_patterns(&self) -> IgnorePatterns {
+        IgnorePatterns::new(Path::new(&self.start_path), &self.ignore_directories)
+    }
+
+    pub fn frecency_path(&self) -> String {
+        config_file("frecency.json")
+    }
+
+    pub fn history_path(&self) -> String {
+        config_file("history")
+    }
+
+    pub fn view_state_path(&self) -> String {
+        config_file("view_state.json")
+    }
+
+    // files named after an extension, e.g. `rs`, are the initial content of
+    // new files with that extension
+    pub fn templates_path(&self) -> String {
+        config_file("templates")
+    }
+
+    pub fn profiles_path(&self) -> String {
+        config_file("profiles")
+    }
+
+    pub fn script_path(&self) -> String {
+        config_file("init.rhai")
+    }
+
     // TODO: should we cache all directories when first loading the app? or is there a better way to do this?
     fn set_default_ignore_directories(&mut self) {
         let default_ignore_dirs = vec![
@@ -65,8 +302,9 @@ impl Configuration {
         self.ignore_directories = default_ignore_dirs.iter().map(|s| s.to_string()).collect();
     }
     pub fn handle_settings_configuration(&mut self) {
-        let append_config_dir = format!("{}/.config/ff", self.root_dir);
-        let find_dir = Path::new(&append_config_dir).try_exists();
+        migrate_legacy_files();
+        let append_config_dir = config_dir();
+        let find_dir = append_config_dir.try_exists();
 
         let get_result = match find_dir {
             Ok(res) => {
@@ -77,7 +315,7 @@ impl Configuration {
                 }
             }
             Err(error) => {
-                println!("error:  {:?}", error);
+                eprintln!("error:  {:?}", error);
                 false
             }
         };
@@ -89,11 +327,36 @@ impl Configuration {
                     self.start_path = get_config.start_path;
                     self.ignore_directories = get_config.ignore_directories;
                     self.root_dir = get_config.root_dir;
-                    self.cache_directory = get_config.cache_directory;
-                    self.settings_path = get_config.settings_path;
+                    // older settings point at ~/.config/ff, the files are
+                    // where config_dir / cache_dir say now
+                    if !get_config
+                        .cache_directory
+                        .starts_with(legacy_config_dir().display().to_string().as_str())
+                    {
+                        self.cache_directory = get_config.cache_directory;
+                    }
+                    self.keybindings = get_config.keybindings;
+                    self.image_protocol = get_config.image_protocol;
+                    self.respect_gitignore = get_config.respect_gitignore;
+                    self.index_files = get_config.index_files;
+                    self.editors.extend(get_config.editors);
+                    self.commands = get_config.commands;
+                    self.converters = get_config.converters;
+                    self.associations = get_config.associations;
+                    self.hooks = get_config.hooks;
+                    self.layout = get_config.layout;
+                    self.sort = get_config.sort;
+                    self.preview_max_bytes = get_config.preview_max_bytes;
+                    self.preview = get_config.preview;
+                    self.watch_depth = get_config.watch_depth;
+                    self.syntax_theme = get_config.syntax_theme;
+                    self.theme = get_config.theme;
+                    self.light_theme = get_config.light_theme;
+                    self.color_mode = get_config.color_mode;
+                    self.color_depth = get_config.color_depth;
                 }
                 Err(err) => {
-                    println!("error {:?}", err);
+                    eprintln!("error {:?}", err);
                 }
             }
         } else {
@@ -103,10 +366,10 @@ impl Configuration {
     }
 
     fn create_files(&self) {
-        let config_root_dir = format!("{}/.config/ff", self.root_dir);
+        let config_root_dir = config_dir();
         match fs::create_dir_all(config_root_dir) {
             Ok(_) => {}
-            Err(error) => println!("error {:?}", error),
+            Err(error) => eprintln!("error {:?}", error),
         };
 
         // only create file if it does not exist
@@ -114,25 +377,57 @@ impl Configuration {
             match self.write_settings_to_file() {
                 Ok(_) => {}
                 Err(error) => {
-                    println!("error {:?}", error);
+                    eprintln!("error {:?}", error);
                 }
             }
         }
     }
 
     pub fn write_settings_to_file(&self) -> anyhow::Result<()> {
-        match File::create(self.settings_path.to_owned()) {
-            Ok(file) => {
-                let writer = BufWriter::new(file);
+        let file = File::create(self.settings_path.to_owned())?;
+        let writer = BufWriter::new(file);
+        serde_json::to_writer(writer, self)?;
 
-                serde_json::to_writer(writer, self).unwrap();
-            }
-            Err(error) => {
-                println!("error {:?}", error);
-            }
+        Ok(())
+    }
+
+    // changes settings.json as it is on disk, so the overrides of a profile
+    // don't end up in it
+    pub fn theme_name(&self, light: bool) -> &str {
+        match light {
+            true => &self.light_theme,
+            false => &self.theme,
         }
+    }
 
-        Ok(())
+    pub fn update_settings_file(
+        &self,
+        update: impl FnOnce(&mut Configuration),
+    ) -> anyhow::Result<()> {
+        let mut settings = self
+            .load_settings_from_file(&self.settings_path)
+            .unwrap_or_else(|_| self.clone());
+        update(&mut settings);
+        settings.settings_path = self.settings_path.clone();
+        settings.write_settings_to_file()
+    }
+
+    // settings.json as it is on disk now, only with the settings that can
+    // change while running, the others need a restart
+    pub fn reloaded(&self) -> anyhow::Result<Configuration> {
+        let settings = self
+            .load_settings_from_file(&self.settings_path)
+            .with_context(|| format!("invalid {}", self.settings_path))?;
+        let mut config = self.clone();
+        config.keybindings = settings.keybindings;
+        config.preview_max_bytes = settings.preview_max_bytes;
+        config.preview = settings.preview;
+        config.syntax_theme = settings.syntax_theme;
+        config.theme = settings.theme;
+        config.light_theme = settings.light_theme;
+        config.color_mode = settings.color_mode;
+        config.color_depth = settings.color_depth;
+        Ok(config)
     }
 
     pub fn load_settings_from_file(&self, path: &str) -> anyhow::Result<Configuration> {
@@ -143,3 +438,7 @@ impl Configuration {
         Ok(settings)
     }
 }
+
+fn config_file(name: &str) -> String {
+    config_dir().join(name).display().to_string()
+}
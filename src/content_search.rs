@@ -0,0 +1,230 @@
+use std::{
+    fs::{self, File},
+    io::{BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc::Sender,
+        Arc,
+    },
+};
+
+use rayon::prelude::*;
+use regex::{Regex, RegexBuilder};
+use walkdir::WalkDir;
+
+use crate::ignore_rules::{ignore_walker, IgnorePatterns};
+
+const MAX_RESULTS: usize = 1000;
+const MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
+const MAX_LINE_LENGTH: usize = 500;
+
+#[derive(Debug, Clone)]
+pub struct ContentMatch {
+    pub path: String,
+    pub line_number: usize,
+    pub line: String,
+    // 1-based char column of the match in the untruncated line
+    pub column: usize,
+    // byte range of the match inside `line`
+    pub match_start: usize,
+    pub match_end: usize,
+}
+
+#[derive(Debug, Clone)]
+pub enum ContentSearchMessage {
+    Matches(Vec<ContentMatch>),
+    Done(usize),
+}
+
+// smart case: the query is case sensitive only if it contains an uppercase
+// char. it is matched literally, case folding is left to the regex engine
+// so the match range always lands on the original line
+struct Matcher {
+    regex: Regex,
+}
+
+impl Matcher {
+    // None for a query too long to compile
+    fn new(query: &str) -> Option<Matcher> {
+        let case_sensitive = query.chars().any(|c| c.is_uppercase());
+        let regex = RegexBuilder::new(&regex::escape(query))
+            .case_insensitive(!case_sensitive)
+            .build()
+            .ok()?;
+        Some(Matcher { regex })
+    }
+
+    fn find(&self, line: &str) -> Option<(usize, usize)> {
+        self.regex
+            .find(line)
+            .map(|found| (found.start(), found.end()))
+    }
+}
+
+fn looks_binary(path: &Path) -> bool {
+    let mut buffer = [0u8; 8000];
+    match File::open(path).and_then(|mut file| file.read(&mut buffer)) {
+        Ok(read) => buffer[..read].contains(&0),
+        Err(_) => true,
+    }
+}
+
+fn search_file(path: &Path, matcher: &Matcher) -> Vec<ContentMatch> {
+    let mut matches = Vec::new();
+
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(_) => return matches,
+    };
+
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = match line {
+            Ok(line) => line,
+            // invalid utf-8 or read error, treat the rest of the file as unsearchable
+            Err(_) => break,
+        };
+
+        if let Some((start, end)) = matcher.find(&line) {
+            let column = line[..start].chars().count() + 1;
+            let (line, start, end) = truncate_line(line, start, end);
+            matches.push(ContentMatch {
+                path: path.to_string_lossy().to_string(),
+                line_number: index + 1,
+                line,
+                column,
+                match_start: start,
+                match_end: end,
+            });
+        }
+    }
+    matches
+}
+
+// keeps minified files from producing giant list rows while making sure the
+// match stays visible
+fn truncate_line(line: String, start: usize, end: usize) -> (String, usize, usize) {
+    if line.len() <= MAX_LINE_LENGTH {
+        return (line, start, end);
+    }
+
+    let mut from = start.saturating_sub(MAX_LINE_LENGTH / 4);
+    while !line.is_char_boundary(from) {
+        from -= 1;
+    }
+    let mut to = (from + MAX_LINE_LENGTH).min(line.len()).max(end);
+    while !line.is_char_boundary(to) {
+        to += 1;
+    }
+
+    (line[from..to].to_string(), start - from, end - from)
+}
+
+pub fn search_contents(
+    root: &str,
+    query: &str,
+    ignore: &IgnorePatterns,
+    show_hidden: bool,
+    respect_gitignore: bool,
+    cancel: Arc<AtomicBool>,
+    sender: Sender<ContentSearchMessage>,
+) {
+    let Some(matcher) = Matcher::new(query) else {
+        let _ = sender.send(ContentSearchMessage::Done(0));
+        return;
+    };
+    let found = AtomicUsize::new(0);
+
+    let candidates: Vec<PathBuf> = if respect_gitignore {
+        let ignore = ignore.clone();
+        ignore_walker(Path::new(root))
+            .hidden(!show_hidden)
+            .filter_entry(move |entry| {
+                let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+                entry.depth() == 0 || !ignore.is_ignored(entry.path(), is_dir)
+            })
+            .build()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().map(|t| t.is_file()).unwrap_or(false))
+            .map(|entry| entry.into_path())
+            .collect()
+    } else {
+        WalkDir::new(root)
+            .into_iter()
+            .filter_entry(|entry| {
+                let hidden =
+                    entry.depth() > 0 && entry.file_name().to_string_lossy().starts_with('.');
+                let ignored = entry.depth() > 0
+                    && ignore.is_ignored(entry.path(), entry.file_type().is_dir());
+                !ignored && (show_hidden || !hidden)
+            })
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_file())
+            .map(|entry| entry.into_path())
+            .collect()
+    };
+
+    let files: Vec<PathBuf> = candidates
+        .into_iter()
+        .filter(|path| {
+            fs::metadata(path)
+                .map(|meta| meta.len() <= MAX_FILE_SIZE)
+                .unwrap_or(false)
+        })
+        .collect();
+
+    files
+        .par_iter()
+        .for_each_with(sender.clone(), |sender, path| {
+            if cancel.load(Ordering::Relaxed) || found.load(Ordering::Relaxed) >= MAX_RESULTS {
+                return;
+            }
+            if looks_binary(path) {
+                return;
+            }
+
+            let matches = search_file(path, &matcher);
+            if !matches.is_empty() {
+                found.fetch_add(matches.len(), Ordering::Relaxed);
+                let _ = sender.send(ContentSearchMessage::Matches(matches));
+            }
+        });
+
+    let _ = sender.send(ContentSearchMessage::Done(
+        found.load(Ordering::Relaxed).min(MAX_RESULTS),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find<'a>(query: &str, line: &'a str) -> Option<&'a str> {
+        let (start, end) = Matcher::new(query).unwrap().find(line)?;
+        Some(&line[start..end])
+    }
+
+    #[test]
+    fn lowercase_queries_ignore_case() {
+        assert_eq!(find("todo", "// TODO: later"), Some("TODO"));
+        assert_eq!(find("Todo", "// TODO: later"), None);
+        assert_eq!(find("Todo", "// Todo: later"), Some("Todo"));
+    }
+
+    #[test]
+    fn matches_land_on_the_original_line() {
+        // lowercasing these changes their length in bytes
+        assert_eq!(find("ⱥb", "xȺB"), Some("ȺB"));
+        // and this one turns into two chars
+        assert_eq!(find("x", "İx"), Some("x"));
+        assert_eq!(find("x", "ẞẞẞ x"), Some("x"));
+        assert_eq!(find("straße", "STRAẞE"), Some("STRAẞE"));
+    }
+
+    #[test]
+    fn queries_are_literal() {
+        assert_eq!(find("a.b", "axb a.b"), Some("a.b"));
+        assert_eq!(find("(x)", "f(x)"), Some("(x)"));
+        assert_eq!(find("a+", "aaa"), None);
+    }
+}
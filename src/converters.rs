@@ -0,0 +1,332 @@
+use std::{
+    collections::BTreeMap,
+    env, fs, io,
+    path::{Path, PathBuf},
+    process::{self, Command, Stdio},
+    sync::mpsc::Sender,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::editor::split_template;
+use crate::jobs::{JobControl, JobMessage};
+
+// a single value or a list, "from": "md" or "from": ["heic", "heif"]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+// "heic-to-jpg": { "from": ["heic", "heif"], "to": "jpg", "command": "magick {input} {output}" },
+// a list of commands is a pipeline, each command reads what the one before
+// it wrote
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConverterSpec {
+    pub from: OneOrMany,
+    pub to: String,
+    pub command: OneOrMany,
+}
+
+#[derive(Debug, Clone)]
+pub struct Converter {
+    pub name: String,
+    // lowercase, without the dot
+    pub from: Vec<String>,
+    pub to: String,
+    pub steps: Vec<String>,
+}
+
+fn values(spec: &OneOrMany) -> Vec<String> {
+    match spec {
+        OneOrMany::One(value) => vec![value.clone()],
+        OneOrMany::Many(values) => values.clone(),
+    }
+}
+
+pub fn converters_from_config(converters: &BTreeMap<String, ConverterSpec>) -> Vec<Converter> {
+    converters
+        .iter()
+        .map(|(name, spec)| Converter {
+            name: name.clone(),
+            from: values(&spec.from)
+                .iter()
+                .map(|ext| ext.trim_start_matches('.').to_lowercase())
+                .collect(),
+            to: spec.to.trim_start_matches('.').to_string(),
+            steps: values(&spec.command),
+        })
+        .collect()
+}
+
+// a new private directory for the files between the steps of a pipeline
+fn scratch_dir() -> io::Result<PathBuf> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.subsec_nanos())
+        .unwrap_or(0);
+    for attempt in 0..100 {
+        let dir = env::temp_dir().join(format!(
+            "ff-convert-{}-{}-{}",
+            process::id(),
+            nanos,
+            attempt
+        ));
+        match fs::create_dir(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "no free name for the conversion directory",
+    ))
+}
+
+// what converting one file did, the output path or why it failed
+#[derive(Debug, Clone)]
+pub struct ConversionResult {
+    pub input: PathBuf,
+    pub output: Result<PathBuf, String>,
+}
+
+impl Converter {
+    pub fn label(&self) -> String {
+        format!("{} ({} -> {})", self.name, self.from.join(", "), self.to)
+    }
+
+    pub fn accepts(&self, path: &Path) -> bool {
+        path.is_file()
+            && path
+                .extension()
+                .is_some_and(|ext| self.from.contains(&ext.to_string_lossy().to_lowercase()))
+    }
+
+    // next to the input, an existing file is never overwritten
+    fn output_path(&self, input: &Path) -> PathBuf {
+        let stem = input
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut output = input.with_extension(&self.to);
+        let mut counter = 1;
+        while output.exists() {
+            output = input.with_file_name(format!("{} ({}).{}", stem, counter, self.to));
+            counter += 1;
+        }
+        output
+    }
+
+    // `{input}` is the file the step reads and `{output}` the one it writes,
+    // `{name}` the name of the converted file without its extension and
+    // `{dir}` its directory
+    fn command_line(
+        &self,
+        step: &str,
+        file: &Path,
+        input: &Path,
+        output: &Path,
+    ) -> Option<(String, Vec<String>)> {
+        let dir = file.parent().unwrap_or(Path::new("."));
+        let name = file
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut args: Vec<String> = split_template(step)
+            .iter()
+            .map(|arg| {
+                arg.replace("{input}", &input.to_string_lossy())
+                    .replace("{output}", &output.to_string_lossy())
+                    .replace("{name}", &name)
+                    .replace("{dir}", &dir.to_string_lossy())
+            })
+            .collect();
+
+        if args.is_empty() {
+            return None;
+        }
+        let program = args.remove(0);
+        Some((program, args))
+    }
+
+    fn convert(&self, input: &Path) -> Result<PathBuf, String> {
+        let output = self.output_path(input);
+        let scratch = match self.steps.len() {
+            0 | 1 => None,
+            _ => Some(scratch_dir().map_err(|e| format!("temporary directory: {}", e))?),
+        };
+        let converted = self.run_steps(input, &output, scratch.as_deref());
+        if let Some(scratch) = &scratch {
+            let _ = fs::remove_dir_all(scratch);
+        }
+        if converted.is_err() {
+            // don't leave half written output behind
+            let _ = fs::remove_file(&output);
+        }
+        converted
+    }
+
+    // every step but the last writes a temporary file in `scratch` that the
+    // next one gets as `{input}`, the last one writes `output`
+    fn run_steps(
+        &self,
+        file: &Path,
+        output: &Path,
+        scratch: Option<&Path>,
+    ) -> Result<PathBuf, String> {
+        if self.steps.is_empty() {
+            return Err(String::from("empty command"));
+        }
+        let mut input = file.to_path_buf();
+        for (index, step) in self.steps.iter().enumerate() {
+            let step_output = match scratch {
+                Some(scratch) if index + 1 < self.steps.len() => {
+                    scratch.join(format!("step-{}.{}", index + 1, self.to))
+                }
+                _ => output.to_path_buf(),
+            };
+            let (program, args) = self
+                .command_line(step, file, &input, &step_output)
+                .ok_or_else(|| String::from("empty command"))?;
+            let result = Command::new(&program)
+                .args(args)
+                .current_dir(file.parent().unwrap_or(Path::new(".")))
+                .stdin(Stdio::null())
+                .output();
+
+            match result {
+                Ok(result) if result.status.success() => {}
+                Ok(result) => {
+                    let stderr = String::from_utf8_lossy(&result.stderr);
+                    return Err(match stderr.lines().last().map(str::trim) {
+                        Some(line) if !line.is_empty() => format!("{}: {}", program, line),
+                        _ => format!("{} exited with {}", program, result.status),
+                    });
+                }
+                Err(e) => return Err(format!("{}: {}", program, e)),
+            }
+            if !step_output.exists() {
+                return Err(format!(
+                    "{} did not create {}",
+                    program,
+                    step_output.display()
+                ));
+            }
+            input = step_output;
+        }
+        Ok(input)
+    }
+
+    // converts `files` one after another, the results go out through
+    // `results` even when the job gets cancelled
+    pub fn run(
+        &self,
+        files: &[PathBuf],
+        control: &JobControl,
+        sender: &Sender<JobMessage>,
+        results: &Sender<Vec<ConversionResult>>,
+    ) -> anyhow::Result<String> {
+        let mut converted = Vec::new();
+        for (index, input) in files.iter().enumerate() {
+            if !control.wait_if_paused() {
+                let _ = results.send(converted);
+                return Err(anyhow::anyhow!("cancelled"));
+            }
+            let _ = sender.send(JobMessage::Progress(format!(
+                "Converting {}/{}: {}",
+                index + 1,
+                files.len(),
+                input.display()
+            )));
+            converted.push(ConversionResult {
+                input: input.clone(),
+                output: self.convert(input),
+            });
+        }
+
+        let failed = converted
+            .iter()
+            .filter(|result| result.output.is_err())
+            .count();
+        let message = match failed {
+            0 => format!("{} converted {} file(s)", self.name, converted.len()),
+            _ => format!(
+                "{} converted {} file(s), {} failed",
+                self.name,
+                converted.len() - failed,
+                failed
+            ),
+        };
+        let _ = results.send(converted);
+        Ok(message)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn converter(steps: &[&str]) -> Converter {
+        Converter {
+            name: String::from("test"),
+            from: vec![String::from("txt")],
+            to: String::from("out"),
+            steps: steps.iter().map(|step| step.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn single_command_writes_the_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("note.txt");
+        fs::write(&input, "hello\n").unwrap();
+
+        let output = converter(&["cp {input} {output}"]).convert(&input).unwrap();
+        assert_eq!(output, dir.path().join("note.out"));
+        assert_eq!(fs::read_to_string(output).unwrap(), "hello\n");
+    }
+
+    #[test]
+    fn steps_read_the_output_of_the_step_before() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("note.txt");
+        fs::write(&input, "hello\n").unwrap();
+
+        let output = converter(&[
+            r#"sh -c 'tr a-z A-Z < "$0" > "$1"' {input} {output}"#,
+            r#"sh -c 'sed s/HELLO/bye/ "$0" > "$1"' {input} {output}"#,
+            r#"sh -c 'echo "$2" | cat "$0" - > "$1"' {input} {output} {name}"#,
+        ])
+        .convert(&input)
+        .unwrap();
+        assert_eq!(output, dir.path().join("note.out"));
+        assert_eq!(fs::read_to_string(output).unwrap(), "bye\nnote\n");
+        // only the input and the final output are left
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn failed_steps_leave_no_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("note.txt");
+        fs::write(&input, "hello\n").unwrap();
+
+        let error = converter(&[
+            "cp {input} {output}",
+            "sh -c 'cp $0 $1; exit 3' {input} {output}",
+        ])
+        .convert(&input)
+        .unwrap_err();
+        assert!(error.starts_with("sh exited with"), "{}", error);
+        assert!(!dir.path().join("note.out").exists());
+
+        let error = converter(&["true {input} {output}"])
+            .convert(&input)
+            .unwrap_err();
+        assert!(error.starts_with("true did not create"), "{}", error);
+        assert!(converter(&[]).convert(&input).is_err());
+    }
+}
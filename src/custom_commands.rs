@@ -0,0 +1,146 @@
+use std::{
+    collections::BTreeMap,
+    env,
+    process::{Command, Stdio},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::editor::split_template;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RunMode {
+    // in the background as a job, only the exit status is shown
+    Silent,
+    // output piped into $PAGER
+    Pager,
+    // on the normal screen with the terminal handed over
+    #[default]
+    Interactive,
+}
+
+impl RunMode {
+    pub fn label(&self) -> &'static str {
+        match self {
+            RunMode::Silent => "silent",
+            RunMode::Pager => "pager",
+            RunMode::Interactive => "interactive",
+        }
+    }
+}
+
+// "gzip": "gzip {file}" or "preview": { "command": "bat {file}", "mode": "pager" }
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum CommandSpec {
+    Template(String),
+    Full {
+        command: String,
+        #[serde(default)]
+        mode: RunMode,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct CustomCommand {
+    pub name: String,
+    pub template: String,
+    pub mode: RunMode,
+}
+
+pub fn commands_from_config(commands: &BTreeMap<String, CommandSpec>) -> Vec<CustomCommand> {
+    commands
+        .iter()
+        .map(|(name, spec)| {
+            let (template, mode) = match spec {
+                CommandSpec::Template(template) => (template.clone(), RunMode::default()),
+                CommandSpec::Full { command, mode } => (command.clone(), *mode),
+            };
+            CustomCommand {
+                name: name.clone(),
+                template,
+                mode,
+            }
+        })
+        .collect()
+}
+
+impl CustomCommand {
+    // `{file}` is the first target, `{dir}` the current directory and a
+    // `{files}` argument expands to one argument per target
+    pub fn command_line(&self, files: &[String], dir: &str) -> Option<(String, Vec<String>)> {
+        let file = files.first().map(|file| file.as_str()).unwrap_or("");
+        let mut args = Vec::new();
+        for arg in split_template(&self.template) {
+            if arg == "{files}" {
+                args.extend(files.iter().cloned());
+            } else {
+                args.push(
+                    arg.replace("{files}", &files.join(" "))
+                        .replace("{file}", file)
+                        .replace("{dir}", dir),
+                );
+            }
+        }
+
+        if args.is_empty() {
+            return None;
+        }
+        let program = args.remove(0);
+        Some((program, args))
+    }
+
+    pub fn run_silent(&self, files: &[String], dir: &str) -> anyhow::Result<String> {
+        let (program, args) = self
+            .command_line(files, dir)
+            .ok_or_else(|| anyhow::anyhow!("{} has an empty command", self.name))?;
+        let output = Command::new(program)
+            .args(args)
+            .current_dir(dir)
+            .stdin(Stdio::null())
+            .output()?;
+
+        if output.status.success() {
+            Ok(format!("{} finished", self.name))
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let reason = stderr.lines().last().unwrap_or("").trim().to_string();
+            Err(anyhow::anyhow!("{} failed: {}", self.name, reason))
+        }
+    }
+
+    // expects the terminal to be suspended already
+    pub fn run_attached(&self, files: &[String], dir: &str) -> anyhow::Result<String> {
+        let (program, args) = self
+            .command_line(files, dir)
+            .ok_or_else(|| anyhow::anyhow!("{} has an empty command", self.name))?;
+        let mut command = Command::new(program);
+        command.args(args).current_dir(dir);
+
+        let status = match self.mode {
+            RunMode::Pager => {
+                let mut child = command.stdout(Stdio::piped()).spawn()?;
+                let output = child
+                    .stdout
+                    .take()
+                    .ok_or_else(|| anyhow::anyhow!("no output from {}", self.name))?;
+                let pager = env::var("PAGER").unwrap_or_else(|_| String::from("less"));
+                let mut pager_parts = pager.split_whitespace();
+                let pager_cmd = pager_parts.next().unwrap_or("less");
+                Command::new(pager_cmd)
+                    .args(pager_parts)
+                    .stdin(output)
+                    .status()?;
+                child.wait()?
+            }
+            _ => command.status()?,
+        };
+
+        if status.success() {
+            Ok(format!("{} finished", self.name))
+        } else {
+            Err(anyhow::anyhow!("{} exited with {}", self.name, status))
+        }
+    }
+}
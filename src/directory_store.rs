@@ -1,17 +1,122 @@
+use ignore::{WalkBuilder, WalkState};
 use serde::{Deserialize, Serialize};
-use std::fs::File;
-use std::io::{self, BufReader, BufWriter};
-use walkdir::WalkDir;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use crate::frecency::FrecencyScores;
+use crate::ignore_rules::{ignore_walker, IgnorePatterns};
+use crate::search_query::SearchQuery;
+
+// bump when the cache layout changes, older caches are rebuilt or migrated
+pub const CACHE_VERSION: u32 = 4;
+
+// the binary cache starts with these bytes, anything else is treated as the
+// json cache written by older versions
+const CACHE_MAGIC: &[u8; 4] = b"FFC\0";
+
+// entries matched between two result batches of a streaming search
+const SEARCH_CHUNK: usize = 50_000;
+// entries found between two progress updates of a cache build
+const PROGRESS_INTERVAL: usize = 5_000;
+
+// everything the cached entries depend on, a cache whose header doesn't
+// match the current settings is thrown away
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CacheHeader {
+    pub version: u32,
+    pub root: String,
+    pub ignore_directories: Vec<String>,
+    pub respect_gitignore: bool,
+    pub index_files: bool,
+}
+
+impl CacheHeader {
+    pub fn new(
+        root: &str,
+        ignore_directories: Vec<String>,
+        respect_gitignore: bool,
+        index_files: bool,
+    ) -> CacheHeader {
+        CacheHeader {
+            version: CACHE_VERSION,
+            root: root.to_string(),
+            ignore_directories,
+            respect_gitignore,
+            index_files,
+        }
+    }
+}
+
+// layout of the json cache before the binary format
+#[derive(Deserialize)]
+struct LegacyStore {
+    #[serde(default)]
+    index_files: bool,
+    directories: Vec<String>,
+    #[serde(default)]
+    files: Vec<String>,
+}
 
 #[derive(Serialize, Deserialize, Default, Clone)]
 pub struct DirectoryStore {
     pub directories: Vec<String>,
+    #[serde(default)]
+    pub files: Vec<String>,
+    // trigram of the lowercased path -> ids of the entries containing it,
+    // directories first and files after them. rebuilt after loading instead
+    // of being written to the cache file
+    #[serde(skip)]
+    index: HashMap<u32, Vec<u32>>,
+}
+
+fn trigrams(value: &str) -> Vec<u32> {
+    let bytes = value.as_bytes();
+    let mut grams: Vec<u32> = bytes
+        .windows(3)
+        .map(|w| (w[0] as u32) << 16 | (w[1] as u32) << 8 | w[2] as u32)
+        .collect();
+    grams.sort_unstable();
+    grams.dedup();
+    grams
+}
+
+// higher is better: exact directory name, then name prefix, then a match in
+// the name. within each group places visited often and recently come first,
+// then shorter paths. the frecency score is kept to three decimals so it
+// can be part of the key
+fn rank(path: &str, query: &str, frecency: f64) -> (u8, u64, std::cmp::Reverse<usize>) {
+    let lower = path.to_lowercase();
+    let name = lower
+        .rsplit(std::path::is_separator)
+        .next()
+        .unwrap_or(&lower);
+    let score = if name == query {
+        3
+    } else if name.starts_with(query) {
+        2
+    } else if name.contains(query) {
+        1
+    } else {
+        0
+    };
+    (
+        score,
+        (frecency * 1000.0) as u64,
+        std::cmp::Reverse(path.len()),
+    )
 }
 
 impl DirectoryStore {
     pub fn new() -> Self {
         DirectoryStore {
             directories: Vec::new(),
+            files: Vec::new(),
+            index: HashMap::new(),
         }
     }
 
@@ -19,64 +124,564 @@ impl DirectoryStore {
         self.directories.push(path.to_string());
     }
 
-    pub fn search(&self, prefix: &str) -> Vec<String> {
-        let mut new_files: Vec<String> = Vec::new();
-        for file in self.directories.iter() {
-            if file.contains(&prefix) {
-                new_files.push(file.clone());
+    pub fn insert_file(&mut self, path: &str) {
+        self.files.push(path.to_string());
+    }
+
+    pub fn len(&self) -> usize {
+        self.directories.len() + self.files.len()
+    }
+
+    fn entry(&self, id: u32) -> &String {
+        let id = id as usize;
+        if id < self.directories.len() {
+            &self.directories[id]
+        } else {
+            &self.files[id - self.directories.len()]
+        }
+    }
+
+    pub fn build_index(&mut self) {
+        self.index.clear();
+        let paths = self.directories.iter().chain(self.files.iter());
+        for (id, path) in paths.enumerate() {
+            for gram in trigrams(&path.to_lowercase()) {
+                self.index.entry(gram).or_default().push(id as u32);
+            }
+        }
+    }
+
+    // entries matching every term of `query` ranked by its longest plain
+    // text term and `frecency`, at most `limit` results
+    pub fn search(
+        &self,
+        query: &SearchQuery,
+        frecency: &FrecencyScores,
+        limit: usize,
+    ) -> Vec<String> {
+        let mut results = Vec::new();
+        self.search_in_batches(query, frecency, limit, &AtomicBool::new(false), |batch| {
+            results = batch;
+            true
+        });
+        results
+    }
+
+    // like `search`, but `on_batch` gets the best results so far after every
+    // chunk of entries. returning false, or setting `cancel`, stops it
+    pub fn search_in_batches(
+        &self,
+        query: &SearchQuery,
+        frecency: &FrecencyScores,
+        limit: usize,
+        cancel: &AtomicBool,
+        mut on_batch: impl FnMut(Vec<String>) -> bool,
+    ) {
+        let text = query.required_text().unwrap_or_default();
+        let candidates = if query.is_empty() {
+            Vec::new()
+        } else {
+            self.candidates(&text)
+        };
+        if candidates.is_empty() {
+            on_batch(Vec::new());
+            return;
+        }
+
+        let mut best: Vec<&String> = Vec::new();
+        for chunk in candidates.chunks(SEARCH_CHUNK) {
+            if cancel.load(Ordering::Relaxed) {
+                return;
+            }
+            best.extend(
+                chunk
+                    .iter()
+                    .map(|id| self.entry(*id))
+                    .filter(|path| query.matches(path)),
+            );
+            best.sort_by_cached_key(|path| {
+                std::cmp::Reverse(rank(path, &text, frecency.get(path)))
+            });
+            best.truncate(limit);
+            if !on_batch(best.iter().map(|path| (*path).clone()).collect()) {
+                return;
             }
         }
-        new_files
+    }
+
+    // ids of the entries that can contain the lowercase `text`, every entry
+    // when it is too short for the trigram index
+    fn candidates(&self, text: &str) -> Vec<u32> {
+        if text.len() < 3 || self.index.is_empty() {
+            return (0..self.len() as u32).collect();
+        }
+        let mut postings: Vec<&Vec<u32>> = Vec::new();
+        for gram in trigrams(text) {
+            match self.index.get(&gram) {
+                Some(ids) => postings.push(ids),
+                None => return Vec::new(),
+            }
+        }
+        // intersect starting from the rarest trigram, ids are ascending
+        postings.sort_by_key(|ids| ids.len());
+        let mut ids = postings[0].clone();
+        for other in postings.iter().skip(1) {
+            ids.retain(|id| other.binary_search(id).is_ok());
+            if ids.is_empty() {
+                break;
+            }
+        }
+        ids
+    }
+
+    // the store without the entries `patterns` ignores, so a new pattern
+    // doesn't need a rebuild
+    pub fn pruned(&self, patterns: &IgnorePatterns) -> DirectoryStore {
+        let mut store = DirectoryStore::new();
+        store.directories = self
+            .directories
+            .iter()
+            .filter(|dir| !patterns.is_ignored_path(Path::new(dir), true))
+            .cloned()
+            .collect();
+        store.files = self
+            .files
+            .iter()
+            .filter(|file| !patterns.is_ignored_path(Path::new(file), false))
+            .cloned()
+            .collect();
+        store.build_index();
+        store
+    }
+
+    // directories starting with `prefix`, cached ones first and then the
+    // filesystem so paths outside the cache root still complete
+    pub fn complete(&self, prefix: &str, limit: usize) -> Vec<String> {
+        let mut candidates: Vec<String> = self
+            .directories
+            .iter()
+            .filter(|dir| dir.starts_with(prefix))
+            .take(limit)
+            .cloned()
+            .collect();
+
+        let (parent, partial) = match prefix.rfind(std::path::is_separator) {
+            Some(index) => (&prefix[..=index], &prefix[index + 1..]),
+            None => return candidates,
+        };
+        if let Ok(entries) = fs::read_dir(parent) {
+            for entry in entries.flatten() {
+                if candidates.len() >= limit {
+                    break;
+                }
+                let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+                let name = entry.file_name().to_string_lossy().to_string();
+                if is_dir && name.starts_with(partial) {
+                    let path = format!("{}{}", parent, name);
+                    if !candidates.contains(&path) {
+                        candidates.push(path);
+                    }
+                }
+            }
+        }
+
+        candidates.sort();
+        candidates
+    }
+}
+
+pub enum CacheMessage {
+    // entries found so far
+    Progress(usize),
+    // the new store and whether it could be written to the cache file
+    Done(DirectoryStore, io::Result<()>),
+}
+
+// entries a walker thread collects before handing them to the store
+const WALK_BATCH: usize = 1_000;
+
+// entries found by one walker thread, the rest is sent when the thread is
+// done with the walk and drops it
+struct WalkBatch {
+    // (is_dir, path)
+    entries: Vec<(bool, String)>,
+    sender: Sender<Vec<(bool, String)>>,
+}
+
+impl WalkBatch {
+    fn push(&mut self, is_dir: bool, path: String) {
+        self.entries.push((is_dir, path));
+        if self.entries.len() >= WALK_BATCH {
+            let _ = self.sender.send(std::mem::take(&mut self.entries));
+        }
+    }
+}
+
+impl Drop for WalkBatch {
+    fn drop(&mut self) {
+        if !self.entries.is_empty() {
+            let _ = self.sender.send(std::mem::take(&mut self.entries));
+        }
     }
 }
 
+// walks the tree on all cores, `on_progress` gets the number of entries
+// found every few thousand entries
 pub fn build_directory_from_store(
     root_dir: &str,
     ignore_directories: Vec<String>,
+    respect_gitignore: bool,
+    index_files: bool,
+    mut on_progress: impl FnMut(usize),
 ) -> DirectoryStore {
     let mut store = DirectoryStore::new();
+    let patterns = IgnorePatterns::new(Path::new(root_dir), &ignore_directories);
 
-    for entry in WalkDir::new(root_dir).min_depth(1) {
-        if let Ok(entry) = entry {
-            if entry.file_type().is_dir() {
-                let path = entry.path().to_string_lossy();
-                let mut should_ignore = false;
-
-                if ignore_directories.len() > 0 {
-                    for ignore in ignore_directories.iter() {
-                        let t = ignore.to_owned();
-                        let update_type = t.as_str();
-                        if path.contains(update_type) {
-                            should_ignore = true;
-                            break;
-                        }
+    let mut builder = if respect_gitignore {
+        ignore_walker(Path::new(root_dir))
+    } else {
+        let mut builder = WalkBuilder::new(root_dir);
+        builder.standard_filters(false);
+        builder
+    };
+    // ignored directories are skipped with everything below them
+    builder.filter_entry(move |entry| {
+        let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+        entry.depth() == 0 || !patterns.is_ignored(entry.path(), is_dir)
+    });
+    let walker = builder.build_parallel();
+
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        scope.spawn(move || {
+            walker.run(|| {
+                let mut batch = WalkBatch {
+                    entries: Vec::new(),
+                    sender: sender.clone(),
+                };
+                Box::new(move |entry| {
+                    let Ok(entry) = entry else {
+                        return WalkState::Continue;
+                    };
+                    if entry.depth() == 0 {
+                        return WalkState::Continue;
                     }
-                }
+                    let file_type = entry.file_type();
+                    let is_dir = file_type.map(|t| t.is_dir()).unwrap_or(false);
+                    let is_file = file_type.map(|t| t.is_file()).unwrap_or(false);
+                    if is_dir || (is_file && index_files) {
+                        batch.push(is_dir, entry.path().to_string_lossy().to_string());
+                    }
+                    WalkState::Continue
+                })
+            });
+        });
 
-                if !should_ignore {
-                    //TODO:should we display All file path dir/dir2/Desktop/  OR
-                    // ../../Desktop OR
-                    // Desktop
-                    store.insert(entry.path().to_str().unwrap());
+        let mut reported = 0;
+        for batch in receiver {
+            for (is_dir, path) in batch {
+                if is_dir {
+                    store.insert(&path);
+                } else {
+                    store.insert_file(&path);
                 }
             }
+            let found = store.len();
+            if found - reported >= PROGRESS_INTERVAL {
+                on_progress(found);
+                reported = found;
+            }
         }
-    }
+    });
+
+    // the walker threads finish in any order, sorting keeps the cache and
+    // the ranking of equal results the same between builds
+    store.directories.sort_unstable();
+    store.files.sort_unstable();
+    store.build_index();
     store
 }
 
-pub fn save_directory_to_file(store: &DirectoryStore, path: &str) -> io::Result<()> {
+// builds the cache described by `header` on a worker thread and writes it,
+// the progress and the finished store arrive on the receiver
+pub fn build_store_in_background(
+    header: CacheHeader,
+    cache_path: String,
+) -> Receiver<CacheMessage> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let progress = sender.clone();
+        let store = build_directory_from_store(
+            &header.root,
+            header.ignore_directories.clone(),
+            header.respect_gitignore,
+            header.index_files,
+            |found| {
+                let _ = progress.send(CacheMessage::Progress(found));
+            },
+        );
+        let saved = save_directory_to_file(&store, &header, &cache_path);
+        let _ = sender.send(CacheMessage::Done(store, saved));
+    });
+    receiver
+}
+
+// magic, bincode header, then the zstd compressed bincode entries
+pub fn save_directory_to_file(
+    store: &DirectoryStore,
+    header: &CacheHeader,
+    path: &str,
+) -> io::Result<()> {
+    // the cache directory doesn't exist yet on a fresh install
+    if let Some(parent) = Path::new(path).parent() {
+        fs::create_dir_all(parent)?;
+    }
     let file = File::create(path)?;
-    let writer = BufWriter::new(file);
+    let mut writer = BufWriter::new(file);
+
+    writer.write_all(CACHE_MAGIC)?;
+    bincode::serialize_into(&mut writer, header).map_err(to_io_error)?;
 
-    serde_json::to_writer(writer, store)?;
+    let mut encoder = zstd::Encoder::new(writer, 3)?;
+    bincode::serialize_into(&mut encoder, store).map_err(to_io_error)?;
+    encoder.finish()?.flush()?;
     Ok(())
 }
 
-pub fn load_directory_from_file(path: &str) -> io::Result<DirectoryStore> {
+// Ok(None) when the cache was built for different settings and needs a rebuild
+pub fn load_directory_from_file(
+    path: &str,
+    expected: &CacheHeader,
+) -> io::Result<Option<DirectoryStore>> {
     let file = File::open(path)?;
-    let reader = BufReader::new(file);
+    let mut reader = BufReader::new(file);
+
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != CACHE_MAGIC {
+        return migrate_json_cache(path, expected);
+    }
+
+    let header: CacheHeader = bincode::deserialize_from(&mut reader).map_err(to_io_error)?;
+    if header != *expected {
+        return Ok(None);
+    }
+
+    let decoder = zstd::Decoder::new(reader)?;
+    let mut store: DirectoryStore = bincode::deserialize_from(decoder).map_err(to_io_error)?;
+    store.build_index();
+    Ok(Some(store))
+}
+
+// the json cache didn't record what it was built from, so it is assumed to
+// match the current settings and rewritten in the binary format
+fn migrate_json_cache(path: &str, expected: &CacheHeader) -> io::Result<Option<DirectoryStore>> {
+    let reader = BufReader::new(File::open(path)?);
+    let legacy: LegacyStore = serde_json::from_reader(reader)?;
+    if legacy.index_files != expected.index_files {
+        return Ok(None);
+    }
+
+    let mut store = DirectoryStore::new();
+    store.directories = legacy.directories;
+    store.files = legacy.files;
+    save_directory_to_file(&store, expected, path)?;
+
+    store.build_index();
+    Ok(Some(store))
+}
+
+fn to_io_error(e: bincode::Error) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, e)
+}
+
+// the cache at `cache_path`, None when it is missing or its header doesn't
+// match, i.e. it was built for another root, ignore list or settings
+pub fn load_store(header: &CacheHeader, cache_path: &str) -> Option<DirectoryStore> {
+    if !Path::new(cache_path).exists() {
+        return None;
+    }
+
+    eprintln!("Loading directory cache from file");
+    match load_directory_from_file(cache_path, header) {
+        Ok(Some(store)) => Some(store),
+        Ok(None) => {
+            eprintln!("Directory cache is out of date");
+            None
+        }
+        Err(e) => {
+            eprintln!("Unable to read directory cache: {}", e);
+            None
+        }
+    }
+}
+
+// loads the cache, or builds it when it can't be used. progress goes to
+// stderr so `ff query` output stays clean
+pub fn load_or_build_store(header: &CacheHeader, cache_path: &str) -> io::Result<DirectoryStore> {
+    if let Some(store) = load_store(header, cache_path) {
+        return Ok(store);
+    }
+
+    eprintln!("Building directory cache, Please wait...");
+    let store = build_directory_from_store(
+        &header.root,
+        header.ignore_directories.clone(),
+        header.respect_gitignore,
+        header.index_files,
+        |_| {},
+    );
+    save_directory_to_file(&store, header, cache_path)?;
+    Ok(store)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(index_files: bool) -> CacheHeader {
+        CacheHeader::new("/home", vec![String::from("target")], true, index_files)
+    }
+
+    fn store(directories: &[&str], files: &[&str]) -> DirectoryStore {
+        let mut store = DirectoryStore::new();
+        for dir in directories {
+            store.insert(dir);
+        }
+        for file in files {
+            store.insert_file(file);
+        }
+        store.build_index();
+        store
+    }
+
+    fn search(store: &DirectoryStore, query: &str) -> Vec<String> {
+        store.search(
+            &SearchQuery::parse(query).unwrap(),
+            &FrecencyScores::default(),
+            10,
+        )
+    }
+
+    #[test]
+    fn cache_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        // the cache directory is created on the first save
+        let path = dir.path().join("cache/ff/cache.bin");
+        let path = path.to_str().unwrap();
+        let saved = store(&["/home/src", "/home/src/app"], &["/home/src/app/main.rs"]);
+
+        save_directory_to_file(&saved, &header(true), path).unwrap();
+        let loaded = load_directory_from_file(path, &header(true))
+            .unwrap()
+            .unwrap();
+        assert_eq!(loaded.directories, saved.directories);
+        assert_eq!(loaded.files, saved.files);
+        // the index isn't stored, it is rebuilt after loading
+        assert_eq!(search(&loaded, "main"), vec!["/home/src/app/main.rs"]);
+    }
+
+    #[test]
+    fn other_settings_need_a_rebuild() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.bin");
+        let path = path.to_str().unwrap();
+        save_directory_to_file(&store(&["/home/src"], &[]), &header(false), path).unwrap();
+
+        assert!(load_directory_from_file(path, &header(true))
+            .unwrap()
+            .is_none());
+        let mut other_root = header(false);
+        other_root.root = String::from("/srv");
+        assert!(load_directory_from_file(path, &other_root)
+            .unwrap()
+            .is_none());
+        let mut older = header(false);
+        older.version = CACHE_VERSION - 1;
+        assert!(load_directory_from_file(path, &older).unwrap().is_none());
+    }
 
-    let trie = serde_json::from_reader(reader)?;
-    Ok(trie)
+    #[test]
+    fn json_caches_are_migrated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.json");
+        fs::write(&path, r#"{"directories": ["/home/docs", "/home/src"]}"#).unwrap();
+        let path = path.to_str().unwrap();
+
+        let migrated = load_directory_from_file(path, &header(false))
+            .unwrap()
+            .unwrap();
+        assert_eq!(migrated.directories, vec!["/home/docs", "/home/src"]);
+        assert_eq!(search(&migrated, "docs"), vec!["/home/docs"]);
+
+        // rewritten in the binary format
+        assert!(fs::read(path).unwrap().starts_with(CACHE_MAGIC));
+        let reloaded = load_directory_from_file(path, &header(false))
+            .unwrap()
+            .unwrap();
+        assert_eq!(reloaded.directories, migrated.directories);
+    }
+
+    #[test]
+    fn json_caches_without_files_are_rebuilt_when_indexing_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.json");
+        fs::write(&path, r#"{"directories": ["/home/src"]}"#).unwrap();
+        let path = path.to_str().unwrap();
+
+        assert!(load_directory_from_file(path, &header(true))
+            .unwrap()
+            .is_none());
+        assert!(load_directory_from_file(path, &header(false)).is_ok());
+    }
+
+    #[test]
+    fn corrupt_caches_are_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.bin");
+        fs::write(&path, "FFC\0garbage").unwrap();
+
+        assert!(load_directory_from_file(path.to_str().unwrap(), &header(false)).is_err());
+    }
+
+    #[test]
+    fn frecency_ranks_within_a_match_group() {
+        let visited = "/home/projects/client/archive/notes-2023";
+        let mut directories = vec!["/home/notes", visited];
+        let others: Vec<String> = (1..=9).map(|i| format!("/home/notes-{}", i)).collect();
+        directories.extend(others.iter().map(String::as_str));
+        let indexed = store(&directories, &[]);
+        let query = SearchQuery::parse("notes").unwrap();
+
+        let ranked = indexed.search(&query, &FrecencyScores::default(), 3);
+        assert_eq!(
+            ranked,
+            vec!["/home/notes", "/home/notes-1", "/home/notes-2"]
+        );
+
+        // a visit lifts it over the shorter prefix matches, not over the
+        // exact name, even though it was past the limit before
+        let mut frecency = crate::frecency::FrecencyStore::default();
+        frecency.add(visited);
+        let ranked = indexed.search(&query, &frecency.scores(), 3);
+        assert_eq!(ranked, vec!["/home/notes", visited, "/home/notes-1"]);
+    }
+
+    #[test]
+    fn candidates_share_every_trigram() {
+        let indexed = store(
+            &["/abc/bcd", "/home/mainframe", "/home/other"],
+            &["/home/main.rs"],
+        );
+
+        // files come after the directories
+        assert_eq!(indexed.candidates("main"), vec![1, 3]);
+        assert_eq!(indexed.candidates("frame"), vec![1]);
+        assert_eq!(indexed.candidates("xyz"), Vec::<u32>::new());
+        // too short for a trigram, every entry is a candidate
+        assert_eq!(indexed.candidates("ma"), vec![0, 1, 2, 3]);
+        // both trigrams but not the text, the search filters it out
+        assert_eq!(indexed.candidates("abcd"), vec![0]);
+        assert!(search(&indexed, "abcd").is_empty());
+    }
 }
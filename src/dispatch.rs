@@ -0,0 +1,937 @@
+use std::{
+    fs,
+    path::{Path, PathBuf, MAIN_SEPARATOR},
+};
+
+use ratatui::{
+    layout::Rect,
+    widgets::{ListState, TableState},
+};
+
+use crate::app::{App, InputMode};
+use crate::associations::{Association, Associations};
+use crate::errors;
+use crate::file_reader_content::{FileContent, FileType};
+use crate::frecency::{record_visit, FrecencyStore};
+use crate::hooks::{HookEvent, Hooks};
+use crate::ignore_rules;
+use crate::image_generator::ImageGenerator;
+use crate::jobs::{JobKind, JobManager};
+use crate::keymap::{Action, ListAction};
+use crate::operations::extract;
+use crate::operations::file_ops::LinkKind;
+use crate::operations::permissions;
+use crate::search_history::SearchHistory;
+use crate::selection::{update_file_references_with_selection_preservation, SelectionController};
+use crate::system::volumes;
+use crate::theme;
+use crate::ui::list_rows;
+use crate::utils::files::{is_file, split_file_name, symlink_target, SortType};
+use crate::view_state::{read_with_view_state, remember_view, ViewStateStore};
+
+// the listing of the normal mode, what follows its selection and what
+// entering a directory goes through
+pub struct Listing<'a, 'b> {
+    pub state: &'a mut ListState,
+    pub table_state: &'a mut TableState,
+    pub area: Rect,
+    pub file_reader_content: &'a mut FileContent<'b>,
+    pub image_generator: &'a mut ImageGenerator,
+    pub sort_type: &'a mut SortType,
+    pub view_states: &'a mut ViewStateStore,
+    pub hooks: &'a Hooks,
+    pub frecency: &'a mut FrecencyStore,
+    pub associations: &'a Associations,
+    pub jobs: &'a mut JobManager,
+    pub search_history: &'a SearchHistory,
+    // ignore patterns are suggested relative to it
+    pub start_path: &'a str,
+}
+
+// what `dispatch` leaves to the main loop, which owns the terminal and the
+// jobs
+pub enum Outcome {
+    Done,
+    Quit,
+    // `files` are opened the way `association` says, the before_open hook
+    // already ran. `selected` is the entry under the cursor
+    Open {
+        selected: String,
+        files: Vec<String>,
+        association: Association,
+    },
+    // the names of `files` are edited in $EDITOR
+    EditNames(Vec<String>),
+    Shell,
+    // a popup list was opened in `app.input_mode`, its list selects this
+    Popup(Option<usize>),
+    // the marked pair is compared on a worker
+    Compare(PathBuf, PathBuf),
+    // the recursive sizes of these entries are calculated on a worker
+    DirSizes(Vec<String>),
+    RebuildCache,
+    TreeView,
+}
+
+// runs `action` in the normal mode, the same for keys and the mouse
+pub fn dispatch(action: Action, app: &mut App, listing: &mut Listing) -> Outcome {
+    let len = app.files.len();
+    let selected = listing
+        .state
+        .selected()
+        .and_then(|index| app.files.get(index))
+        .cloned();
+    match action {
+        Action::Quit => return Outcome::Quit,
+        Action::Search => {
+            app.open_search(listing.frecency);
+            listing.file_reader_content.file_type = FileType::NotAvailable;
+            listing.image_generator.image = None;
+        }
+        Action::GotoPath => app.open_goto(),
+        Action::GotoPrefix => app.pending_goto = true,
+        Action::YankPrefix => app.pending_yank = true,
+        // a focused preview scrolls with the movement keys, going left gives
+        // the focus back to the listing
+        Action::MoveDown | Action::MoveUp if app.preview_focused => {
+            app.preview_scroll = match action {
+                Action::MoveDown => app.preview_scroll.saturating_add(1),
+                _ => app.preview_scroll.saturating_sub(1),
+            };
+        }
+        Action::GoParent if app.preview_focused => app.preview_focused = false,
+        Action::MoveDown if len > 0 => {
+            let index = match listing.state.selected() {
+                Some(i) if i < len - 1 => i + 1,
+                _ => 0,
+            };
+            select(index, app, listing);
+        }
+        Action::MoveUp if len > 0 => {
+            let index = match listing.state.selected() {
+                Some(0) => len - 1,
+                Some(i) => i - 1,
+                None => 0,
+            };
+            select(index, app, listing);
+        }
+        Action::HalfPageDown | Action::HalfPageUp | Action::PageDown | Action::PageUp
+            if len > 0 =>
+        {
+            let rows = list_rows(listing.area, app.detail_view);
+            let step = match action {
+                Action::HalfPageDown | Action::HalfPageUp => (rows / 2).max(1),
+                _ => rows,
+            };
+            let current = listing.state.selected().unwrap_or(0);
+            let index = match action {
+                Action::HalfPageDown | Action::PageDown => (current + step).min(len - 1),
+                _ => current.saturating_sub(step),
+            };
+            select(index, app, listing);
+            // keep the cursor in the middle of the viewport
+            let centered = index.saturating_sub(rows / 2);
+            *listing.state.offset_mut() = centered;
+            *listing.table_state.offset_mut() = centered;
+        }
+        Action::Bottom | Action::ViewTop | Action::ViewMiddle | Action::ViewBottom if len > 0 => {
+            let offset = match app.detail_view {
+                true => listing.table_state.offset(),
+                false => listing.state.offset(),
+            };
+            let rows = list_rows(listing.area, app.detail_view);
+            let top = offset.min(len - 1);
+            let bottom = (offset + rows).min(len) - 1;
+            let index = match action {
+                Action::Bottom => len - 1,
+                Action::ViewTop => top,
+                Action::ViewMiddle => (top + bottom) / 2,
+                _ => bottom,
+            };
+            select(index, app, listing);
+        }
+        Action::GoParent => {
+            if let Some(parent) = app.current_dir.parent().map(|p| p.to_path_buf()) {
+                let child = app.current_dir_string();
+                // keep the directory we came from selected
+                enter_dir(parent, Some(child), app, listing);
+            }
+        }
+        Action::EnterDir => {
+            if let Some(selected) = selected.filter(|selected| !is_file(selected.clone())) {
+                record_visit(listing.frecency, &selected);
+                listing.hooks.fire(
+                    HookEvent::EnterDirectory,
+                    std::slice::from_ref(&selected),
+                    &app.current_dir,
+                );
+                match read_with_view_state(
+                    selected.clone(),
+                    app,
+                    listing.sort_type,
+                    listing.view_states,
+                ) {
+                    Ok(dir) => {
+                        app.enter(dir);
+                        app.preview_focused = false;
+                        select(0, app, listing);
+                    }
+                    Err(e) => {
+                        // stay here, the preview explains why
+                        app.preview_error = Some(errors::describe(&e));
+                        app.curr_stats = errors::status(&selected, e);
+                    }
+                }
+            }
+        }
+        Action::FollowLink => {
+            if let Some(selected) = selected {
+                follow_link(&selected, app, listing);
+            }
+        }
+        Action::ScrollPreviewLeft | Action::ScrollPreviewRight => {
+            if matches!(listing.file_reader_content.file_type, FileType::CSV) {
+                let columns = listing.file_reader_content.curr_csv_content.widths.len();
+                app.preview_column = if action == Action::ScrollPreviewLeft {
+                    app.preview_column.saturating_sub(1)
+                } else {
+                    (app.preview_column + 1).min(columns.saturating_sub(1))
+                };
+            }
+        }
+        Action::FoldPreview => {
+            if let Some(tree) = listing.file_reader_content.structured.as_mut() {
+                app.preview_scroll = tree.fold(app.preview_scroll as usize) as u16;
+            }
+        }
+        Action::UnfoldPreview => {
+            if let Some(tree) = listing.file_reader_content.structured.as_mut() {
+                tree.unfold(app.preview_scroll as usize);
+            }
+        }
+        Action::FollowPreview => {
+            app.follow_preview = !app.follow_preview;
+            if !app.follow_preview {
+                app.preview_scroll = 0;
+            }
+            app.curr_stats = if app.follow_preview {
+                String::from("Following the end of the preview")
+            } else {
+                String::from("Stopped following the preview")
+            };
+        }
+        Action::ToggleStructuredView => {
+            app.structured_view = !app.structured_view;
+            app.preview_scroll = 0;
+        }
+        Action::LoadMore => {
+            let content = &mut listing.file_reader_content;
+            if matches!(content.file_type, FileType::FILE) {
+                // one more chunk per press, the whole file is never read at
+                // once
+                app.preview_limit += app.preview_max_bytes;
+                let path = content.curr_selected_path.clone();
+                if let Some(preview) = content.previews.request(&path, app.preview_limit) {
+                    content.set_preview(preview);
+                }
+            }
+        }
+        Action::Hash => {
+            if let Some(selected) = selected.filter(|selected| is_file(selected.clone())) {
+                app.hash_target = selected;
+                app.hash_result = None;
+                app.input_mode = InputMode::WatchHash;
+            }
+        }
+        Action::Properties => {
+            if let Some(selected) = selected {
+                match permissions::read_properties(&selected) {
+                    Ok(properties) => {
+                        app.pending_mode = properties.mode;
+                        app.permission_cursor = 0;
+                        app.properties = Some(properties);
+                        app.input_mode = InputMode::WatchProperties;
+                    }
+                    Err(e) => {
+                        app.curr_stats = format!("Unable to read properties: {}", e);
+                    }
+                }
+            }
+        }
+        Action::Delete => {
+            app.render_popup = true;
+            app.input_mode = InputMode::WatchDelete;
+        }
+        Action::Create => app.input_mode = InputMode::WatchCreate,
+        Action::Rename => {
+            if let Some(selected) = selected {
+                let (new_path, placeholder_name) = split_file_name(&selected);
+                app.current_path_to_edit = new_path;
+                app.current_name_to_edit = placeholder_name.clone();
+                app.char_index = placeholder_name.chars().count();
+                app.create_edit_file_name = placeholder_name;
+            }
+            app.input_mode = InputMode::WatchRename;
+        }
+        Action::ToggleHidden => {
+            app.show_hidden_files = !app.show_hidden_files;
+            refresh(app, listing);
+            remember_view(app, listing.sort_type, listing.view_states);
+        }
+        Action::ToggleGitignore => {
+            app.respect_gitignore = !app.respect_gitignore;
+            refresh(app, listing);
+            app.curr_stats = if app.respect_gitignore {
+                String::from("Hiding ignored files")
+            } else {
+                String::from("Showing ignored files")
+            };
+        }
+        Action::ToggleDetailView => {
+            app.detail_view = !app.detail_view;
+            remember_view(app, listing.sort_type, listing.view_states);
+        }
+        // nothing to copy in an empty directory
+        Action::Copy => {
+            if let Some(selected) = selected {
+                app.item_to_copy_path = selected;
+                app.input_mode = InputMode::WatchCopy;
+            }
+        }
+        Action::CopyTo | Action::MoveTo => {
+            let sources = marked_or_selected(app, selected);
+            if !sources.is_empty() {
+                app.transfer_is_move = action == Action::MoveTo;
+                open_transfer(app, sources, None, false);
+            }
+        }
+        Action::Sync => {
+            let source = match app.marked_files.as_slice() {
+                [] => selected,
+                [marked] => Some(marked.clone()),
+                _ => None,
+            };
+            match source.filter(|source| Path::new(source).is_dir()) {
+                Some(source) => open_transfer(app, vec![source], None, true),
+                None => app.show_toast(String::from("Select or mark one directory to sync"), true),
+            }
+        }
+        Action::CreateSymlink | Action::CreateHardLink => {
+            if let Some(selected) = selected {
+                let kind = if action == Action::CreateSymlink {
+                    LinkKind::Symbolic
+                } else {
+                    LinkKind::Hard
+                };
+                open_transfer(app, vec![selected], Some(kind), false);
+            }
+        }
+        Action::Sort => app.input_mode = InputMode::WatchSort,
+        Action::ContentSearch => {
+            app.content_search_root = app.current_dir_string();
+            app.input_mode = InputMode::ContentSearch;
+        }
+        Action::Filter => app.input_mode = InputMode::Filter,
+        Action::Archive if len > 0 => app.input_mode = InputMode::WatchArchive,
+        Action::ToggleMark => {
+            if let Some(selected) = selected {
+                app.toggle_mark(&selected);
+            }
+        }
+        Action::BulkRename => {
+            let targets = marked_or_selected(app, selected);
+            if !targets.is_empty() {
+                return Outcome::EditNames(targets);
+            }
+        }
+        Action::PatternRename => {
+            let targets = marked_or_selected(app, selected);
+            if !targets.is_empty() {
+                app.pattern_rename_input.clear();
+                app.pattern_rename_targets = targets;
+                app.preview_pattern_rename();
+                app.input_mode = InputMode::WatchPatternRename;
+            }
+        }
+        Action::Help => app.input_mode = InputMode::WatchKeyBinding,
+        Action::Shell => return Outcome::Shell,
+        // nothing to open in an empty directory
+        Action::Open => {
+            if let Some(selected) = selected {
+                return open(selected, app, listing);
+            }
+        }
+        Action::CancelCopy => {
+            if listing.jobs.latest_active(JobKind::Copy).is_some() {
+                app.input_mode = InputMode::WatchCancelCopy;
+            }
+        }
+        Action::RunCommand => {
+            app.input_mode = InputMode::WatchCommands;
+            return first_of(app.commands.len());
+        }
+        Action::Convert => {
+            app.input_mode = InputMode::WatchConverters;
+            return first_of(app.converters.len());
+        }
+        Action::SelectEditor => {
+            // the first row of the popup is for no editor
+            let current = app
+                .selected_editor
+                .as_ref()
+                .and_then(|selected| app.editors.iter().position(|e| e == selected))
+                .map(|index| index + 1)
+                .unwrap_or(0);
+            app.input_mode = InputMode::WatchEditor;
+            return Outcome::Popup(Some(current));
+        }
+        Action::SavedSearches => {
+            app.input_mode = InputMode::WatchSavedSearches;
+            return first_of(listing.search_history.saved.len());
+        }
+        Action::Themes => {
+            let (themes, errors) = theme::list_themes();
+            app.themes = themes;
+            let current = app
+                .themes
+                .iter()
+                .position(|theme| theme.name == app.theme.name);
+            if let Some(error) = errors.first() {
+                app.curr_stats = error.clone();
+            }
+            app.input_mode = InputMode::WatchThemes;
+            return Outcome::Popup(current.or(Some(0)));
+        }
+        Action::IgnoreDirectory => {
+            if let Some(selected) = selected {
+                let pattern = ignore_rules::suggest_pattern(
+                    Path::new(&selected),
+                    Path::new(listing.start_path),
+                );
+                app.reset_create_edit_values();
+                app.char_index = pattern.chars().count();
+                app.create_edit_file_name = pattern;
+                app.input_mode = InputMode::WatchIgnore;
+            }
+        }
+        Action::RebuildCache => {
+            app.input_mode = InputMode::CacheLoading;
+            return Outcome::RebuildCache;
+        }
+        Action::Volumes => {
+            app.volumes = volumes::list_volumes();
+            app.input_mode = InputMode::WatchVolumes;
+            return first_of(app.volumes.len());
+        }
+        Action::Jump => {
+            app.jump_input.clear();
+            app.jump_results = listing.frecency.query("", 20);
+            app.input_mode = InputMode::WatchJump;
+            return first_of(app.jump_results.len());
+        }
+        Action::Compare => match app.marked_files.as_slice() {
+            [left, right] => {
+                app.curr_stats = String::from("Comparing...");
+                return Outcome::Compare(PathBuf::from(left), PathBuf::from(right));
+            }
+            _ => app.show_toast(
+                String::from("Mark two files or two directories to compare them"),
+                true,
+            ),
+        },
+        Action::Jobs => {
+            app.input_mode = InputMode::WatchJobs;
+            return first_of(listing.jobs.jobs().len());
+        }
+        Action::Extract => {
+            let content = &mut listing.file_reader_content;
+            let path = content.curr_selected_path.clone();
+            if let FileType::ZIP | FileType::Archive = content.get_file_extension(path.clone()) {
+                let extract_dir = extract::generate_extract_dir(Path::new(&path));
+                let description = format!("Extract {}", path);
+                listing
+                    .jobs
+                    .spawn(JobKind::Extract, description, move |control, sender| {
+                        extract::extract_archive(&path, &extract_dir, &control.cancel, &sender)?;
+                        Ok(format!("Extracted to {}", extract_dir.display()))
+                    });
+            }
+        }
+        Action::TreeView => {
+            app.input_mode = InputMode::WatchTree;
+            return Outcome::TreeView;
+        }
+        Action::DirSize if len > 0 => {
+            app.curr_stats = String::from("Calculating sizes...");
+            return Outcome::DirSizes(app.files.clone());
+        }
+        // empty listings
+        _ => {}
+    }
+    Outcome::Done
+}
+
+// the selection of a popup list of `len` entries when it opens
+fn first_of(len: usize) -> Outcome {
+    Outcome::Popup(if len == 0 { None } else { Some(0) })
+}
+
+// the marked entries, or the selected one when nothing is marked
+fn marked_or_selected(app: &App, selected: Option<String>) -> Vec<String> {
+    if app.marked_files.is_empty() {
+        selected.into_iter().collect()
+    } else {
+        app.marked_files.clone()
+    }
+}
+
+// the destination prompt for copying, moving, linking or syncing `sources`
+fn open_transfer(app: &mut App, sources: Vec<String>, link: Option<LinkKind>, sync: bool) {
+    app.transfer_link = link;
+    app.transfer_sync = sync;
+    app.transfer_sources = sources;
+    app.transfer_input = format!("{}{}", app.current_dir_string(), MAIN_SEPARATOR);
+    app.transfer_candidates.clear();
+    app.transfer_error = None;
+    app.input_mode = InputMode::WatchTransfer;
+}
+
+// lists `dir` with `select` under the cursor, or the first entry
+fn enter_dir(dir: PathBuf, select_path: Option<String>, app: &mut App, listing: &mut Listing) {
+    let dir_path = dir.to_string_lossy().to_string();
+    listing.hooks.fire(
+        HookEvent::EnterDirectory,
+        std::slice::from_ref(&dir_path),
+        &app.current_dir,
+    );
+    match read_with_view_state(dir, app, listing.sort_type, listing.view_states) {
+        Ok(dir) => {
+            let index = select_path
+                .and_then(|path| dir.entries.iter().position(|entry| *entry == path))
+                .unwrap_or(0);
+            app.enter(dir);
+            select(index, app, listing);
+        }
+        Err(e) => app.curr_stats = errors::status(&dir_path, e),
+    }
+}
+
+fn follow_link(selected: &str, app: &mut App, listing: &mut Listing) {
+    let target = match symlink_target(selected) {
+        None => {
+            app.curr_stats = String::from("Not a symlink");
+            return;
+        }
+        Some(target) if !target.exists() => {
+            app.curr_stats = format!("Broken symlink: {}", target.display());
+            return;
+        }
+        Some(target) => fs::canonicalize(&target).unwrap_or(target),
+    };
+    // jump into linked directories, for linked files open the containing
+    // directory with the target selected
+    let (dir, select_path) = if target.is_dir() {
+        (target.clone(), None)
+    } else {
+        (
+            target
+                .parent()
+                .map(|parent| parent.to_path_buf())
+                .unwrap_or(target.clone()),
+            Some(target.to_string_lossy().to_string()),
+        )
+    };
+
+    match read_with_view_state(dir, app, listing.sort_type, listing.view_states) {
+        Ok(dir) => {
+            let index = select_path
+                .and_then(|path| dir.entries.iter().position(|entry| *entry == path))
+                .unwrap_or(0);
+            app.enter(dir);
+            select(index, app, listing);
+            app.curr_stats = format!("Followed link to {}", target.display());
+        }
+        Err(e) => app.curr_stats = errors::status(&target.to_string_lossy(), e),
+    }
+}
+
+// decides how the selection is opened, the main loop carries it out
+fn open(selected: String, app: &mut App, listing: &mut Listing) -> Outcome {
+    // marked entries, e.g. picked from the search results, are opened
+    // together
+    let files = if app.marked_files.is_empty() {
+        vec![selected.clone()]
+    } else {
+        app.marked_files.clone()
+    };
+    // marked files always go to the editor together
+    let association = if app.print_on_select {
+        Association::Editor
+    } else if files.len() == 1 && Path::new(&selected).is_file() {
+        listing.associations.resolve(&selected)
+    } else {
+        Association::Editor
+    };
+    if let Association::PreviewOnly = association {
+        app.curr_stats = String::from("Preview only, enter does nothing here");
+        return Outcome::Done;
+    }
+    if !matches!(association, Association::Editor) {
+        if let Err(e) = listing
+            .hooks
+            .run(HookEvent::BeforeOpen, &files, &app.current_dir)
+        {
+            app.curr_stats = e.to_string();
+            return Outcome::Done;
+        }
+    }
+    for file in &files {
+        record_visit(listing.frecency, file);
+    }
+    Outcome::Open {
+        selected,
+        files,
+        association,
+    }
+}
+
+fn refresh(app: &mut App, listing: &mut Listing) {
+    update_file_references_with_selection_preservation(
+        None,
+        app,
+        listing.state,
+        listing.sort_type,
+        listing.file_reader_content,
+        listing.image_generator,
+    );
+}
+
+fn select(index: usize, app: &mut App, listing: &mut Listing) {
+    SelectionController::new(
+        app,
+        listing.state,
+        listing.file_reader_content,
+        listing.image_generator,
+    )
+    .update_selection(index);
+}
+
+// moves the selection of a popup list of `len` entries, wrapping around at
+// both ends. returns the new selection
+pub fn step(state: &mut ListState, len: usize, action: ListAction) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+    let index = match (action, state.selected()) {
+        (ListAction::Next, Some(i)) => (i + 1) % len,
+        (ListAction::Previous, Some(0) | None) => len - 1,
+        (ListAction::Previous, Some(i)) => i - 1,
+        _ => 0,
+    };
+    state.select(Some(index));
+    Some(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::Waker;
+    use crate::keymap::{list_action, prompt_action, PromptAction};
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use std::collections::{BTreeMap, HashMap};
+    use syntect::{highlighting::ThemeSet, parsing::SyntaxSet};
+
+    struct Fixture {
+        app: App,
+        state: ListState,
+        table_state: TableState,
+        file_reader_content: FileContent<'static>,
+        image_generator: ImageGenerator,
+        sort_type: SortType,
+        view_states: ViewStateStore,
+        hooks: Hooks,
+        frecency: FrecencyStore,
+        associations: Associations,
+        jobs: JobManager,
+        search_history: SearchHistory,
+        start_path: String,
+    }
+
+    impl Fixture {
+        // the listing of `dir` with its first entry selected
+        fn new(dir: &Path) -> Fixture {
+            let mut fixture = Fixture {
+                app: App::new(Vec::new()),
+                state: ListState::default(),
+                table_state: TableState::default(),
+                file_reader_content: FileContent::new(
+                    SyntaxSet::load_defaults_newlines(),
+                    ThemeSet::load_defaults(),
+                    "base16-ocean.dark",
+                    None,
+                    Waker::default(),
+                ),
+                image_generator: ImageGenerator::new("halfblocks"),
+                sort_type: SortType::ASC,
+                view_states: ViewStateStore::default(),
+                hooks: Hooks::new(&HashMap::new()),
+                frecency: FrecencyStore::default(),
+                associations: Associations::from_config(&BTreeMap::new()),
+                jobs: JobManager::new(Waker::default()),
+                search_history: SearchHistory::default(),
+                start_path: dir.to_string_lossy().to_string(),
+            };
+            let listed = read_with_view_state(
+                dir,
+                &mut fixture.app,
+                &mut fixture.sort_type,
+                &fixture.view_states,
+            )
+            .unwrap();
+            fixture.app.enter(listed);
+            fixture.run(Action::ViewTop);
+            fixture
+        }
+
+        fn run(&mut self, action: Action) -> Outcome {
+            let mut listing = Listing {
+                state: &mut self.state,
+                table_state: &mut self.table_state,
+                area: Rect::new(0, 0, 40, 6),
+                file_reader_content: &mut self.file_reader_content,
+                image_generator: &mut self.image_generator,
+                sort_type: &mut self.sort_type,
+                view_states: &mut self.view_states,
+                hooks: &self.hooks,
+                frecency: &mut self.frecency,
+                associations: &self.associations,
+                jobs: &mut self.jobs,
+                search_history: &self.search_history,
+                start_path: &self.start_path,
+            };
+            dispatch(action, &mut self.app, &mut listing)
+        }
+
+        fn selected(&self) -> Option<usize> {
+            self.state.selected()
+        }
+    }
+
+    // five files, the listing shows four rows
+    fn files() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["a.txt", "b.txt", "c.txt", "d.txt", "e.txt"] {
+            fs::write(dir.path().join(name), name).unwrap();
+        }
+        dir
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn moving_wraps_around_the_listing() {
+        let dir = files();
+        let mut fixture = Fixture::new(dir.path());
+        assert_eq!(fixture.selected(), Some(0));
+
+        fixture.run(Action::MoveUp);
+        assert_eq!(fixture.selected(), Some(4));
+        fixture.run(Action::MoveDown);
+        assert_eq!(fixture.selected(), Some(0));
+        fixture.run(Action::MoveDown);
+        assert_eq!(fixture.selected(), Some(1));
+        assert_eq!(
+            fixture.file_reader_content.curr_selected_path,
+            fixture.app.files[1]
+        );
+    }
+
+    #[test]
+    fn pages_stop_at_the_ends() {
+        let dir = files();
+        let mut fixture = Fixture::new(dir.path());
+
+        fixture.run(Action::PageDown);
+        assert_eq!(fixture.selected(), Some(4));
+        fixture.run(Action::PageUp);
+        assert_eq!(fixture.selected(), Some(0));
+        fixture.run(Action::Bottom);
+        assert_eq!(fixture.selected(), Some(4));
+    }
+
+    #[test]
+    fn going_up_selects_the_directory_we_came_from() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["one", "two", "three"] {
+            fs::create_dir(dir.path().join(name)).unwrap();
+        }
+        fs::write(dir.path().join("two/inner.txt"), "inner").unwrap();
+        let mut fixture = Fixture::new(dir.path());
+        let two = dir.path().join("two").to_string_lossy().to_string();
+        let index = fixture.app.files.iter().position(|f| *f == two).unwrap();
+        fixture.state.select(Some(index));
+
+        fixture.run(Action::EnterDir);
+        assert_eq!(fixture.app.current_dir, dir.path().join("two"));
+        assert_eq!(fixture.app.files.len(), 1);
+        assert_eq!(fixture.selected(), Some(0));
+
+        fixture.run(Action::GoParent);
+        assert_eq!(fixture.app.current_dir, dir.path());
+        assert_eq!(fixture.selected(), Some(index));
+    }
+
+    #[test]
+    fn entering_a_file_stays_put() {
+        let dir = files();
+        let mut fixture = Fixture::new(dir.path());
+
+        fixture.run(Action::EnterDir);
+        assert_eq!(fixture.app.current_dir, dir.path());
+        assert_eq!(fixture.app.files.len(), 5);
+    }
+
+    #[test]
+    fn empty_listing_ignores_entry_actions() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut fixture = Fixture::new(dir.path());
+        assert_eq!(fixture.selected(), None);
+
+        for action in [
+            Action::MoveDown,
+            Action::MoveUp,
+            Action::PageDown,
+            Action::Bottom,
+            Action::EnterDir,
+            Action::Copy,
+            Action::Open,
+            Action::Hash,
+            Action::ToggleMark,
+            Action::Archive,
+        ] {
+            assert!(matches!(fixture.run(action), Outcome::Done));
+            assert_eq!(fixture.selected(), None);
+            assert!(matches!(fixture.app.input_mode, InputMode::Normal));
+        }
+
+        fixture.run(Action::Rename);
+        assert!(matches!(fixture.app.input_mode, InputMode::WatchRename));
+        assert!(fixture.app.create_edit_file_name.is_empty());
+    }
+
+    #[test]
+    fn rename_puts_the_cursor_after_the_last_char() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("résumé.txt"), "cv").unwrap();
+        let mut fixture = Fixture::new(dir.path());
+
+        fixture.run(Action::Rename);
+        assert_eq!(fixture.app.create_edit_file_name, "résumé.txt");
+        assert_eq!(fixture.app.char_index, 10);
+        fixture.app.delete_c();
+        assert_eq!(fixture.app.create_edit_file_name, "résumé.tx");
+    }
+
+    #[test]
+    fn open_hands_the_selection_to_the_main_loop() {
+        let dir = files();
+        let mut fixture = Fixture::new(dir.path());
+        fixture.run(Action::MoveDown);
+
+        match fixture.run(Action::Open) {
+            Outcome::Open {
+                selected,
+                files,
+                association,
+            } => {
+                assert_eq!(selected, fixture.app.files[1]);
+                assert_eq!(files, vec![selected]);
+                assert!(matches!(association, Association::Editor));
+            }
+            _ => panic!("expected the selection to be opened"),
+        }
+        assert!(matches!(fixture.run(Action::Quit), Outcome::Quit));
+    }
+
+    #[test]
+    fn focused_preview_scrolls_until_going_left() {
+        let dir = files();
+        let mut fixture = Fixture::new(dir.path());
+        fixture.app.preview_focused = true;
+
+        fixture.run(Action::MoveDown);
+        fixture.run(Action::MoveDown);
+        assert_eq!(fixture.app.preview_scroll, 2);
+        assert_eq!(fixture.selected(), Some(0));
+
+        fixture.run(Action::GoParent);
+        assert!(!fixture.app.preview_focused);
+        assert_eq!(fixture.app.current_dir, dir.path());
+        fixture.run(Action::MoveDown);
+        assert_eq!(fixture.selected(), Some(1));
+    }
+
+    #[test]
+    fn popups_select_their_first_entry() {
+        let dir = files();
+        let mut fixture = Fixture::new(dir.path());
+
+        assert!(matches!(
+            fixture.run(Action::RunCommand),
+            Outcome::Popup(None)
+        ));
+        assert!(matches!(fixture.app.input_mode, InputMode::WatchCommands));
+        // the first row is for no editor
+        assert!(matches!(
+            fixture.run(Action::SelectEditor),
+            Outcome::Popup(Some(0))
+        ));
+        assert!(matches!(fixture.app.input_mode, InputMode::WatchEditor));
+    }
+
+    #[test]
+    fn extracting_runs_as_a_job() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut zip = zip::ZipWriter::new(fs::File::create(dir.path().join("bundle.zip")).unwrap());
+        zip.start_file("inside.txt", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        zip.finish().unwrap();
+        let mut fixture = Fixture::new(dir.path());
+
+        fixture.run(Action::Extract);
+        assert_eq!(fixture.jobs.jobs().len(), 1);
+        assert!(matches!(fixture.run(Action::CancelCopy), Outcome::Done));
+        assert!(matches!(fixture.app.input_mode, InputMode::Normal));
+    }
+
+    #[test]
+    fn popup_and_prompt_keys() {
+        assert_eq!(
+            list_action(&key(KeyCode::Char('j'))),
+            Some(ListAction::Next)
+        );
+        assert_eq!(list_action(&key(KeyCode::Up)), Some(ListAction::Previous));
+        assert_eq!(list_action(&key(KeyCode::Char('x'))), None);
+
+        // letters are typed into a prompt, not taken as commands
+        assert_eq!(
+            prompt_action(&key(KeyCode::Char('q'))),
+            Some(PromptAction::Insert('q'))
+        );
+        assert_eq!(
+            prompt_action(&key(KeyCode::Esc)),
+            Some(PromptAction::Cancel)
+        );
+        assert_eq!(prompt_action(&key(KeyCode::Tab)), None);
+    }
+
+    #[test]
+    fn popup_selection_wraps() {
+        let mut state = ListState::default();
+        assert_eq!(step(&mut state, 0, ListAction::Next), None);
+        assert_eq!(step(&mut state, 3, ListAction::Previous), Some(2));
+        assert_eq!(step(&mut state, 3, ListAction::Next), Some(0));
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::BTreeMap;
+
+// editors are command templates from settings.json, `{path}`, `{line}` and
+// `{column}` are replaced inside each argument, so paths with spaces stay one
+// argument. quote an argument to keep spaces in it, e.g.
+// `nvim "+call cursor({line},{column})" {path}`
+pub fn default_editors() -> BTreeMap<String, String> {
+    [
+        ("nvim", "nvim \"+call cursor({line},{column})\" {path}"),
+        ("vscode", "vscode --goto {path}:{line}:{column}"),
+        ("zed", "zed {path}:{line}:{column}"),
+        ("helix", "hx {path}:{line}:{column}"),
+    ]
+    .into_iter()
+    .map(|(name, template)| (name.to_string(), template.to_string()))
+    .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Position {
+    pub line: usize,
+    pub column: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Editor {
+    pub name: String,
+    pub template: String,
+}
+
+pub fn editors_from_config(editors: &BTreeMap<String, String>) -> Vec<Editor> {
+    editors
+        .iter()
+        .map(|(name, template)| Editor {
+            name: name.clone(),
+            template: template.clone(),
+        })
+        .collect()
+}
+
+impl Editor {
+    // program and arguments. without a position, arguments that only hold the
+    // position are dropped and `:{line}` / `:{column}` suffixes are removed so
+    // directories open as a plain path
+    pub fn command_line(
+        &self,
+        path: &str,
+        position: Option<Position>,
+    ) -> Option<(String, Vec<String>)> {
+        let mut parts = split_template(&self.template).into_iter();
+        let program = parts.next()?;
+
+        let args = parts
+            .filter_map(|arg| match position {
+                Some(position) => Some(
+                    arg.replace("{path}", path)
+                        .replace("{line}", &position.line.to_string())
+                        .replace("{column}", &position.column.unwrap_or(1).to_string()),
+                ),
+                None if arg.contains("{path}") => Some(
+                    arg.replace(":{line}", "")
+                        .replace(":{column}", "")
+                        .replace("{path}", path),
+                ),
+                None if arg.contains("{line}") || arg.contains("{column}") => None,
+                None => Some(arg),
+            })
+            .collect();
+        Some((program, args))
+    }
+
+    // opens several paths in one invocation, the argument holding `{path}`
+    // is repeated for each of them, e.g. `nvim a.rs b.rs`
+    pub fn command_line_many(&self, paths: &[String]) -> Option<(String, Vec<String>)> {
+        let mut parts = split_template(&self.template).into_iter();
+        let program = parts.next()?;
+
+        let mut args = Vec::new();
+        for arg in parts {
+            if arg.contains("{path}") {
+                let arg = arg.replace(":{line}", "").replace(":{column}", "");
+                args.extend(paths.iter().map(|path| arg.replace("{path}", path)));
+            } else if !arg.contains("{line}") && !arg.contains("{column}") {
+                args.push(arg);
+            }
+        }
+        Some((program, args))
+    }
+}
+
+// whitespace separated words, single or double quotes group words
+pub fn split_template(template: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+
+    for c in template.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(current);
+    }
+    words
+}
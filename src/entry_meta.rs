@@ -0,0 +1,125 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc::Receiver,
+    thread,
+    time::SystemTime,
+};
+
+use rayon::prelude::*;
+
+use crate::events::Waker;
+use crate::operations::permissions::mode_of;
+
+// what sorting, the listing and the detail view need to know about an entry,
+// so each of them doesn't stat it again
+#[derive(Debug, Clone)]
+pub struct EntryMeta {
+    // of the target for symlinks
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    pub created: Option<SystemTime>,
+    // follow symlinks like Path::is_dir and Path::is_file
+    pub is_dir: bool,
+    pub is_file: bool,
+    pub is_symlink: bool,
+    pub mode: u32,
+    pub link_target: Option<PathBuf>,
+    // a symlink whose target is missing
+    pub broken: bool,
+}
+
+impl EntryMeta {
+    pub fn read(path: &Path) -> Option<EntryMeta> {
+        let link = fs::symlink_metadata(path).ok()?;
+        let is_symlink = link.file_type().is_symlink();
+        let target = match is_symlink {
+            true => fs::metadata(path).ok(),
+            false => None,
+        };
+        let metadata = target.as_ref().unwrap_or(&link);
+
+        Some(EntryMeta {
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+            created: metadata.created().ok(),
+            is_dir: metadata.is_dir(),
+            is_file: metadata.is_file(),
+            is_symlink,
+            mode: mode_of(&link),
+            link_target: match is_symlink {
+                true => fs::read_link(path).ok(),
+                false => None,
+            },
+            broken: is_symlink && target.is_none(),
+        })
+    }
+}
+
+// reads every entry once, in parallel, keeping the order of `paths`. entries
+// that vanished in the meantime are dropped
+pub fn read_all(paths: Vec<PathBuf>) -> Vec<(PathBuf, EntryMeta)> {
+    paths
+        .into_par_iter()
+        .filter_map(|path| EntryMeta::read(&path).map(|meta| (path, meta)))
+        .collect()
+}
+
+// metadata of the entries of the current listing, read on a worker thread
+// whenever the listing changes. lookups it doesn't have yet read the entry
+#[derive(Default)]
+pub struct EntryMetaCache {
+    generation: u64,
+    entries: HashMap<String, EntryMeta>,
+    receiver: Option<Receiver<HashMap<String, EntryMeta>>>,
+    waker: Waker,
+}
+
+impl EntryMetaCache {
+    pub fn new(waker: Waker) -> EntryMetaCache {
+        EntryMetaCache {
+            waker,
+            ..EntryMetaCache::default()
+        }
+    }
+
+    // starts over when `generation` says the listing was read again
+    pub fn update(&mut self, generation: u64, files: &[String]) {
+        if self.generation == generation {
+            return;
+        }
+        self.generation = generation;
+        self.entries.clear();
+
+        let files = files.to_vec();
+        let (sender, receiver) = self.waker.channel();
+        self.receiver = Some(receiver);
+        thread::spawn(move || {
+            let entries = files
+                .into_par_iter()
+                .filter_map(|file| EntryMeta::read(Path::new(&file)).map(|meta| (file, meta)))
+                .collect();
+            let _ = sender.send(entries);
+        });
+    }
+
+    // whether the metadata arrived
+    pub fn poll(&mut self) -> bool {
+        if let Some(receiver) = &self.receiver {
+            if let Ok(entries) = receiver.try_recv() {
+                self.entries = entries;
+                self.receiver = None;
+                return true;
+            }
+        }
+        false
+    }
+
+    pub fn get(&self, path: &str) -> Option<EntryMeta> {
+        match self.entries.get(path) {
+            Some(meta) => Some(meta.clone()),
+            None => EntryMeta::read(Path::new(path)),
+        }
+    }
+}
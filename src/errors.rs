@@ -0,0 +1,25 @@
+use std::io::{self, ErrorKind};
+
+// the TUI owns the terminal while it runs, anything printed to stdout ends up
+// drawn over the ui. errors are turned into one line for the status bar instead
+
+pub fn describe(error: &anyhow::Error) -> String {
+    match error.chain().find_map(|e| e.downcast_ref::<io::Error>()) {
+        Some(e) => describe_io(e),
+        None => error.to_string(),
+    }
+}
+
+fn describe_io(error: &io::Error) -> String {
+    match error.kind() {
+        ErrorKind::NotFound => String::from("no such file or directory"),
+        ErrorKind::PermissionDenied => String::from("permission denied"),
+        ErrorKind::AlreadyExists => String::from("already exists"),
+        _ => error.to_string(),
+    }
+}
+
+// "<context>: <reason>", context is usually the path the operation was on
+pub fn status(context: &str, error: impl Into<anyhow::Error>) -> String {
+    format!("{}: {}", context, describe(&error.into()))
+}
@@ -0,0 +1,242 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crossterm::event::{self, Event};
+
+// how long the input thread waits for the terminal before checking whether
+// it was handed to another program
+const INPUT_POLL: Duration = Duration::from_millis(100);
+
+// everything the main loop waits for arrives on one channel, it sleeps
+// until one of these shows up instead of polling
+#[derive(Debug, Clone)]
+pub enum AppEvent {
+    // key, mouse, paste and resize events read from the terminal
+    Input(Event),
+    // reading the terminal failed, there is no more input
+    InputError(String),
+    // a watched directory changed
+    Watcher,
+    // a job reported progress or finished
+    Job,
+    // another background worker has results waiting
+    Worker,
+    // sent at a fixed interval so toasts expire and job timings stay current
+    Tick,
+}
+
+// handed to background work so its results wake the main loop. the default
+// one wakes nothing, for code that runs without the tui
+#[derive(Clone, Default)]
+pub struct Waker {
+    target: Option<(Sender<AppEvent>, AppEvent)>,
+}
+
+impl Waker {
+    pub fn wake(&self) {
+        if let Some((sender, event)) = &self.target {
+            let _ = sender.send(event.clone());
+        }
+    }
+
+    // a channel whose receiving end wakes the main loop for every message
+    pub fn channel<T: Send + 'static>(&self) -> (Sender<T>, Receiver<T>) {
+        let (sender, receiver) = mpsc::channel();
+        (sender, self.relay(receiver))
+    }
+
+    // forwards what arrives on `receiver` and wakes the main loop after each
+    // message. the returned receiver disconnects once `receiver` does, so
+    // a worker that stops without sending is noticed too
+    pub fn relay<T: Send + 'static>(&self, receiver: Receiver<T>) -> Receiver<T> {
+        if self.target.is_none() {
+            return receiver;
+        }
+        let waker = self.clone();
+        let (sender, relayed) = mpsc::channel();
+        thread::spawn(move || {
+            for message in receiver {
+                if sender.send(message).is_err() {
+                    return;
+                }
+                waker.wake();
+            }
+            drop(sender);
+            waker.wake();
+        });
+        relayed
+    }
+}
+
+pub struct Events {
+    sender: Sender<AppEvent>,
+    receiver: Receiver<AppEvent>,
+    // an input event read while draining background events, handed out next
+    pending: Option<AppEvent>,
+    // held by the input thread while it reads, and by a suspended terminal
+    // for as long as another program uses it
+    input: Arc<Mutex<()>>,
+    input_paused: Arc<AtomicBool>,
+}
+
+impl Default for Events {
+    fn default() -> Events {
+        let (sender, receiver) = mpsc::channel();
+        Events {
+            sender,
+            receiver,
+            pending: None,
+            input: Arc::new(Mutex::new(())),
+            input_paused: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+impl Events {
+    pub fn waker(&self, event: AppEvent) -> Waker {
+        Waker {
+            target: Some((self.sender.clone(), event)),
+        }
+    }
+
+    // starts reading the terminal on its own thread and sending a tick every
+    // `tick`. only called once the terminal is set up, the image protocol
+    // detection reads the terminal's answers itself
+    pub fn start(&self, tick: Duration) {
+        let sender = self.sender.clone();
+        let input = self.input.clone();
+        let paused = self.input_paused.clone();
+        thread::spawn(move || loop {
+            if paused.load(Ordering::Relaxed) {
+                thread::sleep(INPUT_POLL);
+                continue;
+            }
+            let lock = input.lock().unwrap_or_else(PoisonError::into_inner);
+            // polled with a timeout, a blocking read would keep the lock and
+            // take the keys meant for an editor started in the meantime
+            let read = match event::poll(INPUT_POLL) {
+                Ok(true) => event::read().map(Some),
+                Ok(false) => Ok(None),
+                Err(e) => Err(e),
+            };
+            drop(lock);
+            let event = match read {
+                Ok(Some(event)) => AppEvent::Input(event),
+                Ok(None) => continue,
+                Err(e) => AppEvent::InputError(e.to_string()),
+            };
+            let failed = matches!(event, AppEvent::InputError(_));
+            if sender.send(event).is_err() || failed {
+                return;
+            }
+        });
+
+        let sender = self.sender.clone();
+        thread::spawn(move || loop {
+            thread::sleep(tick);
+            if sender.send(AppEvent::Tick).is_err() {
+                return;
+            }
+        });
+    }
+
+    // waits for the next event, or until `deadline` when something is due
+    // then. background events that piled up in the meantime are merged so
+    // the loop polls and draws once for all of them
+    pub fn next(&mut self, deadline: Option<Instant>) -> Option<AppEvent> {
+        let event = match self.pending.take() {
+            Some(event) => event,
+            None => match deadline {
+                Some(deadline) => self
+                    .receiver
+                    .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                    .ok()?,
+                None => self.receiver.recv().ok()?,
+            },
+        };
+        if matches!(event, AppEvent::Input(_) | AppEvent::InputError(_)) {
+            return Some(event);
+        }
+
+        let mut merged = event;
+        while let Ok(next) = self.receiver.try_recv() {
+            match next {
+                AppEvent::Input(_) | AppEvent::InputError(_) => {
+                    self.pending = Some(next);
+                    break;
+                }
+                AppEvent::Tick => merged = AppEvent::Tick,
+                _ => {}
+            }
+        }
+        Some(merged)
+    }
+
+    // stops reading the terminal until the guard is dropped, so a program
+    // started in it gets every key
+    pub fn pause_input(&self) -> InputPause<'_> {
+        self.input_paused.store(true, Ordering::Relaxed);
+        InputPause {
+            paused: &self.input_paused,
+            _lock: self.input.lock().unwrap_or_else(PoisonError::into_inner),
+        }
+    }
+}
+
+pub struct InputPause<'a> {
+    paused: &'a AtomicBool,
+    _lock: MutexGuard<'a, ()>,
+}
+
+impl Drop for InputPause<'_> {
+    fn drop(&mut self) {
+        self.paused.store(false, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    fn key() -> Event {
+        Event::Key(KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE))
+    }
+
+    #[test]
+    fn relayed_messages_wake_the_loop() {
+        let mut events = Events::default();
+        let (sender, receiver) = events.waker(AppEvent::Worker).channel();
+        sender.send(7).unwrap();
+        drop(sender);
+
+        assert!(matches!(events.next(None), Some(AppEvent::Worker)));
+        assert_eq!(receiver.recv().unwrap(), 7);
+        // the worker is gone, the receiver says so instead of waiting forever
+        assert!(receiver.recv().is_err());
+    }
+
+    #[test]
+    fn background_events_are_merged_before_input() {
+        let mut events = Events::default();
+        let job = events.waker(AppEvent::Job);
+        job.wake();
+        job.wake();
+        events.waker(AppEvent::Tick).wake();
+        events.waker(AppEvent::Input(key())).wake();
+        job.wake();
+
+        assert!(matches!(events.next(None), Some(AppEvent::Tick)));
+        assert!(matches!(events.next(None), Some(AppEvent::Input(_))));
+        assert!(matches!(events.next(None), Some(AppEvent::Job)));
+        let deadline = Instant::now() + Duration::from_millis(10);
+        assert!(events.next(Some(deadline)).is_none());
+    }
+}
@@ -1,17 +1,24 @@
-use std::{fs, io, iter::zip, path::Path};
-
-use ratatui::style::Style;
-use ratatui::text::{Line, Span, Text};
-use ratatui::{
-    style::Color,
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+use std::{
+    fs,
+    io::{self, ErrorKind, Read},
+    path::Path,
 };
-use syntect::easy::HighlightLines;
-use syntect::highlighting::{Style as SyntectStyle, ThemeSet};
-use syntect::html::highlighted_html_for_file;
+
+use bzip2::read::BzDecoder;
+use flate2::read::GzDecoder;
+
+use ratatui::text::Text;
+use ratatui::widgets::Paragraph;
+use syntect::highlighting::{self, ThemeSet};
 use syntect::parsing::SyntaxSet;
-use syntect::util::as_24_bit_terminal_escaped;
-use syntect::util::LinesWithEndings;
+
+use crate::events::Waker;
+use crate::office;
+use crate::preview::PreviewManager;
+use crate::structured_preview::{StructuredLoader, StructuredPreview};
+use crate::theme::Theme;
+pub mod exif;
+
 #[derive(Debug, Clone)]
 pub enum FileType {
     FILE,
@@ -21,38 +28,264 @@ pub enum FileType {
     NotAvailable,
     DEFAULT,
     IMG,
+    Archive,
+    Pdf,
+    Office,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArchiveKind {
+    Tar,
+    TarGz,
+    TarBz2,
+    TarZst,
+    SevenZip,
+    Rar,
+}
+
+pub fn archive_kind(path: &str) -> Option<ArchiveKind> {
+    let file_name = Path::new(path).file_name()?.to_str()?.to_lowercase();
+
+    if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
+        Some(ArchiveKind::TarGz)
+    } else if file_name.ends_with(".tar.bz2") || file_name.ends_with(".tbz2") {
+        Some(ArchiveKind::TarBz2)
+    } else if file_name.ends_with(".tar.zst") || file_name.ends_with(".tzst") {
+        Some(ArchiveKind::TarZst)
+    } else if file_name.ends_with(".tar") {
+        Some(ArchiveKind::Tar)
+    } else if file_name.ends_with(".7z") {
+        Some(ArchiveKind::SevenZip)
+    } else if file_name.ends_with(".rar") {
+        Some(ArchiveKind::Rar)
+    } else {
+        None
+    }
+}
+
+// returns the decompressed tar stream for the given archive kind
+pub fn open_tar_decoder(path: &str, kind: ArchiveKind) -> io::Result<Box<dyn Read>> {
+    let file = fs::File::open(path)?;
+
+    match kind {
+        ArchiveKind::Tar => Ok(Box::new(file)),
+        ArchiveKind::TarGz => Ok(Box::new(GzDecoder::new(file))),
+        ArchiveKind::TarBz2 => Ok(Box::new(BzDecoder::new(file))),
+        ArchiveKind::TarZst => Ok(Box::new(zstd::Decoder::new(file)?)),
+        ArchiveKind::SevenZip | ArchiveKind::Rar => Err(io::Error::new(
+            ErrorKind::Unsupported,
+            "no extraction backend available for this archive type",
+        )),
+    }
+}
+
+// at most `limit` bytes of a text file for the preview and whether the file
+// is longer than that, errors are shown in place of the content
+pub fn read_file_content(path: String, limit: u64) -> (String, bool) {
+    let file = match fs::File::open(&path) {
+        Ok(file) => file,
+        Err(err) => return (format!("Encounter Error: '{}'", err.kind()), false),
+    };
+    let len = file.metadata().map(|meta| meta.len()).unwrap_or(0);
+
+    let mut buffer = Vec::new();
+    if let Err(err) = io::BufReader::new(file)
+        .take(limit)
+        .read_to_end(&mut buffer)
+    {
+        return (format!("Encounter Error: '{}'", err.kind()), false);
+    }
+    // the cut can land inside a multi-byte char, drop the partial one
+    let content = match String::from_utf8(buffer) {
+        Ok(content) => content,
+        Err(e) => {
+            let valid = e.utf8_error().valid_up_to();
+            let mut bytes = e.into_bytes();
+            bytes.truncate(valid);
+            String::from_utf8(bytes).unwrap_or_default()
+        }
+    };
+    (content, len > limit)
+}
+
+// rows shown in the csv preview, the rest of the file isn't parsed
+const CSV_MAX_ROWS: usize = 500;
+const CSV_MAX_COLUMN_WIDTH: usize = 30;
+const CSV_DELIMITERS: [u8; 4] = [b',', b'\t', b';', b'|'];
+
+#[derive(Debug, Clone, Default)]
+pub struct CsvTable {
+    pub header: Option<Vec<String>>,
+    pub rows: Vec<Vec<String>>,
+    // display width of every column, capped
+    pub widths: Vec<u16>,
+}
+
+// the delimiter that splits the first lines into the same, largest number
+// of fields. a comma when none of them shows up, ties go to the one earlier
+// in the list
+fn detect_delimiter(sample: &str) -> u8 {
+    let lines: Vec<&str> = sample.lines().take(10).collect();
+    CSV_DELIMITERS
+        .iter()
+        .copied()
+        .rev()
+        .filter_map(|delimiter| {
+            let counts: Vec<usize> = lines
+                .iter()
+                .map(|line| line.bytes().filter(|b| *b == delimiter).count())
+                .collect();
+            let first = counts.first().copied().unwrap_or(0);
+            let consistent = counts.windows(2).all(|pair| pair[0] == pair[1]);
+            (first > 0).then_some((delimiter, (consistent, first)))
+        })
+        .max_by_key(|(_, score)| *score)
+        .map(|(delimiter, _)| delimiter)
+        .unwrap_or(b',')
+}
+
+// a first row without numbers above rows that have some is a header
+fn has_header(rows: &[Vec<String>]) -> bool {
+    let is_number = |cell: &String| cell.trim().parse::<f64>().is_ok();
+    match rows.split_first() {
+        Some((first, rest)) => {
+            !first.iter().any(is_number)
+                && (rest.is_empty() || rest.iter().any(|row| row.iter().any(is_number)))
+        }
+        None => false,
+    }
+}
+
+pub fn read_csv_table(path: &str) -> anyhow::Result<CsvTable> {
+    // files in other encodings, or cut inside a multi-byte char, still give
+    // a sample to look for delimiters in
+    let mut sample = Vec::new();
+    fs::File::open(path)?
+        .take(64 * 1024)
+        .read_to_end(&mut sample)?;
+    let sample = String::from_utf8_lossy(&sample);
+    let delimiter = if path.to_lowercase().ends_with(".tsv") {
+        b'\t'
+    } else {
+        detect_delimiter(&sample)
+    };
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .from_path(path)?;
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    for record in reader.byte_records().take(CSV_MAX_ROWS) {
+        rows.push(
+            record?
+                .iter()
+                .map(|cell| String::from_utf8_lossy(cell).to_string())
+                .collect(),
+        );
+    }
+
+    let header = if has_header(&rows) {
+        Some(rows.remove(0))
+    } else {
+        None
+    };
+    let mut widths: Vec<usize> = Vec::new();
+    for row in header.iter().chain(rows.iter()) {
+        for (index, cell) in row.iter().enumerate() {
+            let width = cell.chars().count().min(CSV_MAX_COLUMN_WIDTH);
+            match widths.get_mut(index) {
+                Some(current) => *current = (*current).max(width),
+                None => widths.push(width),
+            }
+        }
+    }
+
+    Ok(CsvTable {
+        header,
+        rows,
+        widths: widths
+            .into_iter()
+            .map(|width| width.max(1) as u16)
+            .collect(),
+    })
+}
+
+// an unknown theme name falls back to the default one
+fn named_syntax_theme(themes: &ThemeSet, name: &str) -> highlighting::Theme {
+    themes
+        .themes
+        .get(name)
+        .unwrap_or(&themes.themes["base16-ocean.dark"])
+        .clone()
 }
 
 pub struct FileContent<'a> {
     pub file_type: FileType,
-    pub is_error: bool,
-    pub error_message: String,
     pub curr_asset_path: String,
     pub curr_zip_content: Vec<String>,
     pub curr_selected_path: String,
-    pub curr_csv_content: Vec<String>,
+    pub curr_csv_content: CsvTable,
+    pub curr_archive_content: Vec<String>,
+    pub curr_pdf_content: Vec<String>,
+    pub curr_office_content: Vec<String>,
+    pub curr_image_info: Vec<String>,
     pub curr_extension_tpe: Option<String>,
-    pub syntax_set: SyntaxSet,
-    pub theme_set: ThemeSet,
     pub hightlighted_content: Option<Paragraph<'a>>,
+    // lines in `hightlighted_content`
+    pub preview_lines: usize,
+    pub previews: PreviewManager,
+    pub structured: Option<StructuredPreview>,
+    pub trees: StructuredLoader,
+    // the bundled syntect themes and the one picked in settings.json, used
+    // with ui themes that don't color the syntax themselves
+    syntax_themes: ThemeSet,
+    syntax_theme: String,
 }
 
-impl FileContent<'_> {
-    pub fn new(ps: SyntaxSet, ts: ThemeSet) -> FileContent<'static> {
+impl<'a> FileContent<'a> {
+    pub fn new(
+        ps: SyntaxSet,
+        ts: ThemeSet,
+        syntax_theme: &str,
+        preview_command: Option<String>,
+        waker: Waker,
+    ) -> FileContent<'static> {
+        let theme = named_syntax_theme(&ts, syntax_theme);
         FileContent {
             file_type: FileType::NotAvailable,
-            is_error: false,
-            error_message: String::from(""),
             curr_asset_path: String::from(""),
             curr_zip_content: Vec::new(),
             curr_selected_path: String::from(""),
-            curr_csv_content: Vec::new(),
+            curr_csv_content: CsvTable::default(),
+            curr_archive_content: Vec::new(),
+            curr_pdf_content: Vec::new(),
+            curr_office_content: Vec::new(),
+            curr_image_info: Vec::new(),
             curr_extension_tpe: None,
-            syntax_set: ps,
-            theme_set: ts,
+            previews: PreviewManager::new(ps, theme, preview_command, waker.clone()),
+            structured: None,
+            trees: StructuredLoader::new(waker),
+            syntax_themes: ts,
+            syntax_theme: syntax_theme.to_string(),
             hightlighted_content: None,
+            preview_lines: 0,
         }
     }
+    // highlights previews with the [syntax] colors of `theme` when it has them
+    pub fn use_theme(&mut self, theme: &Theme, true_color: bool) {
+        let syntax_theme = theme
+            .syntect_theme()
+            .unwrap_or_else(|| named_syntax_theme(&self.syntax_themes, &self.syntax_theme));
+        self.previews.set_theme(syntax_theme, true_color);
+    }
+
+    // settings.json changed, `use_theme` applies the syntax theme
+    pub fn configure(&mut self, syntax_theme: &str, preview_command: Option<String>) {
+        self.syntax_theme = syntax_theme.to_string();
+        self.previews.set_command(preview_command);
+    }
+
     pub fn is_curr_path_file(path: String) -> bool {
         match fs::metadata(path) {
             Ok(file) => {
@@ -67,78 +300,21 @@ impl FileContent<'_> {
         }
     }
 
-    fn convert_color(&mut self, color: syntect::highlighting::Color) -> Color {
-        Color::Rgb(color.r, color.g, color.b)
+    pub fn set_preview(&mut self, text: Text<'a>) {
+        self.preview_lines = text.lines.len();
+        self.hightlighted_content = Some(Paragraph::new(text));
     }
 
-    pub fn get_highlighted_content(
-        &mut self,
-        content: String,
-        extension_type: Option<String>,
-    ) -> String {
-        if extension_type.is_none() {
-            return content;
+    pub fn get_file_extension(&mut self, path: String) -> FileType {
+        // docx and xlsx are zip files too, their text is more useful than
+        // the member list
+        if office::is_office_document(&path) {
+            return FileType::Office;
         }
-        let mut res = String::from("");
-        let mut spans = vec![];
-        let syntax = self
-            .syntax_set
-            .find_syntax_by_extension(&extension_type.unwrap())
-            .unwrap();
-        let syntax_set = self.syntax_set.clone();
-        let theme = self.theme_set.themes["base16-ocean.dark"].clone();
-        let mut h = HighlightLines::new(syntax, &theme);
-        for line in LinesWithEndings::from(&content) {
-            // LinesWithEndings enables use of newlines mode
-            let mut lines: Vec<Span> = vec![];
-            let ranges: Vec<(SyntectStyle, &str)> =
-                h.highlight_line(line, &syntax_set).unwrap().clone();
-
-            for (style, text) in ranges.clone().into_iter() {
-                let fg_color = self.convert_color(style.foreground);
-                let span = Span::styled(text.to_string(), Style::default().fg(fg_color));
-                lines.push(span);
-            }
-            spans.push(Line::from(lines));
-
-            let escaped = as_24_bit_terminal_escaped(&ranges[..], true);
-            res = escaped.clone();
+        if archive_kind(&path).is_some() {
+            return FileType::Archive;
         }
-        let text = Text::from(spans);
-
-        let paragraph = Paragraph::new(text);
-        self.hightlighted_content = Some(paragraph);
-        res
-    }
-
-    pub fn read_file_content(&mut self, path: String) -> String {
-        let content = match fs::read_to_string(path) {
-            Ok(file_content) => file_content,
-            Err(err) => {
-                let err_kind = err.kind().to_string();
-                let format_error = format!("Encounter Error: '{}'", err_kind);
-                self.is_error = true;
-                self.error_message = String::from("");
-                return format_error;
-            }
-        };
-        content
-    }
-
-    pub fn get_file_extension_type(&mut self, path: String) -> Option<String> {
-        let file_extension = Path::new(&path).extension();
-
-        let curr_extension_type = match file_extension {
-            Some(extension) => Some(extension.to_owned().into_string().unwrap()),
-            None => None, // TODO: find out what would be the best default
-        };
-
-        //self.curr_extension_tpe = curr_extension_type;
 
-        curr_extension_type
-    }
-
-    pub fn get_file_extension(&mut self, path: String) -> FileType {
         let file_extension = Path::new(&path).extension();
 
         match file_extension {
@@ -146,12 +322,14 @@ impl FileContent<'_> {
                 let convert_to_str = extention.to_str().unwrap();
 
                 match convert_to_str {
-                    "js" | "rs" | "py" | "map.js" | "html" | "yml" | "json" | "css" => {
-                        FileType::FILE
+                    "js" | "rs" | "py" | "map.js" | "html" | "yml" | "yaml" | "json" | "toml"
+                    | "css" => FileType::FILE,
+                    "png" | "jpg" | "jpeg" | "gif" | "bmp" | "webp" | "ico" | "tiff" => {
+                        FileType::IMG
                     }
-                    "png" => FileType::IMG,
                     "zip" => FileType::ZIP,
-                    "csv" => FileType::CSV,
+                    "pdf" => FileType::Pdf,
+                    "csv" | "tsv" => FileType::CSV,
                     _ => FileType::NotAvailable,
                 }
             }
@@ -159,24 +337,9 @@ impl FileContent<'_> {
         }
     }
 
-    pub fn read_csv_content(&mut self) {
-        let file = fs::File::open(self.curr_selected_path.clone()).unwrap();
-
-        let mut rdr = csv::Reader::from_reader(file);
-
-        let mut file_content: Vec<String> = Vec::new();
-        for result in rdr.records() {
-            let record = result.unwrap();
-
-            for val in record.iter() {
-                file_content.push(val.to_string());
-                //println!("result: {:?}", val);
-            }
-        }
-
-        self.curr_csv_content = file_content;
-
-        //  for result in rdr.recors() {}
+    pub fn read_csv_content(&mut self, path: &str) -> anyhow::Result<()> {
+        self.curr_csv_content = read_csv_table(path)?;
+        Ok(())
     }
 
     pub fn read_zip_content(&mut self, path: String) -> i32 {
@@ -205,48 +368,137 @@ impl FileContent<'_> {
         0
     }
 
-    pub fn extract_zip_content(&mut self) -> String {
-        let fname = std::path::Path::new(&self.curr_selected_path);
-        let file = fs::File::open(fname).unwrap();
+    pub fn read_archive_content(&mut self, path: String) {
+        let mut list: Vec<String> = Vec::new();
 
-        let mut archive = zip::ZipArchive::new(file).unwrap();
+        match archive_kind(&path) {
+            Some(ArchiveKind::SevenZip) | Some(ArchiveKind::Rar) => {
+                list.push(String::from(
+                    "No extraction backend available for this archive type",
+                ));
+            }
+            Some(kind) => match open_tar_decoder(&path, kind) {
+                Ok(reader) => {
+                    let mut archive = tar::Archive::new(reader);
+                    match archive.entries() {
+                        Ok(entries) => {
+                            for entry in entries.flatten() {
+                                if let Ok(entry_path) = entry.path() {
+                                    list.push(entry_path.display().to_string());
+                                }
+                            }
+                        }
+                        Err(e) => list.push(format!("Unable to read archive: {}", e)),
+                    }
+                }
+                Err(e) => list.push(format!("Unable to read archive: {}", e)),
+            },
+            None => {}
+        }
 
-        for i in 0..archive.len() {
-            let mut file = archive.by_index(i).unwrap();
+        self.curr_archive_content = list;
+    }
 
-            let outpath = match file.enclosed_name() {
-                Some(f_path) => f_path,
-                None => continue,
-            };
+    #[cfg(feature = "pdf")]
+    pub fn read_pdf_content(&mut self, path: String) {
+        let mut lines: Vec<String> = Vec::new();
 
-            {
-                let comment = file.comment();
-                if !comment.is_empty() {
-                    println!("File {i} comment: {comment}");
-                }
-            }
+        match lopdf::Document::load(&path) {
+            Ok(document) => {
+                let pages = document.get_pages();
+                lines.push(format!("Pages: {}", pages.len()));
 
-            if file.is_dir() {
-                fs::create_dir_all(&outpath).unwrap();
-            } else {
-                if let Some(p) = outpath.parent() {
-                    if !p.exists() {
-                        fs::create_dir_all(p).unwrap();
+                for (label, key) in [("Title", b"Title".as_slice()), ("Author", b"Author")] {
+                    if let Some(value) = pdf_info_value(&document, key) {
+                        lines.push(format!("{}: {}", label, value));
                     }
                 }
+                lines.push(String::new());
 
-                let mut outfile = fs::File::create(&outpath).unwrap();
-                io::copy(&mut file, &mut outfile).unwrap();
-            }
-
-            {
-                use std::os::unix::fs::PermissionsExt;
-
-                if let Some(mode) = file.unix_mode() {
-                    let _ = fs::set_permissions(&outpath, fs::Permissions::from_mode(mode));
+                if let Some(first_page) = pages.keys().next() {
+                    match document.extract_text(&[*first_page]) {
+                        Ok(text) => lines.extend(text.lines().map(|line| line.to_string())),
+                        Err(e) => lines.push(format!("Unable to extract text: {}", e)),
+                    }
                 }
             }
+            Err(e) => lines.push(format!("Unable to read pdf: {}", e)),
         }
-        self.curr_selected_path.clone()
+
+        self.curr_pdf_content = lines;
+    }
+
+    #[cfg(not(feature = "pdf"))]
+    pub fn read_pdf_content(&mut self, _path: String) {
+        self.curr_pdf_content = vec![String::from(
+            "PDF preview is not available, build with `--features pdf` to enable it",
+        )];
+    }
+}
+
+#[cfg(feature = "pdf")]
+fn pdf_info_value(document: &lopdf::Document, key: &[u8]) -> Option<String> {
+    let info = match document.trailer.get(b"Info").ok()? {
+        lopdf::Object::Reference(id) => document.get_object(*id).ok()?,
+        object => object,
+    };
+    let value = info.as_dict().ok()?.get(key).ok()?;
+    let bytes = value.as_str().ok()?;
+
+    // text strings are either PDFDocEncoding (close enough to latin-1) or
+    // UTF-16BE with a byte order mark
+    let text = if bytes.starts_with(&[0xFE, 0xFF]) {
+        let units: Vec<u16> = bytes[2..]
+            .chunks(2)
+            .filter(|chunk| chunk.len() == 2)
+            .map(|chunk| u16::from_be_bytes([chunk[0], chunk[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else {
+        bytes.iter().map(|b| *b as char).collect()
+    };
+
+    if text.trim().is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delimiter_falls_back_to_a_comma() {
+        assert_eq!(detect_delimiter(""), b',');
+        assert_eq!(detect_delimiter("one column\nno delimiters\n"), b',');
+        assert_eq!(detect_delimiter("a;b;c\n1;2;3\n"), b';');
+        assert_eq!(detect_delimiter("a\tb\n1\t2\n"), b'\t');
+        // both split every line the same, the comma comes first
+        assert_eq!(detect_delimiter("a,b|c\n1,2|3\n"), b',');
+    }
+
+    #[test]
+    fn samples_that_are_not_utf8_are_still_detected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("latin1.csv");
+        // "café;prix" in latin-1
+        fs::write(&path, b"caf\xe9;prix\nthe;2\n").unwrap();
+
+        let table = read_csv_table(&path.to_string_lossy()).unwrap();
+        assert_eq!(table.widths.len(), 2);
+        assert_eq!(table.rows, vec![vec!["the", "2"]]);
+    }
+
+    #[test]
+    fn empty_files_have_no_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("empty.csv");
+        fs::write(&path, "").unwrap();
+
+        let table = read_csv_table(&path.to_string_lossy()).unwrap();
+        assert!(table.header.is_none());
+        assert!(table.rows.is_empty());
     }
 }
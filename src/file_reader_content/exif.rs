@@ -0,0 +1,67 @@
+#[cfg(feature = "exif")]
+use exif::{In, Reader, Tag};
+
+// dimensions of the image, plus camera, date taken, orientation, location
+// and color space from its EXIF data when built with the exif feature
+pub fn image_info(path: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    if let Ok((width, height)) = image::image_dimensions(path) {
+        lines.push(format!("Dimensions: {} x {}", width, height));
+    }
+    lines.extend(exif_lines(path));
+    lines
+}
+
+#[cfg(feature = "exif")]
+fn exif_lines(path: &str) -> Vec<String> {
+    let Ok(file) = std::fs::File::open(path) else {
+        return Vec::new();
+    };
+    // most images, png and gif included, simply have no EXIF data
+    let Ok(exif) = Reader::new().read_from_container(&mut std::io::BufReader::new(file)) else {
+        return Vec::new();
+    };
+    let value = |tag: Tag| {
+        exif.get_field(tag, In::PRIMARY).map(|field| {
+            field
+                .display_value()
+                .with_unit(&exif)
+                .to_string()
+                .trim_matches('"')
+                .to_string()
+        })
+    };
+
+    let mut lines = Vec::new();
+    let camera: Vec<String> = [Tag::Make, Tag::Model]
+        .into_iter()
+        .filter_map(value)
+        .collect();
+    if !camera.is_empty() {
+        lines.push(format!("Camera: {}", camera.join(" ")));
+    }
+    if let Some(taken) = value(Tag::DateTimeOriginal).or_else(|| value(Tag::DateTime)) {
+        lines.push(format!("Taken: {}", taken));
+    }
+    if let Some(orientation) = value(Tag::Orientation) {
+        lines.push(format!("Orientation: {}", orientation));
+    }
+    if let (Some(latitude), Some(longitude)) = (value(Tag::GPSLatitude), value(Tag::GPSLongitude)) {
+        lines.push(format!(
+            "GPS: {} {}, {} {}",
+            latitude,
+            value(Tag::GPSLatitudeRef).unwrap_or_default(),
+            longitude,
+            value(Tag::GPSLongitudeRef).unwrap_or_default()
+        ));
+    }
+    if let Some(color_space) = value(Tag::ColorSpace) {
+        lines.push(format!("Color space: {}", color_space));
+    }
+    lines
+}
+
+#[cfg(not(feature = "exif"))]
+fn exif_lines(_path: &str) -> Vec<String> {
+    Vec::new()
+}
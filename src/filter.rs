@@ -0,0 +1,291 @@
+use std::{
+    fs,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparison {
+    Less,
+    Greater,
+    Equal,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum EntryType {
+    Dir,
+    File,
+    Symlink,
+}
+
+#[derive(Debug, Clone)]
+enum Term {
+    Glob(String),
+    Extension(String),
+    Type(EntryType),
+    Size(Comparison, u64),
+    Modified(Comparison, Duration),
+    Text(String),
+    Not(Box<Term>),
+}
+
+// space separated terms that all have to match, e.g. `*.rs size>10K modified<7d`
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    terms: Vec<Term>,
+}
+
+impl Filter {
+    pub fn parse(input: &str) -> Result<Filter, String> {
+        let terms = input
+            .split_whitespace()
+            .map(parse_term)
+            .collect::<Result<Vec<Term>, String>>()?;
+        Ok(Filter { terms })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    pub fn matches(&self, path: &str) -> bool {
+        self.terms
+            .iter()
+            .all(|term| term_matches(term, Path::new(path)))
+    }
+
+    pub fn apply(&self, files: &[String]) -> Vec<String> {
+        files
+            .iter()
+            .filter(|file| self.matches(file))
+            .cloned()
+            .collect()
+    }
+}
+
+fn parse_term(term: &str) -> Result<Term, String> {
+    if let Some(negated) = term.strip_prefix('!') {
+        return Ok(Term::Not(Box::new(parse_term(negated)?)));
+    }
+
+    if let Some(value) = term.strip_prefix("type:") {
+        let entry_type = match value {
+            "dir" | "d" => EntryType::Dir,
+            "file" | "f" => EntryType::File,
+            "link" | "symlink" | "l" => EntryType::Symlink,
+            _ => return Err(format!("unknown type '{}', use dir, file or link", value)),
+        };
+        return Ok(Term::Type(entry_type));
+    }
+
+    if let Some(value) = term.strip_prefix("ext:") {
+        return Ok(Term::Extension(
+            value.trim_start_matches('.').to_lowercase(),
+        ));
+    }
+
+    if let Some((comparison, value)) = comparison_after(term, "size") {
+        return Ok(Term::Size(comparison, parse_size(value)?));
+    }
+
+    if let Some((comparison, value)) = comparison_after(term, "modified") {
+        return Ok(Term::Modified(comparison, parse_age(value)?));
+    }
+
+    if term.contains('*') || term.contains('?') {
+        return Ok(Term::Glob(term.to_lowercase()));
+    }
+
+    Ok(Term::Text(term.to_lowercase()))
+}
+
+// `size>10M` or `modified:3d`. anything else starting with the key, like
+// `sizes.txt`, is searched for as text
+fn comparison_after<'a>(term: &'a str, key: &str) -> Option<(Comparison, &'a str)> {
+    let rest = term.strip_prefix(key)?;
+    let mut chars = rest.chars();
+    let comparison = match chars.next()? {
+        '<' => Comparison::Less,
+        '>' => Comparison::Greater,
+        '=' | ':' => Comparison::Equal,
+        _ => return None,
+    };
+    Some((comparison, chars.as_str()))
+}
+
+fn split_number(value: &str) -> Result<(f64, String), String> {
+    let digits_end = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(value.len());
+    let number = value[..digits_end]
+        .parse::<f64>()
+        .map_err(|_| format!("invalid number in '{}'", value))?;
+    Ok((number, value[digits_end..].to_lowercase()))
+}
+
+fn parse_size(value: &str) -> Result<u64, String> {
+    let (number, unit) = split_number(value)?;
+    let multiplier: u64 = match unit.as_str() {
+        "" | "b" => 1,
+        "k" | "kb" => 1024,
+        "m" | "mb" => 1024 * 1024,
+        "g" | "gb" => 1024 * 1024 * 1024,
+        _ => return Err(format!("unknown size unit '{}', use B, K, M or G", unit)),
+    };
+    Ok((number * multiplier as f64) as u64)
+}
+
+fn parse_age(value: &str) -> Result<Duration, String> {
+    let (number, unit) = split_number(value)?;
+    let seconds: u64 = match unit.as_str() {
+        "s" => 1,
+        "min" => 60,
+        "h" => 60 * 60,
+        "" | "d" => 60 * 60 * 24,
+        "w" => 60 * 60 * 24 * 7,
+        "y" => 60 * 60 * 24 * 365,
+        // m is megabytes in sizes, minutes are spelled out
+        "m" => return Err(String::from("use min for minutes")),
+        _ => {
+            return Err(format!(
+                "unknown time unit '{}', use s, min, h, d, w or y",
+                unit
+            ))
+        }
+    };
+    Ok(Duration::from_secs((number * seconds as f64) as u64))
+}
+
+fn compare<T: PartialOrd>(comparison: Comparison, value: T, expected: T) -> bool {
+    match comparison {
+        Comparison::Less => value < expected,
+        Comparison::Greater => value > expected,
+        Comparison::Equal => value == expected,
+    }
+}
+
+fn file_name_lowercase(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+fn term_matches(term: &Term, path: &Path) -> bool {
+    match term {
+        Term::Not(inner) => !term_matches(inner, path),
+        Term::Text(text) => file_name_lowercase(path).contains(text.as_str()),
+        Term::Glob(pattern) => glob_matches(pattern, &file_name_lowercase(path)),
+        Term::Extension(extension) => path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase() == *extension)
+            .unwrap_or(false),
+        Term::Type(entry_type) => match fs::symlink_metadata(path) {
+            Ok(meta) => match entry_type {
+                EntryType::Symlink => meta.file_type().is_symlink(),
+                EntryType::Dir => path.is_dir(),
+                EntryType::File => path.is_file(),
+            },
+            Err(_) => false,
+        },
+        Term::Size(comparison, size) => match fs::metadata(path) {
+            Ok(meta) => compare(*comparison, meta.len(), *size),
+            Err(_) => false,
+        },
+        // modified<7d reads as "modified less than 7 days ago"
+        Term::Modified(comparison, age) => {
+            let modified = fs::metadata(path).and_then(|meta| meta.modified());
+            match modified {
+                Ok(modified) => {
+                    let elapsed = SystemTime::now()
+                        .duration_since(modified)
+                        .unwrap_or(Duration::ZERO);
+                    compare(*comparison, elapsed, *age)
+                }
+                Err(_) => false,
+            }
+        }
+    }
+}
+
+// `*` matches any run of characters and `?` exactly one
+fn glob_matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+
+    while p < pattern.len() && pattern[p] == '*' {
+        p += 1;
+    }
+    p == pattern.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term(input: &str) -> Term {
+        parse_term(input).unwrap()
+    }
+
+    #[test]
+    fn size_and_modified_need_a_comparison() {
+        assert!(matches!(
+            term("size>10M"),
+            Term::Size(Comparison::Greater, 10485760)
+        ));
+        assert!(matches!(
+            term("size:1k"),
+            Term::Size(Comparison::Equal, 1024)
+        ));
+        assert!(matches!(
+            term("modified<7d"),
+            Term::Modified(Comparison::Less, _)
+        ));
+        // names that only start with the key are text
+        assert!(matches!(term("sizes.txt"), Term::Text(text) if text == "sizes.txt"));
+        assert!(matches!(term("modified_at"), Term::Text(_)));
+        assert!(matches!(term("size"), Term::Text(_)));
+        assert!(matches!(term("size*"), Term::Glob(_)));
+    }
+
+    #[test]
+    fn minutes_and_megabytes_are_told_apart() {
+        assert!(matches!(
+            term("modified<30min"),
+            Term::Modified(Comparison::Less, age) if age == Duration::from_secs(30 * 60)
+        ));
+        assert!(matches!(term("size<2m"), Term::Size(_, 2097152)));
+        assert!(parse_term("modified<30m").is_err());
+        assert!(parse_term("size>10q").is_err());
+    }
+
+    #[test]
+    fn terms_are_combined() {
+        let filter = Filter::parse("*.rs !test").unwrap();
+        let files = [
+            String::from("/src/main.rs"),
+            String::from("/src/test.rs"),
+            String::from("/README.md"),
+        ];
+        assert_eq!(filter.apply(&files), vec![String::from("/src/main.rs")]);
+        assert!(Filter::parse("").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,156 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufReader, BufWriter},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+// once the ranks add up to this much every entry is scaled down so old
+// favourites fade out, same idea as zoxide
+const MAX_TOTAL_RANK: f64 = 10_000.0;
+
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct FrecencyEntry {
+    rank: f64,
+    last_access: u64,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct FrecencyStore {
+    entries: HashMap<String, FrecencyEntry>,
+    #[serde(skip)]
+    path: String,
+}
+
+// the scores of every entry at one point in time, the search looks them up
+// for each candidate and a search worker gets its own copy
+#[derive(Debug, Clone, Default)]
+pub struct FrecencyScores {
+    scores: HashMap<String, f64>,
+}
+
+impl FrecencyScores {
+    pub fn get(&self, path: &str) -> f64 {
+        self.scores.get(path).copied().unwrap_or(0.0)
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+impl FrecencyEntry {
+    fn score(&self, now: u64) -> f64 {
+        let age = now.saturating_sub(self.last_access);
+        let weight = if age < HOUR {
+            4.0
+        } else if age < DAY {
+            2.0
+        } else if age < WEEK {
+            0.5
+        } else {
+            0.25
+        };
+        self.rank * weight
+    }
+}
+
+impl FrecencyStore {
+    // a missing or unreadable database starts empty
+    pub fn load(path: &str) -> FrecencyStore {
+        let mut store: Self = File::open(path)
+            .ok()
+            .and_then(|file| serde_json::from_reader(BufReader::new(file)).ok())
+            .unwrap_or_default();
+        store.path = path.to_string();
+        store
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let writer = BufWriter::new(File::create(&self.path)?);
+        serde_json::to_writer(writer, self)?;
+        Ok(())
+    }
+
+    pub fn add(&mut self, path: &str) {
+        let entry = self
+            .entries
+            .entry(path.to_string())
+            .or_insert(FrecencyEntry {
+                rank: 0.0,
+                last_access: 0,
+            });
+        entry.rank += 1.0;
+        entry.last_access = now();
+
+        let total: f64 = self.entries.values().map(|entry| entry.rank).sum();
+        if total > MAX_TOTAL_RANK {
+            for entry in self.entries.values_mut() {
+                entry.rank *= 0.9;
+            }
+            self.entries.retain(|_, entry| entry.rank >= 1.0);
+        }
+    }
+
+    pub fn scores(&self) -> FrecencyScores {
+        let now = now();
+        FrecencyScores {
+            scores: self
+                .entries
+                .iter()
+                .map(|(path, entry)| (path.clone(), entry.score(now)))
+                .collect(),
+        }
+    }
+
+    // every space separated keyword has to appear in the path and the last
+    // one in its final component, e.g. `proj ff` matches ~/projects/ff-rust
+    pub fn query(&self, keywords: &str, limit: usize) -> Vec<String> {
+        let keywords: Vec<String> = keywords
+            .split_whitespace()
+            .map(|keyword| keyword.to_lowercase())
+            .collect();
+
+        let now = now();
+        let mut matches: Vec<(&String, f64)> = self
+            .entries
+            .iter()
+            .filter(|(path, _)| Path::new(path).exists())
+            .filter(|(path, _)| {
+                let lower = path.to_lowercase();
+                let name = lower.rsplit(std::path::is_separator).next().unwrap_or("");
+                keywords
+                    .iter()
+                    .all(|keyword| lower.contains(keyword.as_str()))
+                    && keywords
+                        .last()
+                        .map(|last| name.contains(last.as_str()))
+                        .unwrap_or(true)
+            })
+            .map(|(path, entry)| (path, entry.score(now)))
+            .collect();
+
+        matches.sort_by(|a, b| b.1.total_cmp(&a.1));
+        matches
+            .into_iter()
+            .take(limit)
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+}
+
+// counts a visit to `path` and saves the store
+pub fn record_visit(frecency: &mut FrecencyStore, path: &str) {
+    frecency.add(path);
+    let _ = frecency.save();
+}
@@ -0,0 +1,96 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GitStatus {
+    Modified,
+    Added,
+    Deleted,
+    Renamed,
+    Untracked,
+    Conflicted,
+}
+
+impl GitStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            GitStatus::Modified => "modified",
+            GitStatus::Added => "added",
+            GitStatus::Deleted => "deleted",
+            GitStatus::Renamed => "renamed",
+            GitStatus::Untracked => "untracked",
+            GitStatus::Conflicted => "conflicted",
+        }
+    }
+
+    fn from_code(x: char, y: char) -> GitStatus {
+        match (x, y) {
+            ('?', '?') => GitStatus::Untracked,
+            ('U', _) | (_, 'U') | ('A', 'A') | ('D', 'D') => GitStatus::Conflicted,
+            ('A', _) | (_, 'A') => GitStatus::Added,
+            ('D', _) | (_, 'D') => GitStatus::Deleted,
+            ('R', _) | (_, 'R') => GitStatus::Renamed,
+            _ => GitStatus::Modified,
+        }
+    }
+}
+
+// changed paths of the repository containing `dir`, keyed by absolute path.
+// empty when `dir` isn't inside a repository or git isn't installed
+pub fn repo_statuses(dir: &Path) -> HashMap<PathBuf, GitStatus> {
+    let mut statuses = HashMap::new();
+
+    let root = match git_output(dir, &["rev-parse", "--show-toplevel"]) {
+        Some(root) => PathBuf::from(root.trim_end()),
+        None => return statuses,
+    };
+    let output = match git_output(dir, &["status", "--porcelain=v1", "-z"]) {
+        Some(output) => output,
+        None => return statuses,
+    };
+
+    // records are `XY path`, renames and copies are followed by the old path
+    let mut records = output.split('\0');
+    while let Some(record) = records.next() {
+        let mut codes = record.chars();
+        let (x, y) = match (codes.next(), codes.next()) {
+            (Some(x), Some(y)) if record.len() > 3 => (x, y),
+            _ => continue,
+        };
+        if x == 'R' || x == 'C' {
+            records.next();
+        }
+        statuses.insert(root.join(&record[3..]), GitStatus::from_code(x, y));
+    }
+    statuses
+}
+
+// a directory takes the status of the first changed entry below it
+pub fn status_of(statuses: &HashMap<PathBuf, GitStatus>, path: &Path) -> Option<GitStatus> {
+    if let Some(status) = statuses.get(path) {
+        return Some(*status);
+    }
+    if !path.is_dir() {
+        return None;
+    }
+    statuses
+        .iter()
+        .find(|(changed, _)| changed.starts_with(path))
+        .map(|(_, status)| *status)
+}
+
+fn git_output(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()
+}
@@ -0,0 +1,161 @@
+use std::{
+    collections::HashMap,
+    io::Write,
+    path::Path,
+    process::{Child, Command, Stdio},
+    thread,
+};
+
+use serde::Serialize;
+
+use crate::editor::split_template;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HookEvent {
+    EnterDirectory,
+    Select,
+    Delete,
+    BeforeOpen,
+}
+
+impl HookEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HookEvent::EnterDirectory => "on_enter_directory",
+            HookEvent::Select => "on_select",
+            HookEvent::Delete => "on_delete",
+            HookEvent::BeforeOpen => "before_open",
+        }
+    }
+}
+
+// written to the hook's stdin
+#[derive(Serialize)]
+struct Payload<'a> {
+    event: &'static str,
+    path: Option<&'a String>,
+    paths: &'a [String],
+    cwd: String,
+}
+
+// hook name -> command from the "hooks" section of settings.json
+#[derive(Debug, Clone, Default)]
+pub struct Hooks {
+    commands: HashMap<String, String>,
+}
+
+impl Hooks {
+    pub fn new(commands: &HashMap<String, String>) -> Hooks {
+        Hooks {
+            commands: commands.clone(),
+        }
+    }
+
+    // runs the hook in the background, output is discarded so it can't draw
+    // over the tui. `cwd` is the directory shown in the listing, the hook
+    // runs there
+    pub fn fire(&self, event: HookEvent, paths: &[String], cwd: &Path) {
+        if let Ok(Some(mut child)) = self.spawn(event, paths, cwd, false) {
+            thread::spawn(move || child.wait());
+        }
+    }
+
+    // waits for the hook with the terminal handed over, a failing hook is
+    // reported as an error
+    pub fn run(&self, event: HookEvent, paths: &[String], cwd: &Path) -> anyhow::Result<()> {
+        if let Some(mut child) = self.spawn(event, paths, cwd, true)? {
+            let status = child.wait()?;
+            if !status.success() {
+                return Err(anyhow::anyhow!(
+                    "{} hook exited with {}",
+                    event.name(),
+                    status
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn spawn(
+        &self,
+        event: HookEvent,
+        paths: &[String],
+        cwd: &Path,
+        attached: bool,
+    ) -> anyhow::Result<Option<Child>> {
+        let command = match self.commands.get(event.name()) {
+            Some(command) => command,
+            None => return Ok(None),
+        };
+        let mut parts = split_template(command).into_iter();
+        let program = match parts.next() {
+            Some(program) => program,
+            None => return Ok(None),
+        };
+
+        let mut process = Command::new(program);
+        process.args(parts).current_dir(cwd).stdin(Stdio::piped());
+        if !attached {
+            process.stdout(Stdio::null()).stderr(Stdio::null());
+        }
+        let mut child = process.spawn()?;
+
+        let payload = Payload {
+            event: event.name(),
+            path: paths.first(),
+            paths,
+            cwd: cwd.display().to_string(),
+        };
+        let payload = serde_json::to_string(&payload)?;
+        if let Some(mut stdin) = child.stdin.take() {
+            // written from its own thread, a hook that never reads stdin
+            // would block a large payload. one that closes it early is fine
+            thread::spawn(move || stdin.write_all(payload.as_bytes()));
+        }
+        Ok(Some(child))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn hooks_run_in_the_listed_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut commands = HashMap::new();
+        commands.insert(
+            String::from("before_open"),
+            String::from("sh -c 'cat > payload.json'"),
+        );
+        let hooks = Hooks::new(&commands);
+        let file = dir.path().join("a.txt").to_string_lossy().to_string();
+
+        hooks
+            .run(
+                HookEvent::BeforeOpen,
+                std::slice::from_ref(&file),
+                dir.path(),
+            )
+            .unwrap();
+        let payload: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(dir.path().join("payload.json")).unwrap())
+                .unwrap();
+        assert_eq!(payload["event"], "before_open");
+        assert_eq!(payload["path"], file.as_str());
+        assert_eq!(payload["cwd"], dir.path().to_string_lossy().as_ref());
+    }
+
+    #[test]
+    fn failing_hooks_are_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut commands = HashMap::new();
+        commands.insert(String::from("before_open"), String::from("false"));
+        let hooks = Hooks::new(&commands);
+
+        assert!(hooks.run(HookEvent::BeforeOpen, &[], dir.path()).is_err());
+        // events without a hook do nothing
+        assert!(hooks.run(HookEvent::Delete, &[], dir.path()).is_ok());
+    }
+}
@@ -0,0 +1,4 @@
+// glyphs used next to entry names, plain unicode so they render without a
+// patched font
+
+pub const LOCK: &str = "🔒";
@@ -0,0 +1,122 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use ignore::{
+    gitignore::{Gitignore, GitignoreBuilder},
+    WalkBuilder,
+};
+
+// per directory ignore file for paths that should only be hidden from ff
+pub const FF_IGNORE_FILE: &str = ".ffignore";
+
+// walker honoring .gitignore, .ignore, .ffignore, the global git excludes
+// and .gitignore files of parent directories. hidden entries are left to
+// the caller since ff has its own toggle for them
+pub fn ignore_walker(root: &Path) -> WalkBuilder {
+    let mut builder = WalkBuilder::new(root);
+    builder
+        .hidden(false)
+        .parents(true)
+        .require_git(false)
+        .add_custom_ignore_filename(FF_IGNORE_FILE);
+    builder
+}
+
+// the ignore_directories of settings.json, written like .gitignore lines:
+// `node_modules` matches at any depth, `*.tmp` matches files too, `target/`
+// only directories and `/build` only right below the root
+#[derive(Clone, Debug)]
+pub struct IgnorePatterns {
+    root: PathBuf,
+    matcher: Gitignore,
+}
+
+impl Default for IgnorePatterns {
+    fn default() -> IgnorePatterns {
+        IgnorePatterns {
+            root: PathBuf::new(),
+            matcher: Gitignore::empty(),
+        }
+    }
+}
+
+impl IgnorePatterns {
+    // patterns that don't parse are left out, `ff config check` reports them
+    pub fn new(root: &Path, patterns: &[String]) -> IgnorePatterns {
+        let mut builder = GitignoreBuilder::new(root);
+        for pattern in patterns {
+            let _ = builder.add_line(None, pattern);
+        }
+        IgnorePatterns {
+            root: root.to_path_buf(),
+            matcher: builder.build().unwrap_or_else(|_| Gitignore::empty()),
+        }
+    }
+
+    // only looks at `path` itself, for walks that skip ignored directories
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        self.matcher.matched(path, is_dir).is_ignore()
+    }
+
+    // also true when one of the parent directories below the root is ignored
+    pub fn is_ignored_path(&self, path: &Path, is_dir: bool) -> bool {
+        self.is_ignored(path, is_dir)
+            || path
+                .ancestors()
+                .skip(1)
+                .take_while(|parent| parent.starts_with(&self.root) && *parent != self.root)
+                .any(|parent| self.is_ignored(parent, true))
+    }
+}
+
+// what the ignore popup starts with for `path`: anchored at the root when it
+// is below it, the name otherwise. directories end with a slash
+pub fn suggest_pattern(path: &Path, root: &Path) -> String {
+    let pattern = match path.strip_prefix(root) {
+        Ok(relative) if !relative.as_os_str().is_empty() => {
+            format!("/{}", relative.to_string_lossy().replace('\\', "/"))
+        }
+        _ => path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+    };
+    match path.is_dir() {
+        true => format!("{}/", pattern),
+        false => pattern,
+    }
+}
+
+// the patterns that aren't valid, with the reason
+pub fn invalid_patterns(patterns: &[String]) -> Vec<String> {
+    patterns
+        .iter()
+        .filter_map(|pattern| {
+            GitignoreBuilder::new("/")
+                .add_line(None, pattern)
+                .err()
+                .map(|e| format!("{}: {}", pattern, e))
+        })
+        .collect()
+}
+
+// entries of `dir` that neither the ignore files nor `patterns` hide
+pub fn filter_ignored(
+    dir: &Path,
+    entries: Vec<PathBuf>,
+    patterns: &IgnorePatterns,
+) -> Vec<PathBuf> {
+    let allowed: HashSet<PathBuf> = ignore_walker(dir)
+        .max_depth(Some(1))
+        .build()
+        .filter_map(Result::ok)
+        .map(|entry| entry.into_path())
+        .collect();
+
+    entries
+        .into_iter()
+        .filter(|entry| allowed.contains(entry) && !patterns.is_ignored(entry, entry.is_dir()))
+        .collect()
+}
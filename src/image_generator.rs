@@ -0,0 +1,46 @@
+use image::ImageReader;
+use ratatui_image::{
+    picker::{Picker, ProtocolType},
+    protocol::StatefulProtocol,
+};
+
+// the image in the preview pane and the picker that fits it to the terminal
+#[derive(Clone)]
+pub struct ImageGenerator {
+    pub image: Option<Box<dyn StatefulProtocol>>,
+    pub picker: Picker,
+}
+
+impl ImageGenerator {
+    // the picker is created once so the font size query and protocol
+    // detection (kitty, iTerm2, sixel, falling back to half blocks) only
+    // happen at startup instead of on every selected image
+    pub fn new(image_protocol: &str) -> ImageGenerator {
+        let mut picker = Picker::from_termios().unwrap_or_else(|_| Picker::new((8, 12)));
+
+        match image_protocol {
+            "kitty" => picker.protocol_type = ProtocolType::Kitty,
+            "iterm2" => picker.protocol_type = ProtocolType::Iterm2,
+            "sixel" => picker.protocol_type = ProtocolType::Sixel,
+            "halfblocks" => picker.protocol_type = ProtocolType::Halfblocks,
+            _ => {
+                picker.guess_protocol();
+            }
+        }
+
+        ImageGenerator {
+            image: None,
+            picker,
+        }
+    }
+
+    pub fn load_img(&mut self, path: String) -> anyhow::Result<()> {
+        self.image = None;
+
+        let dyn_img = ImageReader::open(path)?.with_guessed_format()?.decode()?;
+        let image = self.picker.new_resize_protocol(dyn_img);
+
+        self.image = Some(image);
+        Ok(())
+    }
+}
@@ -0,0 +1,329 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{Receiver, Sender},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::events::Waker;
+use crate::utils::format::{format_duration, format_size};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JobKind {
+    Copy,
+    Move,
+    Sync,
+    Delete,
+    Archive,
+    Extract,
+    Hash,
+    Command,
+    Convert,
+}
+
+impl JobKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            JobKind::Copy => "copy",
+            JobKind::Move => "move",
+            JobKind::Sync => "sync",
+            JobKind::Delete => "delete",
+            JobKind::Archive => "archive",
+            JobKind::Extract => "extract",
+            JobKind::Hash => "hash",
+            JobKind::Command => "command",
+            JobKind::Convert => "convert",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum JobMessage {
+    Progress(String),
+    Done(String),
+    Error(String),
+    // running byte counters for jobs that move file contents around
+    Bytes { copied: u64, total: u64 },
+    // the worker noticed the cancel flag and finished cleaning up
+    Cancelled,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobStatus {
+    Running,
+    Paused,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+// shared between the ui and a worker thread, workers poll it between units
+// of work
+#[derive(Debug, Clone, Default)]
+pub struct JobControl {
+    pub cancel: Arc<AtomicBool>,
+    pub pause: Arc<AtomicBool>,
+}
+
+impl JobControl {
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    // blocks while the job is paused, returns false if it got cancelled
+    pub fn wait_if_paused(&self) -> bool {
+        while self.pause.load(Ordering::Relaxed) && !self.is_cancelled() {
+            thread::sleep(Duration::from_millis(100));
+        }
+        !self.is_cancelled()
+    }
+}
+
+pub struct Job {
+    pub id: usize,
+    pub kind: JobKind,
+    pub description: String,
+    pub progress: String,
+    pub status: JobStatus,
+    bytes: Option<(u64, u64)>,
+    started: Instant,
+    control: JobControl,
+    receiver: Receiver<JobMessage>,
+}
+
+impl Job {
+    pub fn line(&self) -> String {
+        let status = match self.status {
+            JobStatus::Running => "running",
+            JobStatus::Paused => "paused",
+            JobStatus::Done => "done",
+            JobStatus::Failed => "failed",
+            JobStatus::Cancelled => "cancelled",
+        };
+        format!(
+            "#{} [{}] {} ({}) {}",
+            self.id,
+            self.kind.label(),
+            self.description,
+            status,
+            self.progress
+        )
+    }
+
+    fn is_active(&self) -> bool {
+        matches!(self.status, JobStatus::Running | JobStatus::Paused)
+    }
+}
+
+// combined byte progress of every running job that reports it
+pub struct TransferProgress {
+    pub copied: u64,
+    pub total: u64,
+    // bytes per second
+    pub throughput: f64,
+}
+
+impl TransferProgress {
+    pub fn ratio(&self) -> f64 {
+        if self.total == 0 {
+            return 1.0;
+        }
+        (self.copied as f64 / self.total as f64).min(1.0)
+    }
+
+    pub fn label(&self) -> String {
+        let eta = if self.throughput > 0.0 {
+            let remaining = self.total.saturating_sub(self.copied) as f64;
+            format_duration(Duration::from_secs_f64(remaining / self.throughput))
+        } else {
+            String::from("--:--")
+        };
+        format!(
+            "{:.0}% {} / {} {}/s ETA {}",
+            self.ratio() * 100.0,
+            format_size(self.copied),
+            format_size(self.total),
+            format_size(self.throughput as u64),
+            eta
+        )
+    }
+}
+
+// a status line for the footer, `finished` is set once the job that sent it
+// has stopped so callers know when to refresh the listing
+pub struct JobUpdate {
+    pub message: String,
+    pub finished: bool,
+}
+
+#[derive(Default)]
+pub struct JobManager {
+    jobs: Vec<Job>,
+    next_id: usize,
+    waker: Waker,
+}
+
+impl JobManager {
+    pub fn new(waker: Waker) -> JobManager {
+        JobManager {
+            waker,
+            ..JobManager::default()
+        }
+    }
+
+    // runs `work` on its own thread, it should check the control between
+    // units of work and can report progress through the sender
+    pub fn spawn<F>(&mut self, kind: JobKind, description: String, work: F) -> usize
+    where
+        F: FnOnce(JobControl, Sender<JobMessage>) -> anyhow::Result<String> + Send + 'static,
+    {
+        self.next_id += 1;
+        let id = self.next_id;
+        let control = JobControl::default();
+        let (sender, receiver) = self.waker.channel();
+
+        let thread_control = control.clone();
+        thread::spawn(move || {
+            let message = match work(thread_control.clone(), sender.clone()) {
+                Ok(message) => JobMessage::Done(message),
+                Err(_) if thread_control.is_cancelled() => JobMessage::Cancelled,
+                Err(e) => JobMessage::Error(e.to_string()),
+            };
+            let _ = sender.send(message);
+        });
+
+        self.jobs.push(Job {
+            id,
+            kind,
+            description,
+            progress: String::new(),
+            status: JobStatus::Running,
+            bytes: None,
+            started: Instant::now(),
+            control,
+            receiver,
+        });
+        id
+    }
+
+    pub fn poll(&mut self) -> Vec<JobUpdate> {
+        let mut updates = Vec::new();
+
+        for job in self.jobs.iter_mut() {
+            while let Ok(message) = job.receiver.try_recv() {
+                match message {
+                    JobMessage::Progress(progress) => {
+                        if !job.is_active() {
+                            continue;
+                        }
+                        job.progress = progress.clone();
+                        updates.push(JobUpdate {
+                            message: progress,
+                            finished: false,
+                        });
+                    }
+                    JobMessage::Bytes { copied, total } => {
+                        job.bytes = Some((copied, total));
+                    }
+                    JobMessage::Done(message) => {
+                        job.status = JobStatus::Done;
+                        job.progress = message.clone();
+                        updates.push(JobUpdate {
+                            message,
+                            finished: true,
+                        });
+                    }
+                    JobMessage::Error(e) => {
+                        job.status = JobStatus::Failed;
+                        job.progress = e.clone();
+                        updates.push(JobUpdate {
+                            message: format!("{} failed: {}", job.kind.label(), e),
+                            finished: true,
+                        });
+                    }
+                    JobMessage::Cancelled => {
+                        job.status = JobStatus::Cancelled;
+                        job.progress = String::from("cancelled, partial files removed");
+                        updates.push(JobUpdate {
+                            message: format!("{} cancelled", job.kind.label()),
+                            finished: true,
+                        });
+                    }
+                }
+            }
+        }
+
+        updates
+    }
+
+    pub fn cancel(&mut self, index: usize) -> Option<&Job> {
+        let job = self.jobs.get_mut(index)?;
+        if job.is_active() {
+            job.control.cancel.store(true, Ordering::Relaxed);
+            job.progress = String::from("cancelling...");
+        }
+        Some(job)
+    }
+
+    pub fn toggle_pause(&mut self, index: usize) -> Option<&Job> {
+        let job = self.jobs.get_mut(index)?;
+        match job.status {
+            JobStatus::Running => {
+                job.control.pause.store(true, Ordering::Relaxed);
+                job.status = JobStatus::Paused;
+            }
+            JobStatus::Paused => {
+                job.control.pause.store(false, Ordering::Relaxed);
+                job.status = JobStatus::Running;
+            }
+            _ => {}
+        }
+        Some(job)
+    }
+
+    // index of the most recently started job of this kind that is still running
+    pub fn latest_active(&self, kind: JobKind) -> Option<usize> {
+        self.jobs
+            .iter()
+            .rposition(|job| job.kind == kind && job.is_active())
+    }
+
+    pub fn transfer_progress(&self) -> Option<TransferProgress> {
+        let mut progress: Option<TransferProgress> = None;
+
+        for job in self.jobs.iter().filter(|job| job.is_active()) {
+            if let Some((copied, total)) = job.bytes {
+                let elapsed = job.started.elapsed().as_secs_f64();
+                let throughput = if elapsed > 0.0 {
+                    copied as f64 / elapsed
+                } else {
+                    0.0
+                };
+                let entry = progress.get_or_insert(TransferProgress {
+                    copied: 0,
+                    total: 0,
+                    throughput: 0.0,
+                });
+                entry.copied += copied;
+                entry.total += total;
+                entry.throughput += throughput;
+            }
+        }
+        progress
+    }
+
+    pub fn running_count(&self) -> usize {
+        self.jobs.iter().filter(|job| job.is_active()).count()
+    }
+
+    pub fn clear_finished(&mut self) {
+        self.jobs.retain(|job| job.is_active());
+    }
+
+    pub fn jobs(&self) -> &[Job] {
+        &self.jobs
+    }
+}
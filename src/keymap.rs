@@ -0,0 +1,743 @@
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Search,
+    Quit,
+    MoveDown,
+    MoveUp,
+    GoParent,
+    EnterDir,
+    Delete,
+    Create,
+    Extract,
+    Rename,
+    ToggleHidden,
+    Copy,
+    Sort,
+    Help,
+    Open,
+    ToggleMark,
+    BulkRename,
+    PatternRename,
+    Compare,
+    Archive,
+    ContentSearch,
+    Filter,
+    ToggleGitignore,
+    DirSize,
+    FollowLink,
+    Properties,
+    Jobs,
+    CancelCopy,
+    CopyTo,
+    MoveTo,
+    Sync,
+    Hash,
+    Jump,
+    SelectEditor,
+    Shell,
+    RunCommand,
+    Convert,
+    ToggleDetailView,
+    TreeView,
+    GotoPath,
+    GotoPrefix,
+    YankPrefix,
+    Bottom,
+    ViewTop,
+    ViewMiddle,
+    ViewBottom,
+    HalfPageDown,
+    HalfPageUp,
+    PageDown,
+    PageUp,
+    LoadMore,
+    ScrollPreviewLeft,
+    ScrollPreviewRight,
+    FoldPreview,
+    UnfoldPreview,
+    ToggleStructuredView,
+    FollowPreview,
+    SavedSearches,
+    CreateSymlink,
+    CreateHardLink,
+    Volumes,
+    Themes,
+    IgnoreDirectory,
+    RebuildCache,
+}
+
+impl Action {
+    // order used when rendering the keybinding help popup
+    pub const ALL: [Action; 64] = [
+        Action::Open,
+        Action::LoadMore,
+        Action::ScrollPreviewLeft,
+        Action::ScrollPreviewRight,
+        Action::FoldPreview,
+        Action::UnfoldPreview,
+        Action::ToggleStructuredView,
+        Action::FollowPreview,
+        Action::SavedSearches,
+        Action::CreateSymlink,
+        Action::CreateHardLink,
+        Action::Volumes,
+        Action::Themes,
+        Action::IgnoreDirectory,
+        Action::RebuildCache,
+        Action::SelectEditor,
+        Action::MoveDown,
+        Action::MoveUp,
+        Action::HalfPageDown,
+        Action::HalfPageUp,
+        Action::PageDown,
+        Action::PageUp,
+        Action::GotoPrefix,
+        Action::Bottom,
+        Action::ViewTop,
+        Action::ViewMiddle,
+        Action::ViewBottom,
+        Action::GoParent,
+        Action::EnterDir,
+        Action::FollowLink,
+        Action::Search,
+        Action::Jump,
+        Action::GotoPath,
+        Action::ContentSearch,
+        Action::Filter,
+        Action::Sort,
+        Action::DirSize,
+        Action::Create,
+        Action::Delete,
+        Action::Rename,
+        Action::Copy,
+        Action::CopyTo,
+        Action::MoveTo,
+        Action::Sync,
+        Action::Properties,
+        Action::YankPrefix,
+        Action::Hash,
+        Action::Compare,
+        Action::Extract,
+        Action::ToggleMark,
+        Action::BulkRename,
+        Action::PatternRename,
+        Action::Archive,
+        Action::Jobs,
+        Action::CancelCopy,
+        Action::Shell,
+        Action::RunCommand,
+        Action::Convert,
+        Action::ToggleHidden,
+        Action::ToggleDetailView,
+        Action::TreeView,
+        Action::ToggleGitignore,
+        Action::Help,
+        Action::Quit,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Search => "search",
+            Action::Quit => "quit",
+            Action::MoveDown => "move_down",
+            Action::MoveUp => "move_up",
+            Action::GoParent => "parent",
+            Action::EnterDir => "enter",
+            Action::Delete => "delete",
+            Action::Create => "create",
+            Action::Extract => "extract",
+            Action::Rename => "rename",
+            Action::ToggleHidden => "toggle_hidden",
+            Action::Copy => "copy",
+            Action::Sort => "sort",
+            Action::Help => "help",
+            Action::Open => "open",
+            Action::ToggleMark => "toggle_mark",
+            Action::BulkRename => "bulk_rename",
+            Action::PatternRename => "pattern_rename",
+            Action::Compare => "compare",
+            Action::Archive => "archive",
+            Action::ContentSearch => "content_search",
+            Action::Filter => "filter",
+            Action::ToggleGitignore => "toggle_gitignore",
+            Action::DirSize => "dir_size",
+            Action::FollowLink => "follow_link",
+            Action::Properties => "properties",
+            Action::Jobs => "jobs",
+            Action::CancelCopy => "cancel_copy",
+            Action::CopyTo => "copy_to",
+            Action::MoveTo => "move_to",
+            Action::Sync => "sync",
+            Action::Hash => "hash",
+            Action::Jump => "jump",
+            Action::SelectEditor => "select_editor",
+            Action::Shell => "shell",
+            Action::RunCommand => "run_command",
+            Action::Convert => "convert",
+            Action::ToggleDetailView => "toggle_detail_view",
+            Action::TreeView => "tree_view",
+            Action::GotoPath => "goto_path",
+            Action::GotoPrefix => "goto_prefix",
+            Action::YankPrefix => "yank_prefix",
+            Action::Bottom => "bottom",
+            Action::ViewTop => "view_top",
+            Action::ViewMiddle => "view_middle",
+            Action::ViewBottom => "view_bottom",
+            Action::HalfPageDown => "half_page_down",
+            Action::HalfPageUp => "half_page_up",
+            Action::PageDown => "page_down",
+            Action::PageUp => "page_up",
+            Action::LoadMore => "load_more",
+            Action::ScrollPreviewLeft => "scroll_preview_left",
+            Action::ScrollPreviewRight => "scroll_preview_right",
+            Action::FoldPreview => "fold_preview",
+            Action::UnfoldPreview => "unfold_preview",
+            Action::ToggleStructuredView => "toggle_structured_view",
+            Action::FollowPreview => "follow_preview",
+            Action::SavedSearches => "saved_searches",
+            Action::CreateSymlink => "create_symlink",
+            Action::CreateHardLink => "create_hard_link",
+            Action::Volumes => "volumes",
+            Action::Themes => "themes",
+            Action::IgnoreDirectory => "ignore_directory",
+            Action::RebuildCache => "rebuild_cache",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Search => "Search mode",
+            Action::Quit => "Exit",
+            Action::MoveDown => "Move down",
+            Action::MoveUp => "Move up",
+            Action::GoParent => "Go to parent directory",
+            Action::EnterDir => "Enter directory",
+            Action::Delete => "Delete",
+            Action::Create => "Create new",
+            Action::Extract => "Extract zip/tar archive",
+            Action::Rename => "Rename",
+            Action::ToggleHidden => "Show hidden files",
+            Action::Copy => "Copy dir/file",
+            Action::Sort => "Sort",
+            Action::Help => "Keybindings",
+            Action::Open => "Open with the selected editor",
+            Action::ToggleMark => "Mark/unmark file",
+            Action::BulkRename => "Bulk rename marked files in $EDITOR",
+            Action::PatternRename => "Rename marked files with s/from/to/ or a {name}_{n}.{ext} template",
+            Action::Archive => "Create zip/tar.gz archive",
+            Action::ContentSearch => "Search inside files",
+            Action::Filter => "Filter (*.rs, ext:rs, type:dir, size>10M, modified<7d, !term)",
+            Action::ToggleGitignore => "Hide/show .gitignore and .ffignore matches",
+            Action::DirSize => "Calculate directory sizes",
+            Action::FollowLink => "Jump to symlink target",
+            Action::Properties => "Properties and permissions",
+            Action::Jobs => "Background jobs",
+            Action::CancelCopy => "Cancel the running copy",
+            Action::CopyTo => "Copy to another directory",
+            Action::MoveTo => "Move to another directory",
+            Action::Sync => "Mirror the selected directory into another one",
+            Action::Hash => "Checksum (MD5, SHA-1, SHA-256, BLAKE3)",
+            Action::Compare => "Diff the two marked files or directories",
+            Action::Jump => "Jump to a frequently used directory",
+            Action::SelectEditor => "Choose the editor used to open files",
+            Action::Shell => "Open a shell in the current directory",
+            Action::RunCommand => "Run a custom command on the selection",
+            Action::Convert => "Convert the selection with a configured converter",
+            Action::ToggleDetailView => "Show size, date and permission columns",
+            Action::TreeView => "Browse the current directory as a tree",
+            Action::GotoPath => "Go to a typed path",
+            Action::GotoPrefix => "gg top, g/ go to path, g<letter> next entry starting with it",
+            Action::YankPrefix => {
+                "Copy to the clipboard: yp path, yn name, yr path from the start directory, yd parent"
+            }
+            Action::Bottom => "Last entry",
+            Action::ViewTop => "Top of the screen",
+            Action::ViewMiddle => "Middle of the screen",
+            Action::ViewBottom => "Bottom of the screen",
+            Action::HalfPageDown => "Half a page down",
+            Action::HalfPageUp => "Half a page up",
+            Action::PageDown => "Page down",
+            Action::PageUp => "Page up",
+            Action::LoadMore => "Load more of a truncated preview",
+            Action::ScrollPreviewLeft => "Scroll the table preview left",
+            Action::ScrollPreviewRight => "Scroll the table preview right",
+            Action::FoldPreview => "Fold the tree preview node",
+            Action::UnfoldPreview => "Unfold the tree preview node",
+            Action::ToggleStructuredView => "Switch between tree and raw preview",
+            Action::FollowPreview => "Follow the end of the previewed file",
+            Action::SavedSearches => "Saved searches",
+            Action::CreateSymlink => "Create a symlink to the selected entry",
+            Action::CreateHardLink => "Create a hard link to the selected file",
+            Action::Volumes => "Mounted drives and network shares",
+            Action::Themes => "Pick a theme",
+            Action::IgnoreDirectory => "Add the selected entry to the ignore list",
+            Action::RebuildCache => "Rebuild the directory cache",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL
+            .iter()
+            .copied()
+            .find(|action| action.name() == name)
+    }
+
+    fn default_keys(&self) -> Vec<&'static str> {
+        match self {
+            Action::Search => vec!["i"],
+            Action::Quit => vec!["q"],
+            Action::MoveDown => vec!["j", "down"],
+            Action::MoveUp => vec!["k", "up"],
+            Action::GoParent => vec!["h"],
+            Action::EnterDir => vec!["l"],
+            Action::Delete => vec!["d"],
+            Action::Create => vec!["a"],
+            Action::Extract => vec!["x"],
+            Action::Rename => vec!["r"],
+            Action::ToggleHidden => vec!["."],
+            Action::Copy => vec!["c"],
+            Action::Sort => vec!["s"],
+            Action::Help => vec!["?"],
+            Action::Open => vec!["enter"],
+            Action::ToggleMark => vec!["space"],
+            Action::BulkRename => vec!["R"],
+            Action::PatternRename => vec!["P"],
+            Action::Compare => vec!["="],
+            Action::Archive => vec!["A"],
+            Action::ContentSearch => vec!["ctrl-f"],
+            Action::Filter => vec!["f"],
+            Action::ToggleGitignore => vec!["I"],
+            Action::DirSize => vec!["u"],
+            Action::FollowLink => vec!["o"],
+            Action::Properties => vec!["ctrl-p"],
+            Action::Jobs => vec!["J"],
+            Action::CancelCopy => vec!["esc"],
+            Action::CopyTo => vec!["C"],
+            Action::MoveTo => vec!["m"],
+            Action::Sync => vec!["ctrl-s"],
+            Action::Hash => vec!["#"],
+            Action::Jump => vec!["z"],
+            Action::SelectEditor => vec!["e"],
+            Action::Shell => vec!["ctrl-z"],
+            Action::RunCommand => vec![":"],
+            Action::Convert => vec!["b"],
+            Action::ToggleDetailView => vec!["ctrl-l"],
+            Action::TreeView => vec!["t"],
+            Action::GotoPath => vec!["ctrl-g"],
+            Action::GotoPrefix => vec!["g"],
+            Action::YankPrefix => vec!["y"],
+            Action::Bottom => vec!["G"],
+            Action::ViewTop => vec!["H"],
+            Action::ViewMiddle => vec!["M"],
+            Action::ViewBottom => vec!["L"],
+            Action::HalfPageDown => vec!["ctrl-d"],
+            Action::HalfPageUp => vec!["ctrl-u"],
+            Action::PageDown => vec!["pagedown"],
+            Action::PageUp => vec!["pageup"],
+            Action::LoadMore => vec!["v"],
+            Action::ScrollPreviewLeft => vec!["["],
+            Action::ScrollPreviewRight => vec!["]"],
+            Action::FoldPreview => vec!["left"],
+            Action::UnfoldPreview => vec!["right"],
+            Action::ToggleStructuredView => vec!["T"],
+            Action::FollowPreview => vec!["F"],
+            Action::SavedSearches => vec!["S"],
+            Action::CreateSymlink => vec!["n"],
+            Action::CreateHardLink => vec!["N"],
+            Action::Volumes => vec!["V"],
+            Action::Themes => vec!["ctrl-t"],
+            Action::IgnoreDirectory => vec!["X"],
+            Action::RebuildCache => vec!["ctrl-r"],
+        }
+    }
+}
+
+// a binding in settings.json can either be a single key or a list of keys,
+// e.g. "delete": "d" or "move_down": ["j", "down"]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum KeySpec {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeySpec {
+    fn keys(&self) -> Vec<String> {
+        match self {
+            KeySpec::One(key) => vec![key.clone()],
+            KeySpec::Many(keys) => keys.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyBinding {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    pub fn parse(value: &str) -> anyhow::Result<KeyBinding> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = value.trim();
+
+        // "-" and "+" are valid keys on their own, only treat them as separators
+        // when a modifier prefix is present
+        loop {
+            if let Some(stripped) = strip_modifier(rest, "ctrl") {
+                modifiers |= KeyModifiers::CONTROL;
+                rest = stripped;
+            } else if let Some(stripped) = strip_modifier(rest, "alt") {
+                modifiers |= KeyModifiers::ALT;
+                rest = stripped;
+            } else {
+                break;
+            }
+        }
+
+        let code = match rest.to_lowercase().as_str() {
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "space" => KeyCode::Char(' '),
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            key if key.starts_with('f') && key.len() > 1 => match key[1..].parse::<u8>() {
+                Ok(n) => KeyCode::F(n),
+                Err(_) => return Err(anyhow!("unknown key '{}'", value)),
+            },
+            _ => {
+                let mut chars = rest.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => return Err(anyhow!("unknown key '{}'", value)),
+                }
+            }
+        };
+
+        Ok(KeyBinding { code, modifiers })
+    }
+
+    pub fn from_event(key: &KeyEvent) -> KeyBinding {
+        // shift is already encoded in the char itself ('R' vs 'r')
+        let modifiers = key.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT);
+        KeyBinding {
+            code: key.code,
+            modifiers,
+        }
+    }
+
+    pub fn display(&self) -> String {
+        let mut prefix = String::new();
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            prefix.push_str("ctrl-");
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            prefix.push_str("alt-");
+        }
+
+        let key = match self.code {
+            KeyCode::Char(' ') => "space".to_string(),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::Enter => "enter".to_string(),
+            KeyCode::Esc => "esc".to_string(),
+            KeyCode::Tab => "tab".to_string(),
+            KeyCode::BackTab => "backtab".to_string(),
+            KeyCode::Backspace => "backspace".to_string(),
+            KeyCode::Delete => "delete".to_string(),
+            KeyCode::Up => "up".to_string(),
+            KeyCode::Down => "down".to_string(),
+            KeyCode::Left => "left".to_string(),
+            KeyCode::Right => "right".to_string(),
+            KeyCode::Home => "home".to_string(),
+            KeyCode::End => "end".to_string(),
+            KeyCode::PageUp => "pageup".to_string(),
+            KeyCode::PageDown => "pagedown".to_string(),
+            KeyCode::F(n) => format!("f{}", n),
+            _ => "?".to_string(),
+        };
+
+        format!("{}{}", prefix, key)
+    }
+}
+
+// the prefix is matched ignoring ascii case only, lowercasing the whole value
+// could change the byte length of the key after it
+fn strip_modifier<'a>(value: &'a str, modifier: &str) -> Option<&'a str> {
+    let prefix = value.get(..modifier.len())?;
+    if !prefix.eq_ignore_ascii_case(modifier) {
+        return None;
+    }
+    let rest = &value[modifier.len()..];
+    let rest = rest.strip_prefix('-').or_else(|| rest.strip_prefix('+'))?;
+    if rest.is_empty() {
+        None
+    } else {
+        Some(rest)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct KeyMap {
+    bindings: HashMap<KeyBinding, Action>,
+    keys_by_action: HashMap<Action, Vec<KeyBinding>>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        KeyMap::new(&HashMap::new()).expect("default keybindings should not conflict")
+    }
+}
+
+impl KeyMap {
+    // user bindings replace the default keys of the action they name, every
+    // other action keeps its defaults
+    pub fn new(overrides: &HashMap<String, KeySpec>) -> anyhow::Result<KeyMap> {
+        let mut errors: Vec<String> = Vec::new();
+        let mut keys_by_action: HashMap<Action, Vec<KeyBinding>> = HashMap::new();
+
+        for action in Action::ALL.iter() {
+            let defaults = action
+                .default_keys()
+                .iter()
+                .map(|key| KeyBinding::parse(key).unwrap())
+                .collect();
+            keys_by_action.insert(*action, defaults);
+        }
+
+        for (name, spec) in overrides.iter() {
+            let action = match Action::from_name(name) {
+                Some(action) => action,
+                None => {
+                    errors.push(format!("unknown action '{}'", name));
+                    continue;
+                }
+            };
+
+            let mut keys = Vec::new();
+            for key in spec.keys() {
+                match KeyBinding::parse(&key) {
+                    Ok(binding) => keys.push(binding),
+                    Err(e) => errors.push(format!("{}: {}", name, e)),
+                }
+            }
+            keys_by_action.insert(action, keys);
+        }
+
+        let mut bindings: HashMap<KeyBinding, Action> = HashMap::new();
+        for action in Action::ALL.iter() {
+            for binding in keys_by_action[action].iter() {
+                if let Some(existing) = bindings.insert(*binding, *action) {
+                    if existing != *action {
+                        errors.push(format!(
+                            "'{}' is bound to both '{}' and '{}'",
+                            binding.display(),
+                            existing.name(),
+                            action.name()
+                        ));
+                    }
+                }
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(anyhow!("invalid keybindings: {}", errors.join(", ")));
+        }
+
+        Ok(KeyMap {
+            bindings,
+            keys_by_action,
+        })
+    }
+
+    pub fn resolve(&self, key: &KeyEvent) -> Option<Action> {
+        self.bindings.get(&KeyBinding::from_event(key)).copied()
+    }
+
+    pub fn keys_for(&self, action: Action) -> String {
+        match self.keys_by_action.get(&action) {
+            Some(keys) if !keys.is_empty() => keys
+                .iter()
+                .map(|key| key.display())
+                .collect::<Vec<String>>()
+                .join(" / "),
+            _ => "unbound".to_string(),
+        }
+    }
+
+    pub fn help_lines(&self) -> Vec<String> {
+        Action::ALL
+            .iter()
+            .map(|action| format!("< {} >: {}", self.keys_for(*action), action.description()))
+            .collect()
+    }
+}
+
+// keys of the popups that are a list to pick from. they aren't configurable,
+// letters a popup handles itself come on top
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListAction {
+    Next,
+    Previous,
+    Confirm,
+    Close,
+}
+
+pub fn list_action(key: &KeyEvent) -> Option<ListAction> {
+    match key.code {
+        KeyCode::Down | KeyCode::Char('j') => Some(ListAction::Next),
+        KeyCode::Up | KeyCode::Char('k') => Some(ListAction::Previous),
+        KeyCode::Enter => Some(ListAction::Confirm),
+        KeyCode::Esc | KeyCode::Char('q') => Some(ListAction::Close),
+        _ => None,
+    }
+}
+
+// keys of the prompts that edit a name, left and right move the cursor in
+// the prompts that have one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptAction {
+    Insert(char),
+    Backspace,
+    Left,
+    Right,
+    Cancel,
+    Confirm,
+}
+
+pub fn prompt_action(key: &KeyEvent) -> Option<PromptAction> {
+    match key.code {
+        KeyCode::Char(c) => Some(PromptAction::Insert(c)),
+        KeyCode::Backspace => Some(PromptAction::Backspace),
+        KeyCode::Left => Some(PromptAction::Left),
+        KeyCode::Right => Some(PromptAction::Right),
+        KeyCode::Esc => Some(PromptAction::Cancel),
+        KeyCode::Enter => Some(PromptAction::Confirm),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyBinding {
+        KeyBinding { code, modifiers }
+    }
+
+    fn overrides(bindings: &[(&str, &str)]) -> HashMap<String, KeySpec> {
+        bindings
+            .iter()
+            .map(|(action, key)| (action.to_string(), KeySpec::One(key.to_string())))
+            .collect()
+    }
+
+    #[test]
+    fn parses_keys_and_modifiers() {
+        let parse = |value| KeyBinding::parse(value).unwrap();
+        assert_eq!(parse("j"), key(KeyCode::Char('j'), KeyModifiers::NONE));
+        assert_eq!(parse("R"), key(KeyCode::Char('R'), KeyModifiers::NONE));
+        assert_eq!(parse("Enter"), key(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(parse("f5"), key(KeyCode::F(5), KeyModifiers::NONE));
+        assert_eq!(
+            parse("ctrl-f"),
+            key(KeyCode::Char('f'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(
+            parse("Ctrl+Alt-down"),
+            key(KeyCode::Down, KeyModifiers::CONTROL | KeyModifiers::ALT)
+        );
+        // separators are keys of their own without a modifier
+        assert_eq!(parse("-"), key(KeyCode::Char('-'), KeyModifiers::NONE));
+        assert_eq!(
+            parse("ctrl--"),
+            key(KeyCode::Char('-'), KeyModifiers::CONTROL)
+        );
+    }
+
+    #[test]
+    fn non_ascii_keys_keep_their_case() {
+        assert_eq!(
+            KeyBinding::parse("ctrl-ẞ").unwrap(),
+            key(KeyCode::Char('ẞ'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(
+            KeyBinding::parse("ALT-é").unwrap(),
+            key(KeyCode::Char('é'), KeyModifiers::ALT)
+        );
+        assert!(KeyBinding::parse("ẞẞ").is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        for value in ["", "ctrl-", "jk", "fx", "shift-a"] {
+            assert!(KeyBinding::parse(value).is_err(), "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for value in [
+            "j",
+            "space",
+            "enter",
+            "backtab",
+            "pagedown",
+            "f12",
+            "ctrl-p",
+            "alt-x",
+            "ctrl-alt-up",
+            "ctrl-ẞ",
+        ] {
+            let binding = KeyBinding::parse(value).unwrap();
+            assert_eq!(binding.display(), value);
+            assert_eq!(KeyBinding::parse(&binding.display()).unwrap(), binding);
+        }
+    }
+
+    #[test]
+    fn overrides_replace_the_default_keys() {
+        let keymap = KeyMap::new(&overrides(&[("delete", "x"), ("extract", "alt-x")])).unwrap();
+        let press = |code| KeyEvent::new(code, KeyModifiers::NONE);
+
+        assert_eq!(
+            keymap.resolve(&press(KeyCode::Char('x'))),
+            Some(Action::Delete)
+        );
+        assert_eq!(keymap.resolve(&press(KeyCode::Char('d'))), None);
+        assert_eq!(keymap.keys_for(Action::Delete), "x");
+        assert_eq!(keymap.keys_for(Action::MoveDown), "j / down");
+    }
+
+    #[test]
+    fn conflicting_bindings_are_an_error() {
+        let error = KeyMap::new(&overrides(&[("delete", "j")]))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("'j' is bound to both"), "{}", error);
+    }
+
+    #[test]
+    fn unknown_actions_and_keys_are_an_error() {
+        let error = KeyMap::new(&overrides(&[("explode", "z"), ("delete", "nope")]))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("unknown action 'explode'"), "{}", error);
+        assert!(error.contains("delete: unknown key 'nope'"), "{}", error);
+    }
+}
@@ -1,208 +1,166 @@
 use app::{App, InputMode};
-use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
+use crossterm::event::{
+    DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+};
 use file_reader_content::{FileContent, FileType};
-use image::ImageReader;
-use rayon::prelude::*;
 use std::{
+    collections::VecDeque,
     env,
-    fs::{self, File, Metadata},
-    io::{self, ErrorKind, Stdout},
-    path::{Path, PathBuf},
+    fs::{self, File},
+    io::{self, ErrorKind},
+    path::{Path, PathBuf, MAIN_SEPARATOR},
     process::Command,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{Receiver, TryRecvError},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime},
 };
-use syntect::{highlighting::ThemeSet, parsing::SyntaxSet, util::LinesWithEndings};
-use walkdir::WalkDir;
+use syntect::{highlighting::ThemeSet, parsing::SyntaxSet};
 
 use ratatui::{prelude::*, widgets::Clear};
 
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEventKind},
+    event::{
+        Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent,
+        MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{
     backend::CrosstermBackend,
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::Text,
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    widgets::{Block, Borders, Gauge, List, ListItem, ListState, Paragraph, TableState, Wrap},
     Terminal,
 };
 
-use ratatui_image::{picker::Picker, protocol::StatefulProtocol, StatefulImage};
+use ratatui_image::StatefulImage;
+
+use crate::associations::{Association, Associations};
+use crate::compare::Comparison;
+use crate::content_search::ContentSearchMessage;
+use crate::dispatch::{dispatch, Listing, Outcome};
+use crate::entry_meta::EntryMetaCache;
+use crate::events::{AppEvent, Events};
+use crate::frecency::{record_visit, FrecencyStore};
+use crate::hooks::{HookEvent, Hooks};
+use crate::ignore_rules::IgnorePatterns;
+use crate::image_generator::ImageGenerator;
+use crate::jobs::{JobKind, JobManager};
+use crate::keymap::{list_action, prompt_action, Action, KeyMap, ListAction, PromptAction};
+use crate::operations::archive::{self, ArchiveFormat};
+use crate::operations::bulk_rename::{self, RenamePlan};
+use crate::operations::copy;
+use crate::operations::dir_size::{self, DirSizeMessage};
+use crate::operations::file_ops::{self, LinkKind};
+use crate::operations::hash::{self, HashAlgorithm};
+use crate::operations::permissions::{self, PERMISSION_BITS};
+use crate::operations::sync::{self, SyncKind, SyncPlan};
+use crate::operations::transfer;
+use crate::utils::files::{
+    convert_file_path_to_string, generate_copy_file_dir_name, get_curr_path, get_file_path_data,
+    is_file, relative_path, SortBy, SortOptions, SortType,
+};
+use crate::utils::format::{capitalize, format_count, format_size};
+use crate::view_state::{read_with_view_state, remember_view, ViewStateStore};
+use crate::watcher::FileSystemWatcher;
 
+use crate::converters::{converters_from_config, ConversionResult};
+use crate::custom_commands::{commands_from_config, CustomCommand, RunMode};
 use crate::directory_store::{
-    build_directory_from_store, load_directory_from_file, save_directory_to_file,
+    build_store_in_background, load_store, save_directory_to_file, CacheMessage, DirectoryStore,
 };
+use crate::editor::{editors_from_config, Position};
+use crate::nav::{containing_dir, expand_path, pasted_paths};
+use crate::profile::Profile;
+use crate::scripting::{ScriptContext, ScriptEffect, ScriptEngine};
+use crate::search_history::{SearchHistory, SearchKind};
+use crate::search_worker::SearchWorker;
+use crate::selection::{update_file_references_with_selection_preservation, SelectionController};
+use crate::status_bar::StatusBar;
+use crate::terminal::{open_shell, with_suspended, TerminalOutput};
+use crate::thumbnails::Thumbnails;
+use crate::ui::{list_rows, tree_list, TreeState};
 
 extern crate copypasta;
 use copypasta::{ClipboardContext, ClipboardProvider};
 
 mod app;
+mod associations;
+mod cli;
+mod compare;
+mod config_check;
 mod configuration;
+mod content_search;
+mod converters;
+mod custom_commands;
 mod directory_store;
+mod dispatch;
+mod editor;
+mod entry_meta;
+mod errors;
+mod events;
 mod file_reader_content;
+mod filter;
+mod frecency;
+mod git_status;
+mod hooks;
+mod icons;
+mod ignore_rules;
+mod image_generator;
+mod jobs;
+mod keymap;
+mod nav;
+mod office;
+mod operations;
+mod picker;
+mod preview;
+mod profile;
+mod remote_browser;
+mod scripting;
+mod search_history;
+mod search_query;
+mod search_worker;
+mod selection;
+mod status_bar;
+mod structured_preview;
+mod system;
+mod terminal;
+mod theme;
+mod thumbnails;
 mod ui;
-
-#[derive(Clone)]
-enum SortType {
-    ASC,
-    DESC,
-}
-
-#[derive(Clone)]
-enum SortBy {
-    Name,
-    Size,
-    DateAdded,
-    Default,
-}
-
-#[derive(Clone)]
-struct ImageGenerator {
-    image: Option<Box<dyn StatefulProtocol>>,
-}
-
-impl ImageGenerator {
-    pub fn new() -> ImageGenerator {
-        ImageGenerator { image: None }
-    }
-
-    pub fn load_img(&mut self, path: String) {
-        let mut picker = Picker::new((8, 12));
-        picker.guess_protocol();
-
-        let dyn_img = ImageReader::open(path)
-            .expect("unable to open img")
-            .decode()
-            .expect("unable to decode image");
-        let image = picker.new_resize_protocol(dyn_img);
-
-        self.image = Some(image);
-
-        //ImageGenerator {image: Some(image)}
-    }
-}
-
-fn sort_entries_by_type(
-    sort_by: SortBy,
-    sort_type: SortType,
-    mut entries: Vec<PathBuf>,
-) -> Vec<PathBuf> {
-    match sort_type {
-        SortType::ASC => match sort_by {
-            SortBy::Name => entries.sort_by(|a, b| {
-                a.file_name()
-                    .unwrap()
-                    .to_ascii_lowercase()
-                    .cmp(&b.file_name().unwrap().to_ascii_lowercase())
-            }),
-            SortBy::Size => entries.sort_by(|a, b| {
-                a.metadata()
-                    .ok()
-                    .map(|meta| meta.len())
-                    .unwrap_or(0)
-                    .cmp(&b.metadata().ok().map(|meta| meta.len()).unwrap_or(0))
-            }),
-
-            SortBy::DateAdded => entries.sort_by(|a, b| {
-                a.metadata()
-                    .ok()
-                    .and_then(|meta| meta.created().ok())
-                    .unwrap_or(std::time::SystemTime::now())
-                    .cmp(
-                        &b.metadata()
-                            .ok()
-                            .and_then(|meta| meta.created().ok())
-                            .unwrap_or(std::time::SystemTime::now()),
-                    )
-            }),
-            _ => {}
-        },
-        SortType::DESC => match sort_by {
-            SortBy::Name => entries.sort_by(|a, b| {
-                b.file_name()
-                    .unwrap()
-                    .to_ascii_lowercase()
-                    .cmp(&a.file_name().unwrap().to_ascii_lowercase())
-            }),
-            SortBy::Size => entries.sort_by(|a, b| {
-                b.metadata()
-                    .ok()
-                    .map(|meta| meta.len())
-                    .unwrap_or(0)
-                    .cmp(&a.metadata().ok().map(|meta| meta.len()).unwrap_or(0))
-            }),
-            SortBy::DateAdded => entries.sort_by(|a, b| {
-                b.metadata()
-                    .ok()
-                    .and_then(|meta| meta.created().ok())
-                    .unwrap_or(std::time::SystemTime::now())
-                    .cmp(
-                        &a.metadata()
-                            .ok()
-                            .and_then(|meta| meta.created().ok())
-                            .unwrap_or(std::time::SystemTime::now()),
-                    )
-            }),
-            _ => {}
-        },
-    }
-
-    entries
-}
+mod utils;
+mod vfs;
+mod view_state;
+mod watcher;
+
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+// how long a toast stays on screen
+const TOAST_DURATION: Duration = Duration::from_secs(4);
+// longest time between two frames while nothing happens
+const REDRAW_TICK: Duration = Duration::from_secs(1);
+// background updates closer together than this share a frame
+const FRAME_INTERVAL: Duration = Duration::from_millis(33);
+const NO_EDITOR: &str = "No editor selected, press e to pick one or yp to copy the path";
 
 // TODO: refator this method, too many string conversions
-fn convert_file_path_to_string(
-    entries: Vec<PathBuf>,
-    show_hidden: bool,
-    sort_by: SortBy,
-    sort_type: SortType,
-) -> Vec<String> {
-    let mut file_strings: Vec<String> = Vec::new();
-
-    let sort_entries = sort_entries_by_type(sort_by, sort_type, entries);
-    let mut path_buf_list = Vec::new();
-
-    for value in sort_entries {
-        if value.is_dir() {
-            path_buf_list.push(value);
-        } else if value.is_file() {
-            let file_name = value.file_name().unwrap();
-
-            path_buf_list.push(value);
-            /* if !file_name.to_str().unwrap().ends_with("png") {
-                path_buf_list.push(value);
-            } */
-        }
-    }
-    if !show_hidden {
-        for entry in path_buf_list {
-            if entry.is_dir() {
-                let file = entry.clone().into_os_string().to_str().unwrap().to_string();
-                file_strings.push(file);
-            } else if entry.is_file() {
-                let file_name = entry.file_name().unwrap().to_str().unwrap();
-                if !file_name.starts_with(".") {
-                    let entry_value = entry.to_str().unwrap().to_string();
-                    file_strings.push(entry_value);
-                }
-            }
-        }
-    } else {
-        for entry in path_buf_list {
-            let file = entry.clone().into_os_string().to_str().unwrap().to_string();
-            file_strings.push(file);
-        }
-    }
-
-    file_strings
-}
-
+// several files are passed to the editor in one invocation, the position is
+// only used for a single file
 fn handle_file_selection(
-    file: &str,
-    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    files: &[String],
+    position: Option<Position>,
+    terminal: &mut Terminal<CrosstermBackend<TerminalOutput>>,
+    events: &Events,
     app: App,
+    hooks: &Hooks,
 ) -> anyhow::Result<()> {
+    // ff exits after the selection, the input thread must not read the keys
+    // meant for the editor
+    let _paused = events.pause_input();
     disable_raw_mode()?;
     execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
 
@@ -210,86 +168,107 @@ fn handle_file_selection(
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
     terminal.show_cursor()?;
     terminal.clear()?;
 
-    let ide = app.get_selected_ide();
-    if ide.is_some() {
-        let selected_ide = ide.unwrap();
+    let paths = files.to_vec();
+    hooks.fire(HookEvent::Select, &paths, &app.current_dir);
+
+    if app.print_on_select {
+        for file in files {
+            let path = std::path::absolute(file).unwrap_or_else(|_| PathBuf::from(file));
+            println!("{}", path.display());
+        }
+        return Ok(());
+    }
 
-        if Path::new(file).exists() {
-            let output = Command::new(selected_ide.to_owned())
-                .arg(file.to_owned())
-                .status()
-                .expect("Failed to open file");
+    if let Some(editor) = app.selected_editor.clone() {
+        let command_line = match files {
+            [file] => editor.command_line(file, position),
+            _ => editor.command_line_many(files),
+        };
+        let exists = !files.is_empty() && files.iter().all(|file| Path::new(file).exists());
+        if let (true, Some((program, args))) = (exists, command_line) {
+            if let Err(e) = hooks.run(HookEvent::BeforeOpen, &paths, &app.current_dir) {
+                println!("Not opening {}: {}", files.join(", "), e);
+                return Ok(());
+            }
+            // a typo in a configured template is the usual cause
+            let output = Command::new(&program).args(args).status().map_err(|e| {
+                anyhow::anyhow!(
+                    "unable to run {} for {}: {}, configured editors: {}",
+                    program,
+                    editor.name,
+                    e,
+                    app.editor_names()
+                )
+            })?;
 
             if output.success() {
-                println!("Successfully opened file with {}", selected_ide);
+                println!("Successfully opened file with {}", editor.name);
             } else {
-                println!("Failed to open file with {}", selected_ide);
+                println!("Failed to open file with {}", editor.name);
             }
         }
-    } else {
-        let mut ctx = ClipboardContext::new().unwrap();
-        ctx.set_contents(file.to_owned()).unwrap();
     }
 
     Ok(())
 }
 
+fn edit_names_in_editor(
+    terminal: &mut Terminal<CrosstermBackend<TerminalOutput>>,
+    events: &Events,
+    paths: &[String],
+) -> anyhow::Result<Vec<String>> {
+    let names_path = bulk_rename::write_names_file(paths)?;
+    let editor = env::var("EDITOR").unwrap_or_else(|_| String::from("vi"));
+    let mut editor_parts = editor.split_whitespace();
+    let editor_cmd = editor_parts.next().unwrap_or("vi");
+
+    let status = with_suspended(terminal, events, |_| {
+        let status = Command::new(editor_cmd)
+            .args(editor_parts)
+            .arg(&names_path)
+            .status()?;
+        Ok(status)
+    })?;
+
+    if !status.success() {
+        return Err(anyhow::anyhow!("{} exited with an error", editor_cmd));
+    }
+
+    let names = bulk_rename::read_names_file(&names_path)?;
+    Ok(names)
+}
+
 fn get_inner_files_info(
     file: String,
     show_hidden_files: bool,
+    ignore: Option<&IgnorePatterns>,
     sort_by: SortBy,
     sort_type: &SortType,
-) -> anyhow::Result<Option<Vec<String>>> {
-    let entries = match fs::read_dir(file) {
-        Ok(en) => {
-            let val = en.map(|res| res.map(|e| e.path())).collect();
-            match val {
-                Ok(v) => v,
-                Err(e) => {
-                    println!("Error: {}", e);
-                    return Ok(None);
-                }
-            }
-        }
-        Err(e) => {
-            println!("Error: {}", e);
-            return Ok(None);
-        }
-    };
-
-    let file_strings =
-        convert_file_path_to_string(entries, show_hidden_files, sort_by, sort_type.clone());
-    Ok(Some(file_strings))
-}
+    sort_options: &SortOptions,
+) -> anyhow::Result<Vec<String>> {
+    let entries = fs::read_dir(&file)?
+        .map(|res| res.map(|e| e.path()))
+        .collect::<Result<Vec<_>, io::Error>>()?;
 
-fn get_content_from_path(path: String) -> Option<Vec<String>> {
-    let mut file_name_list: Vec<String> = Vec::new();
-    match fs::read_dir(path) {
-        Ok(val) => {
-            for name in val.into_iter() {
-                match name {
-                    Ok(result) => {
-                        let file_name = result.file_name().to_str().unwrap().to_string();
-                        file_name_list.push(file_name);
-                    }
-                    Err(e) => {
-                        println!("error getting content from path: {:?}", e);
-                        return None;
-                    }
-                }
-            }
-        }
-        Err(e) => {
-            println!("her: {:?}", e);
-            return None;
-        }
+    let entries = match ignore {
+        Some(patterns) => ignore_rules::filter_ignored(Path::new(&file), entries, patterns),
+        None => entries,
     };
-    Some(file_name_list)
+
+    let file_strings = convert_file_path_to_string(
+        entries,
+        show_hidden_files,
+        sort_by,
+        sort_type.clone(),
+        sort_options,
+    );
+    Ok(file_strings)
 }
 
 fn draw_popup(rect: Rect, percent_x: u16, percent_y: u16) -> Rect {
@@ -309,32 +288,23 @@ fn draw_popup(rect: Rect, percent_x: u16, percent_y: u16) -> Rect {
 }
 
 fn delete_file(file: &str) -> anyhow::Result<()> {
-    match fs::remove_file(file) {
-        Ok(_) => {}
-        Err(e) => {
-            // TODO: show notification to user
-            println!("Error: {:?}", e);
-        }
-    }
+    fs::remove_file(file)?;
     Ok(())
 }
 
 fn delete_dir(file: &str) -> anyhow::Result<()> {
-    match fs::remove_dir_all(file) {
-        Ok(_) => {}
-        Err(e) => {
-            println!("Error: {:?}", e);
-        }
-    }
-
+    fs::remove_dir_all(file)?;
     Ok(())
 }
 
 fn handle_delete_based_on_type(file: &str) -> anyhow::Result<()> {
-    let metadata = fs::metadata(file)?;
+    // symlink_metadata so deleting a link never touches what it points to
+    let metadata = fs::symlink_metadata(file)?;
     let file_type = metadata.file_type();
 
-    if file_type.is_dir() {
+    if file_type.is_symlink() {
+        remove_symlink(file)?;
+    } else if file_type.is_dir() {
         delete_dir(file)?;
     } else {
         delete_file(file)?;
@@ -342,113 +312,358 @@ fn handle_delete_based_on_type(file: &str) -> anyhow::Result<()> {
     Ok(())
 }
 
-fn get_file_path_data(
-    start_path: String,
-    show_hidden: bool,
-    sort_by: SortBy,
-    sort_type: &SortType,
-) -> anyhow::Result<Vec<String>> {
-    let entries = fs::read_dir(start_path)?
-        .map(|res| res.map(|e| e.path()))
-        .collect::<Result<Vec<_>, io::Error>>()?;
-
-    let file_strings =
-        convert_file_path_to_string(entries, show_hidden, sort_by, sort_type.clone());
+fn remove_symlink(file: &str) -> anyhow::Result<()> {
+    // windows directory links have to be removed like directories
+    #[cfg(windows)]
+    if fs::metadata(file).map(|m| m.is_dir()).unwrap_or(false) {
+        fs::remove_dir(file)?;
+        return Ok(());
+    }
+    fs::remove_file(file)?;
+    Ok(())
+}
 
-    Ok(file_strings)
+// grid of the thumbnails loaded so far, four per row
+fn render_thumbnails(f: &mut Frame, area: Rect, thumbnails: &mut Thumbnails) {
+    const COLUMNS: usize = 4;
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("Images ({})", thumbnails.images.len()));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let rows = thumbnails.images.len().div_ceil(COLUMNS);
+    let row_areas = Layout::vertical(vec![Constraint::Ratio(1, rows as u32); rows]).split(inner);
+    for (row, chunk) in thumbnails.images.chunks_mut(COLUMNS).enumerate() {
+        let cells = Layout::horizontal(vec![Constraint::Ratio(1, COLUMNS as u32); COLUMNS])
+            .split(row_areas[row]);
+        for ((path, protocol), cell) in chunk.iter_mut().zip(cells.iter()) {
+            let name = Path::new(path.as_str())
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let cell_block = Block::default().title(name);
+            let image_area = cell_block.inner(*cell);
+            f.render_widget(cell_block, *cell);
+            f.render_stateful_widget(StatefulImage::new(None), image_area, protocol);
+        }
+    }
 }
 
-fn create_new_dir(current_file_path: String, new_item: String) -> anyhow::Result<()> {
-    let append_path = format!("{}/{}", current_file_path, new_item);
+// modification times of settings.json and the theme file in use, the
+// config directory also holds files we write all the time like the history
+fn config_stamp(config: &configuration::Configuration, light: bool) -> Vec<Option<SystemTime>> {
+    let theme_path = theme::themes_dir().join(format!("{}.toml", config.theme_name(light)));
+    [PathBuf::from(&config.settings_path), theme_path]
+        .iter()
+        .map(|path| {
+            fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        })
+        .collect()
+}
 
-    // TODO: implications of using (create_dir) || (create_dir_all)
-    let response = match fs::create_dir(append_path) {
-        Ok(_) => Ok(()),
-        Err(e) => {
-            return Err(e.into());
-        }
+// applies settings.json and the theme file as they are now. when either is
+// invalid nothing changes, otherwise returns whether anything did
+fn reload_config(
+    config: &mut configuration::Configuration,
+    profile: Option<(&str, &Profile)>,
+    keymap: &mut KeyMap,
+    app: &mut App,
+    file_reader_content: &mut FileContent,
+) -> anyhow::Result<bool> {
+    let mut reloaded = config.reloaded()?;
+    if let Some((name, profile)) = profile {
+        profile.apply(name, &mut reloaded);
+    }
+    let reloaded_keymap = KeyMap::new(&reloaded.keybindings)?;
+    // asking the terminal for its background again would mix the answer
+    // into the key presses
+    let light = match reloaded.color_mode.as_str() {
+        "auto" => app.light_mode,
+        mode => theme::use_light_theme(mode),
     };
-    response
+    let reloaded_theme = theme::load(reloaded.theme_name(light))?;
+    // ff writes settings.json itself too, e.g. after picking a theme
+    let changed = serde_json::to_value(&reloaded).ok() != serde_json::to_value(&*config).ok()
+        || reloaded_theme != app.theme;
+
+    *keymap = reloaded_keymap;
+    app.theme = reloaded_theme;
+    app.light_mode = light;
+    app.true_color = theme::true_color(&reloaded.color_depth);
+    app.preview_max_bytes = reloaded.preview_max_bytes;
+    file_reader_content.configure(&reloaded.syntax_theme, reloaded.preview.command.clone());
+    *config = reloaded;
+    Ok(changed)
 }
 
-fn create_new_file(current_file_path: String, file_name: String) -> anyhow::Result<()> {
-    let append_path = format!("{}/{}", current_file_path, file_name);
-    let response = match File::create_new(append_path) {
-        Ok(_) => Ok(()),
-        Err(e) => {
-            return Err(e.into());
-            // kind: AlreadyExists
-        }
-    };
-    response
+// highlights the selected file again with the syntax colors of the new theme
+fn preview_theme(
+    app: &mut App,
+    state: &mut ListState,
+    file_reader_content: &mut FileContent,
+    image_generator: &mut ImageGenerator,
+) {
+    file_reader_content.use_theme(&app.theme, app.true_color);
+    if let Some(index) = state.selected().filter(|index| *index < app.files.len()) {
+        SelectionController::new(app, state, file_reader_content, image_generator)
+            .update_selection(index);
+    }
 }
 
-fn is_file(path: String) -> bool {
-    match fs::metadata(path) {
-        Ok(file) => {
-            let file_t = file.file_type();
-            if file_t.is_file() {
-                true
-            } else {
-                false
+enum MouseOutcome {
+    Action(Action),
+    // header column clicked in the detail view
+    SortColumn(usize),
+}
+
+// rows of the listing for `files`, the slice of `app.files` on screen
+fn file_list_items<'a>(
+    app: &App,
+    entry_meta: &EntryMetaCache,
+    files: &'a [String],
+) -> Vec<ListItem<'a>> {
+    let colors = app.theme_colors();
+    files
+        .iter()
+        .map(|file| {
+            let mut prefix = String::new();
+            if app.is_marked(file) {
+                prefix.push_str("* ");
             }
-        }
-        Err(_) => false,
-    }
+            if app.locked_dirs.contains(file) {
+                prefix = format!("{}{} ", prefix, icons::LOCK);
+            }
+            let mut suffix = String::new();
+            if let Some(meta) = entry_meta.get(file) {
+                if let Some(target) = meta.link_target {
+                    suffix = match meta.broken {
+                        false => format!(" -> {}", target.display()),
+                        true => format!(" -> {} (broken)", target.display()),
+                    };
+                }
+            }
+            if let Some(size) = app.dir_sizes.get(file) {
+                suffix = format!("{}  [{}]", suffix, format_size(*size));
+            }
+
+            // search results show what the query matched
+            let indices = app
+                .search_query
+                .as_ref()
+                .map(|query| query.match_indices(file))
+                .unwrap_or_default();
+            let mut spans = vec![Span::raw(prefix)];
+            spans.extend(ui::highlight_search_term(file, &indices, &colors));
+            spans.push(Span::raw(suffix));
+            ListItem::new(Line::from(spans))
+        })
+        .collect()
 }
 
-fn get_metadata_info(path: String) -> anyhow::Result<Option<Metadata>> {
-    let metadata = match fs::metadata(path) {
-        Ok(info) => Some(info),
-        Err(_) => None,
+// the entry `g<letter>` moves to: the first one whose name starts with the
+// letter, or the next one when the selection already does
+fn next_entry_starting_with(
+    files: &[String],
+    selected: Option<usize>,
+    letter: char,
+) -> Option<usize> {
+    let letter = letter.to_lowercase().to_string();
+    let starts_with = |path: &String| {
+        Path::new(path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase().starts_with(&letter))
+            .unwrap_or(false)
     };
 
-    Ok(metadata)
+    match selected {
+        Some(current) if current < files.len() && starts_with(&files[current]) => (1..files.len())
+            .map(|step| (current + step) % files.len())
+            .find(|&index| starts_with(&files[index])),
+        _ => files.iter().position(starts_with),
+    }
 }
 
-fn generate_metadata_str_info(metadata: anyhow::Result<Option<Metadata>>) -> String {
-    let metadata_info = match metadata {
-        Ok(res) => match res {
-            Some(info) => {
-                let size = info.len();
-                let permissions = info.permissions();
+// clicks select, a second click on the same entry enters or opens it and the
+// wheel moves the selection or scrolls the preview. clicking the other pane
+// moves the focus there, a second click on a focused directory preview moves
+// into that directory
+fn handle_mouse(
+    mouse: MouseEvent,
+    preview_area: Rect,
+    last_click: &mut Option<(usize, Instant)>,
+    app: &mut App,
+    listing: &mut Listing,
+) -> Option<MouseOutcome> {
+    let inside = |area: Rect| {
+        mouse.column >= area.x
+            && mouse.column < area.x + area.width
+            && mouse.row >= area.y
+            && mouse.row < area.y + area.height
+    };
+    let list_area = listing.area;
+    let in_list = inside(list_area);
+    let in_preview = inside(preview_area);
+    let select = |index: usize, app: &mut App, listing: &mut Listing| {
+        SelectionController::new(
+            app,
+            listing.state,
+            listing.file_reader_content,
+            listing.image_generator,
+        )
+        .update_selection(index);
+    };
 
-                let format_str = format!("size: {} | permission: {}", size, permissions.readonly());
-                format_str
-            }
-            None => String::from("Info not available"),
-        },
-        Err(_) => {
-            println!("errr from here",);
-            String::from("Encounter an error")
+    match mouse.kind {
+        // the wheel scrolls the pane under it whichever has the focus
+        MouseEventKind::ScrollDown | MouseEventKind::ScrollUp if in_list => {
+            let current = listing.state.selected()?;
+            let index = match mouse.kind {
+                MouseEventKind::ScrollDown => (current + 1).min(app.files.len().saturating_sub(1)),
+                _ => current.saturating_sub(1),
+            };
+            select(index, app, listing);
+            None
         }
-    };
+        MouseEventKind::ScrollDown if in_preview => {
+            app.preview_scroll = app.preview_scroll.saturating_add(3);
+            None
+        }
+        MouseEventKind::ScrollUp if in_preview => {
+            app.preview_scroll = app.preview_scroll.saturating_sub(3);
+            None
+        }
+        MouseEventKind::Down(MouseButton::Left) if in_list => {
+            app.preview_focused = false;
+            // the list has a border, rows start one line below its top and
+            // the detail view has its header there
+            if app.detail_view && mouse.row == list_area.y + 1 {
+                return ui::detail_column_at(list_area, mouse.column).map(MouseOutcome::SortColumn);
+            }
+            let offset = match app.detail_view {
+                true => listing.table_state.offset(),
+                false => listing.state.offset(),
+            };
+            let first_row = list_area.y + if app.detail_view { 2 } else { 1 };
+            let row = mouse.row.checked_sub(first_row)? as usize;
+            let index = offset + row;
+            if index >= app.files.len() {
+                return None;
+            }
+
+            let double_click = matches!(
+                last_click,
+                Some((last, at)) if *last == index && at.elapsed() < DOUBLE_CLICK_INTERVAL
+            );
+            if double_click {
+                *last_click = None;
+                let action = if is_file(app.files[index].clone()) {
+                    Action::Open
+                } else {
+                    Action::EnterDir
+                };
+                return Some(MouseOutcome::Action(action));
+            }
 
-    metadata_info
+            *last_click = Some((index, Instant::now()));
+            select(index, app, listing);
+            None
+        }
+        MouseEventKind::Down(MouseButton::Left) if in_preview => {
+            if app.preview_focused && !app.preview_files.is_empty() {
+                return Some(MouseOutcome::Action(Action::EnterDir));
+            }
+            app.preview_focused = true;
+            None
+        }
+        _ => None,
+    }
 }
 
-fn generate_copy_file_dir_name(curr_path: String, new_path: String) -> String {
-    let get_info = Path::new(&curr_path);
+// the second key of a `y` sequence picks what is copied of every entry, one
+// line each
+fn yank_text(key: char, files: &[String], start_path: &str) -> Option<(String, &'static str)> {
+    let start = std::path::absolute(start_path).unwrap_or_else(|_| PathBuf::from(start_path));
+    let (what, line): (&'static str, fn(&Path, &Path) -> String) = match key {
+        'p' => ("path", |path, _| path.display().to_string()),
+        'n' => ("name", |path, _| {
+            path.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.display().to_string())
+        }),
+        'r' => ("relative path", |path, start| {
+            relative_path(path, start).display().to_string()
+        }),
+        'd' => ("parent directory", |path, _| {
+            path.parent().unwrap_or(path).display().to_string()
+        }),
+        _ => return None,
+    };
+    let lines: Vec<String> = files
+        .iter()
+        .map(|file| {
+            let path = std::path::absolute(file).unwrap_or_else(|_| PathBuf::from(file));
+            line(&path, &start)
+        })
+        .collect();
+    Some((lines.join("\n"), what))
+}
 
-    let file_name = get_info.file_name().unwrap().to_str().unwrap();
+fn create_new_dir(path: &Path) -> anyhow::Result<()> {
+    if path.exists() {
+        return Err(io::Error::from(ErrorKind::AlreadyExists).into());
+    }
+    fs::create_dir_all(path)?;
+    Ok(())
+}
 
-    let create_new_file_name = format!("{}/copy_{}", new_path, file_name);
-    create_new_file_name
+// missing parent directories are created, the file starts with the template
+// for its extension when there is one
+fn create_new_file(path: &Path, templates: &Path) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = File::create_new(path)?;
+
+    let template = path
+        .extension()
+        .map(|extension| templates.join(extension))
+        .filter(|template| template.is_file());
+    if let Some(template) = template {
+        io::copy(&mut File::open(template)?, &mut file)?;
+    }
+    Ok(())
 }
 
-fn create_item_based_on_type(current_file_path: String, new_item: String) -> anyhow::Result<()> {
-    if new_item.contains(".") {
-        let file_res = create_new_file(current_file_path, new_item);
-        file_res
+// `new_item` may be a nested path like `src/new_mod/mod.rs`, a trailing `/`
+// creates a directory and anything else a file. returns the entry of
+// `current_file_path` that now holds the new item
+fn create_item_based_on_type(
+    current_file_path: &Path,
+    new_item: &str,
+    templates: &Path,
+) -> anyhow::Result<PathBuf> {
+    let path = current_file_path.join(new_item.trim_start_matches('/'));
+    if new_item.ends_with('/') {
+        create_new_dir(&path)?;
     } else {
-        let dir_res = create_new_dir(current_file_path, new_item);
-        dir_res
+        create_new_file(&path, templates)?;
     }
+
+    let top = Path::new(new_item.trim_start_matches('/'))
+        .components()
+        .next()
+        .map(|component| current_file_path.join(component))
+        .unwrap_or(path);
+    Ok(top)
 }
 
 fn handle_rename(app: App) -> io::Result<()> {
-    let curr_path = format!("{}/{}", app.current_path_to_edit, app.current_name_to_edit);
-    let new_path = format!("{}/{}", app.current_path_to_edit, app.create_edit_file_name);
+    let dir = Path::new(&app.current_path_to_edit);
+    let curr_path = dir.join(&app.current_name_to_edit);
+    let new_path = dir.join(&app.create_edit_file_name);
 
     let result = match fs::rename(curr_path, new_path) {
         Ok(res) => res,
@@ -469,45 +684,6 @@ fn check_if_exists(new_path: String) -> bool {
     }
 }
 
-fn get_curr_path(path: String) -> String {
-    let mut split_path = path.split("/").collect::<Vec<&str>>();
-    split_path.pop();
-    let vec_to_str = split_path.join("/");
-    vec_to_str
-}
-
-fn copy_dir_file_helper(src: &Path, new_src: &Path) -> anyhow::Result<()> {
-    if src.is_file() {
-        fs::copy(src, new_src)?;
-    } else {
-        let entries: Vec<_> = WalkDir::new(src)
-            .into_iter()
-            .filter_map(Result::ok)
-            .collect();
-        entries.par_iter().try_for_each(|entry| {
-            let entry_path = entry.path();
-            let relative_path = entry_path.strip_prefix(src).unwrap();
-            let dst_path = new_src.join(relative_path);
-
-            if entry_path.is_dir() {
-                fs::create_dir_all(&dst_path)?;
-            } else if entry_path.is_file() {
-                if let Some(parent) = dst_path.parent() {
-                    fs::create_dir_all(parent)?;
-                }
-                fs::copy(entry_path, dst_path)?;
-            } else {
-                println!("Error, file type not supported");
-                return Err(io::Error::new(ErrorKind::Other, "unsuported file type"));
-            }
-
-            Ok(())
-        })?;
-    }
-
-    Ok(())
-}
-
 fn generate_sort_by_string(sort_type: &SortType) -> String {
     let str_sort_type = match sort_type {
         SortType::ASC => "ASC",
@@ -542,1045 +718,3938 @@ fn validate_file_path(file_path: Option<String>) -> Option<bool> {
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let input_arguments: Vec<String> = env::args().collect();
+    let mut input_arguments: Vec<String> = env::args().collect();
+    let profile_name = profile::take_profile_argument(&mut input_arguments)?;
+    let print_on_select = cli::take_flag(&mut input_arguments, "--print-on-select");
     let ps = SyntaxSet::load_defaults_newlines();
     let ts = ThemeSet::load_defaults();
 
     let mut config = configuration::Configuration::new();
     let mut sort_type = SortType::ASC;
 
-    let mut file_reader_content = FileContent::new(ps, ts);
-    //let file_type = file_reader_content.file_type.clone();
-    let mut image_generator = ImageGenerator::new();
-
     config.handle_settings_configuration();
+    let profile = match &profile_name {
+        Some(name) => {
+            let profile = Profile::load(&config, name)?;
+            profile.apply(name, &mut config);
+            Some(profile)
+        }
+        None => None,
+    };
+    // the main loop sleeps until input or one of the background workers
+    // wakes it
+    let mut events = Events::default();
+    let worker_waker = events.waker(AppEvent::Worker);
+    let mut file_reader_content = FileContent::new(
+        ps,
+        ts,
+        &config.syntax_theme,
+        config.preview.command.clone(),
+        worker_waker.clone(),
+    );
+
+    if let Some(url) = input_arguments.get(1).filter(|arg| vfs::is_remote(arg)) {
+        let colors = theme::configured(&config).0.colors;
+        remote_browser::run(
+            url,
+            colors.with_depth(theme::true_color(&config.color_depth)),
+        )?;
+        return Ok(());
+    }
+
+    // subcommands like `ff query` run without the tui
+    if let Some(command) = cli::parse_command(&input_arguments) {
+        cli::run(command?, &config)?;
+        return Ok(());
+    }
+
+    let mut image_generator = ImageGenerator::new(&config.image_protocol);
+
+    let mut keymap = match KeyMap::new(&config.keybindings) {
+        Ok(keymap) => keymap,
+        Err(e) => {
+            eprintln!("{}, falling back to default keybindings", e);
+            KeyMap::default()
+        }
+    };
     // Setup terminal
 
-    let file_strings = get_file_path_data(
+    let mut view_states = ViewStateStore::load(&config.view_state_path());
+    let mut status_bar = StatusBar::new(worker_waker.clone());
+    let mut app = App::new(Vec::new());
+    app.respect_gitignore = config.respect_gitignore;
+    app.ignore_patterns = config.ignore_patterns();
+    app.sort_options = config.sort.clone();
+    app.preview_max_bytes = config.preview_max_bytes;
+    if let Err(e) = theme::install_builtin_themes() {
+        eprintln!("unable to write the built in themes: {:#}", e);
+    }
+    (app.theme, app.light_mode) = theme::configured(&config);
+    app.true_color = theme::true_color(&config.color_depth);
+    file_reader_content.use_theme(&app.theme, app.true_color);
+    let start_dir = read_with_view_state(
         config.start_path.clone(),
-        false,
-        SortBy::Default,
-        &sort_type,
-    )?; //let file_strings = convert_file_path_to_string(entries);
-    let mut app = App::new(file_strings.clone());
+        &mut app,
+        &mut sort_type,
+        &view_states,
+    )?;
+    app.copy_move_read_only_files = start_dir.entries.clone();
+    app.enter(start_dir);
+    app.editors = editors_from_config(&config.editors);
+    app.commands = commands_from_config(&config.commands);
+    app.converters = converters_from_config(&config.converters);
 
     // handle ide selection from arguments
-    app.handle_arguments(input_arguments);
+    if let Err(e) = app.handle_arguments(input_arguments) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+    if let Some(name) = profile.as_ref().and_then(|profile| profile.editor.as_ref()) {
+        if app.selected_editor.is_none() {
+            match app.validate_user_input(name) {
+                Some(editor) => app.selected_editor = Some(editor),
+                None => eprintln!("Unknown editor {} in the profile", name),
+            }
+        }
+    }
 
-    let store = if Path::new(&config.cache_directory).exists() {
-        let res = load_directory_from_file(&config.cache_directory.to_owned()).unwrap();
-        println!("Loading directory cache from file");
-        res
-    } else {
-        println!("Building directory cache, Please wait...");
-        let new_store =
-            build_directory_from_store(&config.start_path.to_owned(), config.ignore_directories);
-        save_directory_to_file(&new_store, &config.cache_directory.to_owned())?;
-        new_store
+    // without a usable cache the listing shows up right away and the cache
+    // is built in the background, global search waits for it
+    let mut cache_receiver: Option<Receiver<CacheMessage>> = None;
+    let mut cache_ready = true;
+    let mut store = match load_store(&config.cache_header(), &config.cache_directory) {
+        Some(store) => Arc::new(store),
+        None => {
+            cache_receiver = Some(worker_waker.relay(build_store_in_background(
+                config.cache_header(),
+                config.cache_directory.clone(),
+            )));
+            app.cache_build = Some(0);
+            cache_ready = false;
+            Arc::new(DirectoryStore::new())
+        }
+    };
+    let mut search_worker = SearchWorker::new(store.clone(), worker_waker.clone());
+
+    let mut frecency = FrecencyStore::load(&config.frecency_path());
+    let mut search_history = SearchHistory::load(&config.history_path());
+    let hooks = Hooks::new(&config.hooks);
+    let associations = Associations::from_config(&config.associations);
+    let mut scripts = match ScriptEngine::load(&config.script_path()) {
+        Ok(scripts) => scripts,
+        Err(e) => {
+            eprintln!("Unable to load script: {}", e);
+            ScriptEngine::load("")?
+        }
     };
 
+    app.print_on_select = print_on_select;
+
     enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
+    let mut output = TerminalOutput::new(print_on_select);
+    execute!(
+        output,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
+    let backend = CrosstermBackend::new(output);
     let mut terminal = Terminal::new(backend)?;
 
     terminal.clear()?;
     // Initial selected state
     let mut state = ListState::default();
-    state.select(Some(0)); // Select the first item by default
-                           //
+    // where the listing and the preview were drawn last, for mouse hit testing
+    let mut list_area = Rect::default();
+    let mut preview_area = Rect::default();
+    let mut last_click: Option<(usize, Instant)> = None;
+    // text pasted into an input arrives as typed keys, one per loop
+    let mut pasted_keys: VecDeque<KeyEvent> = VecDeque::new();
+    // what handing the picked files over did, with --print-on-select leaving
+    // without picking anything exits with 1
+    let mut selection: Option<anyhow::Result<()>> = None;
+    let mut table_state = TableState::default();
+    let mut tree = TreeState::default();
+    let mut thumbnails = Thumbnails::new(worker_waker.clone());
+    let mut entry_meta = EntryMetaCache::new(worker_waker.clone());
+    // without a watcher the listing is only refreshed by our own operations
+    let mut watcher = FileSystemWatcher::new(
+        config.watch_depth,
+        app.ignore_patterns.clone(),
+        events.waker(AppEvent::Watcher),
+    )
+    .ok();
+    // settings.json and the theme files are reloaded when they change
+    let mut config_watcher = FileSystemWatcher::new(
+        1,
+        IgnorePatterns::default(),
+        events.waker(AppEvent::Watcher),
+    )
+    .ok();
+    if let Some(config_watcher) = config_watcher.as_mut() {
+        let _ = config_watcher.watch(&configuration::config_dir());
+    }
+    let mut config_stamps = config_stamp(&config, app.light_mode);
+    // the parent column of the miller layout has its own
+    let mut parent_watcher = if config.layout == "miller" {
+        FileSystemWatcher::new(
+            0,
+            IgnorePatterns::default(),
+            events.waker(AppEvent::Watcher),
+        )
+        .ok()
+    } else {
+        None
+    };
+    // entries of the parent directory for the miller layout, keyed by the
+    // directory and hidden setting they were read for
+    let mut parent_listing: (String, bool, Vec<String>) = (String::new(), false, Vec::new());
+    // Select the first item by default
+    SelectionController::new(
+        &mut app,
+        &mut state,
+        &mut file_reader_content,
+        &mut image_generator,
+    )
+    .update_selection(0);
     let mut read_only_state = ListState::default();
     read_only_state.select(Some(0));
 
+    let mut jobs = JobManager::new(events.waker(AppEvent::Job));
+    let mut jobs_state = ListState::default();
+    let mut hash_receiver: Option<Receiver<String>> = None;
+    let mut compare_receiver: Option<Receiver<anyhow::Result<Comparison>>> = None;
+    let mut sync_receiver: Option<Receiver<anyhow::Result<SyncPlan>>> = None;
+    let mut conversion_receiver: Option<Receiver<Vec<ConversionResult>>> = None;
+    let mut jump_state = ListState::default();
+    let mut saved_search_state = ListState::default();
+    let mut volume_state = ListState::default();
+    let mut theme_state = ListState::default();
+    let mut editor_state = ListState::default();
+    let mut command_state = ListState::default();
+    let mut converter_state = ListState::default();
+
+    let mut dir_size_receiver: Option<Receiver<DirSizeMessage>> = None;
+    let mut dir_size_cancel = Arc::new(AtomicBool::new(false));
+
+    let mut content_search_state = ListState::default();
+    let mut content_search_receiver: Option<Receiver<ContentSearchMessage>> = None;
+    let mut content_search_cancel = Arc::new(AtomicBool::new(false));
+
+    let mut redraw = true;
+    // input is drawn right away, background updates wait for FRAME_INTERVAL
+    let mut after_input = true;
+    let mut last_draw = Instant::now();
+    events.start(REDRAW_TICK);
+
     // Main loop
     loop {
-        // Filtered items based on input
-        let filtered_items: Vec<ListItem> = app
-            .files
-            .iter()
-            .map(|file| ListItem::new(file.clone()))
-            .collect();
-
-        let filtered_read_only_items: Vec<ListItem> = app
-            .copy_move_read_only_files
-            .iter()
-            .map(|file| ListItem::new(file.clone()))
-            .collect();
-
-        // Draw UI
-        terminal.draw(|f| {
-            let chunks = Layout::default()
-                .direction(Direction::Vertical)
-                .margin(1)
-                .constraints(
-                    [
-                        Constraint::Length(2),
-                        Constraint::Length(3),
-                        Constraint::Min(1),
-                        Constraint::Length(3),
-                        //Constraint::Length(1),
-                    ]
-                    .as_ref(),
-                )
-                .split(f.size());
-
-            let (msg, style) = match app.input_mode {
-                InputMode::Normal => (
-                    vec![
-                        "Exit (q)".bold(),
-                        " find (i)".bold(),
-                        app.input.clone().bold(),
-                        " Enter to select file (enter)".bold(),
-                    ],
-                    Style::default(),
-                ),
-                InputMode::Editing => (vec!["Normal Mode (Esc)".bold()], Style::default()),
-                InputMode::WatchDelete => (vec!["Watch Delete Mode".bold()], Style::default()),
-                InputMode::WatchCreate => (vec!["Watch Delete Mode".bold()], Style::default()),
-                InputMode::WatchRename => (vec!["Watch Delete Mode".bold()], Style::default()),
-                InputMode::WatchSort => (vec!["Watch Delete Mode".bold()], Style::default()),
-                _ => (vec!["Default".bold()], Style::default()),
-            };
-
-            let inner_layout = Layout::default()
-                .direction(Direction::Horizontal)
-                .constraints(vec![
-                    Constraint::Percentage(50),
-                    Constraint::Percentage(50),
-                ])
-                .split(chunks[2]);
-
-            // Input field
-            let input_block = Paragraph::new(app.input.clone())
-                .block(
-                    Block::default()
-                        .borders(Borders::ALL)
-                        .title("Search")
-                        .style(match app.input_mode {
-                            InputMode::Normal => Style::default().fg(Color::White),
-                            InputMode::Editing => Style::default().fg(Color::Green),
-                            _ => Style::default().fg(Color::White),
-                        }),
-                )
-                .style(match app.input_mode {
-                    InputMode::Editing => Style::default().fg(Color::White),
-                    InputMode::Normal => Style::default().fg(Color::White),
-                    InputMode::WatchDelete => Style::default().fg(Color::Gray),
-                    InputMode::WatchCreate => Style::default().fg(Color::Gray),
-                    InputMode::WatchRename => Style::default().fg(Color::Gray),
-                    InputMode::WatchSort => Style::default().fg(Color::Gray),
-                    _ => Style::default().fg(Color::Gray),
-                });
-
-            let mut list_title = String::new();
-            if app.loading {
-                let title_with_loader = format!("Copying Files...");
-                list_title.push_str(&title_with_loader);
-            } else {
-                list_title.push_str(&"List");
+        // late results must not replace the listing once the search is closed
+        if !matches!(app.input_mode, InputMode::Editing) {
+            search_worker.cancel();
+        }
+        if let Some(results) = search_worker.poll() {
+            redraw = true;
+            app.show_search_results(results);
+            if state
+                .selected()
+                .is_none_or(|selected| selected >= app.files.len())
+            {
+                state.select(if app.files.is_empty() { None } else { Some(0) });
             }
-            // List of filtered items
-            // TODO: get first item from the list, 
-            // 1. get first item from list
-            // 2. render content based on type 
-            //    - if type if dir then render its content 
-            //    - if type is file then display content of file if posible
-            // 3. preview mode will only apply when in normal MODE, 
-            let list_block = List::new(filtered_items.clone())
-                .block(
-                    Block::default()
-                        .borders(Borders::ALL)
-                        .title(list_title.as_str())
-                        .style(match app.input_mode {
-                            InputMode::Normal => Style::default().fg(Color::Green),
-                            InputMode::Editing => Style::default().fg(Color::White),
-                            _ => Style::default().fg(Color::White),
-                        }), //.title("Filtered List"),
-                )
-                .highlight_style(
-                    Style::default()
-                        .fg(Color::White)
-                        .add_modifier(Modifier::BOLD),
-                )
-                .highlight_symbol(">")
-                .style(match app.input_mode {
-                    InputMode::Normal => Style::default().fg(Color::White),
-                    InputMode::Editing => Style::default().fg(Color::White),
-                    InputMode::WatchDelete => Style::default().fg(Color::Gray),
-                    InputMode::WatchCreate => Style::default().fg(Color::Gray),
-                    InputMode::WatchRename => Style::default().fg(Color::Gray),
-                    InputMode::WatchSort => Style::default().fg(Color::Gray),
-                    _ => Style::default().fg(Color::Gray),
-                });
-
-            //let preview_list_path = get_preview_path(app.files.clone());
-
-            /* let validate_is_file = match validate_file_path(preview_list_path.clone()) {
-                Some(v) => v,
-                _=>  {
-                    println!("not a valid file or empty");
-                    false
-                },
-            }; */
-
-            /* match validate_is_file {
-                false => {
-let new_preview_files = get_file_path_data(preview_list_path.unwrap(), false, SortBy::Default, &sort_type);
-                    app.preview_files = new_preview_files.unwrap();
-
-                },
-                _ => app.preview_files = Vec::new()
-            }; */
-
-
-            //let file_list = get_file_path_data(valid_preview_list_path.unwrap(), false, SortBy::Default, &sort_type);
-
-            /* let file_list_res = match file_list {
-                Ok(list) => list,
-                Err(err) =>  {
-                   Vec::new() 
-                },
-
-            }; */
-            // TODO: handle first item preview
-            let list_preview_block = List::new(app.preview_files.clone()).block(
-                    Block::default()
-                        .borders(Borders::ALL)
-                        .title("Preview")
-                        .style(match app.input_mode {
-                            InputMode::Normal => Style::default().fg(Color::Green),
-                            InputMode::Editing => Style::default().fg(Color::White),
-                            _ => Style::default().fg(Color::White),
-                        }), //.title("Filtered List"),
-                )
-                //.highlight_symbol(">")
-                .style(Style::default().fg(Color::DarkGray));
-
-
-            let footer_outer_layout = Layout::default()
-                .direction(Direction::Vertical)
-                .constraints(vec![Constraint::Percentage(100)])
-                .split(chunks[3]);
-
-            let footer_inner_layout = Layout::default()
-                .direction(Direction::Horizontal)
-                .constraints(vec![Constraint::Percentage(50), Constraint::Percentage(50)])
-                .split(footer_outer_layout[0]);
-
-            let bottom_instructions = Span::styled(
-                "Open with selected IDE: <Enter> | Keybindings: ?",
-                Style::default(),
-            );
-            //let default_empty_label = Span::styled("", Style::default());
-let footer_stats =
-                Text::from(Line::from(Span::styled(app.curr_stats.clone(), Style::default())));
-                        let footer_stats_paragraph = Paragraph::new(footer_stats)
-                .block(Block::default().borders(Borders::ALL))
-                .style(Style::default());
-                 f.render_widget(footer_stats_paragraph, footer_inner_layout[1]);
-
-             match app.files.len() > 0 {
-                true => {
-        },
-                false =>{}
-                };
-
-            let instructions = Text::from(Line::from(bottom_instructions));
-
-            let parsed_instructions = Paragraph::new(instructions)
-                .block(Block::default().borders(Borders::ALL))
-                .style(Style::default());
-            let text = Text::from(Line::from(msg)).patch_style(style);
-            let help_message = Paragraph::new(text);
-
-            let input_area = chunks[1];
-            match app.input_mode {
-                InputMode::Normal => {}
-                InputMode::WatchDelete => {}
-                InputMode::WatchCreate => {}
-                InputMode::WatchRename => {}
-                InputMode::WatchSort => {}
-                InputMode::Editing => f.set_cursor(
-                    input_area.x + app.character_index as u16 + 1,
-                    input_area.y + 1,
-                ),
-                _ => {}
+        }
+        if let Some(receiver) = &content_search_receiver {
+            let mut finished = false;
+            while let Ok(message) = receiver.try_recv() {
+                redraw = true;
+                match message {
+                    ContentSearchMessage::Matches(matches) => {
+                        app.content_search_results.extend(matches);
+                        if content_search_state.selected().is_none() {
+                            content_search_state.select(Some(0));
+                        }
+                    }
+                    ContentSearchMessage::Done(count) => {
+                        app.content_search_status = format!("{} match(es)", count);
+                        finished = true;
+                    }
+                }
+            }
+            if finished {
+                content_search_receiver = None;
             }
+        }
 
-            f.render_widget(help_message, chunks[0]);
-            f.render_widget(input_block, chunks[1]);
-            //f.render_widget(paragraph, chunks[2]);
-            //f.render_widget(default_label, chunks[2]);
-            //f.render_widget(parsed_instructions.clone(), footer_outer_layout[0]);
-            //f.render_widget(parsed_instructions.clone(), footer_layout[1]);
-            //f.render_widget(parsed_instructions.clone(), chunks[3]);
-            //f.render_stateful_widget(list_block.clone(), inner_layout[0], &mut state);
-            // f.render_widget(list_block, inner_layout[1]);
-            f.render_stateful_widget(list_block.clone(), inner_layout[0], &mut state);
-
-            let t = file_reader_content.file_type.clone();
-            match t {
-                FileType::FILE => {
-        image_generator.image = None;
-let file_preview_text = file_reader_content.hightlighted_content.as_ref().unwrap().clone() 
-                .block(Block::default().borders(Borders::ALL))
-                .style(Style::default());
-            f.render_widget(file_preview_text, inner_layout[1] );
+        let selected_path = file_reader_content.curr_selected_path.clone();
+        file_reader_content.previews.marker_color = app.theme_colors().highlight;
+        if let Some(preview) = file_reader_content.previews.poll(&selected_path) {
+            file_reader_content.set_preview(preview);
+            redraw = true;
+        }
+        if let Some(tree) = file_reader_content.trees.poll(&selected_path) {
+            file_reader_content.structured = Some(tree);
+            redraw = true;
+        }
+        entry_meta.update(app.listing_generation, &app.read_only_files);
+        redraw |= entry_meta.poll();
+        thumbnails.update(&file_reader_content.curr_selected_path);
+        redraw |= thumbnails.poll(&mut image_generator.picker);
+
+        if let Some(receiver) = &dir_size_receiver {
+            let mut finished = false;
+            while let Ok(message) = receiver.try_recv() {
+                redraw = true;
+                match message {
+                    DirSizeMessage::Size(path, size) => {
+                        app.dir_sizes.insert(path, size);
+                    }
+                    DirSizeMessage::Done => {
+                        // the listing was sorted before the sizes were known
+                        if matches!(app.sort_by, SortBy::RealSize) {
+                            update_file_references_with_selection_preservation(
+                                None,
+                                &mut app,
+                                &mut state,
+                                &sort_type,
+                                &mut file_reader_content,
+                                &mut image_generator,
+                            );
+                        }
+                        app.curr_stats = String::from("Directory sizes calculated");
+                        finished = true;
+                    }
                 }
-                FileType::IMG => {
-
-                     //let _ =image_generator.load_img(file_reader_content.curr_asset_path.clone()).clone();
-                    let image = StatefulImage::new(None);
-
-
-            f.render_stateful_widget(image, inner_layout[1], &mut image_generator.image.clone().unwrap());
+            }
+            if finished {
+                dir_size_receiver = None;
+            }
+        }
 
+        // a worker that fails or gets cancelled drops its sender without a
+        // result, the receiver is cleared so the next request isn't blocked
+        if let Some(receiver) = &hash_receiver {
+            match receiver.try_recv() {
+                Ok(digest) => {
+                    if let Ok(mut ctx) = ClipboardContext::new() {
+                        let _ = ctx.set_contents(digest.clone());
+                    }
+                    app.hash_result = Some(digest);
+                    hash_receiver = None;
+                    redraw = true;
                 }
-                FileType::ZIP => {
-let zip_list_content = List::new(file_reader_content.curr_zip_content.clone()).block(
-                    Block::default()
-                        .borders(Borders::ALL)
-                        .title("ZIP Preview")
-                        .style(match app.input_mode {
-                            InputMode::Normal => Style::default().fg(Color::Green),
-                            InputMode::Editing => Style::default().fg(Color::Gray),
-                            _ => Style::default().fg(Color::Gray),
-                        }), //.title("Filtered List"),
-                )
-                .style(Style::default().fg(Color::DarkGray));
-            f.render_widget(zip_list_content, inner_layout[1], );
+                Err(TryRecvError::Disconnected) => {
+                    app.show_toast(
+                        String::from("Hashing failed or was cancelled, see the jobs list"),
+                        true,
+                    );
+                    hash_receiver = None;
+                    redraw = true;
+                }
+                Err(TryRecvError::Empty) => {}
+            }
+        }
 
+        if let Some(receiver) = &compare_receiver {
+            match receiver.try_recv() {
+                Ok(result) => {
+                    match result {
+                        Ok(comparison) if matches!(app.input_mode, InputMode::Normal) => {
+                            app.comparison = Some(comparison);
+                            app.comparison_scroll = 0;
+                            app.input_mode = InputMode::WatchCompare;
+                            // the diff is drawn where the preview was
+                            file_reader_content.file_type = FileType::NotAvailable;
+                            image_generator.image = None;
+                        }
+                        Ok(_) => {}
+                        Err(e) => app.show_toast(format!("Unable to compare: {:#}", e), true),
+                    }
+                    app.curr_stats.clear();
+                    compare_receiver = None;
+                    redraw = true;
                 }
-                FileType::CSV => {
-let csv_list_content = List::new(file_reader_content.curr_csv_content.clone()).block(
-                    Block::default()
-                        .borders(Borders::ALL)
-                        .title("Preview")
-                        .style(match app.input_mode {
-                            InputMode::Normal => Style::default().fg(Color::Green),
-                            InputMode::Editing => Style::default().fg(Color::Gray),
-                            _ => Style::default().fg(Color::Gray),
-                        }), //.title("Filtered List"),
-                )
-                //.highlight_symbol(">")
-                .style(Style::default().fg(Color::DarkGray));
-            f.render_widget(csv_list_content, inner_layout[1], );
+                Err(TryRecvError::Disconnected) => {
+                    app.show_toast(
+                        String::from("Unable to compare: the comparison stopped"),
+                        true,
+                    );
+                    app.curr_stats.clear();
+                    compare_receiver = None;
+                    redraw = true;
+                }
+                Err(TryRecvError::Empty) => {}
+            }
+        }
 
+        if let Some(receiver) = &sync_receiver {
+            match receiver.try_recv() {
+                Ok(result) => {
+                    match result {
+                        Ok(plan) if matches!(app.input_mode, InputMode::Normal) => {
+                            app.sync_plan = Some(plan);
+                            app.sync_delete = false;
+                            app.sync_scroll = 0;
+                            app.input_mode = InputMode::WatchSync;
+                        }
+                        Ok(_) => {}
+                        Err(e) => app.show_toast(format!("Unable to sync: {:#}", e), true),
+                    }
+                    app.curr_stats.clear();
+                    sync_receiver = None;
+                    redraw = true;
+                }
+                Err(TryRecvError::Disconnected) => {
+                    app.show_toast(String::from("Unable to sync: planning stopped"), true);
+                    app.curr_stats.clear();
+                    sync_receiver = None;
+                    redraw = true;
                 }
-                _ => {
+                Err(TryRecvError::Empty) => {}
+            }
+        }
 
-        image_generator.image = None;
-            f.render_stateful_widget(list_preview_block, inner_layout[1], &mut state);
+        if let Some(receiver) = &conversion_receiver {
+            match receiver.try_recv() {
+                Ok(results) => {
+                    app.conversion_results = results;
+                    app.conversion_scroll = 0;
+                    if matches!(app.input_mode, InputMode::Normal) {
+                        app.input_mode = InputMode::WatchConversions;
+                    }
+                    conversion_receiver = None;
+                    redraw = true;
+                }
+                Err(TryRecvError::Disconnected) => {
+                    app.show_toast(
+                        String::from("Conversion stopped without results, see the jobs list"),
+                        true,
+                    );
+                    conversion_receiver = None;
+                    redraw = true;
                 }
+                Err(TryRecvError::Empty) => {}
             }
-            //TODO: add match method here
-            //f.render_stateful_widget(list_block, chunks[2], &mut state);
-            f.render_widget(parsed_instructions.clone(), footer_inner_layout[0]);
-            //f.render_widget(footer_stats_paragraph, footer_inner_layout[1]);
-
-            if app.render_popup {
-                let block = Block::bordered()
-                    .title("Confirm to delete y/n")
-                    .style(Style::default().fg(Color::Red));
-                let area = draw_popup(f.size(), 40, 7);
-                let popup_chuncks = Layout::default()
-                    .direction(Direction::Horizontal)
-                    .margin(1)
-                    .constraints([Constraint::Percentage(100)])
-                    .split(area);
-                f.render_widget(Clear, area);
-                f.render_widget(block, popup_chuncks[0]);
-            }
+        }
 
-            let area = draw_popup(f.size(), 40, 7);
-            let popup_chuncks = Layout::default()
-                .direction(Direction::Horizontal)
-                .margin(1)
-                .constraints([Constraint::Percentage(100)])
-                .split(area);
-
-            let sort_option_area = draw_popup(f.size(), 90, 20);
-            let sort_options_chunks = Layout::default()
-                .direction(Direction::Vertical)
-                .margin(1)
-                .constraints([Constraint::Percentage(100)])
-                .split(sort_option_area);
-
-            let keybinding_area = draw_popup(f.size(), 80, 20);
-            let keybinding_chunks = Layout::default()
-                .direction(Direction::Vertical)
-                .margin(1)
-                .constraints([Constraint::Percentage(100)])
-                .split(keybinding_area);
+        if let Some(watcher) = watcher.as_mut() {
+            let _ = watcher.watch(&app.current_dir);
+            if let Some(batch) = watcher.poll() {
+                redraw = true;
+                update_file_references_with_selection_preservation(
+                    None,
+                    &mut app,
+                    &mut state,
+                    &sort_type,
+                    &mut file_reader_content,
+                    &mut image_generator,
+                );
+                if batch.changes > 1 {
+                    app.curr_stats = format!("{} changes detected", format_count(batch.changes));
+                }
+            }
+        }
 
-            match app.input_mode {
-                InputMode::WatchCreate => {
-                    //f.render_widget(popup_block, area);
-
-                    let create_input_block = Paragraph::new(app.create_edit_file_name.clone())
-                        .block(Block::default().borders(Borders::ALL).title(
-                            match app.is_create_edit_error {
-                                false => "Create File/Dir".to_string(),
-                                true => app.error_message.to_owned(),
-                            },
-                        ))
-                        .style(match app.is_create_edit_error {
-                            true => Style::default().fg(Color::Red),
-                            false => Style::default().fg(Color::LightGreen),
-                        });
+        if config_watcher
+            .as_mut()
+            .and_then(|watcher| watcher.poll())
+            .is_some()
+            && config_stamp(&config, app.light_mode) != config_stamps
+        {
+            let profile = profile_name.as_deref().zip(profile.as_ref());
+            match reload_config(
+                &mut config,
+                profile,
+                &mut keymap,
+                &mut app,
+                &mut file_reader_content,
+            ) {
+                Ok(true) => {
+                    preview_theme(
+                        &mut app,
+                        &mut state,
+                        &mut file_reader_content,
+                        &mut image_generator,
+                    );
+                    app.show_toast(String::from("Settings reloaded"), false);
+                }
+                Ok(false) => {}
+                Err(e) => app.show_toast(format!("{:#}", e), true),
+            }
+            // a broken file isn't reported again until it changes
+            config_stamps = config_stamp(&config, app.light_mode);
+            redraw = true;
+        }
 
-                    f.render_widget(Clear, popup_chuncks[0]);
-                    f.render_widget(create_input_block, popup_chuncks[0]);
+        if let Some(receiver) = &cache_receiver {
+            while let Ok(message) = receiver.try_recv() {
+                redraw = true;
+                match message {
+                    CacheMessage::Progress(found) => app.cache_build = Some(found),
+                    // searches started from now on use the new store
+                    CacheMessage::Done(new_store, saved) => {
+                        store = Arc::new(new_store);
+                        search_worker = SearchWorker::new(store.clone(), worker_waker.clone());
+                        app.cache_build = None;
+                        cache_ready = true;
+                        // a query typed while waiting runs now
+                        if matches!(app.input_mode, InputMode::Editing) && !app.input.is_empty() {
+                            app.perform_global_search(&mut search_worker);
+                        }
+                        app.curr_stats = match saved {
+                            Ok(()) => format!(
+                                "Directory cache ready, {} entries",
+                                format_count(store.len())
+                            ),
+                            Err(e) => errors::status("Unable to save the directory cache", e),
+                        };
+                        if matches!(app.input_mode, InputMode::CacheLoading) {
+                            app.input_mode = InputMode::Normal;
+                        }
+                    }
                 }
-                InputMode::WatchRename => {
-                    let create_input_block = Paragraph::new(app.create_edit_file_name.clone())
-                        .block(Block::default().borders(Borders::ALL).title("Enter file/dir name"))
-                        .style(Style::default().fg(Color::LightGreen));
+            }
+        }
+        if app.cache_build.is_none() {
+            cache_receiver = None;
+        }
 
-                    f.render_widget(create_input_block, popup_chuncks[0]);
+        let job_updates = jobs.poll();
+        redraw |= !job_updates.is_empty();
+        if job_updates.iter().any(|update| update.finished) {
+            update_file_references_with_selection_preservation(
+                None,
+                &mut app,
+                &mut state,
+                &sort_type,
+                &mut file_reader_content,
+                &mut image_generator,
+            );
+        }
+        if let Some(update) = job_updates.last() {
+            app.curr_stats = update.message.clone();
+        }
+
+        let miller = config.layout == "miller";
+        if miller {
+            let cur_dir = app.current_dir_string();
+            if let (Some(parent_watcher), Some(parent)) =
+                (parent_watcher.as_mut(), app.current_dir.parent())
+            {
+                let _ = parent_watcher.watch(parent);
+                if parent_watcher.poll().is_some() {
+                    parent_listing.0.clear();
+                    redraw = true;
                 }
-                InputMode::WatchSort => {
-                    let lines = vec![
-                        Line::from("Press (a) to sort ASC or (d) to sort DESC, (q) to exit"),
-                        Line::from("Name: (n)"),
-                        Line::from("Date Created: (t)"),
-                        Line::from("Size: (s)"),
-                    ];
-
-                    let sort_by_text = generate_sort_by_string(&sort_type);
-                    let list_items = Text::from(lines);
-                    let p = Paragraph::new(list_items)
-                        .block(Block::default().borders(Borders::ALL).title(sort_by_text))
-                        .style(Style::default().fg(Color::LightGreen));
-                    f.render_widget(Clear, sort_options_chunks[0]);
-                    f.render_widget(p, sort_options_chunks[0]);
-
-                    //f.render_widget(create_input_block, sort_options_chunks[0]);
+            }
+            if parent_listing.0 != cur_dir || parent_listing.1 != app.show_hidden_files {
+                let entries = match Path::new(&cur_dir).parent() {
+                    Some(parent) => get_file_path_data(
+                        parent.to_string_lossy().to_string(),
+                        app.show_hidden_files,
+                        app.listing_ignore(),
+                        SortBy::Default,
+                        &sort_type,
+                        &app.sort_options,
+                    )
+                    .unwrap_or_default(),
+                    None => Vec::new(),
+                };
+                parent_listing = (cur_dir, app.show_hidden_files, entries);
+            }
+        }
+
+        // column the detail view is sorted by and whether it is ascending
+        let detail_sort = match app.sort_by {
+            SortBy::Name => Some(0),
+            SortBy::Size | SortBy::RealSize => Some(1),
+            SortBy::DateAdded => Some(2),
+            SortBy::Default => None,
+        }
+        .map(|column| (column, matches!(sort_type, SortType::ASC)));
+
+        let filtered_read_only_items: Vec<ListItem> = app
+            .copy_move_read_only_files
+            .iter()
+            .map(|file| ListItem::new(file.clone()))
+            .collect();
+
+        status_bar.update(
+            &app.current_dir,
+            app.files.len(),
+            &app.marked_files,
+            app.show_hidden_files,
+            app.cache_build,
+        );
+        redraw |= status_bar.poll();
+
+        // Draw UI
+        // frames are only drawn when input or background work changed
+        // something, the tick keeps toasts and job timings current
+        let frame_due = after_input || last_draw.elapsed() >= FRAME_INTERVAL;
+        if redraw && frame_due {
+            redraw = false;
+            after_input = false;
+            last_draw = Instant::now();
+            terminal.draw(|f| {
+                let colors = app.theme_colors();
+                // the pane with the focus, the listing unless the preview was clicked
+                let normal = matches!(app.input_mode, InputMode::Normal);
+                let list_active = normal && !app.preview_focused;
+                let preview_active = normal && app.preview_focused;
+                let chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .margin(1)
+                    .constraints(
+                        [
+                            Constraint::Length(2),
+                            Constraint::Length(3),
+                            Constraint::Min(1),
+                            Constraint::Length(3),
+                            //Constraint::Length(1),
+                        ]
+                        .as_ref(),
+                    )
+                    .split(f.size());
+
+                let (msg, style) = match app.input_mode {
+                    InputMode::Normal => (
+                        vec![
+                            "Exit (q)".bold(),
+                            " find (i)".bold(),
+                            app.input.clone().bold(),
+                            " Enter to select file (enter)".bold(),
+                        ],
+                        Style::default(),
+                    ),
+                    InputMode::Editing => (vec!["Normal Mode (Esc)".bold()], Style::default()),
+                    InputMode::WatchDelete => (vec!["Watch Delete Mode".bold()], Style::default()),
+                    InputMode::WatchCreate => (vec!["Watch Delete Mode".bold()], Style::default()),
+                    InputMode::WatchRename => (vec!["Watch Delete Mode".bold()], Style::default()),
+                    InputMode::WatchSort => (vec!["Watch Delete Mode".bold()], Style::default()),
+                    _ => (vec!["Default".bold()], Style::default()),
+                };
+
+                let columns = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints(if miller {
+                        vec![
+                            Constraint::Percentage(20),
+                            Constraint::Percentage(40),
+                            Constraint::Percentage(40),
+                        ]
+                    } else {
+                        vec![Constraint::Percentage(50), Constraint::Percentage(50)]
+                    })
+                    .split(chunks[2]);
+                // the miller layout puts the parent directory in front of the
+                // list and the preview
+                let inner_layout = if miller { &columns[1..] } else { &columns[..] };
+                if miller {
+                    let (cur_dir, _, entries) = &parent_listing;
+                    let parent_items: Vec<ListItem> = entries
+                        .iter()
+                        .map(|entry| {
+                            let name = Path::new(entry)
+                                .file_name()
+                                .map(|name| name.to_string_lossy().to_string())
+                                .unwrap_or_else(|| entry.clone());
+                            ListItem::new(name)
+                        })
+                        .collect();
+                    let parent_block = List::new(parent_items)
+                        .block(
+                            Block::default()
+                                .borders(Borders::ALL)
+                                .title("Parent")
+                                .style(colors.border(false)),
+                        )
+                        .highlight_style(colors.selection())
+                        .style(Style::default().fg(colors.dim));
+                    let mut parent_state = ListState::default();
+                    parent_state.select(entries.iter().position(|entry| entry == cur_dir));
+                    f.render_stateful_widget(parent_block, columns[0], &mut parent_state);
                 }
-                InputMode::WatchKeyBinding => {
-                    let lines = vec![
-                        Line::from("< Enter >: Open directory with selected IDE. copy path if not IDE option provided."),
-                        Line::from("< s >: Sort"),
-                        Line::from("< a >: Create new"),
-                        Line::from("< d >: Delete"),
-                        Line::from("< i >: Search mode"),
-                        Line::from("< c >: Copy dir/file"),
-                        Line::from("<.> : Show hidden files"),
-                    ];
-
-                    let sort_by_text = generate_sort_by_string(&sort_type);
-                    let list_items = Text::from(lines);
-                    let paragraph = Paragraph::new(list_items)
-                        .block(Block::default().borders(Borders::ALL).title(sort_by_text))
-                        .style(Style::default().fg(Color::LightGreen));
-                    f.render_widget(Clear, keybinding_chunks[0]);
-                    f.render_widget(paragraph, keybinding_chunks[0]);
+
+                // Input field
+                let input_block = Paragraph::new(app.input.clone())
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .title(match (&app.search_error, app.search_stack.is_empty()) {
+                                (Some(e), _) => format!("Search ({})", e),
+                                (None, _) if !cache_ready => format!(
+                                    "Search (waiting for the directory cache, {} found)",
+                                    format_count(app.cache_build.unwrap_or(0))
+                                ),
+                                (None, _) if search_worker.is_running() => {
+                                    String::from("Search (searching...)")
+                                }
+                                (None, true) => String::from("Search"),
+                                (None, false) => format!(
+                                    "Search in {} (backspace to go back)",
+                                    app.search_breadcrumb()
+                                ),
+                            })
+                            .style(colors.border(matches!(app.input_mode, InputMode::Editing))),
+                    )
+                    .style(match app.input_mode {
+                        InputMode::Editing | InputMode::Normal => colors.text(),
+                        _ => Style::default().fg(colors.muted),
+                    });
+
+                let mut list_title = String::new();
+                if app.loading {
+                    let title_with_loader = format!("Copying Files...");
+                    list_title.push_str(&title_with_loader);
+                } else {
+                    list_title.push_str(&"List");
                 }
-                InputMode::WatchCopy => {
-                    let copy_area= draw_popup(f.size(), 80, 60);
-                    let copy_popup_chuncks = Layout::default()
+                // List of filtered items
+                // TODO: get first item from the list, 
+                // 1. get first item from list
+                // 2. render content based on type 
+                //    - if type if dir then render its content 
+                //    - if type is file then display content of file if posible
+                // 3. preview mode will only apply when in normal MODE, 
+                // only the entries on screen are built, so a huge directory
+                // draws as fast as a small one
+                let window = ui::list_window(
+                    state.offset(),
+                    state.selected(),
+                    list_rows(inner_layout[0], app.detail_view),
+                    app.files.len(),
+                );
+                *state.offset_mut() = window.start;
+                let window_selected = state
+                    .selected()
+                    .map(|selected| selected.saturating_sub(window.start));
+                let list_block = List::new(file_list_items(&app, &entry_meta, &app.files[window.clone()]))
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .title(list_title.as_str())
+                            .style(colors.border(list_active)), //.title("Filtered List"),
+                    )
+                    .highlight_style(colors.selection())
+                    .highlight_symbol(">")
+                    .style(match app.input_mode {
+                        InputMode::Normal | InputMode::Editing => colors.text(),
+                        _ => Style::default().fg(colors.muted),
+                    });
+
+                //let preview_list_path = get_preview_path(app.files.clone());
+
+                /* let validate_is_file = match validate_file_path(preview_list_path.clone()) {
+                    Some(v) => v,
+                    _=>  {
+                        println!("not a valid file or empty");
+                        false
+                    },
+                }; */
+
+                /* match validate_is_file {
+                    false => {
+    let new_preview_files = get_file_path_data(preview_list_path.unwrap(), false, SortBy::Default, &sort_type);
+                        app.preview_files = new_preview_files.unwrap();
+
+                    },
+                    _ => app.preview_files = Vec::new()
+                }; */
+
+
+                //let file_list = get_file_path_data(valid_preview_list_path.unwrap(), false, SortBy::Default, &sort_type);
+
+                /* let file_list_res = match file_list {
+                    Ok(list) => list,
+                    Err(err) =>  {
+                       Vec::new() 
+                    },
+
+                }; */
+                // TODO: handle first item preview
+                let list_preview_block = List::new(app.preview_files.clone()).block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .title("Preview")
+                            .style(colors.border(preview_active)), //.title("Filtered List"),
+                    )
+                    //.highlight_symbol(">")
+                    .style(Style::default().fg(colors.dim));
+
+
+                let footer_outer_layout = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints(vec![Constraint::Percentage(100)])
+                    .split(chunks[3]);
+
+                let footer_inner_layout = Layout::default()
                     .direction(Direction::Horizontal)
-                    .margin(1)
-                    .constraints([Constraint::Percentage(100)])
-                    .split(copy_area);
-            // TODO: add dir list here: 
-            let read_only_list = List::new(filtered_read_only_items)
-                .block(
-                    Block::default()
-                        .borders(Borders::ALL)
-                        .title("Select Location")
-                        .style(match app.input_mode {
-                            InputMode::Normal => Style::default().fg(Color::Green),
-                            InputMode::Editing => Style::default().fg(Color::White),
-                            _ => Style::default().fg(Color::White).bg(Color::Black),
-                        }),
-                )
-                .highlight_style(
-                    Style::default()
-                        .fg(Color::White)
-                        .add_modifier(Modifier::BOLD),
-                )
-                .highlight_symbol(">")
-                .style(match app.input_mode {
-                    InputMode::Normal => Style::default().fg(Color::White),
-                    InputMode::Editing => Style::default().fg(Color::White),
-                    InputMode::WatchDelete => Style::default().fg(Color::Gray),
-                    InputMode::WatchCreate => Style::default().fg(Color::Gray),
-                    InputMode::WatchRename => Style::default().fg(Color::Gray),
-                    InputMode::WatchSort => Style::default().fg(Color::Gray),
-                    _ => Style::default().fg(Color::Gray),
-                });
-                f.render_widget(Clear, copy_area);
-                f.render_stateful_widget(read_only_list, copy_popup_chuncks[0], &mut read_only_state);
+                    .constraints(vec![Constraint::Percentage(50), Constraint::Percentage(50)])
+                    .split(footer_outer_layout[0]);
+
+                //let default_empty_label = Span::styled("", Style::default());
+    let footer_text = match jobs.running_count() {
+                    0 => app.curr_stats.clone(),
+                    count => format!("[{} job(s)] {}", count, app.curr_stats),
+                };
+                match jobs.transfer_progress() {
+                    Some(progress) => {
+                        let gauge = Gauge::default()
+                            .block(Block::default().borders(Borders::ALL).title(footer_text))
+                            .gauge_style(Style::default().fg(colors.success).bg(colors.selection_bg))
+                            .ratio(progress.ratio())
+                            .label(progress.label());
+                        f.render_widget(gauge, footer_inner_layout[1]);
+                    }
+                    None => {
+    let footer_stats =
+                    Text::from(Line::from(Span::styled(footer_text, Style::default())));
+                            let footer_stats_paragraph = Paragraph::new(footer_stats)
+                    .block(Block::default().borders(Borders::ALL))
+                    .style(Style::default());
+                     f.render_widget(footer_stats_paragraph, footer_inner_layout[1]);
+                    }
                 }
-                _ => {}
-            }
-        })?;
 
-        // Handle input
-        if let Event::Key(key) = event::read()? {
-            match app.input_mode {
-                InputMode::Normal => match key.code {
-                    KeyCode::Char('i') => {
-                        app.input_mode = InputMode::Editing;
-                        file_reader_content.file_type = FileType::NotAvailable;
+                 match app.files.len() > 0 {
+                    true => {
+            },
+                    false =>{}
+                    };
+
+                let instructions = Text::from(status_bar.line(&colors));
+
+                let parsed_instructions = Paragraph::new(instructions)
+                    .block(Block::default().borders(Borders::ALL))
+                    .style(Style::default());
+                let text = Text::from(Line::from(msg)).patch_style(style);
+                let help_message = Paragraph::new(text);
+
+                let input_area = chunks[1];
+                match app.input_mode {
+                    InputMode::Normal => {}
+                    InputMode::WatchDelete => {}
+                    InputMode::WatchCreate => {}
+                    InputMode::WatchRename => {}
+                    InputMode::WatchSort => {}
+                    InputMode::Editing => f.set_cursor(
+                        input_area.x + app.character_index as u16 + 1,
+                        input_area.y + 1,
+                    ),
+                    _ => {}
+                }
+
+                f.render_widget(help_message, chunks[0]);
+                f.render_widget(input_block, chunks[1]);
+                //f.render_widget(paragraph, chunks[2]);
+                //f.render_widget(default_label, chunks[2]);
+                //f.render_widget(parsed_instructions.clone(), footer_outer_layout[0]);
+                //f.render_widget(parsed_instructions.clone(), footer_layout[1]);
+                //f.render_widget(parsed_instructions.clone(), chunks[3]);
+                //f.render_stateful_widget(list_block.clone(), inner_layout[0], &mut state);
+                // f.render_widget(list_block, inner_layout[1]);
+                if matches!(app.input_mode, InputMode::WatchTree) {
+                    let tree_block = tree_list(&tree)
+                        .block(
+                            Block::default()
+                                .borders(Borders::ALL)
+                                .title("Tree (l expand, h collapse, enter to open, esc to close)")
+                                .style(colors.border(true)),
+                        )
+                        .highlight_style(colors.selection())
+                        .highlight_symbol(">")
+                        .style(colors.text());
+                    f.render_stateful_widget(tree_block, inner_layout[0], &mut tree.list_state);
+                } else if app.detail_view {
+                    let table = ui::detail_table(
+                        &app.files[window.clone()],
+                        &app.marked_files,
+                        &app.locked_dirs,
+                        &app.dir_sizes,
+                        &entry_meta,
+                        detail_sort,
+                        &colors,
+                    )
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .title(list_title.as_str())
+                            .style(colors.border(list_active)),
+                    )
+                    .highlight_style(colors.selection())
+                    .highlight_symbol(">")
+                    .style(colors.text());
+                    table_state.select(state.selected());
+                    *table_state.offset_mut() = window.start;
+                    let mut window_state = TableState::default().with_selected(window_selected);
+                    f.render_stateful_widget(table, inner_layout[0], &mut window_state);
+                } else {
+                    let mut window_state = ListState::default().with_selected(window_selected);
+                    f.render_stateful_widget(list_block.clone(), inner_layout[0], &mut window_state);
+                }
+                list_area = inner_layout[0];
+                preview_area = inner_layout[1];
+
+                let t = file_reader_content.file_type.clone();
+                match t {
+                    FileType::FILE => {
                         image_generator.image = None;
+                        let tree = file_reader_content
+                            .structured
+                            .as_ref()
+                            .filter(|_| app.structured_view);
+                        if app.follow_preview && tree.is_none() {
+                            // the last lines of the file fill the preview, inside its border
+                            let height = inner_layout[1].height.saturating_sub(2) as usize;
+                            app.preview_scroll =
+                                file_reader_content.preview_lines.saturating_sub(height) as u16;
+                        }
+                        let file_preview_text = match tree {
+                            Some(tree) => {
+                                Paragraph::new(tree.text(app.preview_scroll as usize, &colors))
+                                    .block(
+                                        Block::default()
+                                            .borders(Borders::ALL)
+                                            .title("Tree (left/right fold, T for raw)")
+                                            .border_style(colors.border(preview_active)),
+                                    )
+                            }
+                            None => file_reader_content
+                                .hightlighted_content
+                                .as_ref()
+                                .unwrap()
+                                .clone()
+                                .block(Block::default().borders(Borders::ALL).border_style(colors.border(preview_active))),
+                        }
+                        .scroll((app.preview_scroll, 0))
+                        .style(Style::default());
+                        f.render_widget(file_preview_text, inner_layout[1]);
                     }
-                    KeyCode::Char('q') => {
-                        break;
+                    FileType::IMG => {
+                        let image_block = Block::default()
+                            .borders(Borders::ALL)
+                            .title("Image Preview")
+                            .border_style(colors.border(preview_active));
+                        let info = &file_reader_content.curr_image_info;
+                        let [image_area, info_area] = Layout::vertical([
+                            Constraint::Min(0),
+                            Constraint::Length(info.len() as u16),
+                        ])
+                        .areas(image_block.inner(inner_layout[1]));
+                        f.render_widget(image_block, inner_layout[1]);
+                        let info_lines: Vec<Line> = info.iter().map(|line| Line::from(line.clone())).collect();
+                        f.render_widget(
+                            Paragraph::new(info_lines).style(Style::default().fg(colors.dim)),
+                            info_area,
+                        );
+
+                        // render straight into the stored protocol so the encoded
+                        // image is reused between frames instead of re-encoded
+                        if let Some(image_state) = image_generator.image.as_mut() {
+                            let image = StatefulImage::new(None);
+                            f.render_stateful_widget(image, image_area, image_state);
+                        }
                     }
-                    KeyCode::Down | KeyCode::Char('j') => {
-                        if app.files.len() > 0 {
-                            let i = match state.selected() {
-                                Some(i) => {
-                                    if i >= app.files.len() - 1 {
-                                        0
-                                    } else {
-                                        i + 1
-                                    }
-                                }
-                                None => 0,
-                            };
-                            state.select(Some(i));
-                            app.curr_index = Some(i);
+                    FileType::ZIP => {
+    let zip_list_content = List::new(file_reader_content.curr_zip_content.clone()).block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .title("ZIP Preview")
+                            .style(colors.border(preview_active)), //.title("Filtered List"),
+                    )
+                    .style(Style::default().fg(colors.dim));
+                f.render_widget(zip_list_content, inner_layout[1], );
+
+                    }
+                    FileType::Archive => {
+    let archive_list_content = List::new(file_reader_content.curr_archive_content.clone()).block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .title("Archive Preview (x to extract)")
+                            .style(colors.border(preview_active)),
+                    )
+                    .style(Style::default().fg(colors.dim));
+                f.render_widget(archive_list_content, inner_layout[1], );
 
-                            let selected_cur_path = &app.files[i];
-                            let get_metadata = get_metadata_info(selected_cur_path.to_owned());
-                            let generated_metadata_str = generate_metadata_str_info(get_metadata);
+                    }
+                    FileType::Pdf => {
+                        let pdf_lines: Vec<Line> = file_reader_content
+                            .curr_pdf_content
+                            .iter()
+                            .map(|line| Line::from(line.clone()))
+                            .collect();
+                        let pdf_preview = Paragraph::new(pdf_lines)
+                            .block(Block::default().borders(Borders::ALL).title("PDF Preview").border_style(colors.border(preview_active)))
+                            .wrap(Wrap { trim: false })
+                            .scroll((app.preview_scroll, 0))
+                            .style(Style::default());
+                        f.render_widget(pdf_preview, inner_layout[1]);
+                    }
+                    FileType::Office => {
+                        let office_lines: Vec<Line> = file_reader_content
+                            .curr_office_content
+                            .iter()
+                            .map(|line| Line::from(line.clone()))
+                            .collect();
+                        let office_preview = Paragraph::new(office_lines)
+                            .block(Block::default().borders(Borders::ALL).title("Document Preview").border_style(colors.border(preview_active)))
+                            .wrap(Wrap { trim: false })
+                            .scroll((app.preview_scroll, 0))
+                            .style(Style::default());
+                        f.render_widget(office_preview, inner_layout[1]);
+                    }
+                    FileType::CSV => {
+                        let csv = &file_reader_content.curr_csv_content;
+                        let csv_table = ui::csv_table(csv, app.preview_scroll as usize, app.preview_column, &colors)
+                            .block(
+                                Block::default()
+                                    .borders(Borders::ALL)
+                                    .title(format!(
+                                        "Preview ({} rows, column {}/{}, [ ] to scroll)",
+                                        csv.rows.len(),
+                                        app.preview_column + 1,
+                                        csv.widths.len().max(1)
+                                    ))
+                                    .style(colors.border(preview_active)),
+                            );
+                        f.render_widget(csv_table, inner_layout[1]);
+                    }
+                    _ => {
+
+            image_generator.image = None;
+                        match &app.preview_error {
+                            Some(reason) => {
+                                let placeholder = Paragraph::new(vec![
+                                    Line::from(""),
+                                    Line::from(format!("{} {}", icons::LOCK, capitalize(reason))),
+                                ])
+                                .alignment(Alignment::Center)
+                                .block(Block::default().borders(Borders::ALL).title("Preview"))
+                                .style(Style::default().fg(colors.error));
+                                f.render_widget(placeholder, inner_layout[1]);
+                            }
+                            None if !thumbnails.images.is_empty() => {
+                                let preview_split = Layout::vertical([
+                                    Constraint::Percentage(40),
+                                    Constraint::Percentage(60),
+                                ])
+                                .split(inner_layout[1]);
+                                f.render_stateful_widget(list_preview_block, preview_split[0], &mut state);
+                                render_thumbnails(f, preview_split[1], &mut thumbnails);
+                            }
+                            None => f.render_stateful_widget(list_preview_block, inner_layout[1], &mut state),
+                        }
+                    }
+                }
+                // the diff of two marked entries takes over the preview pane
+                if let (InputMode::WatchCompare, Some(comparison)) =
+                    (&app.input_mode, &app.comparison)
+                {
+                    let view = if app.comparison_unified { "unified" } else { "side by side" };
+                    let width = inner_layout[1].width.saturating_sub(2);
+                    let diff = Paragraph::new(comparison.lines(width, app.comparison_unified, &colors))
+                        .block(
+                            Block::default()
+                                .borders(Borders::ALL)
+                                .title(format!(
+                                    "{} ({}, tab to switch, esc to close)",
+                                    comparison.title(),
+                                    view
+                                ))
+                                .style(colors.border(true)),
+                        )
+                        .scroll((app.comparison_scroll, 0))
+                        .style(colors.text());
+                    f.render_widget(Clear, inner_layout[1]);
+                    f.render_widget(diff, inner_layout[1]);
+                }
+                //TODO: add match method here
+                //f.render_stateful_widget(list_block, chunks[2], &mut state);
+                f.render_widget(parsed_instructions.clone(), footer_inner_layout[0]);
+                //f.render_widget(footer_stats_paragraph, footer_inner_layout[1]);
+
+                if app.render_popup {
+                    let block = Block::bordered()
+                        .title("Confirm to delete y/n")
+                        .style(Style::default().fg(colors.error));
+                    let area = draw_popup(f.size(), 40, 7);
+                    let popup_chuncks = Layout::default()
+                        .direction(Direction::Horizontal)
+                        .margin(1)
+                        .constraints([Constraint::Percentage(100)])
+                        .split(area);
+                    f.render_widget(Clear, area);
+                    f.render_widget(block, popup_chuncks[0]);
+                }
+
+                let area = draw_popup(f.size(), 40, 7);
+                let popup_chuncks = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .margin(1)
+                    .constraints([Constraint::Percentage(100)])
+                    .split(area);
 
-                            app.curr_stats = generated_metadata_str.clone();
-                            file_reader_content.curr_selected_path = selected_cur_path.clone();
+                let sort_option_area = draw_popup(f.size(), 90, 40);
+                let sort_options_chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .margin(1)
+                    .constraints([Constraint::Percentage(100)])
+                    .split(sort_option_area);
 
-                            if !is_file(selected_cur_path.to_string()) {
-                                if let Some(file_names) =
-                                    get_content_from_path(selected_cur_path.to_string())
-                                {
-                                    image_generator.image = None;
-                                    file_reader_content.file_type = FileType::NotAvailable;
-                                    app.preview_files = file_names;
-                                }
-                            } else {
-                                let file_extension = file_reader_content
-                                    .get_file_extension(selected_cur_path.clone());
+                let keybinding_area = draw_popup(f.size(), 80, 20);
+                let keybinding_chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .margin(1)
+                    .constraints([Constraint::Percentage(100)])
+                    .split(keybinding_area);
+
+                match app.input_mode {
+                    InputMode::WatchCreate => {
+                        //f.render_widget(popup_block, area);
+
+                        let create_input_block = Paragraph::new(app.create_edit_file_name.clone())
+                            .block(Block::default().borders(Borders::ALL).title(
+                                match app.is_create_edit_error {
+                                    false => "Create File/Dir (end with / for a directory)".to_string(),
+                                    true => app.error_message.to_owned(),
+                                },
+                            ))
+                            .style(match app.is_create_edit_error {
+                                true => Style::default().fg(colors.error),
+                                false => Style::default().fg(colors.success),
+                            });
+
+                        f.render_widget(Clear, popup_chuncks[0]);
+                        f.render_widget(create_input_block, popup_chuncks[0]);
+                    }
+                    InputMode::WatchRename => {
+                        let create_input_block = Paragraph::new(app.create_edit_file_name.clone())
+                            .block(Block::default().borders(Borders::ALL).title("Enter file/dir name"))
+                            .style(Style::default().fg(colors.success));
+
+                        f.render_widget(create_input_block, popup_chuncks[0]);
+                    }
+                    InputMode::WatchCopyName => {
+                        let copy_name_block = Paragraph::new(app.create_edit_file_name.clone())
+                            .block(Block::default().borders(Borders::ALL).title(
+                                match app.is_create_edit_error {
+                                    false => format!("Copy to {}/", app.current_path_to_edit),
+                                    true => app.error_message.to_owned(),
+                                },
+                            ))
+                            .style(match app.is_create_edit_error {
+                                true => Style::default().fg(colors.error),
+                                false => Style::default().fg(colors.success),
+                            });
+
+                        f.render_widget(Clear, popup_chuncks[0]);
+                        f.render_widget(copy_name_block, popup_chuncks[0]);
+                    }
+                    InputMode::WatchSort => {
+                        let lines = vec![
+                            Line::from("Press (a) to sort ASC or (d) to sort DESC, (q) to exit"),
+                            Line::from("Name: (n)"),
+                            Line::from("Date Created: (t)"),
+                            Line::from("Size: (s)"),
+                            Line::from("Real size (u)"),
+                            Line::from(format!(
+                                "Natural order, file2 before file10 (N): {}",
+                                if app.sort_options.natural { "on" } else { "off" }
+                            )),
+                            Line::from(format!(
+                                "Case sensitive names (C): {}",
+                                if app.sort_options.case_sensitive { "on" } else { "off" }
+                            )),
+                            Line::from(format!(
+                                "Directories first (D): {}",
+                                if app.sort_options.directories_first { "on" } else { "off" }
+                            )),
+                        ];
+
+                        let sort_by_text = generate_sort_by_string(&sort_type);
+                        let list_items = Text::from(lines);
+                        let p = Paragraph::new(list_items)
+                            .block(Block::default().borders(Borders::ALL).title(sort_by_text))
+                            .style(Style::default().fg(colors.success));
+                        f.render_widget(Clear, sort_options_chunks[0]);
+                        f.render_widget(p, sort_options_chunks[0]);
+
+                        //f.render_widget(create_input_block, sort_options_chunks[0]);
+                    }
+                    InputMode::WatchKeyBinding => {
+                        let lines: Vec<Line> = keymap
+                            .help_lines()
+                            .into_iter()
+                            .map(Line::from)
+                            .collect();
+
+                        let sort_by_text = generate_sort_by_string(&sort_type);
+                        let list_items = Text::from(lines);
+                        let paragraph = Paragraph::new(list_items)
+                            .block(Block::default().borders(Borders::ALL).title(sort_by_text))
+                            .style(Style::default().fg(colors.success));
+                        f.render_widget(Clear, keybinding_chunks[0]);
+                        f.render_widget(paragraph, keybinding_chunks[0]);
+                    }
+                    InputMode::ContentSearch => {
+                        let search_area = draw_popup(f.size(), 90, 80);
+                        let search_chunks = Layout::default()
+                            .direction(Direction::Vertical)
+                            .constraints([Constraint::Length(3), Constraint::Min(1)])
+                            .split(search_area);
+
+                        let query_block = Paragraph::new(app.content_search_query.clone())
+                            .block(
+                                Block::default()
+                                    .borders(Borders::ALL)
+                                    .title("Search file contents (Enter to search/open, ctrl-o to open every file, Esc to exit)"),
+                            )
+                            .style(Style::default().fg(colors.success));
+
+                        let root_prefix = format!("{}{}", app.content_search_root, MAIN_SEPARATOR);
+                        let result_items: Vec<ListItem> = app
+                            .content_search_results
+                            .iter()
+                            .map(|result| {
+                                let display_path =
+                                    result.path.strip_prefix(&root_prefix).unwrap_or(&result.path);
+                                let line = result.line.as_str();
+                                ListItem::new(Line::from(vec![
+                                    Span::styled(
+                                        format!("{}:{}: ", display_path, result.line_number),
+                                        Style::default().fg(colors.accent),
+                                    ),
+                                    Span::raw(line[..result.match_start].trim_start().to_string()),
+                                    Span::styled(
+                                        line[result.match_start..result.match_end].to_string(),
+                                        Style::default()
+                                            .fg(colors.highlight)
+                                            .add_modifier(Modifier::BOLD),
+                                    ),
+                                    Span::raw(line[result.match_end..].to_string()),
+                                ]))
+                            })
+                            .collect();
+                        let results_title = if content_search_receiver.is_some() {
+                            String::from("Searching...")
+                        } else {
+                            app.content_search_status.clone()
+                        };
+                        let results_list = List::new(result_items)
+                            .block(Block::default().borders(Borders::ALL).title(results_title))
+                            .highlight_style(
+                                colors.selection(),
+                            )
+                            .highlight_symbol(">");
+
+                        f.render_widget(Clear, search_area);
+                        f.render_widget(query_block, search_chunks[0]);
+                        f.render_stateful_widget(results_list, search_chunks[1], &mut content_search_state);
+                        f.set_cursor(
+                            search_chunks[0].x + app.content_search_query.chars().count() as u16 + 1,
+                            search_chunks[0].y + 1,
+                        );
+                    }
+                    InputMode::Filter => {
+                        let (title, color) = match &app.filter_error {
+                            Some(e) => (e.clone(), colors.error),
+                            None => (
+                                format!("Filter ({}/{})", app.files.len(), app.read_only_files.len()),
+                                colors.success,
+                            ),
+                        };
+                        let filter_block = Paragraph::new(app.filter_input.clone())
+                            .block(Block::default().borders(Borders::ALL).title(title))
+                            .style(Style::default().fg(color));
+
+                        f.render_widget(Clear, popup_chuncks[0]);
+                        f.render_widget(filter_block, popup_chuncks[0]);
+                    }
+                    InputMode::WatchSaveSearch => {
+                        let save_block = Paragraph::new(app.create_edit_file_name.clone())
+                            .block(Block::default().borders(Borders::ALL).title(
+                                match app.is_create_edit_error {
+                                    false => String::from("Save search as"),
+                                    true => app.error_message.to_owned(),
+                                },
+                            ))
+                            .style(match app.is_create_edit_error {
+                                true => Style::default().fg(colors.error),
+                                false => Style::default().fg(colors.success),
+                            });
+
+                        f.render_widget(Clear, popup_chuncks[0]);
+                        f.render_widget(save_block, popup_chuncks[0]);
+                    }
+                    InputMode::CacheLoading => {
+                        let cache_area = draw_popup(f.size(), 50, 20);
+                        let found = app.cache_build.unwrap_or(0);
+                        let cache_text = vec![
+                            Line::from(format!("Scanning {}", config.start_path)),
+                            Line::from(format!("{} entries found", format_count(found))),
+                            Line::from(""),
+                            Line::from("esc keeps browsing while it finishes")
+                                .style(Style::default().fg(colors.muted)),
+                        ];
+                        let cache_block = Paragraph::new(cache_text)
+                            .wrap(Wrap { trim: true })
+                            .style(colors.text())
+                            .block(
+                                Block::default()
+                                    .borders(Borders::ALL)
+                                    .title("Rebuilding directory cache")
+                                    .style(colors.border(true)),
+                            );
 
-                                match file_extension {
-                                    FileType::FILE => {
-                                        image_generator.image = None;
-                                        file_reader_content.file_type = FileType::FILE;
-                                        let file_content = file_reader_content
-                                            .read_file_content(selected_cur_path.to_string());
+                        f.render_widget(Clear, cache_area);
+                        f.render_widget(cache_block, cache_area);
+                    }
+                    InputMode::WatchIgnore => {
+                        let ignore_block = Paragraph::new(app.create_edit_file_name.clone())
+                            .block(Block::default().borders(Borders::ALL).title(
+                                match app.is_create_edit_error {
+                                    false => String::from("Ignore pattern (enter add, esc cancel)"),
+                                    true => app.error_message.to_owned(),
+                                },
+                            ))
+                            .style(match app.is_create_edit_error {
+                                true => Style::default().fg(colors.error),
+                                false => Style::default().fg(colors.success),
+                            });
+
+                        f.render_widget(Clear, popup_chuncks[0]);
+                        f.render_widget(ignore_block, popup_chuncks[0]);
+                    }
+                    InputMode::WatchSavedSearches => {
+                        let saved_area = draw_popup(f.size(), 80, 60);
+                        let saved_items: Vec<ListItem> = search_history
+                            .saved
+                            .iter()
+                            .map(|saved| {
+                                ListItem::new(format!(
+                                    "{} [{}]: {}",
+                                    saved.name,
+                                    saved.kind.label(),
+                                    saved.query
+                                ))
+                            })
+                            .collect();
+                        let saved_list = List::new(saved_items)
+                            .block(
+                                Block::default()
+                                    .borders(Borders::ALL)
+                                    .title("Saved searches (enter run, d delete, esc close)"),
+                            )
+                            .highlight_style(
+                                colors.selection(),
+                            )
+                            .highlight_symbol(">");
 
-                                        let curr_file_type = file_reader_content
-                                            .get_file_extension_type(selected_cur_path.clone());
+                        f.render_widget(Clear, saved_area);
+                        f.render_stateful_widget(saved_list, saved_area, &mut saved_search_state);
+                    }
+                    InputMode::WatchThemes => {
+                        let theme_area = draw_popup(f.size(), 40, 50);
+                        let theme_items: Vec<ListItem> = app
+                            .themes
+                            .iter()
+                            .map(|theme| match theme.name == config.theme_name(app.light_mode) {
+                                true => ListItem::new(format!("{} (current)", theme.name)),
+                                false => ListItem::new(theme.name.clone()),
+                            })
+                            .collect();
+                        let theme_list = List::new(theme_items)
+                            .block(
+                                Block::default()
+                                    .borders(Borders::ALL)
+                                    .title("Themes (enter keep, esc cancel)")
+                                    .style(colors.border(true)),
+                            )
+                            .highlight_style(colors.selection())
+                            .highlight_symbol(">")
+                            .style(colors.text());
 
-                                        //app.input = curr_file_type.clone().unwrap().clone();
+                        f.render_widget(Clear, theme_area);
+                        f.render_stateful_widget(theme_list, theme_area, &mut theme_state);
+                    }
+                    InputMode::WatchVolumes => {
+                        let volume_area = draw_popup(f.size(), 80, 60);
+                        let volume_items: Vec<ListItem> = app
+                            .volumes
+                            .iter()
+                            .map(|volume| {
+                                let space = match volume.space {
+                                    Some((free, total)) => format!(
+                                        "{} free of {}",
+                                        format_size(free),
+                                        format_size(total)
+                                    ),
+                                    None => String::new(),
+                                };
+                                ListItem::new(format!(
+                                    "{:<40} {:<10} {}",
+                                    volume.path.display(),
+                                    volume.file_system,
+                                    space
+                                ))
+                            })
+                            .collect();
+                        let volume_list = List::new(volume_items)
+                            .block(
+                                Block::default()
+                                    .borders(Borders::ALL)
+                                    .title("Volumes (enter open, esc close)"),
+                            )
+                            .highlight_style(
+                                colors.selection(),
+                            )
+                            .highlight_symbol(">");
 
-                                        let highlighted_content = file_reader_content
-                                        .get_highlighted_content(file_content, curr_file_type);
+                        f.render_widget(Clear, volume_area);
+                        f.render_stateful_widget(volume_list, volume_area, &mut volume_state);
+                    }
+                    InputMode::WatchJump => {
+                        let jump_area = draw_popup(f.size(), 80, 60);
+                        let jump_chunks = Layout::default()
+                            .direction(Direction::Vertical)
+                            .constraints([Constraint::Length(3), Constraint::Min(1)])
+                            .split(jump_area);
+
+                        let input_block = Paragraph::new(app.jump_input.clone())
+                            .block(Block::default().borders(Borders::ALL).title("Jump to"))
+                            .style(Style::default().fg(colors.success));
+                        let result_items: Vec<ListItem> = app
+                            .jump_results
+                            .iter()
+                            .map(|result| ListItem::new(result.clone()))
+                            .collect();
+                        let results_list = List::new(result_items)
+                            .block(Block::default().borders(Borders::ALL).title("Frequent and recent"))
+                            .highlight_style(
+                                colors.selection(),
+                            )
+                            .highlight_symbol(">");
+
+                        f.render_widget(Clear, jump_area);
+                        f.render_widget(input_block, jump_chunks[0]);
+                        f.render_stateful_widget(results_list, jump_chunks[1], &mut jump_state);
+                        f.set_cursor(
+                            jump_chunks[0].x + app.jump_input.chars().count() as u16 + 1,
+                            jump_chunks[0].y + 1,
+                        );
+                    }
+                    InputMode::WatchEditor => {
+                        let editor_area = draw_popup(f.size(), 80, 60);
+                        // first row copies the path instead of opening an editor
+                        let mut editor_items = vec![ListItem::new("none: copy path to clipboard")];
+                        editor_items.extend(app.editors.iter().map(|editor| {
+                            let selected = app.selected_editor.as_ref() == Some(editor);
+                            let marker = if selected { "* " } else { "  " };
+                            ListItem::new(format!("{}{}: {}", marker, editor.name, editor.template))
+                        }));
+                        let editor_list = List::new(editor_items)
+                            .block(
+                                Block::default()
+                                    .borders(Borders::ALL)
+                                    .title("Editor (enter to select, q to close)"),
+                            )
+                            .highlight_style(
+                                colors.selection(),
+                            )
+                            .highlight_symbol(">");
 
-                                        // only update if there are no errors
-                                        if !file_reader_content.is_error {
-                                            app.preview_file_content = highlighted_content;
-                                        }
-                                    }
-                                    FileType::IMG => {
-                                        image_generator.image = None;
-                                        file_reader_content.curr_asset_path =
-                                            selected_cur_path.to_string();
+                        f.render_widget(Clear, editor_area);
+                        f.render_stateful_widget(editor_list, editor_area, &mut editor_state);
+                    }
+                    InputMode::WatchCommands => {
+                        let command_area = draw_popup(f.size(), 80, 60);
+                        let command_items: Vec<ListItem> = if app.commands.is_empty() {
+                            vec![ListItem::new("No commands, add them under \"commands\" in settings.json")]
+                        } else {
+                            app.commands
+                                .iter()
+                                .map(|command| {
+                                    ListItem::new(format!(
+                                        "{}: {} ({})",
+                                        command.name,
+                                        command.template,
+                                        command.mode.label()
+                                    ))
+                                })
+                                .collect()
+                        };
+                        let command_list = List::new(command_items)
+                            .block(
+                                Block::default()
+                                    .borders(Borders::ALL)
+                                    .title("Run command (enter to run, q to close)"),
+                            )
+                            .highlight_style(
+                                colors.selection(),
+                            )
+                            .highlight_symbol(">");
 
-                                        image_generator.load_img(selected_cur_path.clone());
-                                        file_reader_content.file_type = FileType::IMG;
-                                    }
-                                    FileType::ZIP => {
-                                        image_generator.image = None;
-                                        file_reader_content
-                                            .read_zip_content(selected_cur_path.clone());
-                                        file_reader_content.file_type = FileType::ZIP;
-                                    }
-                                    FileType::CSV => {
-                                        image_generator.image = None;
-                                        file_reader_content.read_csv_content();
-                                        file_reader_content.file_type = FileType::CSV;
-                                    }
-                                    _ => {
-                                        image_generator.image = None;
-                                        file_reader_content.file_type = FileType::NotAvailable;
-                                    }
-                                }
+                        f.render_widget(Clear, command_area);
+                        f.render_stateful_widget(command_list, command_area, &mut command_state);
+                    }
+                    InputMode::WatchConverters => {
+                        let converter_area = draw_popup(f.size(), 80, 60);
+                        let converter_items: Vec<ListItem> = if app.converters.is_empty() {
+                            vec![ListItem::new("No converters, add them under \"converters\" in settings.json")]
+                        } else {
+                            app.converters
+                                .iter()
+                                .map(|converter| ListItem::new(converter.label()))
+                                .collect()
+                        };
+                        let converter_list = List::new(converter_items)
+                            .block(
+                                Block::default()
+                                    .borders(Borders::ALL)
+                                    .title("Convert the selection (enter to convert, q to close)"),
+                            )
+                            .highlight_style(colors.selection())
+                            .highlight_symbol(">");
 
-                                app.preview_files = Vec::new();
+                        f.render_widget(Clear, converter_area);
+                        f.render_stateful_widget(converter_list, converter_area, &mut converter_state);
+                    }
+                    InputMode::WatchConversions => {
+                        let results_area = draw_popup(f.size(), 80, 60);
+                        let failed = app
+                            .conversion_results
+                            .iter()
+                            .filter(|result| result.output.is_err())
+                            .count();
+                        let lines: Vec<Line> = app
+                            .conversion_results
+                            .iter()
+                            .map(|result| {
+                                let input = result.input.file_name().unwrap_or_default().to_string_lossy();
+                                match &result.output {
+                                    Ok(output) => Line::styled(
+                                        format!(
+                                            "✓ {} -> {}",
+                                            input,
+                                            output.file_name().unwrap_or_default().to_string_lossy()
+                                        ),
+                                        Style::default().fg(colors.success),
+                                    ),
+                                    Err(e) => Line::styled(
+                                        format!("✗ {}: {}", input, e),
+                                        Style::default().fg(colors.error),
+                                    ),
+                                }
+                            })
+                            .collect();
+                        let title = format!(
+                            "Converted {} of {} file(s), (esc) close",
+                            app.conversion_results.len() - failed,
+                            app.conversion_results.len()
+                        );
+                        let results = Paragraph::new(Text::from(lines))
+                            .block(Block::default().borders(Borders::ALL).title(title))
+                            .style(colors.text())
+                            .scroll((app.conversion_scroll, 0));
+                        f.render_widget(Clear, results_area);
+                        f.render_widget(results, results_area);
+                    }
+                    InputMode::WatchHash => {
+                        let mut lines = vec![
+                            Line::from(app.hash_target.clone()),
+                            Line::from("MD5: (m)  SHA-1: (s)  SHA-256: (2)  BLAKE3: (b)  Close: (q)"),
+                            Line::from(""),
+                        ];
+                        match &app.hash_result {
+                            Some(digest) => {
+                                lines.push(Line::from(digest.clone()));
+                                lines.push(Line::from("Copied to clipboard"));
                             }
+                            None if hash_receiver.is_some() => lines.push(Line::from("Hashing...")),
+                            None => {}
                         }
+                        let paragraph = Paragraph::new(Text::from(lines))
+                            .block(Block::default().borders(Borders::ALL).title("Checksum"))
+                            .style(Style::default().fg(colors.success))
+                            .wrap(Wrap { trim: false });
+                        f.render_widget(Clear, sort_options_chunks[0]);
+                        f.render_widget(paragraph, sort_options_chunks[0]);
                     }
-                    KeyCode::Up | KeyCode::Char('k') => {
-                        if app.files.len() > 0 {
-                            let i = match state.selected() {
-                                Some(i) => {
-                                    if i == 0 {
-                                        app.files.len() - 1
-                                    } else {
-                                        i - 1
-                                    }
-                                }
-                                None => 0,
-                            };
-                            state.select(Some(i));
-                            app.curr_index = Some(i);
-                            let selected_cur_path = &app.files[i];
-                            let get_metadata = get_metadata_info(selected_cur_path.to_owned());
-                            let generated_metadata_str = generate_metadata_str_info(get_metadata);
-                            app.curr_stats = generated_metadata_str.clone();
-
-                            // INFO: update preview list
-
-                            file_reader_content.curr_selected_path = selected_cur_path.clone();
-                            if !is_file(selected_cur_path.clone()) {
-                                if let Some(file_names) =
-                                    get_content_from_path(selected_cur_path.to_string())
-                                {
-                                    image_generator.image = None;
-                                    file_reader_content.file_type = FileType::NotAvailable;
-                                    app.preview_files = file_names;
-                                }
-                            } else {
-                                let file_extension = file_reader_content
-                                    .get_file_extension(selected_cur_path.clone());
-
-                                match file_extension {
-                                    FileType::FILE => {
-                                        image_generator.image = None;
-                                        file_reader_content.file_type = FileType::FILE;
-                                        let file_content = file_reader_content
-                                            .read_file_content(selected_cur_path.to_string());
-                                        let curr_file_type = file_reader_content
-                                            .get_file_extension_type(selected_cur_path.clone());
-                                        let highlighted_content = file_reader_content
-                                        .get_highlighted_content(file_content, curr_file_type);
-                                        // only update if there are no errors
-                                        if !file_reader_content.is_error {
-                                            //app.preview_file_content = file_content;
-                                             app.preview_file_content = highlighted_content;
-                                        }
-                                    }
-                                    FileType::IMG => {
-                                        // image_generator.image = None;
-                                        file_reader_content.curr_asset_path =
-                                            selected_cur_path.to_string();
-
-                                        image_generator.load_img(selected_cur_path.clone());
-                                        file_reader_content.file_type = FileType::IMG;
-                                    }
-                                    FileType::ZIP => {
-                                        image_generator.image = None;
-                                        file_reader_content
-                                            .read_zip_content(selected_cur_path.clone());
-                                        file_reader_content.file_type = FileType::ZIP;
-                                    }
-                                    FileType::CSV => {
-                                        image_generator.image = None;
-                                        file_reader_content.read_csv_content();
-                                        file_reader_content.file_type = FileType::CSV;
-                                    }
-                                    _ => {
-                                        image_generator.image = None;
-                                        file_reader_content.file_type = FileType::NotAvailable;
-                                    }
+                    InputMode::WatchTransfer => {
+                        let transfer_area = draw_popup(f.size(), 80, 60);
+                        let transfer_chunks = Layout::default()
+                            .direction(Direction::Vertical)
+                            .constraints([Constraint::Length(3), Constraint::Min(1)])
+                            .split(transfer_area);
+
+                        let verb = if app.transfer_is_move { "Move" } else { "Copy" };
+                        let (title, color) = match (&app.transfer_error, app.transfer_link) {
+                            (Some(e), _) => (e.clone(), colors.error),
+                            (None, Some(LinkKind::Symbolic)) => (
+                                format!(
+                                    "{} ({}) at: (tab) complete, (ctrl-r) relative/absolute, (enter) confirm, (esc) cancel",
+                                    LinkKind::Symbolic.label(),
+                                    if app.link_relative { "relative" } else { "absolute" }
+                                ),
+                                colors.success,
+                            ),
+                            (None, Some(kind)) => (
+                                format!("{} at: (tab) complete, (enter) confirm, (esc) cancel", kind.label()),
+                                colors.success,
+                            ),
+                            (None, None) if app.transfer_sync => (
+                                format!(
+                                    "Sync {} into: (tab) complete, (enter) preview, (esc) cancel",
+                                    app.transfer_sources.first().map_or("", String::as_str)
+                                ),
+                                colors.success,
+                            ),
+                            (None, None) => (
+                                format!("{} {} item(s) to: (tab) complete, (enter) confirm, (esc) cancel", verb, app.transfer_sources.len()),
+                                colors.success,
+                            ),
+                        };
+                        let input_block = Paragraph::new(app.transfer_input.clone())
+                            .block(Block::default().borders(Borders::ALL).title(title))
+                            .style(Style::default().fg(color));
+                        let candidate_items: Vec<ListItem> = app
+                            .transfer_candidates
+                            .iter()
+                            .map(|candidate| ListItem::new(candidate.clone()))
+                            .collect();
+                        let candidate_list = List::new(candidate_items)
+                            .block(Block::default().borders(Borders::ALL).title("Completions"))
+                            .style(colors.text());
+
+                        f.render_widget(Clear, transfer_area);
+                        f.render_widget(input_block, transfer_chunks[0]);
+                        f.render_widget(candidate_list, transfer_chunks[1]);
+                        f.set_cursor(
+                            transfer_chunks[0].x + app.transfer_input.chars().count() as u16 + 1,
+                            transfer_chunks[0].y + 1,
+                        );
+                    }
+                    InputMode::WatchSync => {
+                        let sync_area = draw_popup(f.size(), 80, 60);
+                        let delete = app.sync_delete;
+                        let (title, lines) = match &app.sync_plan {
+                            Some(plan) if plan.has_changes(delete) => {
+                                let mut lines = vec![Line::from(plan.summary(delete)), Line::from("")];
+                                for (kind, line) in plan.preview_lines(delete) {
+                                    let color = match kind {
+                                        SyncKind::Create => colors.success,
+                                        SyncKind::Update => colors.warning,
+                                        SyncKind::Replace | SyncKind::Delete => colors.error,
+                                    };
+                                    lines.push(Line::styled(line, Style::default().fg(color)));
                                 }
-                                app.preview_files = Vec::new()
+                                (
+                                    format!(
+                                        "Sync dry run: (d) delete extraneous [{}], (enter) run, (esc) cancel",
+                                        if delete { "on" } else { "off" }
+                                    ),
+                                    lines,
+                                )
                             }
+                            Some(plan) => (
+                                String::from("Sync dry run: (d) delete extraneous, (esc) close"),
+                                vec![Line::from(plan.summary(delete)), Line::from("Already in sync")],
+                            ),
+                            None => (String::from("Sync"), Vec::new()),
+                        };
+                        let preview = Paragraph::new(Text::from(lines))
+                            .block(Block::default().borders(Borders::ALL).title(title))
+                            .style(colors.text())
+                            .scroll((app.sync_scroll, 0));
+                        f.render_widget(Clear, sync_area);
+                        f.render_widget(preview, sync_area);
+                    }
+                    InputMode::WatchGoto => {
+                        let goto_area = draw_popup(f.size(), 80, 60);
+                        let goto_chunks = Layout::default()
+                            .direction(Direction::Vertical)
+                            .constraints([Constraint::Length(3), Constraint::Min(1)])
+                            .split(goto_area);
+
+                        let (title, color) = match &app.goto_error {
+                            Some(e) => (e.clone(), colors.error),
+                            None => (
+                                String::from("Go to: (tab) complete, (enter) go, (esc) cancel"),
+                                colors.success,
+                            ),
+                        };
+                        let input_block = Paragraph::new(app.goto_input.clone())
+                            .block(Block::default().borders(Borders::ALL).title(title))
+                            .style(Style::default().fg(color));
+                        let candidate_items: Vec<ListItem> = app
+                            .goto_candidates
+                            .iter()
+                            .map(|candidate| ListItem::new(candidate.clone()))
+                            .collect();
+                        let candidate_list = List::new(candidate_items)
+                            .block(Block::default().borders(Borders::ALL).title("Completions"))
+                            .style(colors.text());
+
+                        f.render_widget(Clear, goto_area);
+                        f.render_widget(input_block, goto_chunks[0]);
+                        f.render_widget(candidate_list, goto_chunks[1]);
+                        f.set_cursor(
+                            goto_chunks[0].x + app.goto_input.chars().count() as u16 + 1,
+                            goto_chunks[0].y + 1,
+                        );
+                    }
+                    InputMode::WatchCancelCopy => {
+                        let paragraph = Paragraph::new("Cancel the running copy and remove the partial files? (y/n)")
+                            .block(Block::default().borders(Borders::ALL).title("Cancel copy"))
+                            .style(Style::default().fg(colors.warning));
+                        f.render_widget(Clear, popup_chuncks[0]);
+                        f.render_widget(paragraph, popup_chuncks[0]);
+                    }
+                    InputMode::WatchJobs => {
+                        let jobs_area = draw_popup(f.size(), 80, 60);
+                        let job_items: Vec<ListItem> = jobs
+                            .jobs()
+                            .iter()
+                            .map(|job| ListItem::new(job.line()))
+                            .collect();
+                        let jobs_list = List::new(job_items)
+                            .block(
+                                Block::default()
+                                    .borders(Borders::ALL)
+                                    .title("Jobs: (x) cancel, (p) pause/resume, (c) clear finished, (q) close"),
+                            )
+                            .highlight_style(
+                                colors.selection(),
+                            )
+                            .highlight_symbol(">")
+                            .style(Style::default().fg(colors.success));
+                        f.render_widget(Clear, jobs_area);
+                        f.render_stateful_widget(jobs_list, jobs_area, &mut jobs_state);
+                    }
+                    InputMode::WatchProperties => {
+                        let properties_area = draw_popup(f.size(), 80, 60);
+                        let mut lines: Vec<Line> = match &app.properties {
+                            Some(properties) => properties.lines().into_iter().map(Line::from).collect(),
+                            None => Vec::new(),
+                        };
+
+                        let mut bit_spans = vec![Span::raw("chmod:    ")];
+                        for (index, (bit, c)) in PERMISSION_BITS.iter().enumerate() {
+                            let value = if app.pending_mode & bit != 0 { *c } else { '-' };
+                            let style = if index == app.permission_cursor {
+                                colors.selection()
+                            } else {
+                                Style::default()
+                            };
+                            bit_spans.push(Span::styled(value.to_string(), style));
                         }
+                        bit_spans.push(Span::raw(format!(" ({:04o})", app.pending_mode & 0o7777)));
+                        lines.push(Line::from(""));
+                        lines.push(Line::from(bit_spans));
+
+                        let paragraph = Paragraph::new(Text::from(lines))
+                            .block(
+                                Block::default()
+                                    .borders(Borders::ALL)
+                                    .title("Properties: (h/l) move, (space) toggle, (enter) apply, (q) close"),
+                            )
+                            .style(Style::default().fg(colors.success));
+                        f.render_widget(Clear, properties_area);
+                        f.render_widget(paragraph, properties_area);
                     }
-                    KeyCode::Char('h') => {
-                        if app.files.len() > 0 {
-                            let selected = &app.files[state.selected().unwrap()];
-                            let mut split_path = selected.split("/").collect::<Vec<&str>>();
+                    InputMode::WatchArchive => {
+                        let lines = vec![
+                            Line::from("Zip: (z)"),
+                            Line::from("Tar gzip: (t)"),
+                            Line::from("Cancel: (q)"),
+                        ];
+                        let title = if app.marked_files.is_empty() {
+                            "Archive selected item".to_string()
+                        } else {
+                            format!("Archive {} marked item(s)", app.marked_files.len())
+                        };
+                        let paragraph = Paragraph::new(Text::from(lines))
+                            .block(Block::default().borders(Borders::ALL).title(title))
+                            .style(Style::default().fg(colors.success));
+                        f.render_widget(Clear, sort_options_chunks[0]);
+                        f.render_widget(paragraph, sort_options_chunks[0]);
+                    }
+                    InputMode::WatchBulkRename => {
+                        let rename_area = draw_popup(f.size(), 80, 60);
+                        let (title, color) = match &app.bulk_rename_plan {
+                            Some(plan) if plan.is_valid() => ("Bulk rename: (y) apply, (n) cancel", colors.success),
+                            Some(plan) if plan.conflicts.is_empty() => ("Nothing to rename, (n) to close", colors.success),
+                            _ => ("Bulk rename conflicts, (n) to close", colors.error),
+                        };
+                        let preview_lines = match &app.bulk_rename_plan {
+                            Some(plan) => plan.preview_lines(),
+                            None => Vec::new(),
+                        };
+                        let rename_list = List::new(preview_lines)
+                            .block(Block::default().borders(Borders::ALL).title(title))
+                            .style(Style::default().fg(color));
+                        f.render_widget(Clear, rename_area);
+                        f.render_widget(rename_list, rename_area);
+                    }
+                    InputMode::WatchPatternRename => {
+                        let rename_area = draw_popup(f.size(), 80, 60);
+                        let rename_chunks = Layout::default()
+                            .direction(Direction::Vertical)
+                            .constraints([Constraint::Length(3), Constraint::Min(1)])
+                            .split(rename_area);
+
+                        let (title, color) = match (&app.pattern_rename_error, &app.bulk_rename_plan) {
+                            (Some(e), _) => (e.clone(), colors.error),
+                            (None, Some(plan)) if !plan.conflicts.is_empty() => {
+                                (String::from("Conflicts, change the pattern or (esc) cancel"), colors.error)
+                            }
+                            _ => (
+                                format!(
+                                    "Rename {} item(s): s/from/to/[gi] or {{name}} {{ext}} {{n}} {{n:3}}, (enter) apply, (esc) cancel",
+                                    app.pattern_rename_targets.len()
+                                ),
+                                colors.success,
+                            ),
+                        };
+                        let input_block = Paragraph::new(app.pattern_rename_input.clone())
+                            .block(Block::default().borders(Borders::ALL).title(title))
+                            .style(Style::default().fg(color));
+                        // conflicts come first and stand out from the renames
+                        let preview_items: Vec<ListItem> = match &app.bulk_rename_plan {
+                            Some(plan) => plan
+                                .preview_lines()
+                                .into_iter()
+                                .map(|line| {
+                                    let style = match line.starts_with("! ") {
+                                        true => Style::default().fg(colors.error),
+                                        false => colors.text(),
+                                    };
+                                    ListItem::new(line).style(style)
+                                })
+                                .collect(),
+                            None => Vec::new(),
+                        };
+                        let preview_list = List::new(preview_items)
+                            .block(Block::default().borders(Borders::ALL).title("Preview"))
+                            .style(colors.text());
+
+                        f.render_widget(Clear, rename_area);
+                        f.render_widget(input_block, rename_chunks[0]);
+                        f.render_widget(preview_list, rename_chunks[1]);
+                        f.set_cursor(
+                            rename_chunks[0].x + app.pattern_rename_input.chars().count() as u16 + 1,
+                            rename_chunks[0].y + 1,
+                        );
+                    }
+                    InputMode::WatchCopy => {
+                        let copy_area= draw_popup(f.size(), 80, 60);
+                        let copy_popup_chuncks = Layout::default()
+                        .direction(Direction::Horizontal)
+                        .margin(1)
+                        .constraints([Constraint::Percentage(100)])
+                        .split(copy_area);
+                // TODO: add dir list here: 
+                let read_only_list = List::new(filtered_read_only_items)
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .title("Select Location")
+                            .style(colors.border(true)),
+                    )
+                    .highlight_style(colors.selection())
+                    .highlight_symbol(">")
+                    .style(colors.text());
+                    f.render_widget(Clear, copy_area);
+                    f.render_stateful_widget(read_only_list, copy_popup_chuncks[0], &mut read_only_state);
+                    }
+                    _ => {}
+                }
 
-                            let sort_type_copy = sort_type.clone();
-                            // TODO: refactor this to be more idiomatic
-                            if split_path.len() > 4 {
-                                split_path.pop();
-                                split_path.pop();
+                if let Some(toast) = app
+                    .toast
+                    .as_ref()
+                    .filter(|toast| toast.shown.elapsed() < TOAST_DURATION)
+                {
+                    let area = f.size();
+                    let width = (toast.message.len() as u16 + 4).clamp(20, 60).min(area.width);
+                    let lines = (toast.message.len() as u16 / width.saturating_sub(4).max(1)) + 1;
+                    let toast_area = Rect::new(
+                        area.width.saturating_sub(width + 1),
+                        1,
+                        width,
+                        (lines + 2).min(area.height),
+                    );
+                    let style = match toast.error {
+                        true => Style::default().fg(colors.error),
+                        false => Style::default().fg(colors.success),
+                    };
+                    let toast_widget = Paragraph::new(toast.message.clone())
+                        .wrap(Wrap { trim: true })
+                        .style(colors.text())
+                        .block(Block::default().borders(Borders::ALL).style(style));
+                    f.render_widget(Clear, toast_area);
+                    f.render_widget(toast_widget, toast_area);
+                }
+            })?;
+        }
 
-                                let new_path = split_path.join("/");
-                                let files_strings = get_inner_files_info(
-                                    new_path.clone(),
-                                    app.show_hidden_files,
-                                    SortBy::Default,
-                                    &sort_type_copy,
-                                )
-                                .unwrap();
+        // a pasted key that confirmed or closed the prompt ends the paste
+        if !app.input_mode.takes_text() {
+            pasted_keys.clear();
+        }
+        // Handle input
+        // waits for the next event, or for whatever is due first: a debounced
+        // preview or watcher batch, a toast to clear or a frame held back
+        let deadline = [
+            file_reader_content.previews.deadline(),
+            watcher.as_ref().and_then(|watcher| watcher.deadline()),
+            config_watcher
+                .as_ref()
+                .and_then(|watcher| watcher.deadline()),
+            parent_watcher
+                .as_ref()
+                .and_then(|watcher| watcher.deadline()),
+            app.toast
+                .as_ref()
+                .map(|toast| toast.shown + TOAST_DURATION)
+                .filter(|expires| *expires > Instant::now()),
+            redraw.then_some(last_draw + FRAME_INTERVAL),
+        ]
+        .into_iter()
+        .flatten()
+        .min();
+        let event = match pasted_keys.pop_front() {
+            Some(key) => Event::Key(key),
+            None => match events.next(deadline) {
+                Some(AppEvent::Input(event)) => event,
+                Some(AppEvent::InputError(e)) => return Err(e.into()),
+                Some(AppEvent::Tick) | None => {
+                    redraw = true;
+                    continue;
+                }
+                // polled at the top of the loop
+                Some(AppEvent::Watcher | AppEvent::Job | AppEvent::Worker) => continue,
+            },
+        };
+        // any event can change what is on screen, resizes included
+        redraw = true;
+        after_input = true;
+        // mouse events in the normal mode stand in for the action they map to,
+        // the key is a placeholder that nothing is bound to
+        let mouse_action = match event {
+            Event::Key(key) => Ok(key),
+            // paths dropped on the terminal
+            Event::Paste(text) if matches!(app.input_mode, InputMode::Normal) => {
+                let current =
+                    fs::canonicalize(&app.current_dir).unwrap_or_else(|_| app.current_dir.clone());
+                // entries of the listing are selected, the rest can be copied here
+                let mut listed: Vec<usize> = Vec::new();
+                let mut others: Vec<PathBuf> = Vec::new();
+                for path in pasted_paths(&text) {
+                    let in_current = path
+                        .parent()
+                        .and_then(|parent| fs::canonicalize(parent).ok())
+                        .is_some_and(|parent| parent == current);
+                    let index = app
+                        .files
+                        .iter()
+                        .position(|file| Path::new(file).file_name() == path.file_name())
+                        .filter(|_| in_current);
+                    match index {
+                        Some(index) => listed.push(index),
+                        None => others.push(path),
+                    }
+                }
 
-                                if let Some(f_s) = files_strings {
-                                    app.read_only_files = f_s.clone();
-                                    app.files = f_s;
-                                    state.select(Some(0));
+                match (listed.as_slice(), others.as_slice()) {
+                    ([], []) => app.show_toast(String::from("The pasted text isn't a path"), true),
+                    ([], [path]) if path.is_dir() => {
+                        let dir_path = path.to_string_lossy().to_string();
+                        hooks.fire(
+                            HookEvent::EnterDirectory,
+                            std::slice::from_ref(&dir_path),
+                            &app.current_dir,
+                        );
+                        match read_with_view_state(
+                            path.clone(),
+                            &mut app,
+                            &mut sort_type,
+                            &view_states,
+                        ) {
+                            Ok(dir) => {
+                                app.enter(dir);
+                                SelectionController::new(
+                                    &mut app,
+                                    &mut state,
+                                    &mut file_reader_content,
+                                    &mut image_generator,
+                                )
+                                .update_selection(0);
+                                record_visit(&mut frecency, &dir_path);
+                            }
+                            Err(e) => app.curr_stats = errors::status(&dir_path, e),
+                        }
+                    }
+                    (listed, []) => {
+                        // several entries are marked, like with space
+                        if listed.len() > 1 {
+                            for index in listed {
+                                let file = app.files[*index].clone();
+                                if !app.is_marked(&file) {
+                                    app.toggle_mark(&file);
                                 }
                             }
+                        }
+                        SelectionController::new(
+                            &mut app,
+                            &mut state,
+                            &mut file_reader_content,
+                            &mut image_generator,
+                        )
+                        .update_selection(listed[0]);
+                    }
+                    (_, others) => {
+                        app.transfer_is_move = false;
+                        app.transfer_link = None;
+                        app.transfer_sync = false;
+                        app.transfer_sources = others
+                            .iter()
+                            .map(|path| path.to_string_lossy().to_string())
+                            .collect();
+                        app.transfer_input =
+                            format!("{}{}", app.current_dir_string(), MAIN_SEPARATOR);
+                        app.transfer_candidates.clear();
+                        app.transfer_error = None;
+                        app.input_mode = InputMode::WatchTransfer;
+                    }
+                }
+                continue;
+            }
+            // in a prompt it is typed in, line breaks would confirm it.
+            // popups with single key commands ignore it
+            Event::Paste(text) if app.input_mode.takes_text() => {
+                pasted_keys.extend(
+                    text.chars()
+                        .filter(|c| !matches!(c, '\r' | '\n'))
+                        .map(|c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)),
+                );
+                continue;
+            }
+            Event::Paste(_) => continue,
+            Event::Mouse(mouse) if matches!(app.input_mode, InputMode::Normal) => {
+                let mut listing = Listing {
+                    state: &mut state,
+                    table_state: &mut table_state,
+                    area: list_area,
+                    file_reader_content: &mut file_reader_content,
+                    image_generator: &mut image_generator,
+                    sort_type: &mut sort_type,
+                    view_states: &mut view_states,
+                    hooks: &hooks,
+                    frecency: &mut frecency,
+                    associations: &associations,
+                    jobs: &mut jobs,
+                    search_history: &search_history,
+                    start_path: &config.start_path,
+                };
+                match handle_mouse(mouse, preview_area, &mut last_click, &mut app, &mut listing) {
+                    Some(MouseOutcome::Action(action)) => Err(action),
+                    Some(MouseOutcome::SortColumn(column)) => {
+                        let sort_by = match column {
+                            0 => SortBy::Name,
+                            1 => SortBy::Size,
+                            2 => SortBy::DateAdded,
+                            _ => continue,
+                        };
+                        // clicking the sorted column again flips the order
+                        let ascending = match detail_sort {
+                            Some((current, ascending)) if current == column => !ascending,
+                            _ => true,
+                        };
+                        sort_type = if ascending {
+                            SortType::ASC
                         } else {
-                            let copy = sort_type.clone();
-                            let files_strings = get_inner_files_info(
-                                app.prev_dir.clone(),
-                                app.show_hidden_files,
-                                SortBy::Default,
-                                &copy,
-                            )
-                            .unwrap();
-
-                            if let Some(f_s) = files_strings {
-                                app.read_only_files = f_s.clone();
-                                app.files = f_s;
-                                state.select(Some(0));
-                            }
+                            SortType::DESC
+                        };
+                        app.sort_by = sort_by;
+
+                        update_file_references_with_selection_preservation(
+                            None,
+                            &mut app,
+                            &mut state,
+                            &sort_type,
+                            &mut file_reader_content,
+                            &mut image_generator,
+                        );
+                        remember_view(&app, &sort_type, &mut view_states);
+                        continue;
+                    }
+                    None => continue,
+                }
+            }
+            _ => continue,
+        };
+        let (key, mouse_action) = match mouse_action {
+            Ok(key) => (key, None),
+            Err(action) => (
+                KeyEvent::new(KeyCode::Null, KeyModifiers::NONE),
+                Some(action),
+            ),
+        };
+        {
+            match app.input_mode {
+                // second key of a `g` sequence
+                InputMode::Normal if app.pending_goto && mouse_action.is_none() => {
+                    if key.kind != KeyEventKind::Press {
+                        continue;
+                    }
+                    app.pending_goto = false;
+                    let target = match key.code {
+                        KeyCode::Char('g') if !app.files.is_empty() => Some(0),
+                        KeyCode::Char('/') => {
+                            app.open_goto();
+                            None
+                        }
+                        KeyCode::Char(c) => {
+                            next_entry_starting_with(&app.files, state.selected(), c)
                         }
+                        _ => None,
+                    };
+                    if let Some(index) = target {
+                        SelectionController::new(
+                            &mut app,
+                            &mut state,
+                            &mut file_reader_content,
+                            &mut image_generator,
+                        )
+                        .update_selection(index);
                     }
-                    KeyCode::Char('l') => {
-                        let selected_index = state.selected();
-                        if app.files.len() > 0 {
-                            if let Some(selected_indx) = selected_index {
-                                let selected = &app.files[selected_indx];
-
-                                app.prev_dir = get_curr_path(selected.to_string());
-                                if !is_file(selected.to_string()) {
-                                    match get_inner_files_info(
-                                        selected.to_string(),
-                                        app.show_hidden_files,
-                                        SortBy::Default,
-                                        &sort_type,
-                                    ) {
-                                        Ok(files_strings) => {
-                                            if let Some(files_strs) = files_strings {
-                                                app.read_only_files = files_strs.clone();
-                                                app.files = files_strs;
-                                                state.select(Some(0));
+                }
+                // second key of a `y` sequence
+                InputMode::Normal if app.pending_yank && mouse_action.is_none() => {
+                    if key.kind != KeyEventKind::Press {
+                        continue;
+                    }
+                    app.pending_yank = false;
+                    // marked entries are copied together, like enter opens them
+                    let files = match state.selected().and_then(|index| app.files.get(index)) {
+                        _ if !app.marked_files.is_empty() => app.marked_files.clone(),
+                        Some(selected) => vec![selected.clone()],
+                        None => continue,
+                    };
+                    let KeyCode::Char(c) = key.code else {
+                        continue;
+                    };
+                    let Some((text, what)) = yank_text(c, &files, &config.start_path) else {
+                        continue;
+                    };
+                    let copied =
+                        ClipboardContext::new().and_then(|mut ctx| ctx.set_contents(text.clone()));
+                    match copied {
+                        Ok(()) if files.len() == 1 => {
+                            app.show_toast(format!("Copied {}", text), false)
+                        }
+                        Ok(()) => app.show_toast(
+                            format!("Copied the {} of {} entries", what, files.len()),
+                            false,
+                        ),
+                        Err(e) => {
+                            app.show_toast(format!("Unable to copy the {}: {}", what, e), true)
+                        }
+                    }
+                }
+                // keys bound in init.rhai win over the keymap
+                InputMode::Normal if scripts.binding(&key).is_some() => {
+                    if key.kind != KeyEventKind::Press {
+                        continue;
+                    }
+                    let function = scripts.binding(&key).unwrap_or_default();
+                    let cwd = app.current_dir_string();
+                    let context = ScriptContext {
+                        selected: state
+                            .selected()
+                            .and_then(|index| app.files.get(index).cloned())
+                            .unwrap_or_default(),
+                        marked: app.marked_files.clone(),
+                        cwd: cwd.clone(),
+                    };
+
+                    match scripts.call(&function, context) {
+                        Ok(effects) => {
+                            for effect in effects {
+                                match effect {
+                                    ScriptEffect::Cd(path) => {
+                                        let path = transfer::expand_home(&path);
+                                        match read_with_view_state(
+                                            path.clone(),
+                                            &mut app,
+                                            &mut sort_type,
+                                            &view_states,
+                                        ) {
+                                            Ok(dir) => {
+                                                record_visit(&mut frecency, &path);
+                                                hooks.fire(
+                                                    HookEvent::EnterDirectory,
+                                                    std::slice::from_ref(&path),
+                                                    &app.current_dir,
+                                                );
+                                                app.enter(dir);
+                                                SelectionController::new(
+                                                    &mut app,
+                                                    &mut state,
+                                                    &mut file_reader_content,
+                                                    &mut image_generator,
+                                                )
+                                                .update_selection(0);
                                             }
+                                            Err(e) => {
+                                                app.curr_stats = format!("{}: {}", path, e);
+                                            }
+                                        }
+                                    }
+                                    ScriptEffect::Spawn(command) => {
+                                        let custom = CustomCommand {
+                                            name: function.clone(),
+                                            template: command.clone(),
+                                            mode: RunMode::Silent,
+                                        };
+                                        let dir = cwd.clone();
+                                        jobs.spawn(JobKind::Command, command, move |_, _| {
+                                            custom.run_silent(&[], &dir)
+                                        });
+                                    }
+                                    ScriptEffect::Status(message) => app.curr_stats = message,
+                                }
+                            }
+                        }
+                        Err(e) => app.curr_stats = e.to_string(),
+                    }
+                }
+                InputMode::Normal => match mouse_action.or_else(|| keymap.resolve(&key)) {
+                    Some(action) => {
+                        let mut listing = Listing {
+                            state: &mut state,
+                            table_state: &mut table_state,
+                            area: list_area,
+                            file_reader_content: &mut file_reader_content,
+                            image_generator: &mut image_generator,
+                            sort_type: &mut sort_type,
+                            view_states: &mut view_states,
+                            hooks: &hooks,
+                            frecency: &mut frecency,
+                            associations: &associations,
+                            jobs: &mut jobs,
+                            search_history: &search_history,
+                            start_path: &config.start_path,
+                        };
+                        match dispatch(action, &mut app, &mut listing) {
+                            Outcome::Done => {}
+                            Outcome::Quit => break,
+                            Outcome::Open {
+                                selected,
+                                files,
+                                association,
+                            } => match association {
+                                Association::Open => {
+                                    app.curr_stats = match associations::open_with_system(&selected)
+                                    {
+                                        Ok(()) => format!("Opened {}", selected),
+                                        Err(e) => format!("Unable to open {}: {}", selected, e),
+                                    };
+                                }
+                                Association::Command(command)
+                                    if command.mode == RunMode::Silent =>
+                                {
+                                    let description = format!("{} {}", command.name, selected);
+                                    let dir = app.current_dir_string();
+                                    jobs.spawn(JobKind::Command, description, move |_, _| {
+                                        command.run_silent(&files, &dir)
+                                    });
+                                    app.curr_stats = String::from("Command started");
+                                }
+                                Association::Command(command) => {
+                                    let dir = app.current_dir_string();
+                                    let result = with_suspended(&mut terminal, &events, |_| {
+                                        command.run_attached(&files, &dir)
+                                    });
+                                    update_file_references_with_selection_preservation(
+                                        None,
+                                        &mut app,
+                                        &mut state,
+                                        &sort_type,
+                                        &mut file_reader_content,
+                                        &mut image_generator,
+                                    );
+                                    app.curr_stats = match result {
+                                        Ok(message) => message,
+                                        Err(e) => e.to_string(),
+                                    };
+                                }
+                                _ if !app.can_open() => app.show_toast(NO_EDITOR.to_string(), true),
+                                _ => {
+                                    app.input = selected;
+                                    selection = Some(handle_file_selection(
+                                        &files,
+                                        None,
+                                        &mut terminal,
+                                        &events,
+                                        app.clone(),
+                                        &hooks,
+                                    ));
+                                    break;
+                                }
+                            },
+                            Outcome::EditNames(targets) => {
+                                match edit_names_in_editor(&mut terminal, &events, &targets) {
+                                    Ok(new_names) => {
+                                        app.bulk_rename_plan =
+                                            Some(bulk_rename::build_plan(&targets, &new_names));
+                                        app.input_mode = InputMode::WatchBulkRename;
+                                    }
+                                    Err(e) => {
+                                        app.curr_stats = format!("Bulk rename failed: {}", e);
+                                    }
+                                }
+                            }
+                            Outcome::Shell => {
+                                let dir = app.current_dir.clone();
+                                let message = match open_shell(&mut terminal, &events, &dir) {
+                                    Ok(_) => String::from("Back from shell"),
+                                    Err(e) => format!("Unable to open shell: {}", e),
+                                };
+
+                                // the shell may have changed the directory
+                                update_file_references_with_selection_preservation(
+                                    None,
+                                    &mut app,
+                                    &mut state,
+                                    &sort_type,
+                                    &mut file_reader_content,
+                                    &mut image_generator,
+                                );
+                                app.curr_stats = message;
+                            }
+                            Outcome::Popup(selected) => match app.input_mode {
+                                InputMode::WatchCommands => command_state.select(selected),
+                                InputMode::WatchConverters => converter_state.select(selected),
+                                InputMode::WatchEditor => editor_state.select(selected),
+                                InputMode::WatchSavedSearches => {
+                                    saved_search_state.select(selected)
+                                }
+                                InputMode::WatchThemes => theme_state.select(selected),
+                                InputMode::WatchVolumes => volume_state.select(selected),
+                                InputMode::WatchJump => jump_state.select(selected),
+                                // the jobs popup keeps the job selected last time
+                                InputMode::WatchJobs if jobs_state.selected().is_none() => {
+                                    jobs_state.select(selected)
+                                }
+                                _ => {}
+                            },
+                            Outcome::Compare(left, right) => {
+                                let (sender, receiver) = worker_waker.channel();
+                                compare_receiver = Some(receiver);
+                                thread::spawn(move || {
+                                    let _ = sender.send(Comparison::new(&left, &right));
+                                });
+                            }
+                            Outcome::DirSizes(paths) => {
+                                dir_size_cancel.store(true, Ordering::Relaxed);
+                                dir_size_cancel = Arc::new(AtomicBool::new(false));
+
+                                let (sender, receiver) = worker_waker.channel();
+                                dir_size_receiver = Some(receiver);
+                                let cancel = dir_size_cancel.clone();
+                                thread::spawn(move || {
+                                    dir_size::calculate_sizes(paths, cancel, sender);
+                                });
+                            }
+                            Outcome::RebuildCache => {
+                                if cache_receiver.is_none() {
+                                    cache_receiver =
+                                        Some(worker_waker.relay(build_store_in_background(
+                                            config.cache_header(),
+                                            config.cache_directory.clone(),
+                                        )));
+                                    app.cache_build = Some(0);
+                                }
+                            }
+                            Outcome::TreeView => {
+                                tree = TreeState::new(
+                                    &app.current_dir_string(),
+                                    app.show_hidden_files,
+                                    app.listing_ignore().cloned(),
+                                    app.sort_options.clone(),
+                                );
+                            }
+                        }
+                    }
+                    None => {}
+                },
+
+                InputMode::WatchCopyName if key.kind == KeyEventKind::Press => {
+                    match prompt_action(&key) {
+                        Some(PromptAction::Insert(c)) => {
+                            app.add_char(c);
+                        }
+                        Some(PromptAction::Backspace) => {
+                            app.delete_c();
+                        }
+                        Some(PromptAction::Left) => {
+                            app.move_create_edit_cursor_left();
+                        }
+                        Some(PromptAction::Right) => {
+                            app.move_create_edit_cursor_right();
+                        }
+                        Some(PromptAction::Cancel) => {
+                            app.input_mode = InputMode::Normal;
+                            app.reset_create_edit_values();
+                        }
+                        Some(PromptAction::Confirm) => {
+                            let name = app.create_edit_file_name.trim().to_string();
+                            let destination = Path::new(&app.current_path_to_edit)
+                                .join(&name)
+                                .display()
+                                .to_string();
+                            if name.is_empty() || name.contains(std::path::is_separator) {
+                                app.is_create_edit_error = true;
+                                app.error_message = "Invalid name".to_string();
+                            } else if check_if_exists(destination.clone()) {
+                                app.is_create_edit_error = true;
+                                app.error_message = "Already exist".to_string();
+                            } else {
+                                let src = PathBuf::from(&app.item_to_copy_path);
+                                let description = format!("Copy {}", src.display());
+                                jobs.spawn(JobKind::Copy, description, move |control, sender| {
+                                    let new_src = Path::new(&destination);
+                                    copy::copy_dir_file_with_progress(
+                                        &src, new_src, &control, &sender,
+                                    )?;
+                                    Ok(format!("Copied to {}", destination))
+                                });
+                                app.reset_create_edit_values();
+                                app.input_mode = InputMode::Normal;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                InputMode::WatchRename if key.kind == KeyEventKind::Press => {
+                    match prompt_action(&key) {
+                        Some(PromptAction::Insert(c)) => {
+                            app.add_char(c);
+                        }
+                        Some(PromptAction::Backspace) => {
+                            app.delete_c();
+                        }
+                        Some(PromptAction::Cancel) => {
+                            app.input_mode = InputMode::Normal;
+                            app.reset_create_edit_values();
+                        }
+                        Some(PromptAction::Confirm) => {
+                            // rename file to new name
+                            // validate tha the new name and the previous name are not the same,
+                            // if names are equal then exit the current mode
+                            if app.create_edit_file_name == app.current_name_to_edit {
+                                app.input_mode = InputMode::Normal;
+                                app.reset_create_edit_values();
+                            } else {
+                                // proceed with operation
+                                let new_path = Path::new(&app.current_path_to_edit)
+                                    .join(&app.create_edit_file_name)
+                                    .display()
+                                    .to_string();
+                                if !check_if_exists(new_path.clone()) {
+                                    match handle_rename(app.clone()) {
+                                        Ok(_) => {
+                                            app.reset_create_edit_values();
+                                            update_file_references_with_selection_preservation(
+                                                Some(new_path),
+                                                &mut app,
+                                                &mut state,
+                                                &sort_type,
+                                                &mut file_reader_content,
+                                                &mut image_generator,
+                                            );
+                                            app.input_mode = InputMode::Normal;
                                         }
                                         Err(e) => {
-                                            println!("Error: {}", e);
+                                            app.is_create_edit_error = true;
+                                            match e.kind() {
+                                                ErrorKind::InvalidInput => {
+                                                    app.error_message = "Invalid input".to_string();
+                                                }
+                                                _ => {
+                                                    app.error_message = "Other error".to_string();
+                                                }
+                                            }
                                         }
                                     }
+                                } else {
+                                    app.is_create_edit_error = true;
+                                    app.error_message = "Already exist".to_string();
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                InputMode::WatchCreate if key.kind == KeyEventKind::Press => {
+                    match prompt_action(&key) {
+                        Some(PromptAction::Insert(c)) => {
+                            app.add_char(c);
+                        }
+                        Some(PromptAction::Backspace) => {
+                            app.delete_c();
+                        }
+                        Some(PromptAction::Left) => {
+                            app.move_create_edit_cursor_left();
+                        }
+                        Some(PromptAction::Right) => {
+                            app.move_create_edit_cursor_right();
+                        }
+                        Some(PromptAction::Cancel) => {
+                            app.input_mode = InputMode::Normal;
+                            app.reset_create_edit_values();
+                            // create methods to reset the create_edit_file_name and state of it
+                        }
+                        Some(PromptAction::Confirm) => {
+                            // create file/dir
+                            if !app.create_edit_file_name.is_empty() {
+                                match create_item_based_on_type(
+                                    &app.current_dir,
+                                    &app.create_edit_file_name,
+                                    Path::new(&config.templates_path()),
+                                ) {
+                                    Ok(created) => {
+                                        app.input_mode = InputMode::Normal;
+
+                                        app.reset_create_edit_values();
+                                        update_file_references_with_selection_preservation(
+                                            Some(created.to_string_lossy().to_string()),
+                                            &mut app,
+                                            &mut state,
+                                            &sort_type,
+                                            &mut file_reader_content,
+                                            &mut image_generator,
+                                        );
+                                    }
+                                    Err(e) => {
+                                        let kind = e.downcast_ref::<io::Error>().map(|e| e.kind());
+                                        app.error_message = match kind {
+                                            Some(ErrorKind::AlreadyExists) => {
+                                                "File Already Exists".to_string()
+                                            }
+                                            _ => capitalize(&errors::describe(&e)),
+                                        };
+                                        app.is_create_edit_error = true;
+                                    } // show error to user
+                                } // test
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+
+                InputMode::Editing if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Enter => {
+                        search_history.push(SearchKind::Global, &app.input);
+                        let _ = search_history.save();
+                        app.pin_search();
+                    }
+                    KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        if !app.input.trim().is_empty() {
+                            app.search_to_save = Some((SearchKind::Global, app.input.clone()));
+                            app.reset_create_edit_values();
+                            app.input_mode = InputMode::WatchSaveSearch;
+                        }
+                    }
+                    KeyCode::Up => {
+                        if let Some(query) = search_history.previous(SearchKind::Global) {
+                            app.set_input(query, &mut search_worker);
+                        }
+                    }
+                    KeyCode::Down => {
+                        if let Some(query) = search_history.next(SearchKind::Global) {
+                            app.set_input(query, &mut search_worker);
+                        }
+                    }
+                    KeyCode::Char(to_insert) => {
+                        search_history.stop_browsing();
+                        app.enter_char(to_insert, &mut search_worker);
+                    }
+                    KeyCode::Backspace => {
+                        search_history.stop_browsing();
+                        if app.input.is_empty() {
+                            app.pop_search(&mut search_worker);
+                        } else {
+                            app.delete_char(&mut search_worker);
+                        }
+                    }
+                    KeyCode::Left => {
+                        app.move_cursor_left();
+                    }
+
+                    KeyCode::Right => {
+                        app.move_cursor_right();
+                    }
+                    KeyCode::Esc => {
+                        app.input_mode = InputMode::Normal;
+                    }
+
+                    _ => {}
+                },
+                InputMode::WatchDelete => match key.code {
+                    KeyCode::Char('q') => {
+                        app.render_popup = false;
+                        app.input_mode = InputMode::Normal;
+                        break;
+                    }
+                    KeyCode::Char('n') => {
+                        app.render_popup = false;
+                        app.input_mode = InputMode::Normal;
+                    }
+
+                    KeyCode::Char('y') => {
+                        let selected_index = state.selected();
+
+                        if let Some(selected_indx) = selected_index {
+                            let selected = app.files[selected_indx].clone();
+                            hooks.fire(
+                                HookEvent::Delete,
+                                std::slice::from_ref(&selected),
+                                &app.current_dir,
+                            );
+
+                            let description = format!("Delete {}", selected);
+                            jobs.spawn(JobKind::Delete, description, move |_, _| {
+                                handle_delete_based_on_type(&selected)?;
+                                Ok(format!("Deleted {}", selected))
+                            });
+
+                            app.render_popup = false;
+                            app.input_mode = InputMode::Normal;
+                        }
+                    }
+                    _ => {}
+                },
+                InputMode::WatchSort => match key.code {
+                    KeyCode::Char('q') => {
+                        app.input_mode = InputMode::Normal;
+                    }
+                    KeyCode::Char('n') => {
+                        // sort by name
+                        app.sort_by = SortBy::Name;
+                        update_file_references_with_selection_preservation(
+                            None,
+                            &mut app,
+                            &mut state,
+                            &sort_type,
+                            &mut file_reader_content,
+                            &mut image_generator,
+                        );
+                        remember_view(&app, &sort_type, &mut view_states);
+                        app.input_mode = InputMode::Normal;
+                    }
+
+                    KeyCode::Char('s') => {
+                        app.sort_by = SortBy::Size;
+                        update_file_references_with_selection_preservation(
+                            None,
+                            &mut app,
+                            &mut state,
+                            &sort_type,
+                            &mut file_reader_content,
+                            &mut image_generator,
+                        );
+                        remember_view(&app, &sort_type, &mut view_states);
+                        app.input_mode = InputMode::Normal;
+                    }
+
+                    KeyCode::Char('u') => {
+                        app.sort_by = SortBy::RealSize;
+                        update_file_references_with_selection_preservation(
+                            None,
+                            &mut app,
+                            &mut state,
+                            &sort_type,
+                            &mut file_reader_content,
+                            &mut image_generator,
+                        );
+                        remember_view(&app, &sort_type, &mut view_states);
+                        if app.dir_sizes.is_empty() {
+                            app.curr_stats = format!(
+                                "Sizes are not calculated yet, press '{}' to calculate them",
+                                keymap.keys_for(Action::DirSize)
+                            );
+                        }
+                        app.input_mode = InputMode::Normal;
+                    }
+
+                    KeyCode::Char('t') => {
+                        app.sort_by = SortBy::DateAdded;
+                        update_file_references_with_selection_preservation(
+                            None,
+                            &mut app,
+                            &mut state,
+                            &sort_type,
+                            &mut file_reader_content,
+                            &mut image_generator,
+                        );
+                        remember_view(&app, &sort_type, &mut view_states);
+                        app.input_mode = InputMode::Normal;
+                    }
+                    KeyCode::Char(c @ ('N' | 'C')) => {
+                        if c == 'N' {
+                            app.sort_options.natural = !app.sort_options.natural;
+                        } else {
+                            app.sort_options.case_sensitive = !app.sort_options.case_sensitive;
+                        }
+                        config.sort = app.sort_options.clone();
+                        if let Err(e) = config
+                            .update_settings_file(|settings| settings.sort = config.sort.clone())
+                        {
+                            app.curr_stats = format!("Unable to save settings: {}", e);
+                        }
+
+                        // the options only change name sorting
+                        app.sort_by = SortBy::Name;
+                        update_file_references_with_selection_preservation(
+                            None,
+                            &mut app,
+                            &mut state,
+                            &sort_type,
+                            &mut file_reader_content,
+                            &mut image_generator,
+                        );
+                        remember_view(&app, &sort_type, &mut view_states);
+                    }
+                    KeyCode::Char('D') => {
+                        app.sort_options.directories_first = !app.sort_options.directories_first;
+                        config.sort = app.sort_options.clone();
+                        if let Err(e) = config
+                            .update_settings_file(|settings| settings.sort = config.sort.clone())
+                        {
+                            app.curr_stats = format!("Unable to save settings: {}", e);
+                        }
+                        update_file_references_with_selection_preservation(
+                            None,
+                            &mut app,
+                            &mut state,
+                            &sort_type,
+                            &mut file_reader_content,
+                            &mut image_generator,
+                        );
+                    }
+                    KeyCode::Char('a') => {
+                        sort_type = SortType::ASC;
+                    }
+                    KeyCode::Char('d') => {
+                        sort_type = SortType::DESC;
+                    }
+                    _ => {}
+                },
+
+                InputMode::ContentSearch if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Esc => {
+                        content_search_cancel.store(true, Ordering::Relaxed);
+                        content_search_receiver = None;
+                        app.input_mode = InputMode::Normal;
+                    }
+                    KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        // every file with a match, in the order they were found
+                        let mut files: Vec<String> = Vec::new();
+                        for result in &app.content_search_results {
+                            if !files.contains(&result.path) {
+                                files.push(result.path.clone());
+                            }
+                        }
+                        if !app.can_open() {
+                            app.show_toast(NO_EDITOR.to_string(), true);
+                        } else if !files.is_empty() {
+                            content_search_cancel.store(true, Ordering::Relaxed);
+                            selection = Some(handle_file_selection(
+                                &files,
+                                None,
+                                &mut terminal,
+                                &events,
+                                app.clone(),
+                                &hooks,
+                            ));
+                            break;
+                        }
+                    }
+                    KeyCode::Char(c) => {
+                        app.content_search_query.push(c);
+                    }
+                    KeyCode::Backspace => {
+                        app.content_search_query.pop();
+                    }
+                    KeyCode::Down => {
+                        if !app.content_search_results.is_empty() {
+                            let i = match content_search_state.selected() {
+                                Some(i) if i + 1 < app.content_search_results.len() => i + 1,
+                                _ => 0,
+                            };
+                            content_search_state.select(Some(i));
+                        }
+                    }
+                    KeyCode::Up => {
+                        if !app.content_search_results.is_empty() {
+                            let i = match content_search_state.selected() {
+                                Some(0) | None => app.content_search_results.len() - 1,
+                                Some(i) => i - 1,
+                            };
+                            content_search_state.select(Some(i));
+                        }
+                    }
+                    KeyCode::Enter => {
+                        if app.content_search_query != app.content_search_last_query {
+                            if !app.content_search_query.is_empty() {
+                                // stop the previous search before starting a new one
+                                content_search_cancel.store(true, Ordering::Relaxed);
+                                content_search_cancel = Arc::new(AtomicBool::new(false));
+
+                                app.content_search_last_query = app.content_search_query.clone();
+                                app.content_search_results.clear();
+                                app.content_search_status = String::new();
+                                content_search_state.select(None);
+
+                                let (sender, receiver) = worker_waker.channel();
+                                content_search_receiver = Some(receiver);
+
+                                let root = app.content_search_root.clone();
+                                let query = app.content_search_query.clone();
+                                let ignore = app.ignore_patterns.clone();
+                                let show_hidden = app.show_hidden_files;
+                                let respect_gitignore = app.respect_gitignore;
+                                let cancel = content_search_cancel.clone();
+                                thread::spawn(move || {
+                                    content_search::search_contents(
+                                        &root,
+                                        &query,
+                                        &ignore,
+                                        show_hidden,
+                                        respect_gitignore,
+                                        cancel,
+                                        sender,
+                                    );
+                                });
+                            }
+                        } else if !app.can_open() {
+                            app.show_toast(NO_EDITOR.to_string(), true);
+                        } else if let Some(index) = content_search_state.selected() {
+                            if let Some(result) = app.content_search_results.get(index) {
+                                let path = result.path.clone();
+                                let position = Position {
+                                    line: result.line_number,
+                                    column: Some(result.column),
+                                };
+                                content_search_cancel.store(true, Ordering::Relaxed);
+                                selection = Some(handle_file_selection(
+                                    &[path],
+                                    Some(position),
+                                    &mut terminal,
+                                    &events,
+                                    app.clone(),
+                                    &hooks,
+                                ));
+                                break;
+                            }
+                        }
+                    }
+                    _ => {}
+                },
+
+                InputMode::Filter if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        if !app.filter_input.trim().is_empty() {
+                            app.search_to_save =
+                                Some((SearchKind::Local, app.filter_input.clone()));
+                            app.reset_create_edit_values();
+                            app.input_mode = InputMode::WatchSaveSearch;
+                        }
+                    }
+                    KeyCode::Up | KeyCode::Down => {
+                        let query = if key.code == KeyCode::Up {
+                            search_history.previous(SearchKind::Local)
+                        } else {
+                            search_history.next(SearchKind::Local)
+                        };
+                        if let Some(query) = query {
+                            app.filter_input = query;
+                            app.apply_filter();
+                            SelectionController::new(
+                                &mut app,
+                                &mut state,
+                                &mut file_reader_content,
+                                &mut image_generator,
+                            )
+                            .update_selection(0);
+                        }
+                    }
+                    KeyCode::Char(c) => {
+                        search_history.stop_browsing();
+                        app.filter_input.push(c);
+                        app.apply_filter();
+                        SelectionController::new(
+                            &mut app,
+                            &mut state,
+                            &mut file_reader_content,
+                            &mut image_generator,
+                        )
+                        .update_selection(0);
+                    }
+                    KeyCode::Backspace => {
+                        search_history.stop_browsing();
+                        app.filter_input.pop();
+                        app.apply_filter();
+                        SelectionController::new(
+                            &mut app,
+                            &mut state,
+                            &mut file_reader_content,
+                            &mut image_generator,
+                        )
+                        .update_selection(0);
+                    }
+                    KeyCode::Enter => {
+                        search_history.push(SearchKind::Local, &app.filter_input);
+                        let _ = search_history.save();
+                        app.input_mode = InputMode::Normal;
+                    }
+                    KeyCode::Esc => {
+                        app.clear_filter();
+                        SelectionController::new(
+                            &mut app,
+                            &mut state,
+                            &mut file_reader_content,
+                            &mut image_generator,
+                        )
+                        .update_selection(0);
+                        app.input_mode = InputMode::Normal;
+                    }
+                    _ => {}
+                },
+
+                InputMode::WatchSaveSearch if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Char(c) => {
+                        app.add_char(c);
+                    }
+                    KeyCode::Backspace => {
+                        app.delete_c();
+                    }
+                    KeyCode::Esc => {
+                        app.search_to_save = None;
+                        app.reset_create_edit_values();
+                        app.input_mode = InputMode::Normal;
+                    }
+                    KeyCode::Enter => {
+                        let name = app.create_edit_file_name.trim().to_string();
+                        if name.is_empty() {
+                            app.is_create_edit_error = true;
+                            app.error_message = "Invalid name".to_string();
+                        } else if let Some((kind, query)) = app.search_to_save.take() {
+                            search_history.save_search(&name, kind, &query);
+                            app.curr_stats = match search_history.save() {
+                                Ok(_) => format!("Saved {} \"{}\"", kind.label(), name),
+                                Err(e) => errors::status("Unable to save search", e),
+                            };
+                            app.reset_create_edit_values();
+                            app.input_mode = InputMode::Normal;
+                        }
+                    }
+                    _ => {}
+                },
+                InputMode::CacheLoading if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Esc | KeyCode::Char('q') => {
+                        app.input_mode = InputMode::Normal;
+                    }
+                    _ => {}
+                },
+                InputMode::WatchIgnore if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Char(c) => {
+                        app.add_char(c);
+                    }
+                    KeyCode::Backspace => {
+                        app.delete_c();
+                    }
+                    KeyCode::Esc => {
+                        app.reset_create_edit_values();
+                        app.input_mode = InputMode::Normal;
+                    }
+                    KeyCode::Enter => {
+                        let pattern = app.create_edit_file_name.trim().to_string();
+                        let invalid =
+                            ignore_rules::invalid_patterns(std::slice::from_ref(&pattern));
+                        if pattern.is_empty() || !invalid.is_empty() {
+                            app.is_create_edit_error = true;
+                            app.error_message = invalid
+                                .first()
+                                .map(|invalid| format!("Invalid pattern {}", invalid))
+                                .unwrap_or_else(|| "Invalid pattern".to_string());
+                        } else if config.ignore_directories.contains(&pattern) {
+                            app.is_create_edit_error = true;
+                            app.error_message = format!("{} is already ignored", pattern);
+                        } else {
+                            config.ignore_directories.push(pattern.clone());
+                            let saved = config.update_settings_file(|settings| {
+                                settings.ignore_directories.push(pattern.clone())
+                            });
+                            app.ignore_patterns = config.ignore_patterns();
+
+                            // the cache drops the newly ignored entries instead
+                            // of being rebuilt, and is written in the background
+                            let before = store.directories.len() + store.files.len();
+                            store = Arc::new(store.pruned(&app.ignore_patterns));
+                            let removed = before - store.directories.len() - store.files.len();
+                            search_worker = SearchWorker::new(store.clone(), worker_waker.clone());
+                            let header = config.cache_header();
+                            let cache_store = store.clone();
+                            let cache_path = config.cache_directory.clone();
+                            thread::spawn(move || {
+                                let _ = save_directory_to_file(&cache_store, &header, &cache_path);
+                            });
+
+                            update_file_references_with_selection_preservation(
+                                None,
+                                &mut app,
+                                &mut state,
+                                &sort_type,
+                                &mut file_reader_content,
+                                &mut image_generator,
+                            );
+                            app.curr_stats = match saved {
+                                Ok(()) => format!(
+                                    "Ignoring {}, {} cached entries removed",
+                                    pattern,
+                                    format_count(removed)
+                                ),
+                                Err(e) => format!("Unable to save the ignore list: {}", e),
+                            };
+                            app.reset_create_edit_values();
+                            app.input_mode = InputMode::Normal;
+                        }
+                    }
+                    _ => {}
+                },
+                InputMode::WatchThemes if key.kind == KeyEventKind::Press => {
+                    match list_action(&key) {
+                        Some(ListAction::Close) => {
+                            // back to the saved theme
+                            let saved = config.theme_name(app.light_mode);
+                            if let Some(theme) = app.themes.iter().find(|theme| theme.name == saved)
+                            {
+                                app.theme = theme.clone();
+                                preview_theme(
+                                    &mut app,
+                                    &mut state,
+                                    &mut file_reader_content,
+                                    &mut image_generator,
+                                );
+                            }
+                            app.input_mode = InputMode::Normal;
+                        }
+                        Some(action @ (ListAction::Next | ListAction::Previous)) => {
+                            if let Some(i) =
+                                dispatch::step(&mut theme_state, app.themes.len(), action)
+                            {
+                                app.theme = app.themes[i].clone();
+                                preview_theme(
+                                    &mut app,
+                                    &mut state,
+                                    &mut file_reader_content,
+                                    &mut image_generator,
+                                );
+                            }
+                        }
+                        Some(ListAction::Confirm) => {
+                            let theme = theme_state
+                                .selected()
+                                .and_then(|index| app.themes.get(index))
+                                .cloned();
+                            if let Some(theme) = theme {
+                                let name = theme.name.clone();
+                                app.curr_stats = match app.update_theme(theme, &mut config) {
+                                    Ok(()) => format!("Theme {}", name),
+                                    Err(e) => format!("Unable to save the theme: {}", e),
+                                };
+                            }
+                            app.input_mode = InputMode::Normal;
+                        }
+                        _ => {}
+                    }
+                }
+                InputMode::WatchVolumes if key.kind == KeyEventKind::Press => {
+                    match list_action(&key) {
+                        Some(ListAction::Close) => {
+                            app.input_mode = InputMode::Normal;
+                        }
+                        Some(action @ (ListAction::Next | ListAction::Previous)) => {
+                            dispatch::step(&mut volume_state, app.volumes.len(), action);
+                        }
+                        Some(ListAction::Confirm) => {
+                            let path = volume_state
+                                .selected()
+                                .and_then(|index| app.volumes.get(index))
+                                .map(|volume| volume.path.clone());
+                            if let Some(path) = path {
+                                hooks.fire(
+                                    HookEvent::EnterDirectory,
+                                    &[path.to_string_lossy().to_string()],
+                                    &app.current_dir,
+                                );
+                                match read_with_view_state(
+                                    path.clone(),
+                                    &mut app,
+                                    &mut sort_type,
+                                    &view_states,
+                                ) {
+                                    Ok(dir) => {
+                                        app.enter(dir);
+                                        SelectionController::new(
+                                            &mut app,
+                                            &mut state,
+                                            &mut file_reader_content,
+                                            &mut image_generator,
+                                        )
+                                        .update_selection(0);
+                                        record_visit(&mut frecency, &path.to_string_lossy());
+                                    }
+                                    Err(e) => {
+                                        app.curr_stats = errors::status(&path.to_string_lossy(), e)
+                                    }
+                                }
+                            }
+                            app.input_mode = InputMode::Normal;
+                        }
+                        _ => {}
+                    }
+                }
+                InputMode::WatchSavedSearches if key.kind == KeyEventKind::Press => {
+                    match list_action(&key) {
+                        Some(ListAction::Close) => {
+                            app.input_mode = InputMode::Normal;
+                        }
+                        Some(action @ (ListAction::Next | ListAction::Previous)) => {
+                            dispatch::step(
+                                &mut saved_search_state,
+                                search_history.saved.len(),
+                                action,
+                            );
+                        }
+                        Some(ListAction::Confirm) => {
+                            let saved = saved_search_state
+                                .selected()
+                                .and_then(|index| search_history.saved.get(index).cloned());
+                            match saved {
+                                Some(saved) if saved.kind == SearchKind::Global => {
+                                    app.open_search(&frecency);
+                                    app.set_input(saved.query, &mut search_worker);
+                                }
+                                Some(saved) => {
+                                    app.filter_input = saved.query;
+                                    app.apply_filter();
+                                    SelectionController::new(
+                                        &mut app,
+                                        &mut state,
+                                        &mut file_reader_content,
+                                        &mut image_generator,
+                                    )
+                                    .update_selection(0);
+                                    app.input_mode = InputMode::Normal;
+                                }
+                                None => {}
+                            }
+                        }
+                        _ => match key.code {
+                            KeyCode::Char('d') => {
+                                if let Some(index) = saved_search_state.selected() {
+                                    if index < search_history.saved.len() {
+                                        search_history.saved.remove(index);
+                                        let _ = search_history.save();
+                                        saved_search_state.select(
+                                            if search_history.saved.is_empty() {
+                                                None
+                                            } else {
+                                                Some(index.min(search_history.saved.len() - 1))
+                                            },
+                                        );
+                                    }
+                                }
+                            }
+                            _ => {}
+                        },
+                    }
+                }
+                InputMode::WatchGoto if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Esc => {
+                        app.input_mode = InputMode::Normal;
+                    }
+                    KeyCode::Tab => {
+                        // relative paths complete against the current directory
+                        let input = app
+                            .current_dir
+                            .join(expand_path(&app.goto_input))
+                            .to_string_lossy()
+                            .to_string();
+                        let candidates = store.complete(&input, 50);
+                        match candidates.len() {
+                            0 => {}
+                            1 => {
+                                app.goto_input = format!("{}{}", candidates[0], MAIN_SEPARATOR);
+                                app.goto_candidates.clear();
+                            }
+                            _ => {
+                                let common = transfer::longest_common_prefix(&candidates);
+                                app.goto_input = if common.len() > input.len() {
+                                    common
+                                } else {
+                                    input
+                                };
+                                app.goto_candidates = candidates;
+                            }
+                        }
+                    }
+                    KeyCode::Char(c) => {
+                        app.goto_input.push(c);
+                        app.goto_error = None;
+                    }
+                    KeyCode::Backspace => {
+                        app.goto_input.pop();
+                        app.goto_error = None;
+                    }
+                    KeyCode::Enter => {
+                        let target = app.current_dir.join(expand_path(&app.goto_input));
+                        if target.exists() {
+                            // files open their directory with the file selected
+                            let dir = containing_dir(&target);
+                            let select = Some(target.to_string_lossy().to_string())
+                                .filter(|_| !target.is_dir());
+                            match read_with_view_state(
+                                dir.clone(),
+                                &mut app,
+                                &mut sort_type,
+                                &view_states,
+                            ) {
+                                Ok(dir_listing) => {
+                                    let dir_path = dir.to_string_lossy().to_string();
+                                    hooks.fire(
+                                        HookEvent::EnterDirectory,
+                                        std::slice::from_ref(&dir_path),
+                                        &app.current_dir,
+                                    );
+                                    record_visit(&mut frecency, &dir_path);
+                                    let index = select
+                                        .and_then(|select| {
+                                            dir_listing.entries.iter().position(|f| *f == select)
+                                        })
+                                        .unwrap_or(0);
+                                    app.enter(dir_listing);
+                                    SelectionController::new(
+                                        &mut app,
+                                        &mut state,
+                                        &mut file_reader_content,
+                                        &mut image_generator,
+                                    )
+                                    .update_selection(index);
+                                    app.input_mode = InputMode::Normal;
+                                }
+                                Err(e) => {
+                                    app.goto_error = Some(format!("{}: {}", target.display(), e));
+                                }
+                            }
+                        } else {
+                            app.goto_error = Some(format!("{} does not exist", target.display()));
+                        }
+                    }
+                    _ => {}
+                },
+                InputMode::WatchTransfer if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Esc => {
+                        app.input_mode = InputMode::Normal;
+                    }
+                    KeyCode::Tab => {
+                        let input = transfer::expand_home(&app.transfer_input);
+                        let candidates = store.complete(&input, 50);
+                        match candidates.len() {
+                            0 => {}
+                            1 => {
+                                app.transfer_input = format!("{}{}", candidates[0], MAIN_SEPARATOR);
+                                app.transfer_candidates.clear();
+                            }
+                            _ => {
+                                let common = transfer::longest_common_prefix(&candidates);
+                                if common.len() > input.len() {
+                                    app.transfer_input = common;
                                 }
+                                app.transfer_candidates = candidates;
                             }
                         }
                     }
-                    KeyCode::Char('d') => {
-                        app.render_popup = true;
-                        app.input_mode = InputMode::WatchDelete;
+                    KeyCode::Char('r')
+                        if key.modifiers.contains(KeyModifiers::CONTROL)
+                            && app.transfer_link == Some(LinkKind::Symbolic) =>
+                    {
+                        app.link_relative = !app.link_relative;
                     }
-                    KeyCode::Char('a') => {
-                        app.input_mode = InputMode::WatchCreate;
+                    KeyCode::Char(c) => {
+                        app.transfer_input.push(c);
+                        app.transfer_error = None;
                     }
-                    KeyCode::Char('y') => {
-                        let curr_file_path = file_reader_content.curr_selected_path.clone();
-                        let file_type =
-                            file_reader_content.get_file_extension(curr_file_path.clone());
-                        match file_type {
-                            FileType::ZIP => {
-                                let t = file_reader_content.extract_zip_content();
-                                app.input = t;
+                    KeyCode::Backspace => {
+                        app.transfer_input.pop();
+                        app.transfer_error = None;
+                    }
+                    KeyCode::Enter if app.transfer_link.is_some() => {
+                        let dest = transfer::expand_home(&app.transfer_input);
+                        let kind = app.transfer_link.unwrap_or(LinkKind::Symbolic);
+                        let target = PathBuf::from(&app.transfer_sources[0]);
+                        match file_ops::create_link(
+                            &target,
+                            Path::new(&dest),
+                            kind,
+                            app.link_relative,
+                        ) {
+                            Ok(link) => {
+                                app.curr_stats =
+                                    format!("{} created: {}", kind.label(), link.display());
+                                app.input_mode = InputMode::Normal;
+                                update_file_references_with_selection_preservation(
+                                    None,
+                                    &mut app,
+                                    &mut state,
+                                    &sort_type,
+                                    &mut file_reader_content,
+                                    &mut image_generator,
+                                );
                             }
-                            _ => {}
+                            Err(e) => app.transfer_error = Some(e.to_string()),
                         }
                     }
-                    KeyCode::Char('r') => {
-                        let selected_index = state.selected();
-                        if let Some(index) = selected_index {
-                            let selected = &app.files[index];
-                            let mut split_path = selected.split("/").collect::<Vec<&str>>();
-                            let placeholder_name = split_path.pop().unwrap();
-                            let new_path = split_path.join("/");
-                            let placeholder_name_copy = placeholder_name;
-                            app.current_path_to_edit = new_path;
-                            app.current_name_to_edit = placeholder_name_copy.to_string();
-                            app.create_edit_file_name = placeholder_name.to_string();
-                            app.char_index = placeholder_name.len();
-                        }
-                        app.input_mode = InputMode::WatchRename;
-                    }
-                    KeyCode::Char('.') => {
-                        let is_hidden = !app.show_hidden_files;
-                        app.show_hidden_files = is_hidden;
-                        let selected_index = state.selected();
-                        if let Some(indx) = selected_index {
-                            let selected = &app.files[indx];
-
-                            let mut split_path = selected.split("/").collect::<Vec<&str>>();
-                            split_path.pop();
-                            let new_path = split_path.join("/");
-                            match get_inner_files_info(
-                                new_path,
-                                is_hidden,
-                                SortBy::Default,
-                                &sort_type,
-                            ) {
-                                Ok(files) => {
-                                    if let Some(file_strs) = files {
-                                        app.read_only_files = file_strs.clone();
-                                        app.files = file_strs;
+                    KeyCode::Enter if app.transfer_sync => {
+                        let source = PathBuf::from(&app.transfer_sources[0]);
+                        let dest = PathBuf::from(transfer::expand_home(&app.transfer_input));
+                        let (sender, receiver) = worker_waker.channel();
+                        sync_receiver = Some(receiver);
+                        app.curr_stats = String::from("Planning sync...");
+                        app.input_mode = InputMode::Normal;
+                        thread::spawn(move || {
+                            let _ = sender.send(sync::plan(&source, &dest));
+                        });
+                    }
+                    KeyCode::Enter => {
+                        let dest = transfer::expand_home(&app.transfer_input);
+                        match transfer::plan_targets(&app.transfer_sources, Path::new(&dest)) {
+                            Ok(targets) => {
+                                let is_move = app.transfer_is_move;
+                                let (kind, verb) = if is_move {
+                                    (JobKind::Move, "Moved")
+                                } else {
+                                    (JobKind::Copy, "Copied")
+                                };
+                                let description = format!(
+                                    "{} {} item(s) to {}",
+                                    kind.label(),
+                                    targets.len(),
+                                    dest
+                                );
+                                jobs.spawn(kind, description, move |control, sender| {
+                                    if is_move {
+                                        transfer::move_to(&targets, &control, &sender)?;
+                                    } else {
+                                        transfer::copy_to(&targets, &control, &sender)?;
                                     }
-                                }
-                                Err(e) => {
-                                    println!("error  {}", e);
-                                }
+                                    Ok(format!("{} {} item(s) to {}", verb, targets.len(), dest))
+                                });
+                                app.marked_files.clear();
+                                app.input_mode = InputMode::Normal;
+                            }
+                            Err(e) => {
+                                app.transfer_error = Some(e.to_string());
                             }
                         }
                     }
-                    KeyCode::Char('c') => {
-                        // item path to copy
-                        app.input_mode = InputMode::WatchCopy;
-                        let index = state.selected().unwrap();
-                        let selected_path = &app.files[index].to_owned();
-                        app.item_to_copy_path = selected_path.clone();
+                    _ => {}
+                },
+                InputMode::WatchSync if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('n') => {
+                        app.sync_plan = None;
+                        app.input_mode = InputMode::Normal;
                     }
-
-                    KeyCode::Char('s') => {
-                        app.input_mode = InputMode::WatchSort;
+                    KeyCode::Char('d') => {
+                        app.sync_delete = !app.sync_delete;
+                        app.sync_scroll = 0;
                     }
-
-                    KeyCode::Char('?') => {
-                        app.input_mode = InputMode::WatchKeyBinding;
+                    KeyCode::Char('j') | KeyCode::Down => {
+                        let lines = app
+                            .sync_plan
+                            .as_ref()
+                            .map_or(0, |plan| plan.preview_lines(app.sync_delete).len());
+                        if (app.sync_scroll as usize) + 1 < lines {
+                            app.sync_scroll += 1;
+                        }
                     }
-
-                    KeyCode::Enter => {
-                        let app_files = app.files.clone();
-                        let selected = &app_files[state.selected().unwrap()];
-
-                        app.input = selected.clone();
-
-                        let _ = handle_file_selection(&selected, &mut terminal, app.clone());
-                        break;
+                    KeyCode::Char('k') | KeyCode::Up => {
+                        app.sync_scroll = app.sync_scroll.saturating_sub(1);
+                    }
+                    KeyCode::Enter | KeyCode::Char('y') => {
+                        if let Some(plan) = app.sync_plan.take() {
+                            let delete = app.sync_delete;
+                            if plan.has_changes(delete) {
+                                let description = format!(
+                                    "sync {} into {}",
+                                    plan.source.display(),
+                                    plan.dest.display()
+                                );
+                                jobs.spawn(JobKind::Sync, description, move |control, sender| {
+                                    sync::run(&plan, delete, &control, &sender)
+                                });
+                                app.marked_files.clear();
+                            }
+                        }
+                        app.input_mode = InputMode::Normal;
                     }
                     _ => {}
                 },
-
-                InputMode::WatchRename if key.kind == KeyEventKind::Press => match key.code {
+                InputMode::WatchCancelCopy if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Char('y') => {
+                        if let Some(index) = jobs.latest_active(JobKind::Copy) {
+                            if let Some(job) = jobs.cancel(index) {
+                                app.curr_stats = format!("Cancelling job #{}", job.id);
+                            }
+                        }
+                        app.input_mode = InputMode::Normal;
+                    }
+                    KeyCode::Char('n') | KeyCode::Char('q') | KeyCode::Esc => {
+                        app.input_mode = InputMode::Normal;
+                    }
+                    _ => {}
+                },
+                InputMode::WatchJump if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Esc => {
+                        app.input_mode = InputMode::Normal;
+                    }
+                    KeyCode::Down => {
+                        if !app.jump_results.is_empty() {
+                            let i = jump_state
+                                .selected()
+                                .map(|i| (i + 1) % app.jump_results.len())
+                                .unwrap_or(0);
+                            jump_state.select(Some(i));
+                        }
+                    }
+                    KeyCode::Up => {
+                        if !app.jump_results.is_empty() {
+                            let i = match jump_state.selected() {
+                                Some(0) | None => app.jump_results.len() - 1,
+                                Some(i) => i - 1,
+                            };
+                            jump_state.select(Some(i));
+                        }
+                    }
                     KeyCode::Char(c) => {
-                        app.add_char(c);
+                        app.jump_input.push(c);
+                        app.jump_results = frecency.query(&app.jump_input, 20);
+                        jump_state.select(if app.jump_results.is_empty() {
+                            None
+                        } else {
+                            Some(0)
+                        });
                     }
                     KeyCode::Backspace => {
-                        app.delete_c();
+                        app.jump_input.pop();
+                        app.jump_results = frecency.query(&app.jump_input, 20);
+                        jump_state.select(if app.jump_results.is_empty() {
+                            None
+                        } else {
+                            Some(0)
+                        });
                     }
-                    KeyCode::Esc => {
+                    KeyCode::Enter => {
+                        let target = jump_state
+                            .selected()
+                            .and_then(|index| app.jump_results.get(index).cloned());
+                        if let Some(target) = target {
+                            // files open their directory with the file selected
+                            let dir = containing_dir(Path::new(&target));
+                            let select =
+                                Some(target.clone()).filter(|_| !Path::new(&target).is_dir());
+                            hooks.fire(
+                                HookEvent::EnterDirectory,
+                                &[dir.to_string_lossy().to_string()],
+                                &app.current_dir,
+                            );
+                            match read_with_view_state(dir, &mut app, &mut sort_type, &view_states)
+                            {
+                                Ok(dir) => {
+                                    let index = select
+                                        .and_then(|select| {
+                                            dir.entries.iter().position(|f| *f == select)
+                                        })
+                                        .unwrap_or(0);
+                                    app.enter(dir);
+                                    SelectionController::new(
+                                        &mut app,
+                                        &mut state,
+                                        &mut file_reader_content,
+                                        &mut image_generator,
+                                    )
+                                    .update_selection(index);
+                                    record_visit(&mut frecency, &target);
+                                }
+                                Err(e) => app.curr_stats = errors::status(&target, e),
+                            }
+                        }
+                        app.input_mode = InputMode::Normal;
+                    }
+                    _ => {}
+                },
+                InputMode::WatchTree if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('t') => {
                         app.input_mode = InputMode::Normal;
-                        app.reset_create_edit_values();
                     }
+                    KeyCode::Char('j') | KeyCode::Down => tree.move_by(1),
+                    KeyCode::Char('k') | KeyCode::Up => tree.move_by(-1),
+                    KeyCode::Char('l') | KeyCode::Right => tree.expand(),
+                    KeyCode::Char('h') | KeyCode::Left => tree.collapse(),
+                    KeyCode::Char(' ') | KeyCode::Tab => tree.toggle(),
                     KeyCode::Enter => {
-                        // rename file to new name
-                        // validate tha the new name and the previous name are not the same,
-                        // if names are equal then exit the current mode
-                        if app.create_edit_file_name == app.current_name_to_edit {
-                            app.input_mode = InputMode::Normal;
-                            app.reset_create_edit_values();
-                        } else {
-                            // proceed with operation
-                            let new_path = format!(
-                                "{}/{}",
-                                app.current_path_to_edit, app.create_edit_file_name
+                        // directories become the listing, files show their
+                        // directory with the file selected
+                        if let Some(node) = tree.selected().cloned() {
+                            let dir = containing_dir(Path::new(&node.path));
+                            let select = Some(node.path.clone()).filter(|_| !node.is_dir);
+                            let dir_path = dir.to_string_lossy().to_string();
+                            hooks.fire(
+                                HookEvent::EnterDirectory,
+                                std::slice::from_ref(&dir_path),
+                                &app.current_dir,
                             );
-                            if !check_if_exists(new_path) {
-                                match handle_rename(app.clone()) {
-                                    Ok(_) => {
-                                        app.reset_create_edit_values();
-                                        let file_path_list = get_file_path_data(
-                                            config.start_path.to_owned(),
-                                            app.show_hidden_files,
-                                            SortBy::Default,
-                                            &sort_type,
-                                        )?;
-                                        app.files = file_path_list.clone();
-                                        app.read_only_files = file_path_list.clone();
-                                        app.input_mode = InputMode::Normal;
-                                    }
-                                    Err(e) => {
-                                        app.is_create_edit_error = true;
-                                        match e.kind() {
-                                            ErrorKind::InvalidInput => {
-                                                app.error_message = "Invalid input".to_string();
-                                            }
-                                            _ => {
-                                                app.error_message = "Other error".to_string();
-                                            }
+                            record_visit(&mut frecency, &dir_path);
+                            match read_with_view_state(dir, &mut app, &mut sort_type, &view_states)
+                            {
+                                Ok(dir) => {
+                                    let index = select
+                                        .and_then(|select| {
+                                            dir.entries.iter().position(|f| *f == select)
+                                        })
+                                        .unwrap_or(0);
+                                    app.enter(dir);
+                                    SelectionController::new(
+                                        &mut app,
+                                        &mut state,
+                                        &mut file_reader_content,
+                                        &mut image_generator,
+                                    )
+                                    .update_selection(index);
+                                }
+                                Err(e) => app.curr_stats = errors::status(&dir_path, e),
+                            }
+                        }
+                        app.input_mode = InputMode::Normal;
+                    }
+                    _ => {}
+                },
+                InputMode::WatchCommands if key.kind == KeyEventKind::Press => {
+                    match list_action(&key) {
+                        Some(ListAction::Close) => {
+                            app.input_mode = InputMode::Normal;
+                        }
+                        Some(action @ (ListAction::Next | ListAction::Previous)) => {
+                            dispatch::step(&mut command_state, app.commands.len(), action);
+                        }
+                        Some(ListAction::Confirm) => {
+                            app.input_mode = InputMode::Normal;
+                            let command = command_state
+                                .selected()
+                                .and_then(|index| app.commands.get(index).cloned());
+                            if let Some(command) = command {
+                                let targets = if app.marked_files.is_empty() {
+                                    match state.selected() {
+                                        Some(index) if index < app.files.len() => {
+                                            vec![app.files[index].clone()]
                                         }
+                                        _ => Vec::new(),
                                     }
+                                } else {
+                                    app.marked_files.clone()
+                                };
+                                let dir = app.current_dir_string();
+
+                                if command.mode == RunMode::Silent {
+                                    let description =
+                                        format!("{} {}", command.name, targets.join(" "));
+                                    jobs.spawn(JobKind::Command, description, move |_, _| {
+                                        command.run_silent(&targets, &dir)
+                                    });
+                                    app.curr_stats = String::from("Command started");
+                                } else {
+                                    let result = with_suspended(&mut terminal, &events, |_| {
+                                        command.run_attached(&targets, &dir)
+                                    });
+                                    update_file_references_with_selection_preservation(
+                                        None,
+                                        &mut app,
+                                        &mut state,
+                                        &sort_type,
+                                        &mut file_reader_content,
+                                        &mut image_generator,
+                                    );
+                                    app.curr_stats = match result {
+                                        Ok(message) => message,
+                                        Err(e) => e.to_string(),
+                                    };
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                InputMode::WatchConverters if key.kind == KeyEventKind::Press => {
+                    match list_action(&key) {
+                        Some(ListAction::Close) => {
+                            app.input_mode = InputMode::Normal;
+                        }
+                        Some(action @ (ListAction::Next | ListAction::Previous)) => {
+                            dispatch::step(&mut converter_state, app.converters.len(), action);
+                        }
+                        Some(ListAction::Confirm) => {
+                            app.input_mode = InputMode::Normal;
+                            let converter = converter_state
+                                .selected()
+                                .and_then(|index| app.converters.get(index).cloned());
+                            if let Some(converter) = converter {
+                                let selection = if app.marked_files.is_empty() {
+                                    state
+                                        .selected()
+                                        .and_then(|index| app.files.get(index))
+                                        .cloned()
+                                        .into_iter()
+                                        .collect()
+                                } else {
+                                    app.marked_files.clone()
+                                };
+                                let targets: Vec<PathBuf> = selection
+                                    .iter()
+                                    .map(PathBuf::from)
+                                    .filter(|path| converter.accepts(path))
+                                    .collect();
+
+                                if targets.is_empty() {
+                                    app.show_toast(
+                                        format!(
+                                            "{} only converts .{} files",
+                                            converter.name,
+                                            converter.from.join(", .")
+                                        ),
+                                        true,
+                                    );
+                                } else {
+                                    let (results_sender, receiver) = worker_waker.channel();
+                                    conversion_receiver = Some(receiver);
+                                    let description =
+                                        format!("{} {} file(s)", converter.name, targets.len());
+                                    jobs.spawn(
+                                        JobKind::Convert,
+                                        description,
+                                        move |control, sender| {
+                                            converter.run(
+                                                &targets,
+                                                &control,
+                                                &sender,
+                                                &results_sender,
+                                            )
+                                        },
+                                    );
+                                    app.marked_files.clear();
+                                    app.curr_stats = String::from("Conversion started");
                                 }
-                            } else {
-                                app.is_create_edit_error = true;
-                                app.error_message = "Already exist".to_string();
                             }
                         }
+                        _ => {}
+                    }
+                }
+                InputMode::WatchConversions if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Esc | KeyCode::Char('q') | KeyCode::Enter => {
+                        app.input_mode = InputMode::Normal;
+                    }
+                    KeyCode::Char('j') | KeyCode::Down => {
+                        if (app.conversion_scroll as usize) + 1 < app.conversion_results.len() {
+                            app.conversion_scroll += 1;
+                        }
+                    }
+                    KeyCode::Char('k') | KeyCode::Up => {
+                        app.conversion_scroll = app.conversion_scroll.saturating_sub(1);
                     }
                     _ => {}
                 },
-                InputMode::WatchCreate if key.kind == KeyEventKind::Press => match key.code {
-                    KeyCode::Char(c) => {
-                        app.add_char(c);
+                InputMode::WatchEditor if key.kind == KeyEventKind::Press => {
+                    match list_action(&key) {
+                        Some(ListAction::Close) => {
+                            app.input_mode = InputMode::Normal;
+                        }
+                        // the first entry copies the path instead of opening an editor
+                        Some(action @ (ListAction::Next | ListAction::Previous)) => {
+                            dispatch::step(&mut editor_state, app.editors.len() + 1, action);
+                        }
+                        Some(ListAction::Confirm) => {
+                            app.selected_editor = editor_state
+                                .selected()
+                                .and_then(|index| index.checked_sub(1))
+                                .and_then(|index| app.editors.get(index).cloned());
+                            app.curr_stats = match &app.selected_editor {
+                                Some(editor) => format!("Opening files with {}", editor.name),
+                                None => String::from("Enter copies the path to the clipboard"),
+                            };
+                            app.input_mode = InputMode::Normal;
+                        }
+                        _ => {}
                     }
-                    KeyCode::Backspace => {
-                        app.delete_c();
+                }
+                InputMode::WatchHash if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => {
+                        app.input_mode = InputMode::Normal;
                     }
-                    KeyCode::Left => {
-                        app.move_create_edit_cursor_left();
+                    KeyCode::Char(c) if hash_receiver.is_none() => {
+                        let algorithm = match c {
+                            'm' => Some(HashAlgorithm::Md5),
+                            's' => Some(HashAlgorithm::Sha1),
+                            '2' => Some(HashAlgorithm::Sha256),
+                            'b' => Some(HashAlgorithm::Blake3),
+                            _ => None,
+                        };
+                        if let Some(algorithm) = algorithm {
+                            let (result_sender, receiver) = worker_waker.channel();
+                            hash_receiver = Some(receiver);
+                            app.hash_result = None;
+
+                            let target = app.hash_target.clone();
+                            let description = format!("{} {}", algorithm.name(), target);
+                            jobs.spawn(JobKind::Hash, description, move |control, sender| {
+                                let digest = hash::hash_file(
+                                    Path::new(&target),
+                                    algorithm,
+                                    &control,
+                                    &sender,
+                                )?;
+                                let message = format!("{} {}", algorithm.name(), digest);
+                                let _ = result_sender.send(message.clone());
+                                Ok(message)
+                            });
+                        }
                     }
-                    KeyCode::Right => {
-                        app.move_create_edit_cursor_right();
+                    _ => {}
+                },
+                InputMode::WatchJobs if key.kind == KeyEventKind::Press => {
+                    match list_action(&key) {
+                        Some(ListAction::Close) => {
+                            app.input_mode = InputMode::Normal;
+                        }
+                        Some(action @ (ListAction::Next | ListAction::Previous)) => {
+                            dispatch::step(&mut jobs_state, jobs.jobs().len(), action);
+                        }
+                        _ => match key.code {
+                            KeyCode::Char('x') => {
+                                if let Some(index) = jobs_state.selected() {
+                                    if let Some(job) = jobs.cancel(index) {
+                                        app.curr_stats = format!("Cancelling job #{}", job.id);
+                                    }
+                                }
+                            }
+                            KeyCode::Char('p') => {
+                                if let Some(index) = jobs_state.selected() {
+                                    jobs.toggle_pause(index);
+                                }
+                            }
+                            KeyCode::Char('c') => {
+                                jobs.clear_finished();
+                                jobs_state.select(if jobs.jobs().is_empty() {
+                                    None
+                                } else {
+                                    Some(0)
+                                });
+                            }
+                            _ => {}
+                        },
                     }
-                    KeyCode::Esc => {
+                }
+                InputMode::WatchProperties if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => {
+                        app.properties = None;
                         app.input_mode = InputMode::Normal;
-                        app.reset_create_edit_values();
-                        // create methods to reset the create_edit_file_name and state of it
+                    }
+                    KeyCode::Char('h') | KeyCode::Left => {
+                        app.permission_cursor = app.permission_cursor.saturating_sub(1);
+                    }
+                    KeyCode::Char('l') | KeyCode::Right => {
+                        if app.permission_cursor + 1 < PERMISSION_BITS.len() {
+                            app.permission_cursor += 1;
+                        }
+                    }
+                    KeyCode::Char(' ') => {
+                        app.pending_mode =
+                            permissions::toggle_bit(app.pending_mode, app.permission_cursor);
                     }
                     KeyCode::Enter => {
-                        // create file/dir
-                        if !app.create_edit_file_name.is_empty() {
-                            let selected_index = state.selected();
-                            let selected = &app.files[selected_index.unwrap()];
-                            let mut split_path = selected.split("/").collect::<Vec<&str>>();
-                            split_path.pop();
-                            let new_path = split_path.join("/");
-                            match create_item_based_on_type(
-                                new_path,
-                                app.create_edit_file_name.clone(),
-                            ) {
+                        if let Some(properties) = app.properties.clone() {
+                            match permissions::apply_mode(&properties.path, app.pending_mode) {
                                 Ok(_) => {
-                                    app.input_mode = InputMode::Normal;
-
-                                    app.reset_create_edit_values();
-                                    let file_path_list = get_file_path_data(
-                                        config.start_path.to_owned(),
-                                        app.show_hidden_files,
-                                        SortBy::Default,
-                                        &sort_type,
-                                    )?;
-                                    app.files = file_path_list.clone();
-                                    app.read_only_files = file_path_list.clone();
+                                    app.curr_stats = format!(
+                                        "Permissions set to {}",
+                                        permissions::mode_string(app.pending_mode)
+                                    );
+                                    app.properties =
+                                        permissions::read_properties(&properties.path).ok();
                                 }
                                 Err(e) => {
-                                    let error = e.downcast_ref::<io::Error>().unwrap();
-                                    match error.kind() {
-                                        ErrorKind::AlreadyExists => {
-                                            app.error_message = "File Already Exists".to_string();
-                                            app.is_create_edit_error = true;
-                                        }
-                                        _ => {}
-                                    }
-                                } // show error to user
-                            } // test
+                                    app.curr_stats = format!("chmod failed: {}", e);
+                                }
+                            }
                         }
                     }
                     _ => {}
                 },
-
-                InputMode::Editing if key.kind == KeyEventKind::Press => match key.code {
-                    KeyCode::Enter => app.submit_message(),
-                    KeyCode::Char(to_insert) => {
-                        app.enter_char(to_insert, store.clone());
-                    }
-                    KeyCode::Backspace => {
-                        app.delete_char(store.clone());
-                    }
-                    KeyCode::Left => {
-                        app.move_cursor_left();
-                    }
-
-                    KeyCode::Right => {
-                        app.move_cursor_right();
+                InputMode::WatchArchive => match key.code {
+                    KeyCode::Char('z') | KeyCode::Char('t') => {
+                        let format = match key.code {
+                            KeyCode::Char('z') => ArchiveFormat::Zip,
+                            _ => ArchiveFormat::TarGz,
+                        };
+                        let sources = if app.marked_files.is_empty() {
+                            match state.selected() {
+                                Some(index) if index < app.files.len() => {
+                                    vec![app.files[index].clone()]
+                                }
+                                _ => Vec::new(),
+                            }
+                        } else {
+                            app.marked_files.clone()
+                        };
+
+                        if !sources.is_empty() {
+                            let archive_path =
+                                archive::generate_archive_path(&sources, &app.current_dir, format);
+                            app.marked_files.clear();
+
+                            let description = format!("Create {}", archive_path.display());
+                            jobs.spawn(JobKind::Archive, description, move |control, sender| {
+                                archive::create_archive(
+                                    &sources,
+                                    &archive_path,
+                                    format,
+                                    &control.cancel,
+                                    &sender,
+                                )?;
+                                Ok(format!("Created {}", archive_path.display()))
+                            });
+                        }
+                        app.input_mode = InputMode::Normal;
                     }
-                    KeyCode::Esc => {
+                    KeyCode::Char('q') | KeyCode::Esc => {
                         app.input_mode = InputMode::Normal;
                     }
-
                     _ => {}
                 },
-                InputMode::WatchDelete => match key.code {
-                    KeyCode::Char('q') => {
-                        app.render_popup = false;
+
+                InputMode::WatchBulkRename => match key.code {
+                    KeyCode::Char('y') | KeyCode::Enter => {
+                        if let Some(plan) = app.bulk_rename_plan.take() {
+                            if plan.is_valid() {
+                                let result = bulk_rename::apply_plan(&plan);
+                                app.marked_files.clear();
+
+                                update_file_references_with_selection_preservation(
+                                    None,
+                                    &mut app,
+                                    &mut state,
+                                    &sort_type,
+                                    &mut file_reader_content,
+                                    &mut image_generator,
+                                );
+                                app.curr_stats = result.summary();
+                            }
+                        }
                         app.input_mode = InputMode::Normal;
-                        break;
                     }
-                    KeyCode::Char('n') => {
-                        app.render_popup = false;
+                    KeyCode::Char('n') | KeyCode::Char('q') | KeyCode::Esc => {
+                        app.bulk_rename_plan = None;
                         app.input_mode = InputMode::Normal;
                     }
+                    _ => {}
+                },
 
-                    KeyCode::Char('y') => {
-                        let selected_index = state.selected();
-
-                        if let Some(selected_indx) = selected_index {
-                            let selected = &app.files[selected_indx];
-
-                            handle_delete_based_on_type(selected).unwrap();
-
-                            let file_path_list = get_file_path_data(
-                                config.start_path.to_owned(),
-                                app.show_hidden_files,
-                                SortBy::Default,
+                InputMode::WatchPatternRename if key.kind == KeyEventKind::Press => {
+                    match key.code {
+                        KeyCode::Esc => {
+                            app.bulk_rename_plan = None;
+                            app.input_mode = InputMode::Normal;
+                        }
+                        KeyCode::Char(c) => {
+                            app.pattern_rename_input.push(c);
+                            app.preview_pattern_rename();
+                        }
+                        KeyCode::Backspace => {
+                            app.pattern_rename_input.pop();
+                            app.preview_pattern_rename();
+                        }
+                        // conflicts keep the prompt open to fix the pattern
+                        KeyCode::Enter
+                            if app
+                                .bulk_rename_plan
+                                .as_ref()
+                                .is_some_and(RenamePlan::is_valid) =>
+                        {
+                            let plan = app.bulk_rename_plan.take().unwrap_or_default();
+                            let report = bulk_rename::apply_plan(&plan);
+                            app.marked_files.clear();
+
+                            update_file_references_with_selection_preservation(
+                                None,
+                                &mut app,
+                                &mut state,
                                 &sort_type,
-                            )?;
-                            app.render_popup = false;
-                            app.files = file_path_list.clone();
-                            app.read_only_files = file_path_list.clone();
+                                &mut file_reader_content,
+                                &mut image_generator,
+                            );
+                            app.show_toast(report.summary(), !report.errors.is_empty());
                             app.input_mode = InputMode::Normal;
                         }
+                        _ => {}
                     }
-                    _ => {}
-                },
-                InputMode::WatchSort => match key.code {
-                    KeyCode::Char('q') => {
-                        app.input_mode = InputMode::Normal;
-                    }
-                    KeyCode::Char('n') => {
-                        // sort by name
-
-                        // we only care about the path not the selcted item
-                        let get_path_from_list = &app.files[0];
-                        let cur_path = get_curr_path(get_path_from_list.to_string());
-                        let file_path_list = get_file_path_data(
-                            cur_path,
-                            app.show_hidden_files,
-                            SortBy::Name,
-                            &sort_type,
-                        )?;
-                        app.files = file_path_list.clone();
-                        app.read_only_files = file_path_list.clone();
-                        app.input_mode = InputMode::Normal;
-                    }
+                }
 
-                    KeyCode::Char('s') => {
-                        // TODO: this code should be refactor to into a reusable method since is
-                        // used in multiple places
-                        let get_path_from_list = &app.files[0];
-                        let cur_path = get_curr_path(get_path_from_list.to_string());
-
-                        let file_path_list = get_file_path_data(
-                            cur_path,
-                            app.show_hidden_files,
-                            SortBy::Size,
-                            &sort_type,
-                        )?;
-                        app.files = file_path_list.clone();
-                        app.read_only_files = file_path_list.clone();
+                InputMode::WatchCompare if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('=') => {
+                        app.comparison = None;
                         app.input_mode = InputMode::Normal;
+                        // bring the preview of the selection back
+                        if let Some(index) =
+                            state.selected().filter(|index| *index < app.files.len())
+                        {
+                            SelectionController::new(
+                                &mut app,
+                                &mut state,
+                                &mut file_reader_content,
+                                &mut image_generator,
+                            )
+                            .update_selection(index);
+                        }
                     }
-
-                    KeyCode::Char('t') => {
-                        let get_path_from_list = &app.files[0];
-                        let cur_path = get_curr_path(get_path_from_list.to_string());
-
-                        let file_path_list = get_file_path_data(
-                            cur_path,
-                            app.show_hidden_files,
-                            SortBy::DateAdded,
-                            &sort_type,
-                        )?;
-                        app.files = file_path_list.clone();
-                        app.read_only_files = file_path_list.clone();
-                        app.input_mode = InputMode::Normal;
+                    KeyCode::Tab | KeyCode::Char('s') => {
+                        app.comparison_unified = !app.comparison_unified;
+                        app.comparison_scroll = 0;
                     }
-                    KeyCode::Char('a') => {
-                        sort_type = SortType::ASC;
+                    KeyCode::Char('j') | KeyCode::Down => {
+                        let lines = app.comparison.as_ref().map_or(0, |comparison| {
+                            comparison
+                                .lines(0, app.comparison_unified, &app.theme_colors())
+                                .len()
+                        });
+                        if (app.comparison_scroll as usize) + 1 < lines {
+                            app.comparison_scroll += 1;
+                        }
                     }
-                    KeyCode::Char('d') => {
-                        sort_type = SortType::DESC;
+                    KeyCode::Char('k') | KeyCode::Up => {
+                        app.comparison_scroll = app.comparison_scroll.saturating_sub(1);
                     }
                     _ => {}
                 },
@@ -1634,41 +4703,50 @@ let csv_list_content = List::new(file_reader_content.curr_csv_content.clone()).b
                         if app.files.len() > 0 {
                             let selected =
                                 &app.copy_move_read_only_files[read_only_state.selected().unwrap()];
-                            let mut split_path = selected.split("/").collect::<Vec<&str>>();
+                            // the parent of the listed directory, but not above
+                            // a home directory like /Users/name
+                            let grandparent = Path::new(selected)
+                                .parent()
+                                .and_then(Path::parent)
+                                .filter(|_| Path::new(selected).components().count() > 4);
 
                             let sort_type_copy = sort_type.clone();
-                            if split_path.len() > 4 {
-                                split_path.pop();
-                                split_path.pop();
-
-                                let new_path = split_path.join("/");
+                            if let Some(grandparent) = grandparent {
+                                let new_path = grandparent.display().to_string();
                                 app.input = new_path.clone();
-                                let files_strings = get_inner_files_info(
+                                match get_inner_files_info(
                                     new_path.clone(),
                                     app.show_hidden_files,
+                                    app.listing_ignore(),
                                     SortBy::Default,
                                     &sort_type_copy,
-                                )
-                                .unwrap();
-
-                                if let Some(f_s) = files_strings {
-                                    app.copy_move_read_only_files = f_s.clone();
-                                    read_only_state.select(Some(0));
+                                    &app.sort_options,
+                                ) {
+                                    Ok(f_s) => {
+                                        app.copy_move_read_only_files = f_s;
+                                        read_only_state.select(Some(0));
+                                    }
+                                    Err(e) => app.curr_stats = errors::status(&new_path, e),
                                 }
                             }
                         } else {
                             let copy = sort_type.clone();
-                            let files_strings = get_inner_files_info(
+                            match get_inner_files_info(
                                 app.copy_move_read_only_files_prev.clone(),
                                 app.show_hidden_files,
+                                app.listing_ignore(),
                                 SortBy::Default,
                                 &copy,
-                            )
-                            .unwrap();
-
-                            if let Some(f_s) = files_strings {
-                                app.copy_move_read_only_files = f_s.clone();
-                                read_only_state.select(Some(0));
+                                &app.sort_options,
+                            ) {
+                                Ok(f_s) => {
+                                    app.copy_move_read_only_files = f_s;
+                                    read_only_state.select(Some(0));
+                                }
+                                Err(e) => {
+                                    app.curr_stats =
+                                        errors::status(&app.copy_move_read_only_files_prev, e)
+                                }
                             }
                         }
                     }
@@ -1685,18 +4763,16 @@ let csv_list_content = List::new(file_reader_content.curr_csv_content.clone()).b
                                     match get_inner_files_info(
                                         selected.to_string(),
                                         app.show_hidden_files,
+                                        app.listing_ignore(),
                                         SortBy::Default,
                                         &sort_type,
+                                        &app.sort_options,
                                     ) {
-                                        Ok(files_strings) => {
-                                            if let Some(files_strs) = files_strings {
-                                                app.copy_move_read_only_files = files_strs.clone();
-                                                read_only_state.select(Some(0));
-                                            }
-                                        }
-                                        Err(e) => {
-                                            println!("Error: {}", e);
+                                        Ok(files_strs) => {
+                                            app.copy_move_read_only_files = files_strs;
+                                            read_only_state.select(Some(0));
                                         }
+                                        Err(e) => app.curr_stats = errors::status(selected, e),
                                     }
                                 }
                             }
@@ -1711,28 +4787,29 @@ let csv_list_content = List::new(file_reader_content.curr_csv_content.clone()).b
                             let selected_path = &app.copy_move_read_only_files[indx];
 
                             // get current path to add new item
-                            let mut split_path = selected_path.split("/").collect::<Vec<&str>>();
-                            split_path.pop();
-                            let string_path = split_path.join("/");
-                            // append copy to new dir/file
-                            let item_to_copy_cur_path = Path::new(&app.item_to_copy_path);
+                            let string_path = get_curr_path(selected_path.to_string());
 
+                            // ask for the name of the copy, pre-filled with one
+                            // that is free in the destination
                             let new_path_with_new_name = generate_copy_file_dir_name(
                                 app.item_to_copy_path.clone(),
                                 string_path.clone(),
                             );
-
-                            // item to copy path => app.item_to_copy_path.clone();
-                            let new_src = Path::new(&new_path_with_new_name);
-                            copy_dir_file_helper(item_to_copy_cur_path, new_src)?;
-                            // show spinner that is downloading?
+                            let new_name = Path::new(&new_path_with_new_name)
+                                .file_name()
+                                .map(|name| name.to_string_lossy().to_string())
+                                .unwrap_or_default();
+                            app.reset_create_edit_values();
+                            app.current_path_to_edit = string_path;
+                            app.char_index = new_name.chars().count();
+                            app.create_edit_file_name = new_name;
                             app.loading = false;
                             let copy_curr_files = app.files.clone();
                             app.copy_move_read_only_files = copy_curr_files;
                             read_only_state.select(Some(0));
 
                             app.copy_move_read_only_files = app.files.clone();
-                            app.input_mode = InputMode::Normal;
+                            app.input_mode = InputMode::WatchCopyName;
                         }
                         //}
                     }
@@ -1748,10 +4825,19 @@ let csv_list_content = List::new(file_reader_content.curr_csv_content.clone()).b
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
 
     terminal.show_cursor()?;
     terminal.clear()?;
-    Ok(())
+    match selection {
+        // reported once the terminal is restored so the message stays readable
+        Some(Err(e)) => {
+            eprintln!("{:#}", e);
+            std::process::exit(1);
+        }
+        None if print_on_select => std::process::exit(1),
+        _ => Ok(()),
+    }
 }
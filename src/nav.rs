@@ -0,0 +1,261 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use crate::ignore_rules::IgnorePatterns;
+use crate::operations::transfer;
+use crate::utils::files::{sort_by_real_size, SortOptions};
+use crate::{get_file_path_data, SortBy, SortType};
+
+// a directory together with the entries listed for it
+#[derive(Debug, Clone)]
+pub struct Dir {
+    pub path: PathBuf,
+    pub entries: Vec<String>,
+    // directories in `entries` that can't be listed
+    pub locked: HashSet<String>,
+}
+
+impl Dir {
+    // `sizes` are the calculated directory sizes, used by SortBy::RealSize
+    pub fn read(
+        path: impl Into<PathBuf>,
+        show_hidden: bool,
+        ignore: Option<&IgnorePatterns>,
+        sort_by: SortBy,
+        sort_type: &SortType,
+        sort_options: &SortOptions,
+        sizes: &HashMap<String, u64>,
+    ) -> anyhow::Result<Dir> {
+        let path = path.into();
+        let real_size = matches!(sort_by, SortBy::RealSize);
+        let mut entries = get_file_path_data(
+            path.to_string_lossy().to_string(),
+            show_hidden,
+            ignore,
+            sort_by,
+            sort_type,
+            sort_options,
+        )?;
+        if real_size {
+            sort_by_real_size(&mut entries, sizes, sort_type, sort_options);
+        }
+        let locked = entries
+            .iter()
+            .filter(|entry| is_locked(Path::new(entry)))
+            .cloned()
+            .collect();
+        Ok(Dir {
+            path,
+            entries,
+            locked,
+        })
+    }
+}
+
+pub fn is_locked(path: &Path) -> bool {
+    path.is_dir() && matches!(fs::read_dir(path), Err(e) if e.kind() == ErrorKind::PermissionDenied)
+}
+
+// expands `~` and `$VAR` / `${VAR}`, unknown variables are left as typed
+pub fn expand_path(input: &str) -> String {
+    let mut expanded = String::new();
+    let mut rest = input;
+    while let Some(index) = rest.find('$') {
+        expanded.push_str(&rest[..index]);
+        let after = &rest[index + 1..];
+        let (name, remaining) = match after.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) => (&braced[..end], &braced[end + 1..]),
+                None => ("", after),
+            },
+            None => {
+                let end = after
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .unwrap_or(after.len());
+                (&after[..end], &after[end..])
+            }
+        };
+        match std::env::var(name) {
+            Ok(value) if !name.is_empty() => expanded.push_str(&value),
+            _ => expanded.push_str(&rest[index..rest.len() - remaining.len()]),
+        }
+        rest = remaining;
+    }
+    expanded.push_str(rest);
+    transfer::expand_home(&expanded)
+}
+
+// directory a path is shown in: directories are entered, files are shown
+// inside their parent
+pub fn containing_dir(path: &Path) -> PathBuf {
+    if path.is_dir() {
+        path.to_path_buf()
+    } else {
+        path.parent()
+            .map(|parent| parent.to_path_buf())
+            .unwrap_or_else(|| path.to_path_buf())
+    }
+}
+
+// paths in text pasted into the listing, the way terminals insert dropped
+// files: one per line, or on one line quoted or with escaped spaces, and
+// sometimes as file:// urls. paths that don't exist are left out
+pub fn pasted_paths(text: &str) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        // a single path with spaces that wasn't quoted
+        let whole = pasted_path(line);
+        if whole.exists() {
+            paths.push(whole);
+            continue;
+        }
+        paths.extend(
+            split_words(line)
+                .iter()
+                .map(|word| pasted_path(word))
+                .filter(|path| path.exists()),
+        );
+    }
+    paths
+}
+
+fn pasted_path(word: &str) -> PathBuf {
+    match word.strip_prefix("file://") {
+        // the host is empty or localhost on the local machine
+        Some(url) => PathBuf::from(percent_decode(url.strip_prefix("localhost").unwrap_or(url))),
+        None => PathBuf::from(expand_path(word)),
+    }
+}
+
+// words of a shell-like line, quotes group and a backslash escapes the next
+// character
+fn split_words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (None, '\'' | '"') => quote = Some(c),
+            (None | Some('"'), '\\') => word.extend(chars.next()),
+            (None, c) if c.is_whitespace() => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            (_, c) => word.push(c),
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let hex = text
+            .get(index + 1..index + 3)
+            .filter(|hex| hex.chars().all(|c| c.is_ascii_hexdigit()))
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[index], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(path: &Path) -> anyhow::Result<Dir> {
+        Dir::read(
+            path,
+            false,
+            None,
+            SortBy::Name,
+            &SortType::ASC,
+            &SortOptions::default(),
+            &HashMap::new(),
+        )
+    }
+
+    #[test]
+    fn empty_directory_has_no_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let listed = read(dir.path()).unwrap();
+        assert!(listed.entries.is_empty());
+        assert!(listed.locked.is_empty());
+    }
+
+    #[test]
+    fn missing_directory_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(read(&dir.path().join("missing")).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unreadable_directory_is_locked() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let locked = dir.path().join("locked");
+        fs::create_dir(&locked).unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+        // root reads it anyway
+        let readable = fs::read_dir(&locked).is_ok();
+
+        let listed = read(dir.path()).unwrap();
+        let name = locked.to_string_lossy().to_string();
+        assert_eq!(listed.entries, vec![name.clone()]);
+        assert_eq!(listed.locked.contains(&name), !readable);
+        assert_eq!(read(&locked).is_err(), !readable);
+
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[test]
+    fn expands_variables_and_home() {
+        std::env::set_var("FF_NAV_TEST_DIR", "/srv/data");
+        assert_eq!(expand_path("$FF_NAV_TEST_DIR/logs"), "/srv/data/logs");
+        assert_eq!(expand_path("${FF_NAV_TEST_DIR}logs"), "/srv/datalogs");
+        if let Some(home) = dirs::home_dir() {
+            assert_eq!(expand_path("~/notes"), format!("{}/notes", home.display()));
+        }
+    }
+
+    #[test]
+    fn unknown_variables_are_left_as_typed() {
+        assert_eq!(expand_path("$FF_NAV_TEST_UNSET/a"), "$FF_NAV_TEST_UNSET/a");
+        assert_eq!(expand_path("${FF_NAV_TEST_UNSET"), "${FF_NAV_TEST_UNSET");
+        assert_eq!(expand_path("cost $5"), "cost $5");
+        assert_eq!(expand_path("trailing $"), "trailing $");
+    }
+
+    #[test]
+    fn files_are_shown_in_their_parent() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.txt");
+        fs::write(&file, "a").unwrap();
+        assert_eq!(containing_dir(dir.path()), dir.path());
+        assert_eq!(containing_dir(&file), dir.path());
+        assert_eq!(containing_dir(&dir.path().join("missing")), dir.path());
+    }
+}
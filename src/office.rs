@@ -0,0 +1,184 @@
+use std::{fs, io::Read, path::Path};
+
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use zip::ZipArchive;
+
+// a spreadsheet preview doesn't need every row of every sheet
+const MAX_ROWS_PER_SHEET: usize = 200;
+const MAX_SHEETS: usize = 10;
+
+pub fn is_office_document(path: &str) -> bool {
+    matches!(
+        Path::new(path)
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .as_deref(),
+        Some("docx") | Some("xlsx")
+    )
+}
+
+// the readable text of a .docx or .xlsx file, one entry per paragraph or row
+pub fn extract_text(path: &str) -> anyhow::Result<Vec<String>> {
+    let mut archive = ZipArchive::new(fs::File::open(path)?)?;
+    if path.to_lowercase().ends_with(".xlsx") {
+        spreadsheet_text(&mut archive)
+    } else {
+        document_text(&read_member(&mut archive, "word/document.xml")?)
+    }
+}
+
+fn read_member(archive: &mut ZipArchive<fs::File>, name: &str) -> anyhow::Result<String> {
+    let mut content = String::new();
+    archive.by_name(name)?.read_to_string(&mut content)?;
+    Ok(content)
+}
+
+// paragraphs are <w:p>, their text is spread over <w:t> runs
+fn document_text(xml: &str) -> anyhow::Result<Vec<String>> {
+    let mut reader = Reader::from_str(xml);
+    let mut lines = Vec::new();
+    let mut paragraph = String::new();
+    let mut in_text = false;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) if e.local_name().as_ref() == b"t" => in_text = true,
+            Event::End(e) => match e.local_name().as_ref() {
+                b"t" => in_text = false,
+                b"p" => lines.push(std::mem::take(&mut paragraph)),
+                _ => {}
+            },
+            Event::Empty(e) => match e.local_name().as_ref() {
+                b"tab" => paragraph.push('\t'),
+                b"br" => lines.push(std::mem::take(&mut paragraph)),
+                _ => {}
+            },
+            Event::Text(e) if in_text => paragraph.push_str(&e.unescape()?),
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    if !paragraph.is_empty() {
+        lines.push(paragraph);
+    }
+    Ok(lines)
+}
+
+fn spreadsheet_text(archive: &mut ZipArchive<fs::File>) -> anyhow::Result<Vec<String>> {
+    // workbooks without any text cells have no shared strings
+    let shared = match read_member(archive, "xl/sharedStrings.xml") {
+        Ok(xml) => shared_strings(&xml)?,
+        Err(_) => Vec::new(),
+    };
+    let names = sheet_names(&read_member(archive, "xl/workbook.xml")?)?;
+
+    let mut lines = Vec::new();
+    // sheets are stored as sheet1.xml, sheet2.xml... in workbook order unless
+    // the file was reorganized, good enough for a preview
+    for (index, name) in names.iter().take(MAX_SHEETS).enumerate() {
+        let member = format!("xl/worksheets/sheet{}.xml", index + 1);
+        let Ok(xml) = read_member(archive, &member) else {
+            continue;
+        };
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines.push(format!("== {} ==", name));
+        lines.extend(sheet_rows(&xml, &shared)?);
+    }
+    Ok(lines)
+}
+
+fn shared_strings(xml: &str) -> anyhow::Result<Vec<String>> {
+    let mut reader = Reader::from_str(xml);
+    let mut strings = Vec::new();
+    let mut current = String::new();
+    let mut in_text = false;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) if e.local_name().as_ref() == b"t" => in_text = true,
+            Event::End(e) => match e.local_name().as_ref() {
+                b"t" => in_text = false,
+                b"si" => strings.push(std::mem::take(&mut current)),
+                _ => {}
+            },
+            Event::Text(e) if in_text => current.push_str(&e.unescape()?),
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(strings)
+}
+
+fn sheet_names(xml: &str) -> anyhow::Result<Vec<String>> {
+    let mut reader = Reader::from_str(xml);
+    let mut names = Vec::new();
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"sheet" => {
+                if let Some(name) = e.try_get_attribute("name")? {
+                    names.push(name.unescape_value()?.to_string());
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(names)
+}
+
+// rows as " | " separated cells, shared string cells (t="s") hold an index
+// into `shared`
+fn sheet_rows(xml: &str, shared: &[String]) -> anyhow::Result<Vec<String>> {
+    let mut reader = Reader::from_str(xml);
+    let mut rows = Vec::new();
+    let mut row: Vec<String> = Vec::new();
+    let mut cell = String::new();
+    let mut shared_cell = false;
+    let mut in_value = false;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => match e.local_name().as_ref() {
+                b"c" => {
+                    shared_cell = e
+                        .try_get_attribute("t")?
+                        .is_some_and(|kind| kind.value.as_ref() == b"s");
+                }
+                b"v" | b"t" => in_value = true,
+                _ => {}
+            },
+            Event::End(e) => match e.local_name().as_ref() {
+                b"v" | b"t" => in_value = false,
+                b"c" => {
+                    let value = std::mem::take(&mut cell);
+                    row.push(if shared_cell {
+                        value
+                            .parse::<usize>()
+                            .ok()
+                            .and_then(|index| shared.get(index).cloned())
+                            .unwrap_or(value)
+                    } else {
+                        value
+                    });
+                }
+                b"row" => {
+                    rows.push(std::mem::take(&mut row).join(" | "));
+                    if rows.len() >= MAX_ROWS_PER_SHEET {
+                        rows.push(String::from("..."));
+                        break;
+                    }
+                }
+                _ => {}
+            },
+            Event::Empty(e) if e.local_name().as_ref() == b"c" => row.push(String::new()),
+            Event::Text(e) if in_value => cell.push_str(&e.unescape()?),
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(rows)
+}
@@ -0,0 +1,157 @@
+use std::{
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::Sender,
+    },
+};
+
+use flate2::{write::GzEncoder, Compression};
+use walkdir::WalkDir;
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+use crate::jobs::JobMessage;
+
+#[derive(Debug, Clone, Copy)]
+pub enum ArchiveFormat {
+    Zip,
+    TarGz,
+}
+
+impl ArchiveFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => "zip",
+            ArchiveFormat::TarGz => "tar.gz",
+        }
+    }
+}
+
+// picks `<name>.<ext>` next to a single source, or `archive.<ext>` for a
+// selection, adding a numeric suffix when the name is already taken
+pub fn generate_archive_path(
+    sources: &[String],
+    dest_dir: &Path,
+    format: ArchiveFormat,
+) -> PathBuf {
+    let base_name = match sources {
+        [single] => Path::new(single)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| String::from("archive")),
+        _ => String::from("archive"),
+    };
+
+    let mut archive_path = dest_dir.join(format!("{}.{}", base_name, format.extension()));
+    let mut counter = 1;
+    while archive_path.exists() {
+        archive_path = dest_dir.join(format!(
+            "{} ({}).{}",
+            base_name,
+            counter,
+            format.extension()
+        ));
+        counter += 1;
+    }
+    archive_path
+}
+
+pub fn create_archive(
+    sources: &[String],
+    archive_path: &Path,
+    format: ArchiveFormat,
+    cancel: &AtomicBool,
+    sender: &Sender<JobMessage>,
+) -> anyhow::Result<()> {
+    let file = File::create(archive_path)?;
+
+    let result = match format {
+        ArchiveFormat::Zip => write_zip(sources, file, cancel, sender),
+        ArchiveFormat::TarGz => write_tar_gz(sources, file, cancel, sender),
+    };
+
+    // don't leave a half written or cancelled archive behind
+    if result.is_err() {
+        let _ = fs::remove_file(archive_path);
+    }
+    result
+}
+
+// every entry is stored relative to the parent of its source so the archive
+// contains `dir/...` instead of the full absolute path
+fn entries_for(source: &str) -> Vec<(PathBuf, PathBuf)> {
+    let source_path = Path::new(source);
+    let base = source_path.parent().unwrap_or(Path::new(""));
+
+    WalkDir::new(source_path)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let relative = entry.path().strip_prefix(base).ok()?.to_path_buf();
+            Some((entry.path().to_path_buf(), relative))
+        })
+        .collect()
+}
+
+fn write_zip(
+    sources: &[String],
+    file: File,
+    cancel: &AtomicBool,
+    sender: &Sender<JobMessage>,
+) -> anyhow::Result<()> {
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    for source in sources.iter() {
+        for (path, relative) in entries_for(source) {
+            if cancel.load(Ordering::Relaxed) {
+                return Err(anyhow::anyhow!("cancelled"));
+            }
+            let name = relative.to_string_lossy().replace('\\', "/");
+
+            if path.is_dir() {
+                zip.add_directory(name, options)?;
+            } else if path.is_file() {
+                let _ = sender.send(JobMessage::Progress(format!("Archiving: {}", name)));
+                zip.start_file(name, options)?;
+                let mut input = File::open(&path)?;
+                io::copy(&mut input, &mut zip)?;
+            }
+        }
+    }
+
+    zip.finish()?;
+    Ok(())
+}
+
+fn write_tar_gz(
+    sources: &[String],
+    file: File,
+    cancel: &AtomicBool,
+    sender: &Sender<JobMessage>,
+) -> anyhow::Result<()> {
+    let encoder = GzEncoder::new(file, Compression::default());
+    let mut tar = tar::Builder::new(encoder);
+
+    for source in sources.iter() {
+        for (path, relative) in entries_for(source) {
+            if cancel.load(Ordering::Relaxed) {
+                return Err(anyhow::anyhow!("cancelled"));
+            }
+            if path.is_dir() {
+                tar.append_dir(&relative, &path)?;
+            } else if path.is_file() {
+                let _ = sender.send(JobMessage::Progress(format!(
+                    "Archiving: {}",
+                    relative.display()
+                )));
+                tar.append_path_with_name(&path, &relative)?;
+            }
+        }
+    }
+
+    tar.into_inner()?.finish()?;
+    Ok(())
+}
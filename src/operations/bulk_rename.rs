@@ -0,0 +1,700 @@
+use std::{
+    collections::HashSet,
+    env, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use regex::{Regex, RegexBuilder};
+
+// characters that can follow the `s` of a substitution, others start a
+// template like "s_{n}"
+const DELIMITERS: [char; 5] = ['/', '|', '#', ':', '!'];
+
+#[derive(Debug, Clone)]
+pub struct RenameOp {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RenamePlan {
+    pub renames: Vec<RenameOp>,
+    pub conflicts: Vec<String>,
+}
+
+impl RenamePlan {
+    pub fn is_valid(&self) -> bool {
+        self.conflicts.is_empty() && !self.renames.is_empty()
+    }
+
+    pub fn preview_lines(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self
+            .conflicts
+            .iter()
+            .map(|conflict| format!("! {}", conflict))
+            .collect();
+
+        for op in self.renames.iter() {
+            lines.push(format!(
+                "{} -> {}",
+                file_name_of(&op.from),
+                file_name_of(&op.to)
+            ));
+        }
+        lines
+    }
+}
+
+fn file_name_of(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+// writes one file name per line so the user can edit them in their editor.
+// the file is always a new one, never something another user put at the
+// same path in the shared temp directory
+pub fn write_names_file(paths: &[String]) -> io::Result<PathBuf> {
+    let (names_path, mut file) = create_names_file()?;
+
+    for path in paths.iter() {
+        writeln!(file, "{}", file_name_of(Path::new(path)))?;
+    }
+    Ok(names_path)
+}
+
+fn create_names_file() -> io::Result<(PathBuf, fs::File)> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.subsec_nanos())
+        .unwrap_or(0);
+    for attempt in 0..100 {
+        let names_path = env::temp_dir().join(format!(
+            "ff-bulk-rename-{}-{}-{}.txt",
+            process::id(),
+            nanos,
+            attempt
+        ));
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        match options.open(&names_path) {
+            Ok(file) => return Ok((names_path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "no free name for the bulk rename file",
+    ))
+}
+
+pub fn read_names_file(names_path: &Path) -> io::Result<Vec<String>> {
+    let content = fs::read_to_string(names_path)?;
+    let names = content
+        .lines()
+        .map(|line| line.trim_end_matches('\r').to_string())
+        .collect();
+
+    let _ = fs::remove_file(names_path);
+    Ok(names)
+}
+
+pub fn build_plan(paths: &[String], new_names: &[String]) -> RenamePlan {
+    let mut plan = RenamePlan::default();
+
+    // ignore trailing empty lines editors like to add
+    let mut new_names = new_names.to_vec();
+    while new_names.len() > paths.len() && new_names.last().is_some_and(|n| n.is_empty()) {
+        new_names.pop();
+    }
+
+    if new_names.len() != paths.len() {
+        plan.conflicts.push(format!(
+            "expected {} names but found {}, lines must not be added or removed",
+            paths.len(),
+            new_names.len()
+        ));
+        return plan;
+    }
+
+    // only entries that get a new name free up their path, one that keeps
+    // its name still occupies it
+    let sources: HashSet<PathBuf> = paths
+        .iter()
+        .zip(new_names.iter())
+        .filter(|(path, new_name)| file_name_of(Path::new(path)) != new_name.trim())
+        .map(|(path, _)| PathBuf::from(path))
+        .collect();
+    let mut targets: HashSet<PathBuf> = HashSet::new();
+
+    for (path, new_name) in paths.iter().zip(new_names.iter()) {
+        let from = PathBuf::from(path);
+        let new_name = new_name.trim();
+
+        if new_name.is_empty() {
+            plan.conflicts
+                .push(format!("empty name for '{}'", file_name_of(&from)));
+            continue;
+        }
+
+        if new_name.contains(std::path::is_separator) || new_name == "." || new_name == ".." {
+            plan.conflicts.push(format!("invalid name '{}'", new_name));
+            continue;
+        }
+
+        let to = match from.parent() {
+            Some(parent) => parent.join(new_name),
+            None => PathBuf::from(new_name),
+        };
+
+        if to == from {
+            continue;
+        }
+
+        if !targets.insert(to.clone()) {
+            plan.conflicts
+                .push(format!("'{}' is used more than once", new_name));
+            continue;
+        }
+
+        // targets that are renamed away as part of this plan are free to reuse
+        if to.exists() && !sources.contains(&to) {
+            plan.conflicts
+                .push(format!("'{}' already exists", new_name));
+            continue;
+        }
+
+        plan.renames.push(RenameOp { from, to });
+    }
+
+    plan
+}
+
+// what applying a plan did. a plan is applied as a whole: when one rename
+// fails the ones done so far are undone and every file keeps its old name
+#[derive(Debug, Clone, Default)]
+pub struct RenameReport {
+    pub renamed: usize,
+    pub errors: Vec<String>,
+}
+
+impl RenameReport {
+    pub fn summary(&self) -> String {
+        match self.errors.as_slice() {
+            [] => format!("Renamed {} item(s)", self.renamed),
+            [error] => format!("Renamed {} item(s), {}", self.renamed, error),
+            errors => format!(
+                "Renamed {} item(s), {} failed: {}",
+                self.renamed,
+                errors.len(),
+                errors.join(", ")
+            ),
+        }
+    }
+}
+
+// fs::rename replaces an existing file, this refuses to
+fn rename_free(from: &Path, to: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(to).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("'{}' already exists", file_name_of(to)),
+        ));
+    }
+    fs::rename(from, to)
+}
+
+// puts staged files back under their old names, in reverse order
+fn unstage(staged: &[(PathBuf, &RenameOp)], report: &mut RenameReport) {
+    for (tmp_path, op) in staged.iter().rev() {
+        if let Err(e) = rename_free(tmp_path, &op.from) {
+            report.errors.push(format!(
+                "{} left as {}: {}",
+                file_name_of(&op.from),
+                file_name_of(tmp_path),
+                e
+            ));
+        }
+    }
+}
+
+pub fn apply_plan(plan: &RenamePlan) -> RenameReport {
+    let mut report = RenameReport::default();
+
+    // rename through temporary names first so swaps (a -> b, b -> a) and
+    // chains don't clobber each other
+    let mut staged: Vec<(PathBuf, &RenameOp)> = Vec::new();
+    for (index, op) in plan.renames.iter().enumerate() {
+        let tmp_name = format!(".ff-rename-{}-{}", process::id(), index);
+        let tmp_path = match op.from.parent() {
+            Some(parent) => parent.join(tmp_name),
+            None => PathBuf::from(tmp_name),
+        };
+        if let Err(e) = rename_free(&op.from, &tmp_path) {
+            report
+                .errors
+                .push(format!("{}: {}", file_name_of(&op.from), e));
+            unstage(&staged, &mut report);
+            return report;
+        }
+        staged.push((tmp_path, op));
+    }
+
+    for (done, (tmp_path, op)) in staged.iter().enumerate() {
+        if let Err(e) = rename_free(tmp_path, &op.to) {
+            report.errors.push(format!(
+                "{} -> {}: {}, nothing was renamed",
+                file_name_of(&op.from),
+                file_name_of(&op.to),
+                e
+            ));
+            // undo the renames that went through, newest first, so every
+            // file is back in its temporary spot before going home
+            for (tmp_path, op) in staged[..done].iter().rev() {
+                if let Err(e) = rename_free(&op.to, tmp_path) {
+                    report.errors.push(format!(
+                        "{} left as {}: {}",
+                        file_name_of(&op.from),
+                        file_name_of(&op.to),
+                        e
+                    ));
+                }
+            }
+            unstage(&staged, &mut report);
+            return report;
+        }
+    }
+
+    report.renamed = staged.len();
+    report
+}
+
+// a rename expression typed in the pattern rename prompt: `s/from/to/` with
+// a regex and optional `g` (every match) and `i` (ignore case) flags, or a
+// template where `{name}` is the old name without its extension, `{ext}` the
+// extension and `{n}` / `{n:3}` a counter, zero padded to the given width
+#[derive(Debug, Clone)]
+pub enum RenamePattern {
+    Substitute {
+        regex: Regex,
+        replacement: String,
+        global: bool,
+    },
+    Template(String),
+}
+
+impl RenamePattern {
+    pub fn parse(expression: &str) -> Result<RenamePattern, String> {
+        match parse_substitution(expression) {
+            Some(parts) => {
+                let (from, to, flags) = parts?;
+                if let Some(flag) = flags.chars().find(|flag| !matches!(flag, 'g' | 'i')) {
+                    return Err(format!("unknown flag '{}'", flag));
+                }
+                let regex = RegexBuilder::new(&from)
+                    .case_insensitive(flags.contains('i'))
+                    .build()
+                    .map_err(|e| format!("invalid regex: {}", e))?;
+                Ok(RenamePattern::Substitute {
+                    regex,
+                    replacement: replacement_syntax(&to),
+                    global: flags.contains('g'),
+                })
+            }
+            None => {
+                validate_template(expression)?;
+                Ok(RenamePattern::Template(expression.to_string()))
+            }
+        }
+    }
+
+    // new name of the `index`th (from 0) entry called `name`
+    pub fn rename(&self, name: &str, index: usize) -> String {
+        match self {
+            RenamePattern::Substitute {
+                regex,
+                replacement,
+                global: true,
+            } => regex.replace_all(name, replacement.as_str()).to_string(),
+            RenamePattern::Substitute {
+                regex, replacement, ..
+            } => regex.replace(name, replacement.as_str()).to_string(),
+            RenamePattern::Template(template) => fill_template(template, name, index),
+        }
+    }
+
+    pub fn new_names(&self, paths: &[String]) -> Vec<String> {
+        paths
+            .iter()
+            .enumerate()
+            .map(|(index, path)| self.rename(&file_name_of(Path::new(path)), index))
+            .collect()
+    }
+}
+
+// None when `expression` isn't a substitution at all, the parts otherwise.
+// the character after the `s` is the delimiter, `\` escapes it. the closing
+// delimiter may be left out while the expression is still being typed
+fn parse_substitution(expression: &str) -> Option<Result<(String, String, String), String>> {
+    let mut chars = expression.strip_prefix('s')?.chars();
+    let delimiter = chars.next().filter(|c| DELIMITERS.contains(c))?;
+
+    let mut parts = vec![String::new()];
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(next) if next == delimiter => parts.last_mut().unwrap().push(next),
+                Some(next) => {
+                    let part = parts.last_mut().unwrap();
+                    part.push('\\');
+                    part.push(next);
+                }
+                None => parts.last_mut().unwrap().push('\\'),
+            },
+            c if c == delimiter && parts.len() < 3 => parts.push(String::new()),
+            c => parts.last_mut().unwrap().push(c),
+        }
+    }
+    if parts.len() == 2 {
+        parts.push(String::new());
+    }
+    Some(match <[String; 3]>::try_from(parts) {
+        Ok([from, _, _]) if from.is_empty() => Err(String::from("empty pattern")),
+        Ok([from, to, flags]) => Ok((from, to, flags)),
+        Err(_) => Err(format!("expected s{0}from{0}to{0}", delimiter)),
+    })
+}
+
+// sed style `\1` groups to the `${1}` the regex crate expands, everything
+// else is taken literally
+fn replacement_syntax(replacement: &str) -> String {
+    let mut converted = String::new();
+    let mut chars = replacement.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            // a literal `$` would start a group name for the regex crate
+            ('$', _) => converted.push_str("$$"),
+            ('\\', Some(digit)) if digit.is_ascii_digit() => {
+                converted.push_str(&format!("${{{}}}", digit));
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                converted.push('\\');
+                chars.next();
+            }
+            _ => converted.push(c),
+        }
+    }
+    converted
+}
+
+// the placeholders of a template, `{` without a known placeholder is an error
+// so typos don't end up in every name
+fn placeholders(template: &str) -> Vec<(usize, usize, &str)> {
+    let mut found = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = template[offset..].find('{') {
+        let start = offset + start;
+        match template[start..].find('}') {
+            Some(end) => {
+                let end = start + end;
+                found.push((start, end + 1, &template[start + 1..end]));
+                offset = end + 1;
+            }
+            None => {
+                found.push((start, template.len(), &template[start + 1..]));
+                break;
+            }
+        }
+    }
+    found
+}
+
+fn validate_template(template: &str) -> Result<(), String> {
+    if template.trim().is_empty() {
+        return Err(String::from("empty template"));
+    }
+    for (_, end, placeholder) in placeholders(template) {
+        let known = match placeholder.split_once(':') {
+            Some(("n", width)) => width.parse::<usize>().is_ok(),
+            Some(_) => false,
+            None => matches!(placeholder, "n" | "name" | "ext"),
+        };
+        if !known || !template[..end].ends_with('}') {
+            return Err(format!("unknown placeholder {{{}}}", placeholder));
+        }
+    }
+    Ok(())
+}
+
+fn fill_template(template: &str, name: &str, index: usize) -> String {
+    // dot files like .bashrc have no extension
+    let (stem, ext) = match name.rfind('.') {
+        Some(dot) if dot > 0 => (&name[..dot], &name[dot + 1..]),
+        _ => (name, ""),
+    };
+
+    let mut filled = String::new();
+    let mut offset = 0;
+    for (start, end, placeholder) in placeholders(template) {
+        filled.push_str(&template[offset..start]);
+        match placeholder.split_once(':') {
+            Some((_, width)) => {
+                let width = width.parse::<usize>().unwrap_or(0);
+                filled.push_str(&format!("{:0width$}", index + 1, width = width));
+            }
+            None if placeholder == "n" => filled.push_str(&(index + 1).to_string()),
+            None if placeholder == "name" => filled.push_str(stem),
+            None => filled.push_str(ext),
+        }
+        offset = end;
+    }
+    filled.push_str(&template[offset..]);
+    // "{name}.{ext}" of a file without an extension shouldn't end in a dot
+    match ext.is_empty() && template.ends_with(".{ext}") {
+        true => filled.trim_end_matches('.').to_string(),
+        false => filled,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn touch(dir: &TempDir, name: &str, content: &str) -> String {
+        let path = dir.path().join(name);
+        fs::write(&path, content).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn renaming_onto_a_marked_entry_that_keeps_its_name_conflicts() {
+        let dir = TempDir::new().unwrap();
+        let a = touch(&dir, "a", "a");
+        let b = touch(&dir, "b", "b");
+
+        let plan = build_plan(&[a, b.clone()], &names(&["b", "b"]));
+        assert!(!plan.is_valid());
+        assert_eq!(plan.conflicts, vec![String::from("'b' already exists")]);
+
+        apply_plan(&plan);
+        assert_eq!(fs::read_to_string(&b).unwrap(), "b");
+    }
+
+    #[test]
+    fn renaming_onto_a_marked_entry_that_is_renamed_away_is_allowed() {
+        let dir = TempDir::new().unwrap();
+        let a = touch(&dir, "a", "a");
+        let b = touch(&dir, "b", "b");
+
+        let plan = build_plan(&[a, b], &names(&["b", "c"]));
+        assert!(plan.is_valid());
+    }
+
+    fn rename(expression: &str, name: &str) -> String {
+        RenamePattern::parse(expression).unwrap().rename(name, 0)
+    }
+
+    fn parts(expression: &str) -> Option<Result<(String, String, String), String>> {
+        parse_substitution(expression)
+    }
+
+    #[test]
+    fn substitutions_split_on_the_delimiter_after_s() {
+        let expected = (String::from("a"), String::from("b"), String::from("g"));
+        assert_eq!(parts("s/a/b/g"), Some(Ok(expected.clone())));
+        assert_eq!(parts("s|a|b|g"), Some(Ok(expected.clone())));
+        assert_eq!(parts("s#a#b#g"), Some(Ok(expected)));
+        // still being typed
+        assert_eq!(
+            parts("s/a/b"),
+            Some(Ok((String::from("a"), String::from("b"), String::new())))
+        );
+        assert_eq!(parts("s/a"), Some(Err(String::from("expected s/from/to/"))));
+        assert_eq!(parts("s//b/"), Some(Err(String::from("empty pattern"))));
+    }
+
+    #[test]
+    fn expressions_without_a_delimiter_are_templates() {
+        assert_eq!(parts("s_{n}"), None);
+        assert_eq!(parts("photo"), None);
+        assert_eq!(rename("s_{n}", "a.txt"), "s_1");
+    }
+
+    #[test]
+    fn escaped_delimiters_are_literal() {
+        assert_eq!(
+            parts(r"s/a\/b/c/"),
+            Some(Ok((String::from("a/b"), String::from("c"), String::new())))
+        );
+        assert_eq!(
+            parts(r"s/\d+/n/"),
+            Some(Ok((String::from(r"\d+"), String::from("n"), String::new())))
+        );
+        assert_eq!(rename(r"s/\d+/n/", "img123.jpg"), "imgn.jpg");
+    }
+
+    #[test]
+    fn flags_pick_every_match_and_case() {
+        assert_eq!(rename("s/a/x/", "banana"), "bxnana");
+        assert_eq!(rename("s/a/x/g", "banana"), "bxnxnx");
+        assert_eq!(rename("s/B/x/", "banana"), "banana");
+        assert_eq!(rename("s/B/x/i", "banana"), "xanana");
+        assert_eq!(
+            RenamePattern::parse("s/a/x/q").unwrap_err(),
+            "unknown flag 'q'"
+        );
+        assert!(RenamePattern::parse("s/(/x/")
+            .unwrap_err()
+            .starts_with("invalid regex"));
+    }
+
+    #[test]
+    fn replacements_expand_groups_and_keep_dollars() {
+        assert_eq!(
+            rename(r"s/IMG_(\d+)/photo_\1/", "IMG_0042.jpg"),
+            "photo_0042.jpg"
+        );
+        assert_eq!(rename("s/x/$price/", "x.txt"), "$price.txt");
+        assert_eq!(rename("s/x/${1}/", "x.txt"), "${1}.txt");
+        assert_eq!(rename(r"s/x/a\\b/", "x"), r"a\b");
+    }
+
+    #[test]
+    fn templates_fill_name_extension_and_counter() {
+        let pattern = RenamePattern::parse("{name}_{n:3}.{ext}").unwrap();
+        assert_eq!(pattern.rename("photo.jpg", 0), "photo_001.jpg");
+        assert_eq!(pattern.rename("photo.jpg", 41), "photo_042.jpg");
+        assert_eq!(pattern.rename("archive.tar.gz", 0), "archive.tar_001.gz");
+        // no extension, the trailing dot goes away
+        assert_eq!(pattern.rename("README", 0), "README_001");
+        // dot files are all name
+        assert_eq!(pattern.rename(".bashrc", 9), ".bashrc_010");
+        assert_eq!(rename("{n}-{name}", "a.txt"), "1-a");
+    }
+
+    #[test]
+    fn templates_reject_unknown_placeholders() {
+        assert_eq!(
+            RenamePattern::parse("{nme}.{ext}").unwrap_err(),
+            "unknown placeholder {nme}"
+        );
+        assert_eq!(
+            RenamePattern::parse("{n:x}").unwrap_err(),
+            "unknown placeholder {n:x}"
+        );
+        assert_eq!(
+            RenamePattern::parse("{name").unwrap_err(),
+            "unknown placeholder {name}"
+        );
+        assert_eq!(RenamePattern::parse("  ").unwrap_err(), "empty template");
+    }
+
+    #[test]
+    fn plans_report_conflicts() {
+        let dir = TempDir::new().unwrap();
+        let a = touch(&dir, "a", "a");
+        let b = touch(&dir, "b", "b");
+        touch(&dir, "taken", "taken");
+        let paths = vec![a, b];
+
+        let plan = build_plan(&paths, &names(&["c", "c"]));
+        assert_eq!(
+            plan.conflicts,
+            vec![String::from("'c' is used more than once")]
+        );
+        let plan = build_plan(&paths, &names(&["taken", "d"]));
+        assert_eq!(plan.conflicts, vec![String::from("'taken' already exists")]);
+        let plan = build_plan(&paths, &names(&["", "x/y"]));
+        assert_eq!(
+            plan.conflicts,
+            vec![
+                String::from("empty name for 'a'"),
+                String::from("invalid name 'x/y'")
+            ]
+        );
+        let plan = build_plan(&paths, &names(&["c"]));
+        assert!(plan.conflicts[0].starts_with("expected 2 names but found 1"));
+        // trailing empty lines from the editor are fine
+        let plan = build_plan(&paths, &names(&["c", "d", ""]));
+        assert!(plan.is_valid());
+    }
+
+    #[test]
+    fn pattern_renames_onto_another_marked_file_conflict() {
+        let dir = TempDir::new().unwrap();
+        let paths = vec![touch(&dir, "a.txt", "a"), touch(&dir, "b.txt", "b")];
+
+        let pattern = RenamePattern::parse("s/a/b/").unwrap();
+        let plan = build_plan(&paths, &pattern.new_names(&paths));
+        assert!(!plan.is_valid());
+        assert_eq!(plan.conflicts, vec![String::from("'b.txt' already exists")]);
+    }
+
+    #[test]
+    fn swaps_are_applied_through_temporary_names() {
+        let dir = TempDir::new().unwrap();
+        let a = touch(&dir, "a", "a");
+        let b = touch(&dir, "b", "b");
+
+        let plan = build_plan(&[a.clone(), b.clone()], &names(&["b", "a"]));
+        assert!(plan.is_valid());
+        let report = apply_plan(&plan);
+        assert_eq!(report.renamed, 2);
+        assert!(report.errors.is_empty());
+        assert_eq!(fs::read_to_string(&a).unwrap(), "b");
+        assert_eq!(fs::read_to_string(&b).unwrap(), "a");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn a_failed_rename_undoes_the_whole_plan() {
+        let dir = TempDir::new().unwrap();
+        let a = touch(&dir, "a", "a");
+        let b = touch(&dir, "b", "b");
+        let c = touch(&dir, "c", "c");
+
+        // a -> b -> c -> x, and x shows up after the plan was made
+        let plan = build_plan(&[a.clone(), b.clone(), c.clone()], &names(&["b", "c", "x"]));
+        assert!(plan.is_valid());
+        touch(&dir, "x", "x");
+
+        let report = apply_plan(&plan);
+        assert_eq!(report.renamed, 0);
+        assert_eq!(report.errors.len(), 1);
+        for (path, content) in [(&a, "a"), (&b, "b"), (&c, "c")] {
+            assert_eq!(fs::read_to_string(path).unwrap(), content);
+        }
+        assert_eq!(fs::read_to_string(dir.path().join("x")).unwrap(), "x");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 4);
+    }
+
+    #[test]
+    fn names_files_are_never_reused() {
+        let paths = vec![String::from("/tmp/one.txt"), String::from("/tmp/two.txt")];
+        let first = write_names_file(&paths).unwrap();
+        let second = write_names_file(&paths).unwrap();
+        assert_ne!(first, second);
+
+        assert_eq!(
+            read_names_file(&first).unwrap(),
+            names(&["one.txt", "two.txt"])
+        );
+        let _ = fs::remove_file(second);
+        assert!(!first.exists());
+    }
+}
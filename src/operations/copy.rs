@@ -0,0 +1,270 @@
+use std::{
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        mpsc::Sender,
+    },
+};
+
+use rayon::prelude::*;
+use walkdir::WalkDir;
+
+use crate::jobs::{JobControl, JobMessage};
+
+const CHUNK_SIZE: usize = 1024 * 1024;
+// copy_file_range calls are bigger, the data doesn't pass through a buffer
+#[cfg(target_os = "linux")]
+const FAST_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+// shared byte counters for every file copied by one job
+struct ByteProgress<'a> {
+    copied: AtomicU64,
+    total: u64,
+    sender: &'a Sender<JobMessage>,
+}
+
+impl ByteProgress<'_> {
+    fn add(&self, bytes: u64) {
+        let copied = self.copied.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        let _ = self.sender.send(JobMessage::Bytes {
+            copied,
+            total: self.total,
+        });
+    }
+}
+
+pub fn copy_dir_file_with_progress(
+    src: &Path,
+    dest: &Path,
+    control: &JobControl,
+    sender: &Sender<JobMessage>,
+) -> anyhow::Result<()> {
+    // only clean up what this copy created, never a destination that was
+    // already there
+    let dest_existed = dest.exists();
+
+    let result = copy_entries(src, dest, control, sender);
+
+    if result.is_err() && control.is_cancelled() && !dest_existed {
+        remove_partial(dest);
+    }
+    result
+}
+
+fn copy_entries(
+    src: &Path,
+    dest: &Path,
+    control: &JobControl,
+    sender: &Sender<JobMessage>,
+) -> anyhow::Result<()> {
+    if src.is_file() {
+        let progress = ByteProgress {
+            copied: AtomicU64::new(0),
+            total: fs::metadata(src)?.len(),
+            sender,
+        };
+        let _ = sender.send(JobMessage::Progress(format!("Copying: {}", src.display())));
+        copy_file(src, dest, control, &progress, false)?;
+        return Ok(());
+    }
+
+    let entries: Vec<_> = WalkDir::new(src)
+        .into_iter()
+        .filter_map(Result::ok)
+        .collect();
+    let total = entries.len();
+    let copied = AtomicUsize::new(0);
+    let progress = ByteProgress {
+        copied: AtomicU64::new(0),
+        total: entries
+            .iter()
+            .filter(|entry| entry.file_type().is_file())
+            .filter_map(|entry| entry.metadata().ok())
+            .map(|metadata| metadata.len())
+            .sum(),
+        sender,
+    };
+
+    entries.par_iter().try_for_each(|entry| {
+        if !control.wait_if_paused() {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+        }
+
+        let entry_path = entry.path();
+        let relative_path = entry_path.strip_prefix(src).unwrap();
+        let dst_path = dest.join(relative_path);
+
+        if entry_path.is_dir() {
+            fs::create_dir_all(&dst_path)?;
+        } else if entry_path.is_file() {
+            if let Some(parent) = dst_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            copy_file(entry_path, &dst_path, control, &progress, false)?;
+        } else {
+            return Err(io::Error::other("unsupported file type"));
+        }
+
+        let done = copied.fetch_add(1, Ordering::Relaxed) + 1;
+        let _ = sender.send(JobMessage::Progress(format!(
+            "Copying {}/{}: {}",
+            done,
+            total,
+            relative_path.display()
+        )));
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+// copies a list of single files under one byte counter, each copy keeps the
+// modification time of its source
+pub fn copy_files_with_progress(
+    files: &[(PathBuf, PathBuf)],
+    control: &JobControl,
+    sender: &Sender<JobMessage>,
+) -> anyhow::Result<()> {
+    let total = files.len();
+    let copied = AtomicUsize::new(0);
+    let progress = ByteProgress {
+        copied: AtomicU64::new(0),
+        total: files
+            .iter()
+            .filter_map(|(src, _)| fs::metadata(src).ok())
+            .map(|metadata| metadata.len())
+            .sum(),
+        sender,
+    };
+
+    files.par_iter().try_for_each(|(src, dest)| {
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        copy_file(src, dest, control, &progress, true)?;
+
+        let done = copied.fetch_add(1, Ordering::Relaxed) + 1;
+        let _ = sender.send(JobMessage::Progress(format!(
+            "Copying {}/{}: {}",
+            done,
+            total,
+            src.display()
+        )));
+        Ok::<(), io::Error>(())
+    })?;
+
+    Ok(())
+}
+
+// copies in chunks instead of fs::copy so big files report progress and can
+// be paused or cancelled halfway through. `keep_modified` gives the copy the
+// modification time of the source
+fn copy_file(
+    src: &Path,
+    dest: &Path,
+    control: &JobControl,
+    progress: &ByteProgress,
+    keep_modified: bool,
+) -> io::Result<()> {
+    let mut input = File::open(src)?;
+    let mut output = File::create(dest)?;
+    if !fast_copy_file(&input, &output, control, progress)? {
+        let mut buffer = vec![0u8; CHUNK_SIZE];
+        loop {
+            if !control.wait_if_paused() {
+                return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+            }
+            let read = input.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            output.write_all(&buffer[..read])?;
+            progress.add(read as u64);
+        }
+    }
+
+    // through the open handle, a read-only source makes the copy read-only
+    // and it couldn't be opened for writing again
+    let metadata = input.metadata()?;
+    if keep_modified {
+        output.set_modified(metadata.modified()?)?;
+    }
+    output.set_permissions(metadata.permissions())?;
+    Ok(())
+}
+
+// a reflink on btrfs or xfs shares the blocks and is done right away,
+// copy_file_range copies inside the kernel without reading into user space.
+// false when neither is supported here and nothing was written yet
+#[cfg(target_os = "linux")]
+fn fast_copy_file(
+    input: &File,
+    output: &File,
+    control: &JobControl,
+    progress: &ByteProgress,
+) -> io::Result<bool> {
+    use std::os::unix::io::AsRawFd;
+
+    let len = input.metadata()?.len();
+    // files in /proc and /sys report no size, the kernel copies nothing
+    if len == 0 {
+        return Ok(false);
+    }
+    if unsafe { libc::ioctl(output.as_raw_fd(), libc::FICLONE, input.as_raw_fd()) } == 0 {
+        progress.add(len);
+        return Ok(true);
+    }
+
+    let mut copied = 0;
+    loop {
+        if !control.wait_if_paused() {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+        }
+        let result = unsafe {
+            libc::copy_file_range(
+                input.as_raw_fd(),
+                std::ptr::null_mut(),
+                output.as_raw_fd(),
+                std::ptr::null_mut(),
+                FAST_CHUNK_SIZE,
+                0,
+            )
+        };
+        if result < 0 {
+            let error = io::Error::last_os_error();
+            let unsupported = matches!(
+                error.raw_os_error(),
+                Some(libc::EXDEV | libc::ENOSYS | libc::EINVAL | libc::EOPNOTSUPP | libc::EPERM)
+            );
+            if copied == 0 && unsupported {
+                return Ok(false);
+            }
+            return Err(error);
+        }
+        if result == 0 {
+            return Ok(true);
+        }
+        copied += result as u64;
+        progress.add(result as u64);
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn fast_copy_file(
+    _input: &File,
+    _output: &File,
+    _control: &JobControl,
+    _progress: &ByteProgress,
+) -> io::Result<bool> {
+    Ok(false)
+}
+
+fn remove_partial(dest: &Path) {
+    let _ = if dest.is_dir() {
+        fs::remove_dir_all(dest)
+    } else {
+        fs::remove_file(dest)
+    };
+}
@@ -0,0 +1,111 @@
+use std::{
+    fs,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::Sender,
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use rayon::prelude::*;
+use walkdir::WalkDir;
+
+use crate::jobs::JobControl;
+use crate::utils::format::SizeTotals;
+
+// how often the running totals of a selection are sent to the status bar
+const TOTALS_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone)]
+pub enum DirSizeMessage {
+    Size(String, u64),
+    Done,
+}
+
+#[derive(Debug, Clone)]
+pub enum TotalsMessage {
+    Progress(SizeTotals),
+    Done(SizeTotals),
+}
+
+// apparent size of everything below `path`, symlinks are counted as links
+// and never followed
+pub fn recursive_size(path: &Path, cancel: &AtomicBool) -> u64 {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(_) => return 0,
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+
+    let mut total = 0;
+    for entry in WalkDir::new(path)
+        .min_depth(1)
+        .into_iter()
+        .filter_map(Result::ok)
+    {
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+        if let Ok(entry_metadata) = entry.metadata() {
+            if !entry_metadata.is_dir() {
+                total += entry_metadata.len();
+            }
+        }
+    }
+    total
+}
+
+pub fn calculate_sizes(
+    paths: Vec<String>,
+    cancel: Arc<AtomicBool>,
+    sender: Sender<DirSizeMessage>,
+) {
+    paths
+        .par_iter()
+        .for_each_with(sender.clone(), |sender, path| {
+            if cancel.load(Ordering::Relaxed) {
+                return;
+            }
+            let size = recursive_size(Path::new(path), &cancel);
+            let _ = sender.send(DirSizeMessage::Size(path.clone(), size));
+        });
+
+    let _ = sender.send(DirSizeMessage::Done);
+}
+
+// counts and sizes of the marked entries and everything below them, the
+// running totals are sent while walking so large selections fill in
+// gradually. stops without a Done when the control gets cancelled
+pub fn selection_totals(paths: Vec<String>, control: JobControl, sender: Sender<TotalsMessage>) {
+    let mut totals = SizeTotals::default();
+    let mut last_sent = Instant::now();
+
+    for path in paths {
+        for entry in WalkDir::new(&path).into_iter().filter_map(Result::ok) {
+            if control.is_cancelled() {
+                return;
+            }
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                totals.dirs += 1;
+            } else {
+                totals.files += 1;
+                totals.bytes += metadata.len();
+            }
+
+            if last_sent.elapsed() >= TOTALS_INTERVAL {
+                last_sent = Instant::now();
+                if sender.send(TotalsMessage::Progress(totals)).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+    let _ = sender.send(TotalsMessage::Done(totals));
+}
@@ -0,0 +1,132 @@
+use std::{
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::Sender,
+    },
+};
+
+use crate::file_reader_content::{archive_kind, open_tar_decoder};
+use crate::jobs::JobMessage;
+
+const ARCHIVE_SUFFIXES: [&str; 10] = [
+    ".tar.gz", ".tar.bz2", ".tar.zst", ".tgz", ".tbz2", ".tzst", ".tar", ".zip", ".7z", ".rar",
+];
+
+// extracts into a fresh `<archive name>` directory next to the archive so
+// nothing already on disk gets overwritten
+pub fn generate_extract_dir(archive_path: &Path) -> PathBuf {
+    let file_name = archive_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| String::from("archive"));
+    let lower_name = file_name.to_lowercase();
+
+    let stem = ARCHIVE_SUFFIXES
+        .iter()
+        .find(|suffix| lower_name.ends_with(*suffix))
+        .map(|suffix| file_name[..file_name.len() - suffix.len()].to_string())
+        .filter(|stem| !stem.is_empty())
+        .unwrap_or(file_name);
+
+    let parent = archive_path.parent().unwrap_or(Path::new("."));
+    let mut extract_dir = parent.join(&stem);
+    let mut counter = 1;
+    while extract_dir.exists() {
+        extract_dir = parent.join(format!("{} ({})", stem, counter));
+        counter += 1;
+    }
+    extract_dir
+}
+
+pub fn extract_archive(
+    archive_path: &str,
+    dest_dir: &Path,
+    cancel: &AtomicBool,
+    sender: &Sender<JobMessage>,
+) -> anyhow::Result<()> {
+    fs::create_dir_all(dest_dir)?;
+
+    let result = if archive_path.to_lowercase().ends_with(".zip") {
+        extract_zip(archive_path, dest_dir, cancel, sender)
+    } else {
+        extract_tar(archive_path, dest_dir, cancel, sender)
+    };
+
+    if result.is_err() {
+        let _ = fs::remove_dir_all(dest_dir);
+    }
+    result
+}
+
+fn extract_zip(
+    archive_path: &str,
+    dest_dir: &Path,
+    cancel: &AtomicBool,
+    sender: &Sender<JobMessage>,
+) -> anyhow::Result<()> {
+    let file = File::open(archive_path)?;
+    let mut archive = zip::ZipArchive::new(file)?;
+
+    for i in 0..archive.len() {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(anyhow::anyhow!("cancelled"));
+        }
+        let mut file = archive.by_index(i)?;
+
+        // enclosed_name rejects entries that would escape the destination
+        let outpath = match file.enclosed_name() {
+            Some(path) => dest_dir.join(path),
+            None => continue,
+        };
+
+        if file.is_dir() {
+            fs::create_dir_all(&outpath)?;
+            continue;
+        }
+
+        let _ = sender.send(JobMessage::Progress(format!("Extracting: {}", file.name())));
+        if let Some(parent) = outpath.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut outfile = File::create(&outpath)?;
+        io::copy(&mut file, &mut outfile)?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            if let Some(mode) = file.unix_mode() {
+                let _ = fs::set_permissions(&outpath, fs::Permissions::from_mode(mode));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn extract_tar(
+    archive_path: &str,
+    dest_dir: &Path,
+    cancel: &AtomicBool,
+    sender: &Sender<JobMessage>,
+) -> anyhow::Result<()> {
+    let kind =
+        archive_kind(archive_path).ok_or_else(|| anyhow::anyhow!("unsupported archive type"))?;
+    let reader = open_tar_decoder(archive_path, kind)?;
+    let mut archive = tar::Archive::new(reader);
+
+    for entry in archive.entries()? {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(anyhow::anyhow!("cancelled"));
+        }
+        let mut entry = entry?;
+        let name = entry.path()?.display().to_string();
+        let _ = sender.send(JobMessage::Progress(format!("Extracting: {}", name)));
+
+        // unpack_in refuses paths outside of dest_dir
+        entry.unpack_in(dest_dir)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use std::{
+    fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::bail;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LinkKind {
+    Symbolic,
+    Hard,
+}
+
+impl LinkKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            LinkKind::Symbolic => "Symlink",
+            LinkKind::Hard => "Hard link",
+        }
+    }
+}
+
+// `dest` is the path of the new link, or a directory to create it in under
+// the name of `target`
+pub fn link_path(target: &Path, dest: &Path) -> PathBuf {
+    match target.file_name() {
+        Some(name) if dest.is_dir() => dest.join(name),
+        _ => dest.to_path_buf(),
+    }
+}
+
+// creates a link to `target` and returns its path. a relative symlink keeps
+// working when the directory holding both is moved somewhere else
+pub fn create_link(
+    target: &Path,
+    dest: &Path,
+    kind: LinkKind,
+    relative: bool,
+) -> anyhow::Result<PathBuf> {
+    let link = link_path(target, dest);
+    if link.symlink_metadata().is_ok() {
+        bail!("{} already exists", link.display());
+    }
+
+    match kind {
+        LinkKind::Hard => {
+            if target.is_dir() {
+                bail!("directories can't be hard linked");
+            }
+            fs::hard_link(target, &link)?;
+        }
+        LinkKind::Symbolic => {
+            let link_dir = link.parent().unwrap_or(Path::new("."));
+            let link_target = if relative {
+                relative_path(link_dir, target)?
+            } else {
+                absolute(target)?
+            };
+            symlink(&link_target, &link, target.is_dir())?;
+        }
+    }
+    Ok(link)
+}
+
+// `path` relative to `from`. the last component of `path` isn't resolved, a
+// link to a link points at the link. paths on different drives stay absolute
+fn relative_path(from: &Path, path: &Path) -> io::Result<PathBuf> {
+    let from = fs::canonicalize(from)?;
+    let path = absolute(path)?;
+
+    let from_components: Vec<Component> = from.components().collect();
+    let path_components: Vec<Component> = path.components().collect();
+    if from_components.first() != path_components.first() {
+        return Ok(path);
+    }
+    let common = from_components
+        .iter()
+        .zip(path_components.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut relative = PathBuf::new();
+    for _ in common..from_components.len() {
+        relative.push("..");
+    }
+    for component in &path_components[common..] {
+        relative.push(component);
+    }
+    if relative.as_os_str().is_empty() {
+        relative.push(".");
+    }
+    Ok(relative)
+}
+
+fn absolute(path: &Path) -> io::Result<PathBuf> {
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) if !parent.as_os_str().is_empty() => {
+            Ok(fs::canonicalize(parent)?.join(name))
+        }
+        _ => fs::canonicalize(path),
+    }
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, link: &Path, _is_dir: bool) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+// windows has separate links for files and directories, creating them needs
+// developer mode or an elevated prompt
+#[cfg(windows)]
+fn symlink(target: &Path, link: &Path, is_dir: bool) -> io::Result<()> {
+    if is_dir {
+        std::os::windows::fs::symlink_dir(target, link)
+    } else {
+        std::os::windows::fs::symlink_file(target, link)
+    }
+}
@@ -0,0 +1,10 @@
+pub mod archive;
+pub mod bulk_rename;
+pub mod copy;
+pub mod dir_size;
+pub mod extract;
+pub mod file_ops;
+pub mod hash;
+pub mod permissions;
+pub mod sync;
+pub mod transfer;
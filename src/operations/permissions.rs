@@ -0,0 +1,262 @@
+use std::{fs, io};
+
+use crate::utils::format::{format_size, format_timestamp};
+
+// the nine rwx bits in display order, owner -> group -> other
+pub const PERMISSION_BITS: [(u32, char); 9] = [
+    (0o400, 'r'),
+    (0o200, 'w'),
+    (0o100, 'x'),
+    (0o040, 'r'),
+    (0o020, 'w'),
+    (0o010, 'x'),
+    (0o004, 'r'),
+    (0o002, 'w'),
+    (0o001, 'x'),
+];
+
+#[derive(Debug, Clone)]
+pub struct Properties {
+    pub path: String,
+    pub kind: String,
+    // symlinks show and change the mode of their target
+    pub link_target: Option<String>,
+    pub size: u64,
+    pub mode: u32,
+    pub owner: String,
+    pub group: String,
+    pub modified: Option<String>,
+    pub accessed: Option<String>,
+    pub created: Option<String>,
+}
+
+impl Properties {
+    pub fn lines(&self) -> Vec<String> {
+        let not_available = || String::from("n/a");
+        let mode_of = match self.link_target {
+            Some(_) => " of the target",
+            None => "",
+        };
+        let mut lines = vec![
+            format!("Path:     {}", self.path),
+            format!("Type:     {}", self.kind),
+        ];
+        if let Some(target) = &self.link_target {
+            lines.push(format!("Target:   {}", target));
+        }
+        lines.extend([
+            format!("Size:     {} ({} bytes)", format_size(self.size), self.size),
+            format!(
+                "Mode:     {} ({:04o}){}",
+                mode_string(self.mode),
+                self.mode & 0o7777,
+                mode_of
+            ),
+            format!("Owner:    {}", self.owner),
+            format!("Group:    {}", self.group),
+            format!(
+                "Modified: {}",
+                self.modified.clone().unwrap_or_else(not_available)
+            ),
+            format!(
+                "Accessed: {}",
+                self.accessed.clone().unwrap_or_else(not_available)
+            ),
+            format!(
+                "Created:  {}",
+                self.created.clone().unwrap_or_else(not_available)
+            ),
+        ]);
+        lines
+    }
+}
+
+pub fn mode_string(mode: u32) -> String {
+    PERMISSION_BITS
+        .iter()
+        .map(|(bit, c)| if mode & bit != 0 { *c } else { '-' })
+        .collect()
+}
+
+pub fn toggle_bit(mode: u32, index: usize) -> u32 {
+    match PERMISSION_BITS.get(index) {
+        Some((bit, _)) => mode ^ bit,
+        None => mode,
+    }
+}
+
+pub fn read_properties(path: &str) -> io::Result<Properties> {
+    let metadata = fs::symlink_metadata(path)?;
+    let file_type = metadata.file_type();
+    let kind = if file_type.is_symlink() {
+        "symlink"
+    } else if file_type.is_dir() {
+        "directory"
+    } else {
+        "file"
+    };
+
+    let (mut mode, owner, group) = ownership(&metadata);
+    // changing the mode of a symlink changes its target, so that is the mode
+    // shown. a broken link has none to show or change
+    let link_target = match file_type.is_symlink() {
+        true => {
+            let target = fs::read_link(path)?;
+            mode = mode_of(&fs::metadata(path).map_err(|e| {
+                io::Error::new(
+                    e.kind(),
+                    format!("the target {} of the link: {}", target.display(), e),
+                )
+            })?);
+            Some(target.display().to_string())
+        }
+        false => None,
+    };
+
+    Ok(Properties {
+        path: path.to_string(),
+        kind: kind.to_string(),
+        link_target,
+        size: metadata.len(),
+        mode,
+        owner,
+        group,
+        modified: metadata.modified().ok().map(format_timestamp),
+        accessed: metadata.accessed().ok().map(format_timestamp),
+        created: metadata.created().ok().map(format_timestamp),
+    })
+}
+
+#[cfg(unix)]
+pub fn mode_of(metadata: &fs::Metadata) -> u32 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.mode()
+}
+
+#[cfg(not(unix))]
+pub fn mode_of(metadata: &fs::Metadata) -> u32 {
+    if metadata.permissions().readonly() {
+        0o444
+    } else {
+        0o666
+    }
+}
+
+#[cfg(unix)]
+fn ownership(metadata: &fs::Metadata) -> (u32, String, String) {
+    use std::os::unix::fs::MetadataExt;
+
+    let owner = lookup_name("/etc/passwd", metadata.uid());
+    let group = lookup_name("/etc/group", metadata.gid());
+    (mode_of(metadata), owner, group)
+}
+
+#[cfg(not(unix))]
+fn ownership(metadata: &fs::Metadata) -> (u32, String, String) {
+    (mode_of(metadata), String::from("n/a"), String::from("n/a"))
+}
+
+// both files use `name:password:id:...`, fall back to the numeric id for
+// users that only exist in a directory service
+#[cfg(unix)]
+fn lookup_name(database: &str, id: u32) -> String {
+    let content = fs::read_to_string(database).unwrap_or_default();
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let value = fields.nth(1)?.parse::<u32>().ok()?;
+            Some((name, value))
+        })
+        .find(|(_, value)| *value == id)
+        .map(|(name, _)| name.to_string())
+        .unwrap_or_else(|| id.to_string())
+}
+
+// follows symlinks, the mode of a link itself can't be changed on most
+// systems
+#[cfg(unix)]
+pub fn apply_mode(path: &str, mode: u32) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o7777))
+}
+
+#[cfg(not(unix))]
+pub fn apply_mode(_path: &str, _mode: u32) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "changing permissions is only supported on unix",
+    ))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::{
+        os::unix::fs::{symlink, PermissionsExt},
+        path::{Path, PathBuf},
+    };
+
+    fn file_with_mode(dir: &Path, mode: u32) -> PathBuf {
+        let file = dir.join("file.txt");
+        fs::write(&file, "content").unwrap();
+        fs::set_permissions(&file, fs::Permissions::from_mode(mode)).unwrap();
+        file
+    }
+
+    fn link_to(dir: &Path, target: &Path) -> String {
+        let link = dir.join("link");
+        symlink(target, &link).unwrap();
+        link.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn symlinks_show_and_change_their_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = file_with_mode(dir.path(), 0o640);
+        let link = link_to(dir.path(), &file);
+
+        let properties = read_properties(&link).unwrap();
+        assert_eq!(properties.kind, "symlink");
+        assert_eq!(properties.mode & 0o7777, 0o640);
+        assert_eq!(
+            properties.link_target.as_deref(),
+            Some(file.to_string_lossy().as_ref())
+        );
+        assert!(properties
+            .lines()
+            .iter()
+            .any(|line| line.ends_with("of the target")));
+
+        apply_mode(&link, 0o600).unwrap();
+        let mode = fs::metadata(&file).unwrap().permissions().mode();
+        assert_eq!(mode & 0o7777, 0o600);
+        assert_eq!(read_properties(&link).unwrap().mode & 0o7777, 0o600);
+    }
+
+    #[test]
+    fn broken_symlinks_have_no_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        let link = link_to(dir.path(), &dir.path().join("missing"));
+
+        assert!(read_properties(&link).is_err());
+        assert!(apply_mode(&link, 0o600).is_err());
+    }
+
+    #[test]
+    fn files_have_no_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = file_with_mode(dir.path(), 0o644);
+
+        let properties = read_properties(&file.to_string_lossy()).unwrap();
+        assert_eq!(properties.kind, "file");
+        assert_eq!(properties.mode & 0o7777, 0o644);
+        assert!(properties.link_target.is_none());
+        assert!(!properties
+            .lines()
+            .iter()
+            .any(|line| line.contains("Target")));
+    }
+}
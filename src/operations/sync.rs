@@ -0,0 +1,356 @@
+use std::{
+    collections::HashSet,
+    fs::{self, Metadata},
+    path::{Path, PathBuf},
+    sync::mpsc::Sender,
+    time::UNIX_EPOCH,
+};
+
+use walkdir::WalkDir;
+
+use crate::jobs::{JobControl, JobMessage};
+use crate::operations::copy::copy_files_with_progress;
+use crate::utils::format::format_size;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SyncKind {
+    // missing in the destination
+    Create,
+    // size or modification time differ
+    Update,
+    // a file where the source has a directory or the other way around, the
+    // destination entry is removed before copying even when the sync keeps
+    // extraneous entries
+    Replace,
+    // only in the destination, removed when the sync deletes extraneous
+    // entries
+    Delete,
+}
+
+impl SyncKind {
+    pub fn marker(&self) -> &'static str {
+        match self {
+            SyncKind::Create => "+",
+            SyncKind::Update => "~",
+            SyncKind::Replace => "!",
+            SyncKind::Delete => "-",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SyncAction {
+    pub kind: SyncKind,
+    // relative to both the source and the destination
+    pub path: PathBuf,
+    pub is_dir: bool,
+    // bytes copied, 0 for directories and deletions
+    pub size: u64,
+    // a replaced destination entry is a directory, it goes with everything
+    // inside it
+    pub replaces_dir: bool,
+}
+
+// what mirroring `source` into `dest` would do, built without touching
+// anything so it can be shown as a dry run first
+#[derive(Debug, Clone)]
+pub struct SyncPlan {
+    pub source: PathBuf,
+    pub dest: PathBuf,
+    pub actions: Vec<SyncAction>,
+}
+
+impl SyncPlan {
+    pub fn count(&self, kind: SyncKind) -> usize {
+        self.actions
+            .iter()
+            .filter(|action| action.kind == kind)
+            .count()
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.actions.iter().map(|action| action.size).sum()
+    }
+
+    pub fn has_changes(&self, delete: bool) -> bool {
+        self.actions
+            .iter()
+            .any(|action| delete || action.kind != SyncKind::Delete)
+    }
+
+    pub fn summary(&self, delete: bool) -> String {
+        let deleted = match delete {
+            true => format!("{} to delete", self.count(SyncKind::Delete)),
+            false => format!("{} extraneous kept", self.count(SyncKind::Delete)),
+        };
+        format!(
+            "{} new, {} changed, {} replaced, {}, {} to copy",
+            self.count(SyncKind::Create),
+            self.count(SyncKind::Update),
+            self.count(SyncKind::Replace),
+            deleted,
+            format_size(self.bytes())
+        )
+    }
+
+    // one line per action, deletions only when they will happen. replacements
+    // say what they remove, they happen either way
+    pub fn preview_lines(&self, delete: bool) -> Vec<(SyncKind, String)> {
+        self.actions
+            .iter()
+            .filter(|action| delete || action.kind != SyncKind::Delete)
+            .map(|action| {
+                let suffix = if action.is_dir { "/" } else { "" };
+                let note = match (action.kind, action.replaces_dir) {
+                    (SyncKind::Replace, true) => {
+                        " (deletes the directory there and everything in it)"
+                    }
+                    (SyncKind::Replace, false) => " (deletes the file there)",
+                    _ => "",
+                };
+                (
+                    action.kind,
+                    format!(
+                        "{} {}{}{}",
+                        action.kind.marker(),
+                        action.path.display(),
+                        suffix,
+                        note
+                    ),
+                )
+            })
+            .collect()
+    }
+}
+
+// whole seconds, file systems keep modification times with different
+// precision
+fn modified_secs(metadata: &Metadata) -> Option<u64> {
+    metadata
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|duration| duration.as_secs())
+}
+
+// symlinks and other special files are skipped, only directories and regular
+// files are mirrored
+pub fn plan(source: &Path, dest: &Path) -> anyhow::Result<SyncPlan> {
+    if !source.is_dir() {
+        return Err(anyhow::anyhow!("{} is not a directory", source.display()));
+    }
+    if dest.exists() && !dest.is_dir() {
+        return Err(anyhow::anyhow!("{} is not a directory", dest.display()));
+    }
+    if dest.starts_with(source) || source.starts_with(dest) {
+        return Err(anyhow::anyhow!(
+            "{} and {} overlap",
+            source.display(),
+            dest.display()
+        ));
+    }
+
+    let mut actions = Vec::new();
+    let mut source_paths = HashSet::new();
+
+    for entry in WalkDir::new(source).min_depth(1).sort_by_file_name() {
+        let entry = entry?;
+        let file_type = entry.file_type();
+        if !file_type.is_dir() && !file_type.is_file() {
+            continue;
+        }
+        let path = entry.path().strip_prefix(source)?.to_path_buf();
+        source_paths.insert(path.clone());
+
+        let is_dir = file_type.is_dir();
+        let metadata = entry.metadata()?;
+        let size = if is_dir { 0 } else { metadata.len() };
+        let mut replaces_dir = false;
+        let kind = match fs::symlink_metadata(dest.join(&path)) {
+            Err(_) => SyncKind::Create,
+            Ok(existing) if existing.is_dir() != is_dir || existing.is_symlink() => {
+                replaces_dir = existing.is_dir();
+                SyncKind::Replace
+            }
+            Ok(_) if is_dir => continue,
+            Ok(existing) => {
+                if existing.len() == metadata.len()
+                    && modified_secs(&existing) == modified_secs(&metadata)
+                {
+                    continue;
+                }
+                SyncKind::Update
+            }
+        };
+        actions.push(SyncAction {
+            kind,
+            path,
+            is_dir,
+            size,
+            replaces_dir,
+        });
+    }
+
+    if dest.is_dir() {
+        let mut walker = WalkDir::new(dest)
+            .min_depth(1)
+            .sort_by_file_name()
+            .into_iter();
+        while let Some(entry) = walker.next() {
+            let entry = entry?;
+            let path = entry.path().strip_prefix(dest)?.to_path_buf();
+            if source_paths.contains(&path) {
+                continue;
+            }
+            // a deleted directory takes everything inside it along
+            let is_dir = entry.file_type().is_dir();
+            if is_dir {
+                walker.skip_current_dir();
+            }
+            actions.push(SyncAction {
+                kind: SyncKind::Delete,
+                path,
+                is_dir,
+                size: 0,
+                replaces_dir: false,
+            });
+        }
+    }
+
+    Ok(SyncPlan {
+        source: source.to_path_buf(),
+        dest: dest.to_path_buf(),
+        actions,
+    })
+}
+
+fn remove(path: &Path) -> std::io::Result<()> {
+    match fs::symlink_metadata(path)?.is_dir() {
+        true => fs::remove_dir_all(path),
+        false => fs::remove_file(path),
+    }
+}
+
+// carries out a plan, extraneous entries are only removed when `delete` is
+// set. copied files keep the source modification time so the next sync
+// skips them
+pub fn run(
+    plan: &SyncPlan,
+    delete: bool,
+    control: &JobControl,
+    sender: &Sender<JobMessage>,
+) -> anyhow::Result<String> {
+    fs::create_dir_all(&plan.dest)?;
+
+    if delete {
+        for action in plan.actions.iter().filter(|a| a.kind == SyncKind::Delete) {
+            if !control.wait_if_paused() {
+                return Err(anyhow::anyhow!("cancelled"));
+            }
+            let _ = sender.send(JobMessage::Progress(format!(
+                "Deleting: {}",
+                action.path.display()
+            )));
+            remove(&plan.dest.join(&action.path))?;
+        }
+    }
+
+    let mut files = Vec::new();
+    for action in plan.actions.iter().filter(|a| a.kind != SyncKind::Delete) {
+        let target = plan.dest.join(&action.path);
+        // an updated file is removed too, a read-only copy from an earlier
+        // sync can't be opened for writing
+        if action.kind == SyncKind::Replace || action.kind == SyncKind::Update {
+            remove(&target)?;
+        }
+        if action.is_dir {
+            fs::create_dir_all(&target)?;
+        } else {
+            files.push((plan.source.join(&action.path), target));
+        }
+    }
+    copy_files_with_progress(&files, control, sender)?;
+
+    let deleted = match delete {
+        true => plan.count(SyncKind::Delete),
+        false => 0,
+    };
+    Ok(format!(
+        "Synced {} into {}: {} copied, {} deleted",
+        plan.source.display(),
+        plan.dest.display(),
+        files.len(),
+        deleted
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jobs::JobControl;
+    use std::sync::mpsc::channel;
+
+    fn sync(source: &Path, dest: &Path, delete: bool) -> SyncPlan {
+        let plan = plan(source, dest).unwrap();
+        let (sender, _receiver) = channel();
+        run(&plan, delete, &JobControl::default(), &sender).unwrap();
+        plan
+    }
+
+    #[test]
+    fn a_second_sync_has_nothing_to_do() {
+        let dir = tempfile::tempdir().unwrap();
+        let (source, dest) = (dir.path().join("src"), dir.path().join("dest"));
+        fs::create_dir_all(source.join("sub")).unwrap();
+        fs::write(source.join("a.txt"), "a").unwrap();
+        fs::write(source.join("sub/b.txt"), "bb").unwrap();
+
+        let first = sync(&source, &dest, false);
+        assert_eq!(first.count(SyncKind::Create), 3);
+        assert_eq!(fs::read_to_string(dest.join("sub/b.txt")).unwrap(), "bb");
+        assert!(plan(&source, &dest).unwrap().actions.is_empty());
+    }
+
+    #[test]
+    fn read_only_files_are_copied_and_updated() {
+        let dir = tempfile::tempdir().unwrap();
+        let (source, dest) = (dir.path().join("src"), dir.path().join("dest"));
+        fs::create_dir(&source).unwrap();
+        let file = source.join("locked.txt");
+        fs::write(&file, "one").unwrap();
+        let mut permissions = fs::metadata(&file).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&file, permissions.clone()).unwrap();
+
+        sync(&source, &dest, false);
+        assert!(plan(&source, &dest).unwrap().actions.is_empty());
+
+        fs::remove_file(&file).unwrap();
+        fs::write(&file, "changed").unwrap();
+        fs::set_permissions(&file, permissions).unwrap();
+
+        let update = sync(&source, &dest, false);
+        assert_eq!(update.count(SyncKind::Update), 1);
+        assert_eq!(
+            fs::read_to_string(dest.join("locked.txt")).unwrap(),
+            "changed"
+        );
+    }
+
+    #[test]
+    fn replacing_a_directory_is_in_the_preview_without_delete() {
+        let dir = tempfile::tempdir().unwrap();
+        let (source, dest) = (dir.path().join("src"), dir.path().join("dest"));
+        fs::create_dir(&source).unwrap();
+        fs::write(source.join("notes"), "file now").unwrap();
+        fs::create_dir_all(dest.join("notes")).unwrap();
+        fs::write(dest.join("notes/old.txt"), "old").unwrap();
+
+        let plan = plan(&source, &dest).unwrap();
+        let lines = plan.preview_lines(false);
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].0, SyncKind::Replace);
+        assert!(lines[0].1.contains("deletes the directory there"));
+    }
+}
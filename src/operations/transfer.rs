@@ -0,0 +1,161 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc::Sender,
+};
+
+use crate::jobs::{JobControl, JobMessage};
+use crate::operations::copy::copy_dir_file_with_progress;
+
+// expands a leading `~` so prompts accept the same paths a shell would
+pub fn expand_home(input: &str) -> String {
+    match input.strip_prefix('~') {
+        Some(rest) => match dirs::home_dir() {
+            Some(home) => format!("{}{}", home.display(), rest),
+            None => input.to_string(),
+        },
+        None => input.to_string(),
+    }
+}
+
+pub fn longest_common_prefix(candidates: &[String]) -> String {
+    let first = match candidates.first() {
+        Some(first) => first,
+        None => return String::new(),
+    };
+
+    let mut end = first.len();
+    for candidate in candidates.iter().skip(1) {
+        end = first
+            .char_indices()
+            .zip(candidate.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map(|((i, a), _)| i + a.len_utf8())
+            .unwrap_or(0)
+            .min(end);
+    }
+    first[..end].to_string()
+}
+
+// checks the destination up front so nothing gets copied when one of the
+// sources would overwrite an existing entry or another source of the same name
+pub fn plan_targets(
+    sources: &[String],
+    dest_dir: &Path,
+) -> anyhow::Result<Vec<(PathBuf, PathBuf)>> {
+    if !dest_dir.is_dir() {
+        return Err(anyhow::anyhow!("{} is not a directory", dest_dir.display()));
+    }
+
+    let mut targets = Vec::new();
+    let mut names = HashSet::new();
+    for source in sources.iter() {
+        let source = PathBuf::from(source);
+        let name = source
+            .file_name()
+            .ok_or_else(|| anyhow::anyhow!("invalid source {}", source.display()))?;
+        let target = dest_dir.join(name);
+
+        if target.exists() {
+            return Err(anyhow::anyhow!("{} already exists", target.display()));
+        }
+        if !names.insert(name.to_os_string()) {
+            return Err(anyhow::anyhow!(
+                "more than one source is called {}",
+                name.to_string_lossy()
+            ));
+        }
+        if source.is_dir() && dest_dir.starts_with(&source) {
+            return Err(anyhow::anyhow!(
+                "cannot copy or move {} into itself",
+                source.display()
+            ));
+        }
+        targets.push((source, target));
+    }
+    Ok(targets)
+}
+
+pub fn copy_to(
+    targets: &[(PathBuf, PathBuf)],
+    control: &JobControl,
+    sender: &Sender<JobMessage>,
+) -> anyhow::Result<()> {
+    for (source, target) in targets.iter() {
+        copy_dir_file_with_progress(source, target, control, sender)?;
+    }
+    Ok(())
+}
+
+// rename when source and destination share a filesystem, otherwise fall back
+// to copy and delete
+pub fn move_to(
+    targets: &[(PathBuf, PathBuf)],
+    control: &JobControl,
+    sender: &Sender<JobMessage>,
+) -> anyhow::Result<()> {
+    for (source, target) in targets.iter() {
+        if control.is_cancelled() {
+            return Err(anyhow::anyhow!("cancelled"));
+        }
+        let _ = sender.send(JobMessage::Progress(format!(
+            "Moving: {}",
+            source.display()
+        )));
+
+        if fs::rename(source, target).is_err() {
+            copy_dir_file_with_progress(source, target, control, sender)?;
+            if source.is_dir() {
+                fs::remove_dir_all(source)?;
+            } else {
+                fs::remove_file(source)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn sources_with_the_same_name_are_refused() {
+        let dir = TempDir::new().unwrap();
+        let (one, two, dest) = (
+            dir.path().join("one"),
+            dir.path().join("two"),
+            dir.path().join("dest"),
+        );
+        for path in [&one, &two, &dest] {
+            fs::create_dir(path).unwrap();
+        }
+        fs::write(one.join("notes.txt"), "one").unwrap();
+        fs::write(two.join("notes.txt"), "two").unwrap();
+
+        let sources = vec![
+            one.join("notes.txt").to_string_lossy().to_string(),
+            two.join("notes.txt").to_string_lossy().to_string(),
+        ];
+        let error = plan_targets(&sources, &dest).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "more than one source is called notes.txt"
+        );
+        assert!(plan_targets(&sources[..1], &dest).is_ok());
+    }
+
+    #[test]
+    fn directories_are_not_copied_into_themselves() {
+        let dir = TempDir::new().unwrap();
+        let inner = dir.path().join("a").join("b");
+        fs::create_dir_all(&inner).unwrap();
+
+        let sources = vec![dir.path().join("a").to_string_lossy().to_string()];
+        let error = plan_targets(&sources, &inner).unwrap_err();
+        assert!(error.to_string().starts_with("cannot copy or move"));
+    }
+}
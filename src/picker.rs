@@ -0,0 +1,212 @@
+use std::{
+    cmp::Reverse,
+    io::{self, BufRead, Stderr},
+};
+
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout},
+    text::Line,
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame, Terminal,
+};
+
+use crate::theme::ThemeColors;
+use crate::ui::highlight_search_term;
+
+// candidates and query for `ff --pick`, the ui is drawn on stderr so stdout
+// only ever carries the selection
+struct Picker {
+    candidates: Vec<String>,
+    input: String,
+    // indices into `candidates`, best match first, with the char indices
+    // the query matched
+    matches: Vec<(usize, Vec<usize>)>,
+    state: ListState,
+    matcher: SkimMatcherV2,
+}
+
+impl Picker {
+    fn new(candidates: Vec<String>) -> Picker {
+        let mut picker = Picker {
+            candidates,
+            input: String::new(),
+            matches: Vec::new(),
+            state: ListState::default(),
+            matcher: SkimMatcherV2::default(),
+        };
+        picker.update_matches();
+        picker
+    }
+
+    fn update_matches(&mut self) {
+        if self.input.is_empty() {
+            self.matches = (0..self.candidates.len())
+                .map(|index| (index, Vec::new()))
+                .collect();
+        } else {
+            let mut scored: Vec<(i64, usize, Vec<usize>)> = self
+                .candidates
+                .iter()
+                .enumerate()
+                .filter_map(|(index, candidate)| {
+                    self.matcher
+                        .fuzzy_indices(candidate, &self.input)
+                        .map(|(score, indices)| (score, index, indices))
+                })
+                .collect();
+            // stable, so equal scores keep the input order
+            scored.sort_by_key(|entry| Reverse(entry.0));
+            self.matches = scored
+                .into_iter()
+                .map(|(_, index, indices)| (index, indices))
+                .collect();
+        }
+        self.state.select(if self.matches.is_empty() {
+            None
+        } else {
+            Some(0)
+        });
+    }
+
+    fn move_selection(&mut self, down: bool) {
+        if self.matches.is_empty() {
+            return;
+        }
+        let last = self.matches.len() - 1;
+        let selected = self.state.selected().unwrap_or(0);
+        let next = match (down, selected) {
+            (true, i) if i >= last => 0,
+            (true, i) => i + 1,
+            (false, 0) => last,
+            (false, i) => i - 1,
+        };
+        self.state.select(Some(next));
+    }
+
+    fn selected(&self) -> Option<String> {
+        let (index, _) = self.matches.get(self.state.selected()?)?;
+        Some(self.candidates[*index].clone())
+    }
+}
+
+// one candidate per line, empty lines are skipped
+pub fn read_candidates() -> io::Result<Vec<String>> {
+    let mut candidates = Vec::new();
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        if !line.is_empty() {
+            candidates.push(line);
+        }
+    }
+    Ok(candidates)
+}
+
+// None when the picker was closed without choosing anything
+pub fn pick(candidates: Vec<String>, colors: ThemeColors) -> anyhow::Result<Option<String>> {
+    // crossterm reads keys from /dev/tty when stdin is a pipe
+    enable_raw_mode()?;
+    let mut stderr = io::stderr();
+    execute!(stderr, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stderr))?;
+
+    let result = run(&mut terminal, Picker::new(candidates), &colors);
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    result
+}
+
+fn run(
+    terminal: &mut Terminal<CrosstermBackend<Stderr>>,
+    mut picker: Picker,
+    colors: &ThemeColors,
+) -> anyhow::Result<Option<String>> {
+    loop {
+        terminal.draw(|f| draw(f, &mut picker, colors))?;
+
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+            match key.code {
+                KeyCode::Esc => return Ok(None),
+                KeyCode::Char('c') if ctrl => return Ok(None),
+                KeyCode::Enter => return Ok(picker.selected()),
+                KeyCode::Down => picker.move_selection(true),
+                KeyCode::Up => picker.move_selection(false),
+                KeyCode::Char('j' | 'n') if ctrl => picker.move_selection(true),
+                KeyCode::Char('k' | 'p') if ctrl => picker.move_selection(false),
+                // unhandled chords aren't typed into the query
+                KeyCode::Char(_)
+                    if key
+                        .modifiers
+                        .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {}
+                KeyCode::Char(c) => {
+                    picker.input.push(c);
+                    picker.update_matches();
+                }
+                KeyCode::Backspace => {
+                    picker.input.pop();
+                    picker.update_matches();
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn draw(f: &mut Frame, picker: &mut Picker, colors: &ThemeColors) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(1)])
+        .split(f.size());
+
+    let title = format!(
+        "Pick ({}/{})",
+        picker.matches.len(),
+        picker.candidates.len()
+    );
+    let input = Paragraph::new(picker.input.as_str()).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .style(colors.border(true)),
+    );
+    f.render_widget(input, chunks[0]);
+    f.set_cursor(
+        chunks[0].x + picker.input.chars().count() as u16 + 1,
+        chunks[0].y + 1,
+    );
+
+    let items: Vec<ListItem> = picker
+        .matches
+        .iter()
+        .map(|(index, indices)| {
+            ListItem::new(Line::from(highlight_search_term(
+                &picker.candidates[*index],
+                indices,
+                colors,
+            )))
+        })
+        .collect();
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("List")
+                .style(colors.border(false)),
+        )
+        .highlight_style(colors.selection())
+        .highlight_symbol(">")
+        .style(colors.text());
+    f.render_stateful_widget(list, chunks[1], &mut picker.state);
+}
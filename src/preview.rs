@@ -0,0 +1,425 @@
+use std::{
+    collections::VecDeque,
+    fs,
+    io::Read,
+    process::{Command, Stdio},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span, Text},
+};
+use syntect::{
+    easy::HighlightLines, highlighting::Theme, parsing::SyntaxSet, util::LinesWithEndings,
+};
+
+use crate::editor::split_template;
+use crate::events::Waker;
+use crate::file_reader_content::read_file_content;
+use crate::theme::reduce_color;
+use crate::utils::format::format_size;
+
+// holding j shouldn't read and highlight every file passed on the way
+const DEBOUNCE: Duration = Duration::from_millis(80);
+const CACHE_SIZE: usize = 32;
+// bigger files, or minified ones with huge lines, are shown as plain text
+const HIGHLIGHT_MAX_BYTES: usize = 512 * 1024;
+const HIGHLIGHT_MAX_LINE_LENGTH: usize = 2000;
+// lines past this are appended without highlighting
+const HIGHLIGHT_MAX_LINES: usize = 2000;
+
+struct PreviewResult {
+    path: String,
+    modified: Option<SystemTime>,
+    limit: u64,
+    text: Text<'static>,
+    // false for the plain text sent ahead of the highlighted version
+    complete: bool,
+    truncated: bool,
+}
+
+// loads text previews on a worker thread. requests are debounced and the
+// last few highlighted files are kept, keyed by path and modification time.
+// with a previewer command configured its output is shown instead
+pub struct PreviewManager {
+    pub external: bool,
+    // of the line saying a preview was truncated, follows the theme
+    pub marker_color: Color,
+    wanted: Option<(String, u64, Instant)>,
+    cache: VecDeque<PreviewResult>,
+    requests: Sender<(String, u64)>,
+    themes: Sender<(Theme, bool)>,
+    commands: Sender<Option<String>>,
+    results: Receiver<PreviewResult>,
+}
+
+impl PreviewManager {
+    pub fn new(
+        syntax_set: SyntaxSet,
+        theme: Theme,
+        command: Option<String>,
+        waker: Waker,
+    ) -> PreviewManager {
+        let external = command.is_some();
+        let (requests, worker_requests) = mpsc::channel::<(String, u64)>();
+        let (worker_results, results) = waker.channel();
+        let (themes, worker_themes) = mpsc::channel::<(Theme, bool)>();
+        let (commands, worker_commands) = mpsc::channel::<Option<String>>();
+
+        thread::spawn(move || {
+            let mut theme = theme;
+            let mut true_color = true;
+            let mut command = command;
+            let mut next = None;
+            loop {
+                let mut request = match next.take() {
+                    Some(request) => request,
+                    None => match worker_requests.recv() {
+                        Ok(request) => request,
+                        Err(_) => return,
+                    },
+                };
+                // only the latest request still matters
+                while let Ok(newer) = worker_requests.try_recv() {
+                    request = newer;
+                }
+                while let Ok(newer) = worker_commands.try_recv() {
+                    command = newer;
+                }
+                let (path, limit) = request;
+                let modified = modified(&path);
+
+                // a previewer that fails falls back to the built in preview
+                if let Some(Ok(text)) = command
+                    .as_ref()
+                    .map(|command| run_external(command, &path, limit))
+                {
+                    let result = PreviewResult {
+                        path: path.clone(),
+                        modified,
+                        limit,
+                        text,
+                        complete: true,
+                        truncated: false,
+                    };
+                    if worker_results.send(result).is_err() {
+                        return;
+                    }
+                    continue;
+                }
+                let (content, truncated) = read_file_content(path.clone(), limit);
+                let plain_only = !should_highlight(&content);
+
+                let send = |text: Text<'static>, complete: bool| {
+                    let result = PreviewResult {
+                        path: path.clone(),
+                        modified,
+                        limit,
+                        text,
+                        complete,
+                        truncated,
+                    };
+                    worker_results.send(result).is_ok()
+                };
+
+                // plain text first so the preview shows up while highlighting
+                if !send(plain(&content), plain_only) {
+                    return;
+                }
+                if plain_only {
+                    continue;
+                }
+                // the selection moved on, highlighting this file is wasted work
+                if let Ok(newer) = worker_requests.try_recv() {
+                    next = Some(newer);
+                    continue;
+                }
+                while let Ok(newer) = worker_themes.try_recv() {
+                    (theme, true_color) = newer;
+                }
+                let text = highlight(&content, &path, &syntax_set, &theme, true_color);
+                if !send(text, true) {
+                    return;
+                }
+            }
+        });
+
+        PreviewManager {
+            external,
+            marker_color: Color::Reset,
+            wanted: None,
+            cache: VecDeque::new(),
+            requests,
+            themes,
+            commands,
+            results,
+        }
+    }
+
+    // previews highlighted from now on use `theme`, reduced to 256 colors
+    // without `true_color`. the cached ones are dropped so they get
+    // highlighted again
+    pub fn set_theme(&mut self, theme: Theme, true_color: bool) {
+        self.cache.clear();
+        let _ = self.themes.send((theme, true_color));
+    }
+
+    // previews are generated by `command` from now on, or by the built in
+    // preview without one
+    pub fn set_command(&mut self, command: Option<String>) {
+        self.external = command.is_some();
+        self.cache.clear();
+        let _ = self.commands.send(command);
+    }
+
+    // a cached preview is returned right away, anything else is loaded once
+    // the selection stayed on `path` for a moment
+    pub fn request(&mut self, path: &str, limit: u64) -> Option<Text<'static>> {
+        let modified = modified(path);
+        if let Some(cached) = self.cache.iter().find(|cached| {
+            cached.path == path && cached.modified == modified && cached.limit == limit
+        }) {
+            self.wanted = None;
+            return Some(self.with_marker(cached));
+        }
+
+        self.wanted = Some((path.to_string(), limit, Instant::now()));
+        None
+    }
+
+    // sends the debounced request and returns the preview of `selected`
+    // once the worker finished it
+    pub fn poll(&mut self, selected: &str) -> Option<Text<'static>> {
+        if let Some((path, limit, requested)) = &self.wanted {
+            if requested.elapsed() >= DEBOUNCE {
+                let _ = self.requests.send((path.clone(), *limit));
+                self.wanted = None;
+            }
+        }
+
+        let mut ready = None;
+        while let Ok(result) = self.results.try_recv() {
+            if result.path == selected {
+                ready = Some(self.with_marker(&result));
+            }
+            if !result.complete {
+                continue;
+            }
+            self.cache.retain(|cached| cached.path != result.path);
+            self.cache.push_back(result);
+            if self.cache.len() > CACHE_SIZE {
+                self.cache.pop_front();
+            }
+        }
+        ready
+    }
+
+    // when the debounced request goes out, the caller polls again then
+    pub fn deadline(&self) -> Option<Instant> {
+        self.wanted
+            .as_ref()
+            .map(|(_, _, requested)| *requested + DEBOUNCE)
+    }
+
+    fn with_marker(&self, result: &PreviewResult) -> Text<'static> {
+        let mut text = result.text.clone();
+        if result.truncated {
+            text.lines
+                .push(truncated_line(result.limit, self.marker_color));
+        }
+        text
+    }
+}
+
+// runs the previewer command with `{file}` replaced by the path and reads
+// up to `limit` bytes of its output
+fn run_external(template: &str, path: &str, limit: u64) -> anyhow::Result<Text<'static>> {
+    let mut args: Vec<String> = split_template(template)
+        .iter()
+        .map(|arg| arg.replace("{file}", path))
+        .collect();
+    if args.is_empty() {
+        anyhow::bail!("the previewer command is empty");
+    }
+    let program = args.remove(0);
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    let mut output = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        stdout.take(limit).read_to_end(&mut output)?;
+    }
+    // the previewer may still be writing a huge file, it isn't needed anymore
+    let _ = child.kill();
+    let status = child.wait()?;
+    if output.is_empty() && !status.success() {
+        anyhow::bail!("the previewer exited with {}", status);
+    }
+    Ok(ansi_text(&String::from_utf8_lossy(&output)))
+}
+
+// colored terminal output to styled text, only SGR sequences are
+// understood, every other escape sequence is dropped
+pub fn ansi_text(output: &str) -> Text<'static> {
+    let mut style = Style::default();
+    let mut lines = Vec::new();
+    for line in output.lines() {
+        let mut spans = Vec::new();
+        let mut current = String::new();
+        let mut chars = line.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            if c != '\x1b' {
+                current.push(c);
+                continue;
+            }
+            if chars.peek() != Some(&'[') {
+                chars.next();
+                continue;
+            }
+            chars.next();
+            let mut params = String::new();
+            let mut command = None;
+            for c in chars.by_ref() {
+                if ('\x40'..='\x7e').contains(&c) {
+                    command = Some(c);
+                    break;
+                }
+                params.push(c);
+            }
+            if command != Some('m') {
+                continue;
+            }
+            if !current.is_empty() {
+                spans.push(Span::styled(std::mem::take(&mut current), style));
+            }
+            style = apply_sgr(style, &params);
+        }
+        if !current.is_empty() {
+            spans.push(Span::styled(current, style));
+        }
+        lines.push(Line::from(spans));
+    }
+    Text::from(lines)
+}
+
+fn apply_sgr(mut style: Style, params: &str) -> Style {
+    let codes: Vec<u16> = params
+        .split(';')
+        .map(|code| code.parse().unwrap_or(0))
+        .collect();
+    let mut codes = codes.iter().copied();
+
+    while let Some(code) = codes.next() {
+        style = match code {
+            0 => Style::default(),
+            1 => style.add_modifier(Modifier::BOLD),
+            2 => style.add_modifier(Modifier::DIM),
+            3 => style.add_modifier(Modifier::ITALIC),
+            4 => style.add_modifier(Modifier::UNDERLINED),
+            7 => style.add_modifier(Modifier::REVERSED),
+            22 => style.remove_modifier(Modifier::BOLD | Modifier::DIM),
+            23 => style.remove_modifier(Modifier::ITALIC),
+            24 => style.remove_modifier(Modifier::UNDERLINED),
+            27 => style.remove_modifier(Modifier::REVERSED),
+            30..=37 => style.fg(Color::Indexed((code - 30) as u8)),
+            90..=97 => style.fg(Color::Indexed((code - 90 + 8) as u8)),
+            40..=47 => style.bg(Color::Indexed((code - 40) as u8)),
+            100..=107 => style.bg(Color::Indexed((code - 100 + 8) as u8)),
+            39 => style.fg(Color::Reset),
+            49 => style.bg(Color::Reset),
+            38 | 48 => {
+                // 5;n is a 256 color index, 2;r;g;b a true color
+                let color = match codes.next() {
+                    Some(5) => codes.next().map(|index| Color::Indexed(index as u8)),
+                    Some(2) => match (codes.next(), codes.next(), codes.next()) {
+                        (Some(r), Some(g), Some(b)) => Some(Color::Rgb(r as u8, g as u8, b as u8)),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                match (code, color) {
+                    (38, Some(color)) => style.fg(color),
+                    (_, Some(color)) => style.bg(color),
+                    _ => style,
+                }
+            }
+            _ => style,
+        };
+    }
+    style
+}
+
+fn should_highlight(content: &str) -> bool {
+    content.len() <= HIGHLIGHT_MAX_BYTES
+        && content
+            .lines()
+            .all(|line| line.len() <= HIGHLIGHT_MAX_LINE_LENGTH)
+}
+
+fn truncated_line(limit: u64, color: Color) -> Line<'static> {
+    Line::from(Span::styled(
+        format!(
+            "-- truncated at {}, press v to load more --",
+            format_size(limit)
+        ),
+        Style::default().fg(color),
+    ))
+}
+
+fn plain(content: &str) -> Text<'static> {
+    Text::from(
+        content
+            .lines()
+            .map(|line| Line::from(line.to_string()))
+            .collect::<Vec<_>>(),
+    )
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+pub fn highlight(
+    content: &str,
+    path: &str,
+    syntax_set: &SyntaxSet,
+    theme: &Theme,
+    true_color: bool,
+) -> Text<'static> {
+    let syntax = std::path::Path::new(path)
+        .extension()
+        .and_then(|extension| syntax_set.find_syntax_by_extension(&extension.to_string_lossy()))
+        .unwrap_or_else(|| syntax_set.find_syntax_plain_text());
+
+    let mut highlighter = HighlightLines::new(syntax, theme);
+    let mut lines = Vec::new();
+    for (index, line) in LinesWithEndings::from(content).enumerate() {
+        if index >= HIGHLIGHT_MAX_LINES {
+            lines.push(Line::from(line.trim_end_matches('\n').to_string()));
+            continue;
+        }
+        let spans: Vec<Span<'static>> = match highlighter.highlight_line(line, syntax_set) {
+            Ok(ranges) => ranges
+                .into_iter()
+                .map(|(style, text)| {
+                    let fg = style.foreground;
+                    Span::styled(
+                        text.trim_end_matches('\n').to_string(),
+                        Style::default().fg(reduce_color(Color::Rgb(fg.r, fg.g, fg.b), true_color)),
+                    )
+                })
+                .collect(),
+            Err(_) => vec![Span::raw(line.trim_end_matches('\n').to_string())],
+        };
+        lines.push(Line::from(spans));
+    }
+    Text::from(lines)
+}
@@ -0,0 +1,78 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use anyhow::{anyhow, Context};
+use serde::Deserialize;
+
+use crate::configuration::{cache_dir, Configuration};
+use crate::keymap::KeySpec;
+use crate::operations::transfer::expand_home;
+
+// a named set of overrides, `ff --profile work` reads
+// profiles/work.toml in the config directory and applies it over settings.json:
+//
+//   start_path = "~/work"
+//   ignore_directories = ["node_modules", "target"]
+//   syntax_theme = "InspiredGitHub"
+//   editor = "vscode"
+//
+//   [keybindings]
+//   search = "/"
+#[derive(Deserialize, Default, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub start_path: Option<String>,
+    pub ignore_directories: Option<Vec<String>>,
+    pub syntax_theme: Option<String>,
+    // selected like `ff --editor <name>` unless one is passed explicitly
+    pub editor: Option<String>,
+    #[serde(default)]
+    pub keybindings: HashMap<String, KeySpec>,
+}
+
+impl Profile {
+    pub fn load(config: &Configuration, name: &str) -> anyhow::Result<Profile> {
+        if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+            return Err(anyhow!("invalid profile name: {}", name));
+        }
+        let path = Path::new(&config.profiles_path())
+            .join(format!("{}.toml", name))
+            .display()
+            .to_string();
+        let content =
+            fs::read_to_string(&path).with_context(|| format!("unable to read {}", path))?;
+        toml::from_str(&content).with_context(|| format!("invalid profile {}", path))
+    }
+
+    // profile values win over settings.json. every profile gets its own
+    // directory cache so switching doesn't rebuild the shared one
+    pub fn apply(&self, name: &str, config: &mut Configuration) {
+        if let Some(start_path) = &self.start_path {
+            config.start_path = expand_home(start_path);
+        }
+        if let Some(ignore_directories) = &self.ignore_directories {
+            config.ignore_directories = ignore_directories.clone();
+        }
+        if let Some(syntax_theme) = &self.syntax_theme {
+            config.syntax_theme = syntax_theme.clone();
+        }
+        config.keybindings.extend(self.keybindings.clone());
+        config.cache_directory = cache_dir()
+            .join(format!("cache_directory-{}.bin", name))
+            .display()
+            .to_string();
+    }
+}
+
+// removes `--profile <name>` from the arguments so the rest parse as before
+pub fn take_profile_argument(args: &mut Vec<String>) -> anyhow::Result<Option<String>> {
+    let index = match args.iter().position(|arg| arg == "--profile") {
+        Some(index) => index,
+        None => return Ok(None),
+    };
+    if index + 1 >= args.len() {
+        return Err(anyhow!("--profile needs a name"));
+    }
+    let name = args.remove(index + 1);
+    args.remove(index);
+    Ok(Some(name))
+}
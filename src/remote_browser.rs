@@ -0,0 +1,385 @@
+use std::{
+    env,
+    io::{self, Stderr},
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout},
+    style::Style,
+    widgets::{Block, Borders, Gauge, List, ListItem, ListState, Paragraph},
+    Frame, Terminal,
+};
+
+use crate::theme::ThemeColors;
+use crate::utils::format::format_size;
+use crate::vfs::{self, Vfs, VfsEntry};
+
+// objects bigger than this aren't previewed, of the others only the start
+const PREVIEW_MAX_SIZE: u64 = 1024 * 1024;
+const PREVIEW_BYTES: u64 = 64 * 1024;
+
+enum TransferMessage {
+    Progress(u64, u64),
+    Done(String),
+}
+
+struct Browser {
+    vfs: Arc<dyn Vfs>,
+    dir: String,
+    entries: Vec<VfsEntry>,
+    state: ListState,
+    preview: Vec<String>,
+    previews: Option<Receiver<(String, Vec<String>)>>,
+    status: String,
+    // local path typed in the upload prompt
+    upload_input: Option<String>,
+    transfer: Option<(String, Receiver<TransferMessage>)>,
+    progress: (u64, u64),
+    colors: ThemeColors,
+}
+
+// a small browser for locations that aren't on the local disk, like
+// `ff s3://bucket/prefix`. "d" downloads the selected object to the current
+// directory and "u" uploads a local file into the listed one
+pub fn run(url: &str, colors: ThemeColors) -> anyhow::Result<()> {
+    let vfs: Arc<dyn Vfs> = Arc::from(vfs::open(url)?);
+    let dir = if url.ends_with('/') {
+        url.to_string()
+    } else {
+        format!("{}/", url)
+    };
+    let entries = vfs.list(&dir)?;
+    let mut browser = Browser {
+        vfs,
+        dir,
+        entries,
+        state: ListState::default(),
+        preview: Vec::new(),
+        previews: None,
+        status: String::new(),
+        upload_input: None,
+        transfer: None,
+        progress: (0, 0),
+        colors,
+    };
+    browser.select(0);
+
+    enable_raw_mode()?;
+    let mut stderr = io::stderr();
+    execute!(stderr, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stderr))?;
+
+    let result = browse(&mut terminal, &mut browser);
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    result
+}
+
+fn browse(
+    terminal: &mut Terminal<CrosstermBackend<Stderr>>,
+    browser: &mut Browser,
+) -> anyhow::Result<()> {
+    loop {
+        browser.poll();
+        terminal.draw(|f| draw(f, browser))?;
+
+        if !event::poll(Duration::from_millis(100))? {
+            continue;
+        }
+        let key = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key,
+            _ => continue,
+        };
+
+        if let Some(input) = browser.upload_input.as_mut() {
+            match key.code {
+                KeyCode::Esc => browser.upload_input = None,
+                KeyCode::Char(c) => input.push(c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Enter => {
+                    let source = PathBuf::from(input.trim());
+                    browser.upload_input = None;
+                    browser.upload(source);
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Char('j') | KeyCode::Down => {
+                let next = browser.state.selected().map_or(0, |i| i + 1);
+                browser.select(next.min(browser.entries.len().saturating_sub(1)));
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                let previous = browser.state.selected().unwrap_or(0).saturating_sub(1);
+                browser.select(previous);
+            }
+            KeyCode::Char('l') | KeyCode::Right | KeyCode::Enter => {
+                if let Some(entry) = browser.selected().filter(|entry| entry.is_dir) {
+                    let path = entry.path.clone();
+                    browser.open(path);
+                }
+            }
+            KeyCode::Char('h') | KeyCode::Left | KeyCode::Backspace => {
+                if let Some(parent) = vfs::parent(&browser.dir) {
+                    browser.open(parent);
+                }
+            }
+            KeyCode::Char('r') => {
+                let dir = browser.dir.clone();
+                browser.open(dir);
+            }
+            KeyCode::Char('d') => browser.download(),
+            KeyCode::Char('u') if browser.transfer.is_none() => {
+                browser.upload_input = Some(String::new());
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Browser {
+    fn selected(&self) -> Option<&VfsEntry> {
+        self.state
+            .selected()
+            .and_then(|index| self.entries.get(index))
+    }
+
+    fn open(&mut self, dir: String) {
+        match self.vfs.list(&dir) {
+            Ok(entries) => {
+                self.dir = dir;
+                self.entries = entries;
+                self.status.clear();
+                self.select(0);
+            }
+            Err(e) => self.status = format!("Unable to list {}: {}", dir, e),
+        }
+    }
+
+    // previews are read on a thread, the result for an entry that is no
+    // longer selected is dropped
+    fn select(&mut self, index: usize) {
+        self.state.select(if self.entries.is_empty() {
+            None
+        } else {
+            Some(index)
+        });
+        self.preview.clear();
+        self.previews = None;
+
+        let entry = match self.selected() {
+            Some(entry) if !entry.is_dir => entry.clone(),
+            _ => return,
+        };
+        if entry.size > PREVIEW_MAX_SIZE {
+            self.preview = vec![format!("{}, too big to preview", format_size(entry.size))];
+            return;
+        }
+        self.preview = vec![String::from("Loading...")];
+        let (sender, receiver) = mpsc::channel();
+        self.previews = Some(receiver);
+        let vfs = self.vfs.clone();
+        thread::spawn(move || {
+            let lines = match vfs.read(&entry.path, PREVIEW_BYTES) {
+                Ok(content) if content.contains(&0) => vec![String::from("Binary object")],
+                Ok(content) => String::from_utf8_lossy(&content)
+                    .lines()
+                    .map(|line| line.to_string())
+                    .collect(),
+                Err(e) => vec![format!("Unable to read {}: {}", entry.path, e)],
+            };
+            let _ = sender.send((entry.path, lines));
+        });
+    }
+
+    fn poll(&mut self) {
+        if let Some(receiver) = &self.previews {
+            if let Ok((path, lines)) = receiver.try_recv() {
+                if self.selected().map(|entry| &entry.path) == Some(&path) {
+                    self.preview = lines;
+                }
+                self.previews = None;
+            }
+        }
+
+        let mut finished = false;
+        if let Some((_, receiver)) = &self.transfer {
+            while let Ok(message) = receiver.try_recv() {
+                match message {
+                    TransferMessage::Progress(done, total) => self.progress = (done, total),
+                    TransferMessage::Done(status) => {
+                        self.status = status;
+                        finished = true;
+                    }
+                }
+            }
+        }
+        if finished {
+            self.transfer = None;
+            let dir = self.dir.clone();
+            let selected = self.state.selected().unwrap_or(0);
+            if let Ok(entries) = self.vfs.list(&dir) {
+                self.entries = entries;
+                self.select(selected.min(self.entries.len().saturating_sub(1)));
+            }
+        }
+    }
+
+    fn download(&mut self) {
+        if self.transfer.is_some() {
+            return;
+        }
+        let entry = match self.selected() {
+            Some(entry) if !entry.is_dir => entry.clone(),
+            _ => return,
+        };
+        let dest = env::current_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .join(&entry.name);
+        if dest.exists() {
+            self.status = format!("{} already exists", dest.display());
+            return;
+        }
+        let vfs = self.vfs.clone();
+        self.start_transfer(format!("Downloading {}", entry.name), move |progress| {
+            vfs.download(&entry.path, &dest, progress)
+                .map(|_| format!("Downloaded {}", dest.display()))
+        });
+    }
+
+    fn upload(&mut self, source: PathBuf) {
+        let name = match source.file_name() {
+            Some(name) if source.is_file() => name.to_string_lossy().to_string(),
+            _ => {
+                self.status = format!("{} is not a file", source.display());
+                return;
+            }
+        };
+        let path = format!("{}{}", self.dir, name);
+        let vfs = self.vfs.clone();
+        self.start_transfer(format!("Uploading {}", name), move |progress| {
+            vfs.upload(Path::new(&source), &path, progress)
+                .map(|_| format!("Uploaded {}", path))
+        });
+    }
+
+    fn start_transfer(
+        &mut self,
+        label: String,
+        work: impl FnOnce(&dyn Fn(u64, u64)) -> anyhow::Result<String> + Send + 'static,
+    ) {
+        let (sender, receiver) = mpsc::channel();
+        self.transfer = Some((label, receiver));
+        self.progress = (0, 0);
+        thread::spawn(move || {
+            let progress = |done, total| {
+                let _ = sender.send(TransferMessage::Progress(done, total));
+            };
+            let status = match work(&progress) {
+                Ok(status) => status,
+                Err(e) => format!("Transfer failed: {}", e),
+            };
+            let _ = sender.send(TransferMessage::Done(status));
+        });
+    }
+}
+
+fn draw(f: &mut Frame, browser: &mut Browser) {
+    let colors = browser.colors;
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(3)])
+        .split(f.size());
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(rows[0]);
+
+    let items: Vec<ListItem> = browser
+        .entries
+        .iter()
+        .map(|entry| {
+            if entry.is_dir {
+                ListItem::new(format!("{}/", entry.name)).style(Style::default().fg(colors.accent))
+            } else {
+                ListItem::new(format!("{} ({})", entry.name, format_size(entry.size)))
+            }
+        })
+        .collect();
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(browser.dir.clone())
+                .style(colors.border(true)),
+        )
+        .highlight_style(colors.selection())
+        .highlight_symbol(">")
+        .style(colors.text());
+    f.render_stateful_widget(list, columns[0], &mut browser.state);
+
+    let preview = Paragraph::new(browser.preview.join("\n"))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Preview")
+                .style(colors.border(false)),
+        )
+        .style(Style::default().fg(colors.dim));
+    f.render_widget(preview, columns[1]);
+
+    if let Some(input) = &browser.upload_input {
+        let prompt = Paragraph::new(input.as_str())
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Upload local file: (enter) confirm, (esc) cancel"),
+            )
+            .style(Style::default().fg(colors.success));
+        f.render_widget(prompt, rows[1]);
+        f.set_cursor(rows[1].x + input.chars().count() as u16 + 1, rows[1].y + 1);
+    } else if let Some((label, _)) = &browser.transfer {
+        let (done, total) = browser.progress;
+        let ratio = if total == 0 {
+            0.0
+        } else {
+            (done as f64 / total as f64).min(1.0)
+        };
+        let gauge = Gauge::default()
+            .block(Block::default().borders(Borders::ALL).title(label.clone()))
+            .gauge_style(Style::default().fg(colors.success).bg(colors.selection_bg))
+            .ratio(ratio)
+            .label(format!("{} / {}", format_size(done), format_size(total)));
+        f.render_widget(gauge, rows[1]);
+    } else {
+        let status = if browser.status.is_empty() {
+            String::from("l open, h back, d download, u upload, r reload, q quit")
+        } else {
+            browser.status.clone()
+        };
+        let status = Paragraph::new(status)
+            .block(Block::default().borders(Borders::ALL))
+            .style(colors.text());
+        f.render_widget(status, rows[1]);
+    }
+}
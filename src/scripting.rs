@@ -0,0 +1,179 @@
+use crossterm::event::KeyEvent;
+
+#[cfg(feature = "scripting")]
+use std::{cell::RefCell, path::Path, rc::Rc};
+
+#[cfg(feature = "scripting")]
+use rhai::{Array, Dynamic, Engine, Module, Scope, AST};
+
+use crate::keymap::KeyBinding;
+
+// what a script asked for, applied by the main loop after the call returns
+#[cfg_attr(not(feature = "scripting"), allow(dead_code))]
+#[derive(Debug, Clone)]
+pub enum ScriptEffect {
+    Cd(String),
+    Spawn(String),
+    Status(String),
+}
+
+// the selection a script call sees through ff::selected() and friends
+#[cfg_attr(not(feature = "scripting"), allow(dead_code))]
+#[derive(Debug, Clone, Default)]
+pub struct ScriptContext {
+    pub selected: String,
+    pub marked: Vec<String>,
+    pub cwd: String,
+}
+
+#[cfg(feature = "scripting")]
+#[derive(Default)]
+struct ScriptState {
+    context: ScriptContext,
+    effects: Vec<ScriptEffect>,
+    bindings: Vec<(String, String)>,
+}
+
+// ~/.config/ff/init.rhai, e.g.
+//   fn projects() { ff::cd("~/projects"); }
+//   ff::bind("g", "projects");
+pub struct ScriptEngine {
+    bindings: Vec<(KeyBinding, String)>,
+    #[cfg(feature = "scripting")]
+    engine: Engine,
+    #[cfg(feature = "scripting")]
+    ast: AST,
+    #[cfg(feature = "scripting")]
+    state: Rc<RefCell<ScriptState>>,
+}
+
+impl ScriptEngine {
+    pub fn binding(&self, key: &KeyEvent) -> Option<String> {
+        let pressed = KeyBinding::from_event(key);
+        self.bindings
+            .iter()
+            .find(|(binding, _)| *binding == pressed)
+            .map(|(_, function)| function.clone())
+    }
+
+    #[cfg(not(feature = "scripting"))]
+    pub fn load(_path: &str) -> anyhow::Result<ScriptEngine> {
+        Ok(ScriptEngine {
+            bindings: Vec::new(),
+        })
+    }
+
+    #[cfg(not(feature = "scripting"))]
+    pub fn call(
+        &mut self,
+        function: &str,
+        _context: ScriptContext,
+    ) -> anyhow::Result<Vec<ScriptEffect>> {
+        Err(anyhow::anyhow!(
+            "cannot run {}, build with `--features scripting` to enable scripts",
+            function
+        ))
+    }
+
+    // a missing script is not an error, there are just no bindings
+    #[cfg(feature = "scripting")]
+    pub fn load(path: &str) -> anyhow::Result<ScriptEngine> {
+        let state = Rc::new(RefCell::new(ScriptState::default()));
+        let mut engine = Engine::new();
+        engine.register_static_module("ff", Rc::new(ff_module(&state)));
+
+        let ast = if Path::new(path).exists() {
+            let ast = engine
+                .compile_file(path.into())
+                .map_err(|e| anyhow::anyhow!("{}: {}", path, e))?;
+            // top level statements run once, that's where ff::bind is called
+            engine
+                .run_ast_with_scope(&mut Scope::new(), &ast)
+                .map_err(|e| anyhow::anyhow!("{}: {}", path, e))?;
+            ast
+        } else {
+            AST::empty()
+        };
+
+        let mut bindings = Vec::new();
+        for (key, function) in state.borrow_mut().bindings.drain(..) {
+            let binding =
+                KeyBinding::parse(&key).map_err(|e| anyhow::anyhow!("{}: {}", path, e))?;
+            bindings.push((binding, function));
+        }
+
+        Ok(ScriptEngine {
+            bindings,
+            engine,
+            ast,
+            state,
+        })
+    }
+
+    #[cfg(feature = "scripting")]
+    pub fn call(
+        &mut self,
+        function: &str,
+        context: ScriptContext,
+    ) -> anyhow::Result<Vec<ScriptEffect>> {
+        self.state.borrow_mut().context = context;
+        // whatever the function returns is ignored
+        let _: Dynamic = self
+            .engine
+            .call_fn(&mut Scope::new(), &self.ast, function, ())
+            .map_err(|e| anyhow::anyhow!("{}: {}", function, e))?;
+        Ok(self.state.borrow_mut().effects.drain(..).collect())
+    }
+}
+
+#[cfg(feature = "scripting")]
+fn ff_module(state: &Rc<RefCell<ScriptState>>) -> Module {
+    let mut module = Module::new();
+
+    let s = state.clone();
+    module.set_native_fn("selected", move || Ok(s.borrow().context.selected.clone()));
+    let s = state.clone();
+    module.set_native_fn("marked", move || {
+        let marked: Array = s
+            .borrow()
+            .context
+            .marked
+            .iter()
+            .map(|path| Dynamic::from(path.clone()))
+            .collect();
+        Ok(marked)
+    });
+    let s = state.clone();
+    module.set_native_fn("cwd", move || Ok(s.borrow().context.cwd.clone()));
+
+    let s = state.clone();
+    module.set_native_fn("cd", move |path: &str| {
+        s.borrow_mut()
+            .effects
+            .push(ScriptEffect::Cd(path.to_string()));
+        Ok(())
+    });
+    let s = state.clone();
+    module.set_native_fn("spawn", move |command: &str| {
+        s.borrow_mut()
+            .effects
+            .push(ScriptEffect::Spawn(command.to_string()));
+        Ok(())
+    });
+    let s = state.clone();
+    module.set_native_fn("status", move |message: &str| {
+        s.borrow_mut()
+            .effects
+            .push(ScriptEffect::Status(message.to_string()));
+        Ok(())
+    });
+    let s = state.clone();
+    module.set_native_fn("bind", move |key: &str, function: &str| {
+        s.borrow_mut()
+            .bindings
+            .push((key.to_string(), function.to_string()));
+        Ok(())
+    });
+
+    module
+}
@@ -0,0 +1,125 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter},
+};
+
+use serde::{Deserialize, Serialize};
+
+const MAX_ENTRIES: usize = 100;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchKind {
+    // the search prompt over the directory cache
+    Global,
+    // the filter of the current listing
+    Local,
+}
+
+impl SearchKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SearchKind::Global => "search",
+            SearchKind::Local => "filter",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SavedSearch {
+    pub name: String,
+    pub kind: SearchKind,
+    pub query: String,
+}
+
+// past queries of the search and filter prompts, newest last, and the
+// searches saved under a name
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct SearchHistory {
+    #[serde(default)]
+    global: Vec<String>,
+    #[serde(default)]
+    local: Vec<String>,
+    #[serde(default)]
+    pub saved: Vec<SavedSearch>,
+    #[serde(skip)]
+    path: String,
+    // entry shown while stepping through the history with up and down
+    #[serde(skip)]
+    browsing: Option<usize>,
+}
+
+impl SearchHistory {
+    // a missing or unreadable history starts empty
+    pub fn load(path: &str) -> SearchHistory {
+        let mut history: Self = File::open(path)
+            .ok()
+            .and_then(|file| serde_json::from_reader(BufReader::new(file)).ok())
+            .unwrap_or_default();
+        history.path = path.to_string();
+        history
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let writer = BufWriter::new(File::create(&self.path)?);
+        serde_json::to_writer(writer, self)?;
+        Ok(())
+    }
+
+    fn entries(&mut self, kind: SearchKind) -> &mut Vec<String> {
+        match kind {
+            SearchKind::Global => &mut self.global,
+            SearchKind::Local => &mut self.local,
+        }
+    }
+
+    pub fn push(&mut self, kind: SearchKind, query: &str) {
+        self.browsing = None;
+        let query = query.trim();
+        if query.is_empty() {
+            return;
+        }
+        let entries = self.entries(kind);
+        entries.retain(|entry| entry != query);
+        entries.push(query.to_string());
+        if entries.len() > MAX_ENTRIES {
+            entries.remove(0);
+        }
+    }
+
+    // the query before the one shown, None at the oldest
+    pub fn previous(&mut self, kind: SearchKind) -> Option<String> {
+        let browsing = self.browsing;
+        let entries = self.entries(kind);
+        let index = match browsing {
+            Some(0) => return None,
+            Some(index) => index - 1,
+            None => entries.len().checked_sub(1)?,
+        };
+        let query = entries.get(index).cloned();
+        self.browsing = Some(index);
+        query
+    }
+
+    // the query after the one shown, past the newest the prompt is empty again
+    pub fn next(&mut self, kind: SearchKind) -> Option<String> {
+        let index = self.browsing? + 1;
+        let query = self.entries(kind).get(index).cloned();
+        self.browsing = query.as_ref().map(|_| index);
+        Some(query.unwrap_or_default())
+    }
+
+    pub fn stop_browsing(&mut self) {
+        self.browsing = None;
+    }
+
+    // a search saved under an existing name replaces it
+    pub fn save_search(&mut self, name: &str, kind: SearchKind, query: &str) {
+        self.saved.retain(|saved| saved.name != name);
+        self.saved.push(SavedSearch {
+            name: name.to_string(),
+            kind,
+            query: query.to_string(),
+        });
+    }
+}
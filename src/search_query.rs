@@ -0,0 +1,277 @@
+use regex::{Regex, RegexBuilder};
+
+#[derive(Debug, Clone)]
+enum Term {
+    // case insensitive substring of the path
+    Plain(String),
+    // "quoted", case sensitive substring of the path
+    Exact(String),
+    // re:pattern, smart case
+    Regex(Regex),
+    // ^start, end$ or ^whole$, matched against the lowercase file name
+    Name {
+        text: String,
+        start: bool,
+        end: bool,
+    },
+}
+
+// whitespace separated terms that all have to match, `!` in front of a
+// term excludes what it matches
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    terms: Vec<(bool, Term)>,
+}
+
+// splits on whitespace outside of double quotes, the quotes are kept
+fn tokens(query: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in query.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                current.push(c);
+            }
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+// char index of `needle` in `haystack`, both compared lowercase
+fn find_lowercase(haystack: &[char], needle: &str) -> Option<usize> {
+    let needle: Vec<char> = needle.chars().flat_map(char::to_lowercase).collect();
+    if needle.is_empty() || needle.len() > haystack.len() {
+        return None;
+    }
+    haystack.windows(needle.len()).position(|window| {
+        window
+            .iter()
+            .zip(needle.iter())
+            .all(|(a, b)| a.to_lowercase().eq(std::iter::once(*b)))
+    })
+}
+
+fn unquote(text: &str) -> Option<&str> {
+    text.strip_prefix('"')?.strip_suffix('"')
+}
+
+fn parse_term(token: &str) -> Result<Term, String> {
+    if let Some(pattern) = token.strip_prefix("re:") {
+        let pattern = unquote(pattern).unwrap_or(pattern);
+        return RegexBuilder::new(pattern)
+            .case_insensitive(!pattern.chars().any(char::is_uppercase))
+            .build()
+            .map(Term::Regex)
+            .map_err(|e| format!("invalid regex: {}", e));
+    }
+    if let Some(exact) = unquote(token) {
+        return Ok(Term::Exact(exact.to_string()));
+    }
+
+    let start = token.starts_with('^');
+    let end = token.len() > 1 && token.ends_with('$');
+    let text = token
+        .trim_start_matches('^')
+        .trim_end_matches('$')
+        .to_lowercase();
+    if start || end {
+        Ok(Term::Name { text, start, end })
+    } else {
+        Ok(Term::Plain(text))
+    }
+}
+
+impl SearchQuery {
+    pub fn parse(query: &str) -> Result<SearchQuery, String> {
+        let mut terms = Vec::new();
+        for token in tokens(query) {
+            let (negated, token) = match token.strip_prefix('!') {
+                Some(rest) => (true, rest),
+                None => (false, token.as_str()),
+            };
+            if token.is_empty() {
+                continue;
+            }
+            terms.push((negated, parse_term(token)?));
+        }
+        Ok(SearchQuery { terms })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    // lowercase text every match contains, narrows the candidates and ranks
+    // the results
+    pub fn required_text(&self) -> Option<String> {
+        self.terms
+            .iter()
+            .filter(|(negated, _)| !negated)
+            .filter_map(|(_, term)| match term {
+                Term::Plain(text) => Some(text.clone()),
+                Term::Exact(text) => Some(text.to_lowercase()),
+                Term::Name { text, .. } => Some(text.clone()),
+                Term::Regex(_) => None,
+            })
+            .max_by_key(|text| text.len())
+    }
+
+    // char indices of `path` the positive terms matched, for highlighting
+    pub fn match_indices(&self, path: &str) -> Vec<usize> {
+        let chars: Vec<char> = path.chars().collect();
+        let name_start = chars
+            .iter()
+            .rposition(|c| std::path::is_separator(*c))
+            .map_or(0, |index| index + 1);
+        let mut indices = Vec::new();
+
+        for (_, term) in self.terms.iter().filter(|(negated, _)| !negated) {
+            let range = match term {
+                Term::Plain(text) => {
+                    find_lowercase(&chars, text).map(|start| start..start + text.chars().count())
+                }
+                Term::Exact(text) => path.find(text.as_str()).map(|start| {
+                    let start = path[..start].chars().count();
+                    start..start + text.chars().count()
+                }),
+                Term::Regex(regex) => regex.find(path).map(|found| {
+                    path[..found.start()].chars().count()..path[..found.end()].chars().count()
+                }),
+                Term::Name { text, start, .. } => {
+                    let length = text.chars().count();
+                    let name_length = chars.len() - name_start;
+                    if *start {
+                        Some(name_start..name_start + length.min(name_length))
+                    } else {
+                        Some(chars.len() - length.min(name_length)..chars.len())
+                    }
+                }
+            };
+            indices.extend(range.into_iter().flatten());
+        }
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+    }
+
+    pub fn matches(&self, path: &str) -> bool {
+        let lower = path.to_lowercase();
+        let name = lower
+            .rsplit(std::path::is_separator)
+            .next()
+            .unwrap_or(&lower);
+        self.terms.iter().all(|(negated, term)| {
+            let matched = match term {
+                Term::Plain(text) => lower.contains(text.as_str()),
+                Term::Exact(text) => path.contains(text.as_str()),
+                Term::Regex(regex) => regex.is_match(path),
+                Term::Name { text, start, end } => match (start, end) {
+                    (true, true) => name == text,
+                    (true, false) => name.starts_with(text.as_str()),
+                    _ => name.ends_with(text.as_str()),
+                },
+            };
+            matched != *negated
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(text: &str) -> SearchQuery {
+        SearchQuery::parse(text).unwrap()
+    }
+
+    fn matching<'a>(text: &str, paths: &[&'a str]) -> Vec<&'a str> {
+        let query = query(text);
+        paths
+            .iter()
+            .copied()
+            .filter(|path| query.matches(path))
+            .collect()
+    }
+
+    #[test]
+    fn quotes_keep_whitespace_in_a_token() {
+        assert_eq!(
+            tokens(r#"  src "read me"  !"old notes" "#),
+            vec!["src", r#""read me""#, r#"!"old notes""#]
+        );
+        assert!(query("  ").is_empty());
+        assert!(query("! !").is_empty());
+    }
+
+    #[test]
+    fn plain_terms_ignore_case_and_quoted_ones_do_not() {
+        let paths = ["/src/ReadMe.md", "/src/readme.md"];
+        assert_eq!(matching("README", &paths), paths);
+        assert_eq!(matching(r#""ReadMe""#, &paths), ["/src/ReadMe.md"]);
+        assert_eq!(matching(r#""read me""#, &["/docs/read me.txt"]).len(), 1);
+    }
+
+    #[test]
+    fn negated_terms_exclude_their_matches() {
+        let paths = ["/app/main.rs", "/app/main_test.rs", "/app/lib.rs"];
+        assert_eq!(matching("main !test", &paths), ["/app/main.rs"]);
+        assert_eq!(matching("!re:^/app/m", &paths), ["/app/lib.rs"]);
+    }
+
+    #[test]
+    fn regexes_are_smart_case() {
+        let paths = ["/logs/Error1.log", "/logs/error2.log"];
+        assert_eq!(matching(r"re:error\d", &paths), paths);
+        assert_eq!(matching(r"re:Error\d", &paths), ["/logs/Error1.log"]);
+        assert_eq!(matching(r#"re:"r\d\.log$""#, &paths), paths);
+        assert!(SearchQuery::parse("re:(").is_err());
+    }
+
+    #[test]
+    fn anchors_match_the_file_name() {
+        let paths = ["/main/lib.rs", "/src/main.rs", "/src/Main.rs.bak"];
+        assert_eq!(
+            matching("^main", &paths),
+            ["/src/main.rs", "/src/Main.rs.bak"]
+        );
+        assert_eq!(matching("rs$", &paths), ["/main/lib.rs", "/src/main.rs"]);
+        assert_eq!(matching("^main.rs$", &paths), ["/src/main.rs"]);
+        // a lone $ is plain text
+        assert_eq!(matching("$", &["/a/$HOME"]).len(), 1);
+    }
+
+    #[test]
+    fn required_text_is_the_longest_positive_term() {
+        assert_eq!(
+            query(r#"rs !documentation "Main" re:x"#).required_text(),
+            Some(String::from("main"))
+        );
+        assert_eq!(query("re:x !y").required_text(), None);
+    }
+
+    #[test]
+    fn match_indices_count_chars() {
+        let path = "/tmp/café/Über.txt";
+        // "/tmp/café/" is ten chars but eleven bytes
+        assert_eq!(query("über").match_indices(path), vec![10, 11, 12, 13]);
+        assert_eq!(query(r#""Über""#).match_indices(path), vec![10, 11, 12, 13]);
+        assert_eq!(query("re:é/ü").match_indices(path), vec![8, 9, 10]);
+        assert_eq!(query("^üb").match_indices(path), vec![10, 11]);
+        assert_eq!(query("txt$").match_indices(path), vec![15, 16, 17]);
+        assert_eq!(
+            query("café !zzz tmp").match_indices(path),
+            vec![1, 2, 3, 5, 6, 7, 8]
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::Receiver,
+        Arc,
+    },
+    thread,
+};
+
+use crate::app::GLOBAL_SEARCH_LIMIT;
+use crate::directory_store::DirectoryStore;
+use crate::events::Waker;
+use crate::frecency::FrecencyScores;
+use crate::search_query::SearchQuery;
+
+pub enum SearchMessage {
+    // best results so far, each batch replaces the previous one
+    Results(Vec<String>),
+    Done,
+}
+
+// runs the global search on a worker thread so typing doesn't wait for a
+// scan of the whole cache, a new query cancels the one in flight
+pub struct SearchWorker {
+    store: Arc<DirectoryStore>,
+    receiver: Option<Receiver<SearchMessage>>,
+    cancel: Arc<AtomicBool>,
+    waker: Waker,
+}
+
+impl SearchWorker {
+    pub fn new(store: Arc<DirectoryStore>, waker: Waker) -> SearchWorker {
+        SearchWorker {
+            store,
+            waker,
+            receiver: None,
+            cancel: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    // a query that doesn't parse keeps the running search going
+    pub fn start(
+        &mut self,
+        query: &str,
+        frecency: Arc<FrecencyScores>,
+    ) -> Result<SearchQuery, String> {
+        let query = SearchQuery::parse(query)?;
+        self.cancel.store(true, Ordering::Relaxed);
+        self.cancel = Arc::new(AtomicBool::new(false));
+
+        let (sender, receiver) = self.waker.channel();
+        self.receiver = Some(receiver);
+        let store = self.store.clone();
+        let cancel = self.cancel.clone();
+        let worker_query = query.clone();
+        thread::spawn(move || {
+            store.search_in_batches(
+                &worker_query,
+                &frecency,
+                GLOBAL_SEARCH_LIMIT,
+                &cancel,
+                |results| sender.send(SearchMessage::Results(results)).is_ok(),
+            );
+            if !cancel.load(Ordering::Relaxed) {
+                let _ = sender.send(SearchMessage::Done);
+            }
+        });
+        Ok(query)
+    }
+
+    pub fn cancel(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+        self.receiver = None;
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.receiver.is_some()
+    }
+
+    // the latest batch received since the last poll
+    pub fn poll(&mut self) -> Option<Vec<String>> {
+        let receiver = self.receiver.as_ref()?;
+        let mut latest = None;
+        let mut done = false;
+        while let Ok(message) = receiver.try_recv() {
+            match message {
+                SearchMessage::Results(results) => latest = Some(results),
+                SearchMessage::Done => done = true,
+            }
+        }
+        if done {
+            self.receiver = None;
+        }
+        latest
+    }
+}
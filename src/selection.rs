@@ -0,0 +1,380 @@
+use std::fs::{self, Metadata};
+
+use ratatui::{text::Text, widgets::ListState};
+
+use crate::app::App;
+use crate::errors;
+use crate::file_reader_content::{exif, FileContent, FileType};
+use crate::image_generator::ImageGenerator;
+use crate::office;
+use crate::utils::files::{get_content_from_path, is_file, SortType};
+
+// what follows the cursor of the listing: the selection, the metadata in the
+// status line and the preview of the selected entry
+pub struct SelectionController<'a, 'b> {
+    app: &'a mut App,
+    state: &'a mut ListState,
+    file_reader_content: &'a mut FileContent<'b>,
+    image_generator: &'a mut ImageGenerator,
+}
+
+impl<'a, 'b> SelectionController<'a, 'b> {
+    pub fn new(
+        app: &'a mut App,
+        state: &'a mut ListState,
+        file_reader_content: &'a mut FileContent<'b>,
+        image_generator: &'a mut ImageGenerator,
+    ) -> SelectionController<'a, 'b> {
+        SelectionController {
+            app,
+            state,
+            file_reader_content,
+            image_generator,
+        }
+    }
+
+    // selects `index` in the listing and loads its metadata and preview
+    pub fn update_selection(&mut self, index: usize) {
+        let SelectionController {
+            app,
+            state,
+            file_reader_content,
+            image_generator,
+        } = self;
+
+        // an empty listing has nothing to select or preview
+        if index >= app.files.len() {
+            state.select(None);
+            app.curr_index = None;
+            app.preview_files.clear();
+            file_reader_content.curr_selected_path.clear();
+            file_reader_content.file_type = FileType::NotAvailable;
+            image_generator.image = None;
+            return;
+        }
+        state.select(Some(index));
+        app.curr_index = Some(index);
+        let selected_cur_path = app.files[index].clone();
+        // the same entry again after a refresh, its preview is reloaded in place
+        let reloading = file_reader_content.curr_selected_path == selected_cur_path;
+        if !reloading {
+            app.preview_scroll = 0;
+            app.preview_column = 0;
+            app.preview_limit = app.preview_max_bytes;
+        }
+        app.preview_error = None;
+        let get_metadata = get_metadata_info(selected_cur_path.to_owned());
+        let generated_metadata_str = generate_metadata_str_info(get_metadata);
+
+        app.curr_stats = generated_metadata_str.clone();
+        file_reader_content.curr_selected_path = selected_cur_path.clone();
+
+        if !is_file(selected_cur_path.to_string()) {
+            match get_content_from_path(selected_cur_path.to_string()) {
+                Ok(file_names) => {
+                    image_generator.image = None;
+                    file_reader_content.file_type = FileType::NotAvailable;
+                    app.preview_files = file_names;
+                }
+                Err(e) => {
+                    // keeps the previous directory's entries from showing as
+                    // the contents of this one
+                    image_generator.image = None;
+                    file_reader_content.file_type = FileType::NotAvailable;
+                    app.preview_files.clear();
+                    app.preview_error = Some(errors::describe(&e.into()));
+                }
+            }
+        } else {
+            // the previewer command also handles files without a built in preview
+            let file_extension = match file_reader_content
+                .get_file_extension(selected_cur_path.clone())
+            {
+                FileType::NotAvailable if file_reader_content.previews.external => FileType::FILE,
+                file_type => file_type,
+            };
+
+            match file_extension {
+                FileType::FILE => {
+                    image_generator.image = None;
+                    file_reader_content.file_type = FileType::FILE;
+                    // read and highlighted in the background, see PreviewManager.
+                    // a reloaded file keeps showing the old content meanwhile
+                    match file_reader_content
+                        .previews
+                        .request(&selected_cur_path, app.preview_limit)
+                    {
+                        Some(preview) => file_reader_content.set_preview(preview),
+                        None if !reloading => {
+                            file_reader_content.set_preview(Text::from("Loading..."))
+                        }
+                        None => {}
+                    }
+                    // the tree is parsed in the background too, see
+                    // StructuredLoader
+                    file_reader_content.structured = None;
+                    file_reader_content.trees.request(&selected_cur_path);
+                }
+                FileType::IMG => {
+                    file_reader_content.curr_asset_path = selected_cur_path.to_string();
+                    file_reader_content.curr_image_info = exif::image_info(&selected_cur_path);
+
+                    match image_generator.load_img(selected_cur_path.clone()) {
+                        Ok(_) => file_reader_content.file_type = FileType::IMG,
+                        Err(e) => {
+                            file_reader_content.file_type = FileType::NotAvailable;
+                            app.curr_stats = format!("Unable to load image: {}", e);
+                        }
+                    }
+                }
+                FileType::ZIP => {
+                    image_generator.image = None;
+                    file_reader_content.read_zip_content(selected_cur_path.clone());
+                    file_reader_content.file_type = FileType::ZIP;
+                }
+                FileType::Archive => {
+                    image_generator.image = None;
+                    file_reader_content.read_archive_content(selected_cur_path.clone());
+                    file_reader_content.file_type = FileType::Archive;
+                }
+                FileType::Pdf => {
+                    image_generator.image = None;
+                    file_reader_content.read_pdf_content(selected_cur_path.clone());
+                    file_reader_content.file_type = FileType::Pdf;
+                }
+                FileType::Office => {
+                    image_generator.image = None;
+                    file_reader_content.curr_office_content =
+                        match office::extract_text(&selected_cur_path) {
+                            Ok(lines) => lines,
+                            Err(e) => {
+                                vec![format!("Unable to read document: {}", errors::describe(&e))]
+                            }
+                        };
+                    file_reader_content.file_type = FileType::Office;
+                }
+                FileType::CSV => {
+                    image_generator.image = None;
+                    match file_reader_content.read_csv_content(&selected_cur_path) {
+                        Ok(_) => file_reader_content.file_type = FileType::CSV,
+                        Err(e) => {
+                            file_reader_content.file_type = FileType::NotAvailable;
+                            app.curr_stats = errors::status(&selected_cur_path, e);
+                        }
+                    }
+                }
+                _ => {
+                    image_generator.image = None;
+                    file_reader_content.file_type = FileType::NotAvailable;
+                }
+            }
+
+            app.preview_files = Vec::new();
+        }
+    }
+}
+
+// rereads the current directory after something in it or the view settings
+// changed. the cursor goes to `select` when given, else stays on the entry
+// that was selected or at the same position when that entry is gone
+pub fn update_file_references_with_selection_preservation(
+    select: Option<String>,
+    app: &mut App,
+    state: &mut ListState,
+    sort_type: &SortType,
+    file_reader_content: &mut FileContent,
+    image_generator: &mut ImageGenerator,
+) {
+    // None when the directory is empty now, which clears the selection
+    let index = app
+        .refresh_file_list(sort_type, state.selected(), select)
+        .unwrap_or(0);
+    SelectionController::new(app, state, file_reader_content, image_generator)
+        .update_selection(index);
+}
+
+fn get_metadata_info(path: String) -> anyhow::Result<Option<Metadata>> {
+    let metadata = match fs::metadata(path) {
+        Ok(info) => Some(info),
+        Err(_) => None,
+    };
+
+    Ok(metadata)
+}
+
+fn generate_metadata_str_info(metadata: anyhow::Result<Option<Metadata>>) -> String {
+    let metadata_info = match metadata {
+        Ok(res) => match res {
+            Some(info) => {
+                let size = info.len();
+                let permissions = info.permissions();
+
+                let format_str = format!("size: {} | permission: {}", size, permissions.readonly());
+                format_str
+            }
+            None => String::from("Info not available"),
+        },
+        Err(e) => errors::describe(&e),
+    };
+
+    metadata_info
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::Waker;
+    use std::{
+        fs::{self, File},
+        io::Write,
+        path::Path,
+        thread,
+        time::{Duration, Instant},
+    };
+    use syntect::{highlighting::ThemeSet, parsing::SyntaxSet};
+
+    struct Fixture {
+        app: App,
+        state: ListState,
+        file_reader_content: FileContent<'static>,
+        image_generator: ImageGenerator,
+    }
+
+    impl Fixture {
+        fn new(files: &[&Path]) -> Fixture {
+            let files = files
+                .iter()
+                .map(|path| path.to_string_lossy().to_string())
+                .collect();
+            Fixture {
+                app: App::new(files),
+                state: ListState::default(),
+                file_reader_content: FileContent::new(
+                    SyntaxSet::load_defaults_newlines(),
+                    ThemeSet::load_defaults(),
+                    "base16-ocean.dark",
+                    None,
+                    Waker::default(),
+                ),
+                image_generator: ImageGenerator::new("halfblocks"),
+            }
+        }
+
+        fn select(&mut self, index: usize) -> &FileType {
+            SelectionController::new(
+                &mut self.app,
+                &mut self.state,
+                &mut self.file_reader_content,
+                &mut self.image_generator,
+            )
+            .update_selection(index);
+            &self.file_reader_content.file_type
+        }
+    }
+
+    #[test]
+    fn directories_list_their_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("inner.txt"), "inner").unwrap();
+        let mut fixture = Fixture::new(&[dir.path()]);
+
+        assert!(matches!(fixture.select(0), FileType::NotAvailable));
+        assert_eq!(fixture.state.selected(), Some(0));
+        assert_eq!(fixture.app.preview_files.len(), 1);
+        assert!(fixture.image_generator.image.is_none());
+    }
+
+    #[test]
+    fn text_files_load_in_the_background() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("main.rs");
+        fs::write(&file, "fn main() {}\n").unwrap();
+        let mut fixture = Fixture::new(&[&file]);
+
+        assert!(matches!(fixture.select(0), FileType::FILE));
+        assert!(fixture.file_reader_content.hightlighted_content.is_some());
+        assert!(fixture.file_reader_content.structured.is_none());
+    }
+
+    #[test]
+    fn trees_are_parsed_off_the_selection() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("data.json");
+        fs::write(&file, r#"{"a": [1, 2], "b": {"c": true}}"#).unwrap();
+        let mut fixture = Fixture::new(&[&file]);
+
+        assert!(matches!(fixture.select(0), FileType::FILE));
+        // not parsed while selecting
+        assert!(fixture.file_reader_content.structured.is_none());
+
+        let path = file.to_string_lossy().to_string();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut tree = None;
+        while tree.is_none() && Instant::now() < deadline {
+            tree = fixture.file_reader_content.trees.poll(&path);
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(tree.is_some());
+    }
+
+    #[test]
+    fn images_are_decoded() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("pixel.png");
+        image::RgbImage::new(2, 2).save(&file).unwrap();
+        let broken = dir.path().join("broken.png");
+        fs::write(&broken, "not a png").unwrap();
+        let mut fixture = Fixture::new(&[&file, &broken]);
+
+        assert!(matches!(fixture.select(0), FileType::IMG));
+        assert!(fixture.image_generator.image.is_some());
+
+        assert!(matches!(fixture.select(1), FileType::NotAvailable));
+        assert!(fixture.app.curr_stats.starts_with("Unable to load image"));
+    }
+
+    #[test]
+    fn archives_list_their_members() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("bundle.zip");
+        let mut zip = zip::ZipWriter::new(File::create(&file).unwrap());
+        zip.start_file("inside.txt", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(b"inside").unwrap();
+        zip.finish().unwrap();
+        let mut fixture = Fixture::new(&[&file]);
+
+        assert!(matches!(fixture.select(0), FileType::ZIP));
+        assert_eq!(
+            fixture.file_reader_content.curr_zip_content,
+            vec![String::from("inside.txt")]
+        );
+    }
+
+    #[test]
+    fn csv_files_are_read_as_a_table() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("table.csv");
+        fs::write(&file, "name,size\na,1\nb,2\n").unwrap();
+        let mut fixture = Fixture::new(&[&file]);
+
+        assert!(matches!(fixture.select(0), FileType::CSV));
+        assert_eq!(fixture.file_reader_content.curr_csv_content.widths.len(), 2);
+    }
+
+    #[test]
+    fn empty_listing_clears_the_preview() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("pixel.png");
+        image::RgbImage::new(2, 2).save(&file).unwrap();
+        let mut fixture = Fixture::new(&[&file]);
+        fixture.select(0);
+
+        fixture.app.files.clear();
+        assert!(matches!(fixture.select(0), FileType::NotAvailable));
+        assert_eq!(fixture.state.selected(), None);
+        assert_eq!(fixture.app.curr_index, None);
+        assert!(fixture.file_reader_content.curr_selected_path.is_empty());
+        assert!(fixture.image_generator.image.is_none());
+    }
+}
@@ -0,0 +1,156 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{atomic::Ordering, mpsc::Receiver},
+    thread,
+    time::{Duration, Instant},
+};
+
+use ratatui::{
+    style::Style,
+    text::{Line, Span},
+};
+
+use crate::events::Waker;
+use crate::jobs::JobControl;
+use crate::operations::dir_size::{self, TotalsMessage};
+use crate::system::volumes::disk_space;
+use crate::theme::ThemeColors;
+use crate::utils::format::{format_count, format_size, SizeTotals};
+
+// free space only changes through copies and deletes, no need to ask the
+// filesystem on every frame
+const SPACE_REFRESH: Duration = Duration::from_secs(2);
+
+#[derive(Default)]
+pub struct StatusBar {
+    items: usize,
+    // the marked entries the totals are being summed for
+    marked: Vec<String>,
+    totals: SizeTotals,
+    // set while the totals are still being walked
+    totals_receiver: Option<Receiver<TotalsMessage>>,
+    totals_control: JobControl,
+    show_hidden: bool,
+    // entries found by a cache build running in the background
+    cache_build: Option<usize>,
+    // (free, total) of the volume holding `space_dir`
+    space: Option<(u64, u64)>,
+    space_dir: PathBuf,
+    space_checked: Option<Instant>,
+    waker: Waker,
+}
+
+impl StatusBar {
+    pub fn new(waker: Waker) -> StatusBar {
+        StatusBar {
+            waker,
+            ..StatusBar::default()
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        current_dir: &Path,
+        items: usize,
+        marked_files: &[String],
+        show_hidden: bool,
+        cache_build: Option<usize>,
+    ) {
+        self.items = items;
+        self.show_hidden = show_hidden;
+        self.cache_build = cache_build;
+        if self.marked != marked_files {
+            self.sum_marked(marked_files);
+        }
+
+        let stale = self
+            .space_checked
+            .is_none_or(|checked| checked.elapsed() >= SPACE_REFRESH);
+        if stale || self.space_dir != current_dir {
+            self.space = disk_space(current_dir);
+            self.space_dir = current_dir.to_path_buf();
+            self.space_checked = Some(Instant::now());
+        }
+    }
+
+    // a changed selection cancels the walk of the previous one
+    fn sum_marked(&mut self, marked_files: &[String]) {
+        self.totals_control.cancel.store(true, Ordering::Relaxed);
+        self.marked = marked_files.to_vec();
+        self.totals = SizeTotals::default();
+        self.totals_receiver = None;
+        if marked_files.is_empty() {
+            return;
+        }
+
+        self.totals_control = JobControl::default();
+        let (sender, receiver) = self.waker.channel();
+        self.totals_receiver = Some(receiver);
+        let paths = self.marked.clone();
+        let control = self.totals_control.clone();
+        thread::spawn(move || dir_size::selection_totals(paths, control, sender));
+    }
+
+    // whether the totals of the marked entries changed
+    pub fn poll(&mut self) -> bool {
+        let mut changed = false;
+        if let Some(receiver) = &self.totals_receiver {
+            let mut finished = false;
+            while let Ok(message) = receiver.try_recv() {
+                changed = true;
+                match message {
+                    TotalsMessage::Progress(totals) => self.totals = totals,
+                    TotalsMessage::Done(totals) => {
+                        self.totals = totals;
+                        finished = true;
+                    }
+                }
+            }
+            if finished {
+                self.totals_receiver = None;
+            }
+        }
+        changed
+    }
+
+    pub fn line(&self, colors: &ThemeColors) -> Line<'static> {
+        let separator = Span::styled(" | ", Style::default().fg(colors.dim));
+        let mut spans = vec![Span::raw(format!("{} items", self.items))];
+
+        if !self.marked.is_empty() {
+            // the totals keep growing until the walk is done
+            let pending = if self.totals_receiver.is_some() {
+                "..."
+            } else {
+                ""
+            };
+            spans.push(separator.clone());
+            spans.push(Span::styled(
+                format!("{}{} selected", self.totals.label(), pending),
+                Style::default().fg(colors.highlight),
+            ));
+        }
+        if self.show_hidden {
+            spans.push(separator.clone());
+            spans.push(Span::raw("hidden"));
+        }
+        if let Some(found) = self.cache_build {
+            spans.push(separator.clone());
+            spans.push(Span::styled(
+                format!("indexing, {} found", format_count(found)),
+                Style::default().fg(colors.warning),
+            ));
+        }
+        if let Some((free, total)) = self.space {
+            spans.push(separator.clone());
+            spans.push(Span::raw(format!(
+                "{} free of {}",
+                format_size(free),
+                format_size(total)
+            )));
+        }
+        spans.push(separator);
+        spans.push(Span::raw("Keybindings: ?"));
+        Line::from(spans)
+    }
+}
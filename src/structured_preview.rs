@@ -0,0 +1,351 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::Path,
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+use ratatui::{
+    style::{Modifier, Style},
+    text::{Line, Span, Text},
+};
+use serde_json::Value;
+
+use crate::events::Waker;
+use crate::theme::ThemeColors;
+
+// bigger files stay in the plain text preview
+const MAX_BYTES: u64 = 4 * 1024 * 1024;
+// a tree longer than this starts with everything below the top level folded
+const FOLD_ABOVE_LINES: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl Format {
+    pub fn of(path: &str) -> Option<Format> {
+        let extension = Path::new(path)
+            .extension()?
+            .to_string_lossy()
+            .to_lowercase();
+        match extension.as_str() {
+            "json" => Some(Format::Json),
+            "yaml" | "yml" => Some(Format::Yaml),
+            "toml" => Some(Format::Toml),
+            _ => None,
+        }
+    }
+}
+
+// what a piece of a line is, styled with the theme when drawn
+#[derive(Debug, Clone, Copy)]
+enum Part {
+    Plain,
+    Key,
+    Summary,
+    String,
+    Number,
+    Bool,
+    Null,
+}
+
+impl Part {
+    fn style(self, colors: &ThemeColors) -> Style {
+        match self {
+            Part::Plain => Style::default(),
+            Part::Key => Style::default().fg(colors.accent),
+            Part::Summary | Part::Null => Style::default().fg(colors.dim),
+            Part::String => Style::default().fg(colors.success),
+            Part::Number => Style::default().fg(colors.warning),
+            Part::Bool => Style::default().fg(colors.highlight),
+        }
+    }
+}
+
+struct TreeLine {
+    // json pointer of the node the line belongs to
+    pointer: String,
+    container: bool,
+    parts: Vec<(Part, String)>,
+}
+
+// json, yaml and toml files as a tree of values where objects and arrays
+// can be folded
+pub struct StructuredPreview {
+    value: Value,
+    folded: HashSet<String>,
+    lines: Vec<TreeLine>,
+}
+
+impl StructuredPreview {
+    pub fn load(path: &str, format: Format) -> anyhow::Result<StructuredPreview> {
+        if fs::metadata(path)?.len() > MAX_BYTES {
+            anyhow::bail!("too large for the tree preview");
+        }
+        let content = fs::read_to_string(path)?;
+        let value: Value = match format {
+            Format::Json => serde_json::from_str(&content)?,
+            Format::Yaml => serde_yaml::from_str(&content)?,
+            Format::Toml => toml::from_str(&content)?,
+        };
+
+        let mut preview = StructuredPreview {
+            value,
+            folded: HashSet::new(),
+            lines: Vec::new(),
+        };
+        preview.rebuild();
+        if preview.lines.len() > FOLD_ABOVE_LINES {
+            let mut deep = Vec::new();
+            collect_containers(&preview.value, String::new(), 0, &mut deep);
+            preview.folded = deep.into_iter().collect();
+            preview.rebuild();
+        }
+        Ok(preview)
+    }
+
+    // folds the object or array on line `cursor`, returns the line its
+    // folded form ended up on
+    pub fn fold(&mut self, cursor: usize) -> usize {
+        let Some(pointer) = self.container_at(cursor) else {
+            return cursor;
+        };
+        self.folded.insert(pointer.clone());
+        self.rebuild();
+        self.line_of(&pointer).unwrap_or(cursor)
+    }
+
+    pub fn unfold(&mut self, cursor: usize) {
+        if let Some(pointer) = self.container_at(cursor) {
+            self.folded.remove(&pointer);
+            self.rebuild();
+        }
+    }
+
+    pub fn text(&self, cursor: usize, colors: &ThemeColors) -> Text<'static> {
+        let lines: Vec<Line<'static>> = self
+            .lines
+            .iter()
+            .enumerate()
+            .map(|(index, tree_line)| {
+                let line = Line::from(
+                    tree_line
+                        .parts
+                        .iter()
+                        .map(|(part, text)| Span::styled(text.clone(), part.style(colors)))
+                        .collect::<Vec<_>>(),
+                );
+                if index == cursor {
+                    line.patch_style(Style::default().add_modifier(Modifier::REVERSED))
+                } else {
+                    line
+                }
+            })
+            .collect();
+        Text::from(lines)
+    }
+
+    fn container_at(&self, cursor: usize) -> Option<String> {
+        self.lines
+            .get(cursor)
+            .filter(|tree_line| tree_line.container)
+            .map(|tree_line| tree_line.pointer.clone())
+    }
+
+    fn line_of(&self, pointer: &str) -> Option<usize> {
+        self.lines
+            .iter()
+            .position(|tree_line| tree_line.pointer == pointer)
+    }
+
+    fn rebuild(&mut self) {
+        let mut lines = Vec::new();
+        push_value(
+            &self.value,
+            None,
+            String::new(),
+            0,
+            &self.folded,
+            &mut lines,
+        );
+        self.lines = lines;
+    }
+}
+
+// parses trees on a worker thread, so selecting a big json file doesn't
+// hold up moving through the listing
+pub struct StructuredLoader {
+    requests: Sender<(String, Format)>,
+    results: Receiver<(String, Option<StructuredPreview>)>,
+}
+
+impl StructuredLoader {
+    pub fn new(waker: Waker) -> StructuredLoader {
+        let (requests, worker_requests) = mpsc::channel::<(String, Format)>();
+        let (worker_results, results) = waker.channel();
+
+        thread::spawn(move || {
+            while let Ok(mut request) = worker_requests.recv() {
+                // only the latest request still matters
+                while let Ok(newer) = worker_requests.try_recv() {
+                    request = newer;
+                }
+                let (path, format) = request;
+                // files that don't parse keep the plain text preview
+                let tree = StructuredPreview::load(&path, format).ok();
+                if worker_results.send((path, tree)).is_err() {
+                    return;
+                }
+            }
+        });
+
+        StructuredLoader { requests, results }
+    }
+
+    // files that aren't json, yaml or toml have no tree to load
+    pub fn request(&self, path: &str) {
+        if let Some(format) = Format::of(path) {
+            let _ = self.requests.send((path.to_string(), format));
+        }
+    }
+
+    // the tree of `selected` once it was parsed, trees of entries selected
+    // before are dropped
+    pub fn poll(&self, selected: &str) -> Option<StructuredPreview> {
+        let mut ready = None;
+        while let Ok((path, tree)) = self.results.try_recv() {
+            if path == selected {
+                ready = tree;
+            }
+        }
+        ready
+    }
+}
+
+fn collect_containers(value: &Value, pointer: String, depth: usize, out: &mut Vec<String>) {
+    let children: Vec<(String, &Value)> = match value {
+        Value::Object(map) => map
+            .iter()
+            .map(|(key, child)| (escape(key), child))
+            .collect(),
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .map(|(index, child)| (index.to_string(), child))
+            .collect(),
+        _ => return,
+    };
+    if depth >= 1 {
+        out.push(pointer.clone());
+    }
+    for (key, child) in children {
+        collect_containers(child, format!("{}/{}", pointer, key), depth + 1, out);
+    }
+}
+
+// json pointer escaping, so keys holding "/" don't collide
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+fn push_value(
+    value: &Value,
+    key: Option<&str>,
+    pointer: String,
+    depth: usize,
+    folded: &HashSet<String>,
+    lines: &mut Vec<TreeLine>,
+) {
+    let mut parts = vec![(Part::Plain, "  ".repeat(depth))];
+    if let Some(key) = key {
+        parts.push((Part::Key, key.to_string()));
+        parts.push((Part::Plain, String::from(": ")));
+    }
+
+    let (open, close, children): (&str, &str, Vec<(String, String, &Value)>) = match value {
+        Value::Object(map) => (
+            "{",
+            "}",
+            map.iter()
+                .map(|(key, child)| (key.clone(), escape(key), child))
+                .collect(),
+        ),
+        Value::Array(items) => (
+            "[",
+            "]",
+            items
+                .iter()
+                .enumerate()
+                .map(|(index, child)| (index.to_string(), index.to_string(), child))
+                .collect(),
+        ),
+        scalar => {
+            parts.push(scalar_part(scalar));
+            lines.push(TreeLine {
+                pointer,
+                container: false,
+                parts,
+            });
+            return;
+        }
+    };
+
+    if folded.contains(&pointer) || children.is_empty() {
+        let summary = match (value, children.len()) {
+            (_, 0) => String::new(),
+            (Value::Object(_), 1) => " 1 key ".to_string(),
+            (Value::Object(_), count) => format!(" {} keys ", count),
+            (_, 1) => " 1 item ".to_string(),
+            (_, count) => format!(" {} items ", count),
+        };
+        parts.push((Part::Plain, open.to_string()));
+        parts.push((Part::Summary, summary));
+        parts.push((Part::Plain, close.to_string()));
+        lines.push(TreeLine {
+            pointer,
+            container: !children.is_empty(),
+            parts,
+        });
+        return;
+    }
+
+    parts.push((Part::Plain, open.to_string()));
+    lines.push(TreeLine {
+        pointer: pointer.clone(),
+        container: true,
+        parts,
+    });
+    for (label, segment, child) in children {
+        let child_key = match value {
+            Value::Array(_) => None,
+            _ => Some(label.as_str()),
+        };
+        push_value(
+            child,
+            child_key,
+            format!("{}/{}", pointer, segment),
+            depth + 1,
+            folded,
+            lines,
+        );
+    }
+    lines.push(TreeLine {
+        pointer,
+        container: true,
+        parts: vec![(Part::Plain, format!("{}{}", "  ".repeat(depth), close))],
+    });
+}
+
+fn scalar_part(value: &Value) -> (Part, String) {
+    match value {
+        Value::String(text) => (Part::String, format!("{:?}", text)),
+        Value::Number(number) => (Part::Number, number.to_string()),
+        Value::Bool(flag) => (Part::Bool, flag.to_string()),
+        _ => (Part::Null, String::from("null")),
+    }
+}
@@ -0,0 +1,138 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+// file systems from /proc/mounts that are worth jumping to besides the ones
+// on a /dev device
+#[cfg(target_os = "linux")]
+const NETWORK_FILE_SYSTEMS: [&str; 6] = ["nfs", "nfs4", "cifs", "smbfs", "fuse.sshfs", "9p"];
+// pseudo and system mounts that only clutter the list
+#[cfg(target_os = "linux")]
+const HIDDEN_MOUNT_PREFIXES: [&str; 5] = ["/proc", "/sys", "/dev", "/run", "/snap"];
+
+#[derive(Debug, Clone)]
+pub struct Volume {
+    pub path: PathBuf,
+    pub file_system: String,
+    // available and total bytes
+    pub space: Option<(u64, u64)>,
+}
+
+impl Volume {
+    fn new(path: PathBuf, file_system: String) -> Volume {
+        let space = disk_space(&path);
+        Volume {
+            path,
+            file_system,
+            space,
+        }
+    }
+}
+
+// mounted drives and network shares, the root first
+#[cfg(target_os = "macos")]
+pub fn list_volumes() -> Vec<Volume> {
+    let mut volumes = vec![Volume::new(PathBuf::from("/"), String::new())];
+    let mut mounted: Vec<PathBuf> = fs::read_dir("/Volumes")
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                // the startup disk shows up in /Volumes as a link to /
+                .filter(|path| fs::canonicalize(path).map_or(true, |real| real != Path::new("/")))
+                .collect()
+        })
+        .unwrap_or_default();
+    mounted.sort();
+    volumes.extend(
+        mounted
+            .into_iter()
+            .map(|path| Volume::new(path, String::new())),
+    );
+    volumes
+}
+
+#[cfg(target_os = "linux")]
+pub fn list_volumes() -> Vec<Volume> {
+    let mounts = fs::read_to_string("/proc/mounts").unwrap_or_default();
+    let mut volumes: Vec<Volume> = Vec::new();
+    for line in mounts.lines() {
+        let mut fields = line.split_whitespace();
+        let (device, mount_point, file_system) = match (fields.next(), fields.next(), fields.next())
+        {
+            (Some(device), Some(mount_point), Some(file_system)) => {
+                (device, unescape_mount_point(mount_point), file_system)
+            }
+            _ => continue,
+        };
+        let is_device = device.starts_with("/dev/");
+        if !is_device && !NETWORK_FILE_SYSTEMS.contains(&file_system) {
+            continue;
+        }
+        // removable drives are mounted under /run/media
+        let hidden = HIDDEN_MOUNT_PREFIXES.iter().any(|prefix| {
+            mount_point == *prefix || mount_point.starts_with(&format!("{}/", prefix))
+        });
+        if hidden && !mount_point.starts_with("/run/media/") {
+            continue;
+        }
+        let path = PathBuf::from(mount_point);
+        if volumes.iter().any(|volume| volume.path == path) {
+            continue;
+        }
+        volumes.push(Volume::new(path, file_system.to_string()));
+    }
+    volumes.sort_by(|a, b| a.path.cmp(&b.path));
+    volumes
+}
+
+// spaces and tabs in mount points are written as octal escapes
+#[cfg(target_os = "linux")]
+fn unescape_mount_point(mount_point: &str) -> String {
+    mount_point
+        .replace("\\040", " ")
+        .replace("\\011", "\t")
+        .replace("\\012", "\n")
+        .replace("\\134", "\\")
+}
+
+#[cfg(windows)]
+pub fn list_volumes() -> Vec<Volume> {
+    (b'A'..=b'Z')
+        .map(|letter| PathBuf::from(format!("{}:\\", letter as char)))
+        .filter(|path| path.exists())
+        .map(|path| Volume::new(path, String::new()))
+        .collect()
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", windows)))]
+pub fn list_volumes() -> Vec<Volume> {
+    vec![Volume::new(PathBuf::from("/"), String::new())]
+}
+
+#[cfg(unix)]
+pub fn disk_space(path: &Path) -> Option<(u64, u64)> {
+    use std::{ffi::CString, mem::MaybeUninit, os::unix::ffi::OsStrExt};
+
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat = MaybeUninit::<libc::statvfs>::uninit();
+    // statvfs only writes into the buffer and reads the nul terminated path
+    let stat = unsafe {
+        if libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) != 0 {
+            return None;
+        }
+        stat.assume_init()
+    };
+
+    let block_size = stat.f_frsize as u64;
+    Some((
+        stat.f_bavail as u64 * block_size,
+        stat.f_blocks as u64 * block_size,
+    ))
+}
+
+#[cfg(not(unix))]
+pub fn disk_space(_path: &Path) -> Option<(u64, u64)> {
+    None
+}
@@ -0,0 +1,116 @@
+use std::{
+    env,
+    io::{self, Stderr, Stdout, Write},
+    path::Path,
+    process::{Command, ExitStatus},
+};
+
+use crossterm::{
+    event::{DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{backend::CrosstermBackend, Terminal};
+
+use crate::events::Events;
+
+// the tui draws on stdout, or on stderr with --print-on-select so the
+// selected paths are the only thing written to stdout
+pub enum TerminalOutput {
+    Stdout(Stdout),
+    Stderr(Stderr),
+}
+
+impl TerminalOutput {
+    pub fn new(use_stderr: bool) -> TerminalOutput {
+        if use_stderr {
+            TerminalOutput::Stderr(io::stderr())
+        } else {
+            TerminalOutput::Stdout(io::stdout())
+        }
+    }
+}
+
+impl Write for TerminalOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            TerminalOutput::Stdout(stdout) => stdout.write(buf),
+            TerminalOutput::Stderr(stderr) => stderr.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            TerminalOutput::Stdout(stdout) => stdout.flush(),
+            TerminalOutput::Stderr(stderr) => stderr.flush(),
+        }
+    }
+}
+
+pub fn suspend_terminal(
+    terminal: &mut Terminal<CrosstermBackend<TerminalOutput>>,
+) -> anyhow::Result<()> {
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        DisableBracketedPaste
+    )?;
+    terminal.show_cursor()?;
+    Ok(())
+}
+
+pub fn resume_terminal(
+    terminal: &mut Terminal<CrosstermBackend<TerminalOutput>>,
+) -> anyhow::Result<()> {
+    enable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
+    // whatever ran in between drew over the screen, force a full redraw
+    terminal.clear()?;
+    Ok(())
+}
+
+// runs `work` on the normal screen and restores the tui afterwards, also when
+// `work` fails. the input thread stays away from the terminal meanwhile.
+// `work` gets the output the tui draws on, stdout stays free with
+// --print-on-select
+pub fn with_suspended<T>(
+    terminal: &mut Terminal<CrosstermBackend<TerminalOutput>>,
+    events: &Events,
+    work: impl FnOnce(&mut dyn Write) -> anyhow::Result<T>,
+) -> anyhow::Result<T> {
+    let _paused = events.pause_input();
+    suspend_terminal(terminal)?;
+    let result = work(terminal.backend_mut());
+    resume_terminal(terminal)?;
+    result
+}
+
+fn user_shell() -> String {
+    if cfg!(windows) {
+        env::var("COMSPEC").unwrap_or_else(|_| String::from("cmd.exe"))
+    } else {
+        env::var("SHELL").unwrap_or_else(|_| String::from("sh"))
+    }
+}
+
+// interactive shell in `dir`, returns once the user exits it
+pub fn open_shell(
+    terminal: &mut Terminal<CrosstermBackend<TerminalOutput>>,
+    events: &Events,
+    dir: &Path,
+) -> anyhow::Result<ExitStatus> {
+    let shell = user_shell();
+    with_suspended(terminal, events, |output| {
+        writeln!(output, "ff: {} in {}, exit to return", shell, dir.display())?;
+        output.flush()?;
+        let status = Command::new(&shell).current_dir(dir).status()?;
+        Ok(status)
+    })
+}
@@ -0,0 +1,593 @@
+use std::{fs, path::PathBuf, str::FromStr};
+#[cfg(unix)]
+use std::{
+    fs::OpenOptions,
+    io::{Read, Write},
+    os::fd::AsRawFd,
+};
+
+use anyhow::Context;
+use ratatui::style::{Color, Modifier, Style};
+use serde::{Deserialize, Serialize};
+use syntect::highlighting::{self, ScopeSelectors, StyleModifier, ThemeItem, ThemeSettings};
+
+use crate::configuration::{config_dir, Configuration};
+
+pub const DEFAULT_THEME: &str = "onedark";
+pub const DEFAULT_LIGHT_THEME: &str = "solarized-light";
+
+// themes/<name>.toml in the config directory, colors are `#rrggbb`, a
+// terminal color name like `dark-gray` or a 256 color index:
+//
+//   [colors]
+//   foreground = "#ABB2BF"
+//   border_active = "#98C379"
+//   selection_bg = "#3E4451"
+//
+//   [syntax]
+//   keyword = "#C678DD"
+//
+// without a [syntax] table previews use the syntect theme in settings.json
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    pub colors: ThemeColors,
+    pub syntax: Option<SyntaxTheme>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+struct ThemeFile {
+    colors: ThemeColors,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    syntax: Option<SyntaxTheme>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SyntaxTheme {
+    #[serde(with = "color_format")]
+    pub comment: Color,
+    #[serde(with = "color_format")]
+    pub keyword: Color,
+    #[serde(with = "color_format")]
+    pub string: Color,
+    #[serde(with = "color_format")]
+    pub number: Color,
+    #[serde(with = "color_format")]
+    pub function: Color,
+    #[serde(rename = "type", with = "color_format")]
+    pub type_name: Color,
+    #[serde(with = "color_format")]
+    pub constant: Color,
+    #[serde(with = "color_format")]
+    pub operator: Color,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ThemeColors {
+    #[serde(with = "color_format")]
+    pub foreground: Color,
+    // inactive panes and prompts
+    #[serde(with = "color_format")]
+    pub muted: Color,
+    // hints and the dimmed parent / preview listings
+    #[serde(with = "color_format")]
+    pub dim: Color,
+    #[serde(with = "color_format")]
+    pub border: Color,
+    // the border of the pane that has the focus
+    #[serde(with = "color_format")]
+    pub border_active: Color,
+    #[serde(with = "color_format")]
+    pub selection_fg: Color,
+    #[serde(with = "color_format")]
+    pub selection_bg: Color,
+    #[serde(with = "color_format")]
+    pub accent: Color,
+    // matched text and things that need attention
+    #[serde(with = "color_format")]
+    pub highlight: Color,
+    #[serde(with = "color_format")]
+    pub success: Color,
+    #[serde(with = "color_format")]
+    pub warning: Color,
+    #[serde(with = "color_format")]
+    pub error: Color,
+}
+
+impl ThemeColors {
+    pub fn text(&self) -> Style {
+        Style::default().fg(self.foreground)
+    }
+
+    pub fn border(&self, active: bool) -> Style {
+        match active {
+            true => Style::default().fg(self.border_active),
+            false => Style::default().fg(self.border),
+        }
+    }
+
+    pub fn selection(&self) -> Style {
+        Style::default()
+            .fg(self.selection_fg)
+            .bg(self.selection_bg)
+            .add_modifier(Modifier::BOLD)
+    }
+
+    // the same colors as the terminal can show them
+    pub fn with_depth(self, true_color: bool) -> ThemeColors {
+        let reduce = |color| reduce_color(color, true_color);
+        ThemeColors {
+            foreground: reduce(self.foreground),
+            muted: reduce(self.muted),
+            dim: reduce(self.dim),
+            border: reduce(self.border),
+            border_active: reduce(self.border_active),
+            selection_fg: reduce(self.selection_fg),
+            selection_bg: reduce(self.selection_bg),
+            accent: reduce(self.accent),
+            highlight: reduce(self.highlight),
+            success: reduce(self.success),
+            warning: reduce(self.warning),
+            error: reduce(self.error),
+        }
+    }
+}
+
+impl Theme {
+    pub fn onedark() -> Theme {
+        Theme::from_palette(
+            DEFAULT_THEME,
+            [
+                0xabb2bf, 0x7f848e, 0x5c6370, 0xabb2bf, 0x98c379, 0xffffff, 0x3e4451, 0x61afef,
+                0xe5c07b, 0x98c379, 0xd19a66, 0xe06c75,
+            ],
+            [
+                0x5c6370, 0xc678dd, 0x98c379, 0xd19a66, 0x61afef, 0xe5c07b, 0xd19a66, 0x56b6c2,
+            ],
+        )
+    }
+
+    pub fn gruvbox() -> Theme {
+        Theme::from_palette(
+            "gruvbox",
+            [
+                0xebdbb2, 0xa89984, 0x7c6f64, 0xa89984, 0xb8bb26, 0xfbf1c7, 0x504945, 0x83a598,
+                0xfabd2f, 0xb8bb26, 0xfe8019, 0xfb4934,
+            ],
+            [
+                0x928374, 0xfb4934, 0xb8bb26, 0xd3869b, 0x8ec07c, 0xfabd2f, 0xd3869b, 0xfe8019,
+            ],
+        )
+    }
+
+    // the mocha flavor
+    pub fn catppuccin() -> Theme {
+        Theme::from_palette(
+            "catppuccin",
+            [
+                0xcdd6f4, 0xa6adc8, 0x6c7086, 0x9399b2, 0xa6e3a1, 0xcdd6f4, 0x45475a, 0x89b4fa,
+                0xf9e2af, 0xa6e3a1, 0xfab387, 0xf38ba8,
+            ],
+            [
+                0x6c7086, 0xcba6f7, 0xa6e3a1, 0xfab387, 0x89b4fa, 0xf9e2af, 0xfab387, 0x89dceb,
+            ],
+        )
+    }
+
+    pub fn dracula() -> Theme {
+        Theme::from_palette(
+            "dracula",
+            [
+                0xf8f8f2, 0xbfbfbf, 0x6272a4, 0x6272a4, 0x50fa7b, 0xf8f8f2, 0x44475a, 0xbd93f9,
+                0xf1fa8c, 0x50fa7b, 0xffb86c, 0xff5555,
+            ],
+            [
+                0x6272a4, 0xff79c6, 0xf1fa8c, 0xbd93f9, 0x50fa7b, 0x8be9fd, 0xbd93f9, 0xff79c6,
+            ],
+        )
+    }
+
+    pub fn solarized_light() -> Theme {
+        Theme::from_palette(
+            DEFAULT_LIGHT_THEME,
+            [
+                0x657b83, 0x839496, 0x93a1a1, 0x93a1a1, 0x859900, 0x073642, 0xeee8d5, 0x268bd2,
+                0xb58900, 0x859900, 0xcb4b16, 0xdc322f,
+            ],
+            [
+                0x93a1a1, 0x859900, 0x2aa198, 0xd33682, 0x268bd2, 0xb58900, 0xcb4b16, 0x657b83,
+            ],
+        )
+    }
+
+    // the colors in the order of the ThemeColors and SyntaxTheme fields
+    fn from_palette(name: &str, palette: [u32; 12], syntax: [u32; 8]) -> Theme {
+        let rgb = |rgb: u32| Color::Rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8);
+        Theme {
+            name: name.to_string(),
+            colors: ThemeColors {
+                foreground: rgb(palette[0]),
+                muted: rgb(palette[1]),
+                dim: rgb(palette[2]),
+                border: rgb(palette[3]),
+                border_active: rgb(palette[4]),
+                selection_fg: rgb(palette[5]),
+                selection_bg: rgb(palette[6]),
+                accent: rgb(palette[7]),
+                highlight: rgb(palette[8]),
+                success: rgb(palette[9]),
+                warning: rgb(palette[10]),
+                error: rgb(palette[11]),
+            },
+            syntax: Some(SyntaxTheme {
+                comment: rgb(syntax[0]),
+                keyword: rgb(syntax[1]),
+                string: rgb(syntax[2]),
+                number: rgb(syntax[3]),
+                function: rgb(syntax[4]),
+                type_name: rgb(syntax[5]),
+                constant: rgb(syntax[6]),
+                operator: rgb(syntax[7]),
+            }),
+        }
+    }
+
+    // a highlighting theme for the previews made of the [syntax] colors
+    pub fn syntect_theme(&self) -> Option<highlighting::Theme> {
+        let syntax = self.syntax.as_ref()?;
+        let scopes = [
+            ("comment", syntax.comment),
+            ("keyword, storage", syntax.keyword),
+            ("string", syntax.string),
+            ("constant", syntax.constant),
+            ("constant.numeric", syntax.number),
+            ("entity.name.function, support.function", syntax.function),
+            (
+                "entity.name.type, entity.name.class, support.type, support.class, storage.type",
+                syntax.type_name,
+            ),
+            ("keyword.operator, punctuation", syntax.operator),
+        ];
+        let scopes = scopes
+            .into_iter()
+            .filter_map(|(scope, color)| {
+                Some(ThemeItem {
+                    scope: ScopeSelectors::from_str(scope).ok()?,
+                    style: StyleModifier {
+                        foreground: Some(syntect_color(color)?),
+                        background: None,
+                        font_style: None,
+                    },
+                })
+            })
+            .collect();
+        Some(highlighting::Theme {
+            name: Some(self.name.clone()),
+            author: None,
+            settings: ThemeSettings {
+                foreground: syntect_color(self.colors.foreground),
+                ..ThemeSettings::default()
+            },
+            scopes,
+        })
+    }
+}
+
+fn syntect_color(color: Color) -> Option<highlighting::Color> {
+    let (r, g, b) = rgb_of(color)?;
+    Some(highlighting::Color { r, g, b, a: 0xff })
+}
+
+// the xterm default palette for the named and indexed colors
+pub fn rgb_of(color: Color) -> Option<(u8, u8, u8)> {
+    const BASIC: [(u8, u8, u8); 16] = [
+        (0x00, 0x00, 0x00),
+        (0xcd, 0x00, 0x00),
+        (0x00, 0xcd, 0x00),
+        (0xcd, 0xcd, 0x00),
+        (0x00, 0x00, 0xee),
+        (0xcd, 0x00, 0xcd),
+        (0x00, 0xcd, 0xcd),
+        (0xe5, 0xe5, 0xe5),
+        (0x7f, 0x7f, 0x7f),
+        (0xff, 0x00, 0x00),
+        (0x00, 0xff, 0x00),
+        (0xff, 0xff, 0x00),
+        (0x5c, 0x5c, 0xff),
+        (0xff, 0x00, 0xff),
+        (0x00, 0xff, 0xff),
+        (0xff, 0xff, 0xff),
+    ];
+    let index = match color {
+        Color::Reset => return None,
+        Color::Rgb(r, g, b) => return Some((r, g, b)),
+        Color::Indexed(index) => index,
+        Color::Black => 0,
+        Color::Red => 1,
+        Color::Green => 2,
+        Color::Yellow => 3,
+        Color::Blue => 4,
+        Color::Magenta => 5,
+        Color::Cyan => 6,
+        Color::Gray => 7,
+        Color::DarkGray => 8,
+        Color::LightRed => 9,
+        Color::LightGreen => 10,
+        Color::LightYellow => 11,
+        Color::LightBlue => 12,
+        Color::LightMagenta => 13,
+        Color::LightCyan => 14,
+        Color::White => 15,
+    };
+    Some(match index {
+        0..=15 => BASIC[index as usize],
+        16..=231 => {
+            let level = |value: u8| if value == 0 { 0 } else { 55 + value * 40 };
+            let cube = index - 16;
+            (level(cube / 36), level(cube / 6 % 6), level(cube % 6))
+        }
+        _ => {
+            let gray = 8 + (index - 232) * 10;
+            (gray, gray, gray)
+        }
+    })
+}
+
+// without 24 bit color an rgb color is drawn as the closest of the 256
+// colors, the first 16 are left out since every terminal scheme changes them
+pub fn reduce_color(color: Color, true_color: bool) -> Color {
+    let Color::Rgb(r, g, b) = color else {
+        return color;
+    };
+    if true_color {
+        return color;
+    }
+    let distance = |index: u8| {
+        let (ir, ig, ib) = rgb_of(Color::Indexed(index)).unwrap_or_default();
+        let delta = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+        delta(r, ir) + delta(g, ig) + delta(b, ib)
+    };
+    Color::Indexed(
+        (16..=255)
+            .min_by_key(|index| distance(*index))
+            .unwrap_or(16),
+    )
+}
+
+// "auto" trusts $COLORTERM, which terminals with 24 bit color set, and the
+// terminfo names of $TERM that say so
+pub fn true_color(color_depth: &str) -> bool {
+    match color_depth {
+        "truecolor" | "24bit" => true,
+        "256" => false,
+        _ => {
+            let colorterm = std::env::var("COLORTERM").unwrap_or_default();
+            let term = std::env::var("TERM").unwrap_or_default();
+            matches!(colorterm.as_str(), "truecolor" | "24bit")
+                || term.ends_with("-direct")
+                || term.contains("truecolor")
+        }
+    }
+}
+
+fn builtin_themes() -> Vec<Theme> {
+    vec![
+        Theme::onedark(),
+        Theme::gruvbox(),
+        Theme::catppuccin(),
+        Theme::dracula(),
+        Theme::solarized_light(),
+    ]
+}
+
+pub fn themes_dir() -> PathBuf {
+    config_dir().join("themes")
+}
+
+// writes the built in themes to the themes directory the first time, as a
+// starting point for your own
+pub fn install_builtin_themes() -> anyhow::Result<()> {
+    let dir = themes_dir();
+    if dir.exists() {
+        return Ok(());
+    }
+    fs::create_dir_all(&dir)?;
+    for theme in builtin_themes() {
+        let file = ThemeFile {
+            colors: theme.colors,
+            syntax: theme.syntax,
+        };
+        fs::write(
+            dir.join(format!("{}.toml", theme.name)),
+            toml::to_string(&file)?,
+        )?;
+    }
+    Ok(())
+}
+
+// a theme file wins over the built in theme with the same name
+pub fn load(name: &str) -> anyhow::Result<Theme> {
+    let path = themes_dir().join(format!("{}.toml", name));
+    if !path.exists() {
+        if let Some(theme) = builtin_themes()
+            .into_iter()
+            .find(|theme| theme.name == name)
+        {
+            return Ok(theme);
+        }
+    }
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("unable to read {}", path.display()))?;
+    let file: ThemeFile =
+        toml::from_str(&content).with_context(|| format!("invalid theme {}", path.display()))?;
+    Ok(Theme {
+        name: name.to_string(),
+        colors: file.colors,
+        syntax: file.syntax,
+    })
+}
+
+// the theme set for the background of the terminal, or the built in one
+// when it can't be loaded, and whether the background is light
+pub fn configured(config: &Configuration) -> (Theme, bool) {
+    let light = use_light_theme(&config.color_mode);
+    let theme = match load(config.theme_name(light)) {
+        Ok(theme) => theme,
+        Err(e) if light => {
+            eprintln!("{:#}, falling back to {}", e, DEFAULT_LIGHT_THEME);
+            Theme::solarized_light()
+        }
+        Err(e) => {
+            eprintln!("{:#}, falling back to {}", e, DEFAULT_THEME);
+            Theme::onedark()
+        }
+    };
+    (theme, light)
+}
+
+// the .toml files in the themes directory, sorted
+pub fn theme_files() -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(themes_dir())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "toml")
+        })
+        .collect();
+    paths.sort();
+    paths
+}
+
+pub fn is_builtin(name: &str) -> bool {
+    builtin_themes().iter().any(|theme| theme.name == name)
+}
+
+// the errors `load` would report for the content of a theme file
+pub fn validate(content: &str) -> Result<(), toml::de::Error> {
+    toml::from_str::<ThemeFile>(content).map(|_| ())
+}
+
+// every theme that can be picked, sorted by name, and the errors of the
+// theme files that couldn't be loaded
+pub fn list_themes() -> (Vec<Theme>, Vec<String>) {
+    let mut names: Vec<String> = builtin_themes()
+        .into_iter()
+        .map(|theme| theme.name)
+        .collect();
+    names.extend(theme_files().into_iter().filter_map(|path| {
+        path.file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+    }));
+    names.sort();
+    names.dedup();
+
+    let mut themes = Vec::new();
+    let mut errors = Vec::new();
+    for name in names {
+        match load(&name) {
+            Ok(theme) => themes.push(theme),
+            Err(e) => errors.push(format!("{:#}", e)),
+        }
+    }
+    (themes, errors)
+}
+
+// "dark", "light" or "auto" to ask the terminal for its background color
+pub fn use_light_theme(color_mode: &str) -> bool {
+    match color_mode {
+        "light" => true,
+        "auto" => light_background().unwrap_or(false),
+        _ => false,
+    }
+}
+
+fn light_background() -> Option<bool> {
+    // set by rxvt, konsole and others as "foreground;background"
+    if let Ok(colors) = std::env::var("COLORFGBG") {
+        if let Some(Ok(background)) = colors.rsplit(';').next().map(str::parse::<u8>) {
+            return Some(background == 7 || background == 15);
+        }
+    }
+    query_background()
+}
+
+// asks the terminal for its background with OSC 11, terminals that don't
+// answer within a moment are treated as dark
+#[cfg(unix)]
+fn query_background() -> Option<bool> {
+    let mut tty = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .ok()?;
+    crossterm::terminal::enable_raw_mode().ok()?;
+    let answer = (|| {
+        tty.write_all(b"\x1b]11;?\x1b\\").ok()?;
+        tty.flush().ok()?;
+        let mut answer = Vec::new();
+        let mut buffer = [0u8; 64];
+        while !answer.ends_with(b"\x07") && !answer.ends_with(b"\x1b\\") {
+            let mut poll = libc::pollfd {
+                fd: tty.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            if unsafe { libc::poll(&mut poll, 1, 100) } <= 0 {
+                return None;
+            }
+            let read = tty.read(&mut buffer).ok()?;
+            if read == 0 {
+                return None;
+            }
+            answer.extend_from_slice(&buffer[..read]);
+        }
+        Some(answer)
+    })();
+    let _ = crossterm::terminal::disable_raw_mode();
+    parse_background(&String::from_utf8_lossy(&answer?))
+}
+
+#[cfg(not(unix))]
+fn query_background() -> Option<bool> {
+    None
+}
+
+// "\x1b]11;rgb:ffff/ffff/ffff\x07", each channel has 1 to 4 hex digits
+fn parse_background(answer: &str) -> Option<bool> {
+    let rgb = answer.split("rgb:").nth(1)?;
+    let channels: Vec<f64> = rgb
+        .split(['/', '\x07', '\x1b'])
+        .take(3)
+        .map(|channel| {
+            let value = u32::from_str_radix(channel, 16).ok()?;
+            Some(value as f64 / ((1u32 << (4 * channel.len())) - 1) as f64)
+        })
+        .collect::<Option<_>>()?;
+    if channels.len() != 3 {
+        return None;
+    }
+    let luminance = 0.2126 * channels[0] + 0.7152 * channels[1] + 0.0722 * channels[2];
+    Some(luminance > 0.5)
+}
+
+mod color_format {
+    use std::str::FromStr;
+
+    use ratatui::style::Color;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(color: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&color.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Color::from_str(&value).map_err(|_| D::Error::custom(format!("invalid color {:?}", value)))
+    }
+}
@@ -0,0 +1,178 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{Receiver, Sender},
+        Arc,
+    },
+    thread,
+    time::UNIX_EPOCH,
+};
+
+use image::{DynamicImage, ImageReader};
+use ratatui_image::{picker::Picker, protocol::StatefulProtocol};
+
+use crate::configuration;
+use crate::events::Waker;
+
+// a directory full of photos shouldn't decode all of them
+const MAX_THUMBNAILS: usize = 12;
+const THUMBNAIL_SIZE: u32 = 256;
+const IMAGE_EXTENSIONS: [&str; 8] = ["png", "jpg", "jpeg", "gif", "bmp", "webp", "ico", "tiff"];
+
+pub struct ThumbnailMessage {
+    pub dir: String,
+    pub path: String,
+    pub image: DynamicImage,
+}
+
+// thumbnails of the images in the selected directory, generated in the
+// background and cached on disk in the thumbs folder of the cache directory
+#[derive(Default)]
+pub struct Thumbnails {
+    pub dir: String,
+    pub images: Vec<(String, Box<dyn StatefulProtocol>)>,
+    receiver: Option<Receiver<ThumbnailMessage>>,
+    cancel: Arc<AtomicBool>,
+    waker: Waker,
+}
+
+impl Thumbnails {
+    pub fn new(waker: Waker) -> Thumbnails {
+        Thumbnails {
+            waker,
+            ..Thumbnails::default()
+        }
+    }
+
+    // starts over when the selection moved to another entry
+    pub fn update(&mut self, selected: &str) {
+        if self.dir == selected {
+            return;
+        }
+        self.cancel.store(true, Ordering::Relaxed);
+        self.dir = selected.to_string();
+        self.images.clear();
+        self.receiver = None;
+
+        if !Path::new(selected).is_dir() {
+            return;
+        }
+        let paths = image_files(Path::new(selected));
+        if paths.is_empty() {
+            return;
+        }
+
+        self.cancel = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = self.waker.channel();
+        self.receiver = Some(receiver);
+        let dir = self.dir.clone();
+        let cancel = self.cancel.clone();
+        thread::spawn(move || generate(dir, paths, cache_dir(), cancel, sender));
+    }
+
+    // whether a thumbnail was added
+    pub fn poll(&mut self, picker: &mut Picker) -> bool {
+        let mut added = false;
+        if let Some(receiver) = &self.receiver {
+            while let Ok(message) = receiver.try_recv() {
+                if message.dir == self.dir {
+                    let protocol = picker.new_resize_protocol(message.image);
+                    self.images.push((message.path, protocol));
+                    added = true;
+                }
+            }
+        }
+        added
+    }
+}
+
+pub fn cache_dir() -> PathBuf {
+    configuration::cache_dir().join("thumbs")
+}
+
+fn image_files(dir: &Path) -> Vec<String> {
+    let mut paths: Vec<String> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| is_image(path))
+            .map(|path| path.to_string_lossy().to_string())
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    paths.sort();
+    paths.truncate(MAX_THUMBNAILS);
+    paths
+}
+
+fn is_image(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .map(|extension| {
+                let extension = extension.to_string_lossy().to_lowercase();
+                IMAGE_EXTENSIONS.contains(&extension.as_str())
+            })
+            .unwrap_or(false)
+}
+
+// the cached file is named after the path, size and modification time so an
+// edited image gets a new thumbnail
+fn cache_path(cache: &Path, path: &str) -> Option<PathBuf> {
+    let metadata = fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    metadata.len().hash(&mut hasher);
+    modified.hash(&mut hasher);
+    Some(cache.join(format!("{:016x}.png", hasher.finish())))
+}
+
+fn thumbnail(path: &str, cache: &Path) -> anyhow::Result<DynamicImage> {
+    let cached = cache_path(cache, path);
+    if let Some(cached) = cached.as_ref().filter(|cached| cached.exists()) {
+        if let Ok(image) = image::open(cached) {
+            return Ok(image);
+        }
+    }
+
+    let image = ImageReader::open(path)?
+        .with_guessed_format()?
+        .decode()?
+        .thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE);
+    if let Some(cached) = cached {
+        // a cache that can't be written only costs the decode next time
+        let _ = fs::create_dir_all(cache);
+        let _ = image.save(&cached);
+    }
+    Ok(image)
+}
+
+pub fn generate(
+    dir: String,
+    paths: Vec<String>,
+    cache: PathBuf,
+    cancel: Arc<AtomicBool>,
+    sender: Sender<ThumbnailMessage>,
+) {
+    for path in paths {
+        if cancel.load(Ordering::Relaxed) {
+            return;
+        }
+        if let Ok(image) = thumbnail(&path, &cache) {
+            let message = ThumbnailMessage {
+                dir: dir.clone(),
+                path,
+                image,
+            };
+            if sender.send(message).is_err() {
+                return;
+            }
+        }
+    }
+}
@@ -1,11 +1,363 @@
-use std::rc::Rc;
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+    path::Path,
+};
 
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    Frame,
+    style::{Modifier, Style},
+    text::Span,
+    widgets::{List, ListItem, ListState, Row, Table},
 };
 
-#[derive(Debug)]
-pub struct Ui {}
+use crate::entry_meta::EntryMetaCache;
+use crate::file_reader_content::CsvTable;
+use crate::icons;
+use crate::ignore_rules::IgnorePatterns;
+use crate::operations::permissions::mode_string;
+use crate::theme::ThemeColors;
+use crate::utils::files::SortOptions;
+use crate::utils::format::{format_size, format_timestamp};
+use crate::{get_file_path_data, SortBy, SortType};
+
+pub const DETAIL_COLUMNS: [&str; 4] = ["Name", "Size", "Modified", "Permissions"];
+
+const DETAIL_WIDTHS: [Constraint; 4] = [
+    Constraint::Min(20),
+    Constraint::Length(10),
+    Constraint::Length(19),
+    Constraint::Length(11),
+];
+
+// table of the current listing, `sorted` is the column the listing is sorted
+// by and gets an arrow in the header
+// `text` with the chars at `indices` in the search match style
+// the entries a list of `len` entries shows in `rows` rows, scrolled as
+// little as possible from `offset` to keep `selected` in view
+pub fn list_window(
+    offset: usize,
+    selected: Option<usize>,
+    rows: usize,
+    len: usize,
+) -> Range<usize> {
+    let mut start = offset.min(len.saturating_sub(rows));
+    if let Some(selected) = selected.map(|selected| selected.min(len.saturating_sub(1))) {
+        if selected < start {
+            start = selected;
+        } else if selected >= start + rows {
+            start = selected + 1 - rows;
+        }
+    }
+    start..(start + rows).min(len)
+}
+
+pub fn highlight_search_term(
+    text: &str,
+    indices: &[usize],
+    colors: &ThemeColors,
+) -> Vec<Span<'static>> {
+    let matched = header_style(colors);
+    let mut spans = Vec::new();
+    let mut current = String::new();
+    let mut current_matched = false;
+    for (index, c) in text.chars().enumerate() {
+        let is_match = indices.binary_search(&index).is_ok();
+        if is_match != current_matched && !current.is_empty() {
+            let run = std::mem::take(&mut current);
+            spans.push(if current_matched {
+                Span::styled(run, matched)
+            } else {
+                Span::raw(run)
+            });
+        }
+        current_matched = is_match;
+        current.push(c);
+    }
+    if !current.is_empty() {
+        spans.push(if current_matched {
+            Span::styled(current, matched)
+        } else {
+            Span::raw(current)
+        });
+    }
+    spans
+}
+
+// search matches and table headers
+fn header_style(colors: &ThemeColors) -> Style {
+    Style::default()
+        .fg(colors.highlight)
+        .add_modifier(Modifier::BOLD)
+}
+
+// csv preview starting at row `row_offset` and column `column_offset`
+pub fn csv_table(
+    csv: &CsvTable,
+    row_offset: usize,
+    column_offset: usize,
+    colors: &ThemeColors,
+) -> Table<'static> {
+    let cells =
+        |row: &Vec<String>| -> Vec<String> { row.iter().skip(column_offset).cloned().collect() };
+    let widths: Vec<Constraint> = csv
+        .widths
+        .iter()
+        .skip(column_offset)
+        .map(|width| Constraint::Length(*width))
+        .collect();
+
+    let rows: Vec<Row> = csv
+        .rows
+        .iter()
+        .skip(row_offset)
+        .map(|row| Row::new(cells(row)))
+        .collect();
+    let mut table = Table::new(rows, widths).column_spacing(2);
+    if let Some(header) = &csv.header {
+        table = table.header(Row::new(cells(header)).style(header_style(colors)));
+    }
+    table
+}
+
+pub fn detail_table<'a>(
+    files: &'a [String],
+    marked: &[String],
+    locked: &HashSet<String>,
+    dir_sizes: &HashMap<String, u64>,
+    entry_meta: &EntryMetaCache,
+    sorted: Option<(usize, bool)>,
+    colors: &ThemeColors,
+) -> Table<'a> {
+    let header = Row::new(
+        DETAIL_COLUMNS
+            .iter()
+            .enumerate()
+            .map(|(index, title)| match sorted {
+                Some((column, ascending)) if column == index => {
+                    format!("{} {}", title, if ascending { "^" } else { "v" })
+                }
+                _ => title.to_string(),
+            }),
+    )
+    .style(header_style(colors));
+
+    let rows = files.iter().map(|file| {
+        let metadata = entry_meta.get(file);
+        let is_dir = metadata
+            .as_ref()
+            .map(|meta| meta.is_dir && !meta.is_symlink)
+            .unwrap_or(false);
+
+        let mut name = Path::new(file)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| file.clone());
+        if is_dir {
+            name.push('/');
+        }
+        if locked.contains(file) {
+            name = format!("{} {}", icons::LOCK, name);
+        }
+        if marked.contains(file) {
+            name = format!("* {}", name);
+        }
+
+        let size = match (dir_sizes.get(file), &metadata) {
+            (Some(size), _) => format_size(*size),
+            (None, Some(_)) if is_dir => String::from("-"),
+            (None, Some(meta)) => format_size(meta.size),
+            (None, None) => String::new(),
+        };
+        let modified = metadata
+            .as_ref()
+            .and_then(|meta| meta.modified)
+            .map(format_timestamp)
+            .unwrap_or_default();
+        let permissions = metadata
+            .as_ref()
+            .map(|meta| {
+                let kind = if meta.is_symlink {
+                    'l'
+                } else if is_dir {
+                    'd'
+                } else {
+                    '-'
+                };
+                format!("{}{}", kind, mode_string(meta.mode))
+            })
+            .unwrap_or_default();
+
+        Row::new(vec![name, size, modified, permissions])
+    });
+
+    Table::new(rows, DETAIL_WIDTHS).header(header)
+}
+
+// column under `x` when clicking the header of a table drawn in `area`
+pub fn detail_column_at(area: Rect, x: u16) -> Option<usize> {
+    // border on the left and the highlight symbol column
+    let inner = Rect {
+        x: area.x + 2,
+        width: area.width.saturating_sub(3),
+        ..area
+    };
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(DETAIL_WIDTHS)
+        .spacing(1)
+        .split(inner)
+        .iter()
+        .position(|column| x >= column.x && x < column.x + column.width)
+}
+
+#[derive(Debug, Clone)]
+pub struct TreeNode {
+    pub path: String,
+    pub depth: usize,
+    pub is_dir: bool,
+    pub expanded: bool,
+}
+
+// the tree flattened in display order, children are read the first time a
+// directory is expanded and dropped again when it is collapsed
+#[derive(Debug, Default)]
+pub struct TreeState {
+    pub nodes: Vec<TreeNode>,
+    pub list_state: ListState,
+    show_hidden: bool,
+    // with the ignore toggle on
+    ignore: Option<IgnorePatterns>,
+    sort_options: SortOptions,
+}
+
+impl TreeState {
+    pub fn new(
+        root: &str,
+        show_hidden: bool,
+        ignore: Option<IgnorePatterns>,
+        sort_options: SortOptions,
+    ) -> TreeState {
+        let mut tree = TreeState {
+            nodes: Vec::new(),
+            list_state: ListState::default(),
+            show_hidden,
+            ignore,
+            sort_options,
+        };
+        tree.nodes = tree.children(root, 0);
+        if !tree.nodes.is_empty() {
+            tree.list_state.select(Some(0));
+        }
+        tree
+    }
+
+    fn children(&self, dir: &str, depth: usize) -> Vec<TreeNode> {
+        let paths = get_file_path_data(
+            dir.to_string(),
+            self.show_hidden,
+            self.ignore.as_ref(),
+            SortBy::Name,
+            &SortType::ASC,
+            &self.sort_options,
+        )
+        .unwrap_or_default();
+
+        let mut nodes: Vec<TreeNode> = paths
+            .into_iter()
+            .map(|path| TreeNode {
+                is_dir: Path::new(&path).is_dir(),
+                path,
+                depth,
+                expanded: false,
+            })
+            .collect();
+        // directories first, the sort is stable so names stay in order
+        nodes.sort_by_key(|node| !node.is_dir);
+        nodes
+    }
+
+    pub fn selected(&self) -> Option<&TreeNode> {
+        self.list_state
+            .selected()
+            .and_then(|index| self.nodes.get(index))
+    }
+
+    pub fn move_by(&mut self, delta: isize) {
+        if self.nodes.is_empty() {
+            return;
+        }
+        let current = self.list_state.selected().unwrap_or(0) as isize;
+        let index = (current + delta).clamp(0, self.nodes.len() as isize - 1);
+        self.list_state.select(Some(index as usize));
+    }
+
+    pub fn expand(&mut self) {
+        if let Some(index) = self.list_state.selected() {
+            let node = &self.nodes[index];
+            if !node.is_dir || node.expanded {
+                return;
+            }
+            let children = self.children(&node.path, node.depth + 1);
+            self.nodes[index].expanded = true;
+            self.nodes.splice(index + 1..index + 1, children);
+        }
+    }
+
+    // collapses the selected directory, or selects its parent when there is
+    // nothing to collapse
+    pub fn collapse(&mut self) {
+        if let Some(index) = self.list_state.selected() {
+            let depth = self.nodes[index].depth;
+            if self.nodes[index].expanded {
+                let end = self.nodes[index + 1..]
+                    .iter()
+                    .position(|node| node.depth <= depth)
+                    .map(|offset| index + 1 + offset)
+                    .unwrap_or(self.nodes.len());
+                self.nodes.drain(index + 1..end);
+                self.nodes[index].expanded = false;
+            } else if let Some(parent) = self.nodes[..index]
+                .iter()
+                .rposition(|node| node.depth < depth)
+            {
+                self.list_state.select(Some(parent));
+            }
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        match self.selected() {
+            Some(node) if node.expanded => self.collapse(),
+            Some(_) => self.expand(),
+            None => {}
+        }
+    }
+}
+
+pub fn tree_list(tree: &TreeState) -> List<'static> {
+    let items: Vec<ListItem> = tree
+        .nodes
+        .iter()
+        .map(|node| {
+            let name = Path::new(&node.path)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| node.path.clone());
+            let label = match (node.is_dir, node.expanded) {
+                (true, true) => format!("v {}/", name),
+                (true, false) => format!("> {}/", name),
+                (false, _) => format!("  {}", name),
+            };
+            ListItem::new(format!("{}{}", "  ".repeat(node.depth), label))
+        })
+        .collect();
+    List::new(items)
+}
 
-impl Ui {}
+// entries that fit in the listing drawn in `list_area`, inside the border
+// and below the header of the detail view
+pub fn list_rows(list_area: Rect, detail_view: bool) -> usize {
+    let chrome = if detail_view { 3 } else { 2 };
+    list_area.height.saturating_sub(chrome).max(1) as usize
+}
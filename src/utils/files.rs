@@ -0,0 +1,485 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::HashMap,
+    fs, io,
+    iter::Peekable,
+    path::{Path, PathBuf},
+    str::Chars,
+    time::SystemTime,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::entry_meta::{self, EntryMeta};
+use crate::ignore_rules::{self, IgnorePatterns};
+
+#[derive(Clone)]
+pub enum SortType {
+    ASC,
+    DESC,
+}
+
+#[derive(Debug, Clone)]
+pub enum SortBy {
+    Name,
+    Size,
+    DateAdded,
+    // recursive size of directories, see sort_by_real_size
+    RealSize,
+    Default,
+}
+
+// how names are compared when sorting by name
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SortOptions {
+    // numbers in names compare by value, file2 before file10
+    #[serde(default)]
+    pub natural: bool,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    // directories above files whatever the sort key
+    #[serde(default)]
+    pub directories_first: bool,
+}
+
+fn take_digits(chars: &mut Peekable<Chars>) -> String {
+    let mut digits = String::new();
+    while let Some(c) = chars.peek().copied() {
+        if !c.is_ascii_digit() {
+            break;
+        }
+        digits.push(c);
+        chars.next();
+    }
+    digits
+}
+
+// compares runs of digits by their value and everything else char by char
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+
+    loop {
+        match (a_chars.peek().copied(), b_chars.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let a_number = take_digits(&mut a_chars);
+                let b_number = take_digits(&mut b_chars);
+                let a_value = a_number.trim_start_matches('0');
+                let b_value = b_number.trim_start_matches('0');
+                // longer without leading zeros is bigger, `007` sorts after `7`
+                let ordering = a_value
+                    .len()
+                    .cmp(&b_value.len())
+                    .then_with(|| a_value.cmp(b_value))
+                    .then_with(|| a_number.len().cmp(&b_number.len()));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(&y);
+                }
+                a_chars.next();
+                b_chars.next();
+            }
+        }
+    }
+}
+
+fn compare_names(a: &PathBuf, b: &PathBuf, options: &SortOptions) -> Ordering {
+    let name = |path: &PathBuf| {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if options.case_sensitive {
+            name
+        } else {
+            name.to_lowercase()
+        }
+    };
+
+    let (a, b) = (name(a), name(b));
+    if options.natural {
+        natural_cmp(&a, &b)
+    } else {
+        a.cmp(&b)
+    }
+}
+
+pub fn sort_entries_by_type(
+    sort_by: SortBy,
+    sort_type: SortType,
+    options: &SortOptions,
+    mut entries: Vec<(PathBuf, EntryMeta)>,
+) -> Vec<(PathBuf, EntryMeta)> {
+    let now = SystemTime::now();
+    match sort_by {
+        SortBy::Name => entries.sort_by(|(a, _), (b, _)| compare_names(a, b, options)),
+        SortBy::Size | SortBy::RealSize => entries.sort_by_key(|(_, meta)| meta.size),
+        SortBy::DateAdded => entries.sort_by_key(|(_, meta)| meta.created.unwrap_or(now)),
+        SortBy::Default => {}
+    }
+
+    if let (SortType::DESC, false) = (sort_type, matches!(sort_by, SortBy::Default)) {
+        entries.reverse();
+    }
+    // stable, so both groups keep the order of the sort key
+    if options.directories_first {
+        entries.sort_by_key(|(_, meta)| !meta.is_dir);
+    }
+    entries
+}
+
+// sorts by the calculated recursive size, entries that were not calculated yet
+// fall back to their own size
+pub fn sort_by_real_size(
+    files: &mut [String],
+    sizes: &HashMap<String, u64>,
+    sort_type: &SortType,
+    options: &SortOptions,
+) {
+    let size_of = |path: &String| -> u64 {
+        match sizes.get(path) {
+            Some(size) => *size,
+            None => fs::symlink_metadata(path).map(|m| m.len()).unwrap_or(0),
+        }
+    };
+
+    match sort_type {
+        SortType::ASC => files.sort_by_key(|path| size_of(path)),
+        SortType::DESC => files.sort_by_key(|path| Reverse(size_of(path))),
+    }
+    if options.directories_first {
+        files.sort_by_key(|path| !Path::new(path).is_dir());
+    }
+}
+
+pub fn convert_file_path_to_string(
+    entries: Vec<PathBuf>,
+    show_hidden: bool,
+    sort_by: SortBy,
+    sort_type: SortType,
+    sort_options: &SortOptions,
+) -> Vec<String> {
+    // every entry is read once up front, sorting and filtering reuse it
+    let entries = entry_meta::read_all(entries);
+    sort_entries_by_type(sort_by, sort_type, sort_options, entries)
+        .into_iter()
+        // symlinks are kept even when their target is missing so broken links
+        // can still be seen and removed
+        .filter(|(_, meta)| meta.is_dir || meta.is_file || meta.is_symlink)
+        .filter(|(path, meta)| {
+            show_hidden
+                || meta.is_dir
+                || !path.file_name().unwrap().to_string_lossy().starts_with('.')
+        })
+        .map(|(path, _)| path.to_string_lossy().to_string())
+        .collect()
+}
+
+pub fn get_file_path_data(
+    start_path: String,
+    show_hidden: bool,
+    ignore: Option<&IgnorePatterns>,
+    sort_by: SortBy,
+    sort_type: &SortType,
+    sort_options: &SortOptions,
+) -> anyhow::Result<Vec<String>> {
+    let mut entries = fs::read_dir(&start_path)?
+        .map(|res| res.map(|e| e.path()))
+        .collect::<Result<Vec<_>, io::Error>>()?;
+
+    if let Some(patterns) = ignore {
+        entries = ignore_rules::filter_ignored(Path::new(&start_path), entries, patterns);
+    }
+
+    let file_strings = convert_file_path_to_string(
+        entries,
+        show_hidden,
+        sort_by,
+        sort_type.clone(),
+        sort_options,
+    );
+
+    Ok(file_strings)
+}
+
+// `path` as seen from `base`, going up with ".." where they diverge. both are
+// expected to be absolute
+pub fn relative_path(path: &Path, base: &Path) -> PathBuf {
+    let path: Vec<_> = path.components().collect();
+    let base: Vec<_> = base.components().collect();
+    let common = path
+        .iter()
+        .zip(base.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut relative = PathBuf::new();
+    for _ in common..base.len() {
+        relative.push("..");
+    }
+    for component in &path[common..] {
+        relative.push(component);
+    }
+    if relative.as_os_str().is_empty() {
+        relative.push(".");
+    }
+    relative
+}
+
+pub fn is_file(path: String) -> bool {
+    fs::metadata(path)
+        .map(|metadata| metadata.is_file())
+        .unwrap_or(false)
+}
+
+pub fn get_content_from_path(path: String) -> io::Result<Vec<String>> {
+    let mut file_name_list: Vec<String> = Vec::new();
+    for entry in fs::read_dir(path)? {
+        let file_name = entry?.file_name().to_string_lossy().to_string();
+        file_name_list.push(file_name);
+    }
+    Ok(file_name_list)
+}
+
+// the link target as written in the link, relative targets are resolved
+// against the directory that contains the link
+pub fn symlink_target(path: &str) -> Option<PathBuf> {
+    let target = fs::read_link(path).ok()?;
+    if target.is_absolute() {
+        Some(target)
+    } else {
+        Path::new(path).parent().map(|parent| parent.join(target))
+    }
+}
+
+// the directory holding `path`, empty for a root like `/` or `C:\`
+pub fn get_curr_path(path: String) -> String {
+    Path::new(&path)
+        .parent()
+        .map(|parent| parent.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+// directory and name of `path`
+pub fn split_file_name(path: &str) -> (String, String) {
+    let name = Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    (get_curr_path(path.to_string()), name)
+}
+
+// the copied name when it is free in `new_path`, else a numeric suffix before
+// the extension, `photo.jpg` -> `photo (1).jpg`
+pub fn generate_copy_file_dir_name(curr_path: String, new_path: String) -> String {
+    let get_info = Path::new(&curr_path);
+    let file_name = get_info
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let candidate = Path::new(&new_path).join(&file_name).display().to_string();
+    if !Path::new(&candidate).exists() {
+        return candidate;
+    }
+
+    // directories and dotfiles like `.bashrc` have no extension to keep
+    let (stem, extension) = match get_info.extension() {
+        Some(extension) if get_info.is_file() => (
+            get_info
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            format!(".{}", extension.to_string_lossy()),
+        ),
+        _ => (file_name, String::new()),
+    };
+    (1..)
+        .map(|n| {
+            Path::new(&new_path)
+                .join(format!("{} ({}){}", stem, n, extension))
+                .display()
+                .to_string()
+        })
+        .find(|candidate| !Path::new(candidate).exists())
+        .unwrap_or(candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, is_dir: bool) -> (PathBuf, EntryMeta) {
+        let meta = EntryMeta {
+            size: 0,
+            modified: None,
+            created: None,
+            is_dir,
+            is_file: !is_dir,
+            is_symlink: false,
+            mode: 0,
+            link_target: None,
+            broken: false,
+        };
+        (PathBuf::from("/dir").join(name), meta)
+    }
+
+    fn sorted(sort_type: SortType, options: SortOptions, names: &[(&str, bool)]) -> Vec<String> {
+        let entries = names
+            .iter()
+            .map(|(name, is_dir)| entry(name, *is_dir))
+            .collect();
+        sort_entries_by_type(SortBy::Name, sort_type, &options, entries)
+            .into_iter()
+            .map(|(path, _)| path.file_name().unwrap().to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn numbers_compare_by_value() {
+        assert_eq!(natural_cmp("file2", "file10"), Ordering::Less);
+        assert_eq!(natural_cmp("file10", "file2"), Ordering::Greater);
+        assert_eq!(natural_cmp("v1.9.txt", "v1.10.txt"), Ordering::Less);
+        assert_eq!(natural_cmp("file10", "file10"), Ordering::Equal);
+    }
+
+    #[test]
+    fn leading_zeros_break_ties_only() {
+        assert_eq!(natural_cmp("7", "007"), Ordering::Less);
+        assert_eq!(natural_cmp("007", "7"), Ordering::Greater);
+        assert_eq!(natural_cmp("file007", "file8"), Ordering::Less);
+        assert_eq!(natural_cmp("file07", "file007"), Ordering::Less);
+    }
+
+    #[test]
+    fn digits_at_the_end_of_a_name() {
+        assert_eq!(natural_cmp("track", "track1"), Ordering::Less);
+        assert_eq!(natural_cmp("track9", "track10"), Ordering::Less);
+        assert_eq!(natural_cmp("track1", "track1a"), Ordering::Less);
+    }
+
+    #[test]
+    fn case_folding() {
+        let names = [("b", false), ("A", false), ("a", false), ("B", false)];
+        let natural = SortOptions {
+            natural: true,
+            ..Default::default()
+        };
+        // folded names that compare equal keep their order
+        assert_eq!(
+            sorted(SortType::ASC, natural.clone(), &names),
+            vec!["A", "a", "b", "B"]
+        );
+
+        let case_sensitive = SortOptions {
+            case_sensitive: true,
+            ..natural
+        };
+        assert_eq!(
+            sorted(SortType::ASC, case_sensitive, &names),
+            vec!["A", "B", "a", "b"]
+        );
+    }
+
+    #[test]
+    fn natural_order_is_used_only_when_enabled() {
+        let names = [("file10", false), ("file2", false)];
+        assert_eq!(
+            sorted(SortType::ASC, SortOptions::default(), &names),
+            vec!["file10", "file2"]
+        );
+        let natural = SortOptions {
+            natural: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            sorted(SortType::ASC, natural, &names),
+            vec!["file2", "file10"]
+        );
+    }
+
+    #[test]
+    fn directories_first_with_descending_names() {
+        let names = [("b", true), ("a.txt", false), ("c.txt", false), ("d", true)];
+        let options = SortOptions {
+            directories_first: true,
+            ..Default::default()
+        };
+        // both groups descend, directories still on top
+        assert_eq!(
+            sorted(SortType::DESC, options, &names),
+            vec!["d", "b", "c.txt", "a.txt"]
+        );
+        assert_eq!(
+            sorted(SortType::DESC, SortOptions::default(), &names),
+            vec!["d", "c.txt", "b", "a.txt"]
+        );
+    }
+
+    #[test]
+    fn real_size_uses_calculated_directory_sizes() {
+        let dir = tempfile::tempdir().unwrap();
+        let big = dir.path().join("big");
+        let small = dir.path().join("small.txt");
+        fs::create_dir(&big).unwrap();
+        fs::write(&small, "small").unwrap();
+        let (big, small) = (
+            big.to_string_lossy().to_string(),
+            small.to_string_lossy().to_string(),
+        );
+        let sizes = HashMap::from([(big.clone(), 1_000_000)]);
+
+        let mut files = vec![big.clone(), small.clone()];
+        sort_by_real_size(&mut files, &sizes, &SortType::ASC, &SortOptions::default());
+        assert_eq!(files, vec![small.clone(), big.clone()]);
+
+        let options = SortOptions {
+            directories_first: true,
+            ..SortOptions::default()
+        };
+        sort_by_real_size(&mut files, &sizes, &SortType::ASC, &options);
+        assert_eq!(files, vec![big, small]);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn windows_separators_and_drive_roots() {
+        assert_eq!(
+            get_curr_path(String::from(r"C:\Users\me\notes.txt")),
+            r"C:\Users\me"
+        );
+        assert_eq!(get_curr_path(String::from(r"C:\Users")), r"C:\");
+        assert_eq!(get_curr_path(String::from(r"C:\")), "");
+        assert_eq!(
+            split_file_name(r"C:\Users\me\notes.txt"),
+            (String::from(r"C:\Users\me"), String::from("notes.txt"))
+        );
+        assert_eq!(split_file_name(r"D:\"), (String::new(), String::new()));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn windows_copy_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("photo.jpg");
+        fs::write(&source, "jpg").unwrap();
+        let source = source.to_string_lossy().to_string();
+        let dest = dir.path().to_string_lossy().to_string();
+
+        assert_eq!(
+            generate_copy_file_dir_name(source.clone(), dest.clone()),
+            format!(r"{}\photo (1).jpg", dest)
+        );
+        assert_eq!(
+            generate_copy_file_dir_name(source, String::from(r"Z:\")),
+            r"Z:\photo.jpg"
+        );
+    }
+}
@@ -0,0 +1,76 @@
+use std::path::Path;
+
+#[cfg(feature = "s3")]
+pub mod s3;
+
+// an entry of a listing that isn't on the local file system
+#[derive(Debug, Clone)]
+pub struct VfsEntry {
+    pub path: String,
+    pub name: String,
+    pub is_dir: bool,
+    pub size: u64,
+}
+
+// called with the bytes transferred so far and the total
+pub type Progress<'a> = &'a dyn Fn(u64, u64);
+
+// a place to browse besides the local disk, paths are urls like
+// `s3://bucket/prefix/` and directories end with a `/`
+pub trait Vfs: Send + Sync {
+    fn list(&self, dir: &str) -> anyhow::Result<Vec<VfsEntry>>;
+    // the first `limit` bytes, for previews
+    fn read(&self, path: &str, limit: u64) -> anyhow::Result<Vec<u8>>;
+    fn download(&self, path: &str, dest: &Path, progress: Progress) -> anyhow::Result<()>;
+    fn upload(&self, source: &Path, path: &str, progress: Progress) -> anyhow::Result<()>;
+}
+
+pub fn is_remote(path: &str) -> bool {
+    path.starts_with("s3://")
+}
+
+pub fn open(url: &str) -> anyhow::Result<Box<dyn Vfs>> {
+    match url.split_once("://").map(|(scheme, _)| scheme) {
+        #[cfg(feature = "s3")]
+        Some("s3") => Ok(Box::new(s3::S3::from_env(url)?)),
+        #[cfg(not(feature = "s3"))]
+        Some("s3") => Err(anyhow::anyhow!(
+            "cannot open {}, build with `--features s3` to browse buckets",
+            url
+        )),
+        _ => Err(anyhow::anyhow!("unsupported location: {}", url)),
+    }
+}
+
+// the directory holding `path`, None at the root of the bucket
+pub fn parent(path: &str) -> Option<String> {
+    let (scheme, rest) = path.split_once("://")?;
+    let trimmed = rest.trim_end_matches('/');
+    let index = trimmed.rfind('/')?;
+    Some(format!("{}://{}/", scheme, &trimmed[..index]))
+}
+
+#[cfg(all(test, windows))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn local_windows_paths_are_not_remote() {
+        assert!(!is_remote(r"C:\Users\me"));
+        assert_eq!(parent(r"C:\Users\me"), None);
+        assert_eq!(parent(r"C:\"), None);
+    }
+
+    #[test]
+    fn backslashes_in_keys_are_not_separators() {
+        assert_eq!(
+            parent("s3://bucket/photos/2024/"),
+            Some(String::from("s3://bucket/photos/"))
+        );
+        assert_eq!(
+            parent(r"s3://bucket/photos\2024.jpg"),
+            Some(String::from("s3://bucket/"))
+        );
+        assert_eq!(parent("s3://bucket/"), None);
+    }
+}
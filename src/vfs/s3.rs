@@ -0,0 +1,378 @@
+use std::{
+    fs::File,
+    io::{self, Read, Write},
+    path::Path,
+    time::SystemTime,
+};
+
+use anyhow::{anyhow, bail, Context};
+use hmac::{Hmac, Mac};
+use quick_xml::{events::Event, Reader};
+use sha2::{Digest, Sha256};
+
+use super::{Progress, Vfs, VfsEntry};
+use crate::utils::format::format_timestamp;
+
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+// an S3 compatible bucket. credentials come from the usual AWS_* variables,
+// AWS_ENDPOINT_URL points at other services like minio or r2
+pub struct S3 {
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+    session_token: Option<String>,
+    // scheme and host, objects are addressed as {endpoint}/{bucket}/{key}
+    // with a custom endpoint and as {bucket}.{endpoint}/{key} on aws
+    endpoint: String,
+    path_style: bool,
+}
+
+impl S3 {
+    pub fn from_env(url: &str) -> anyhow::Result<S3> {
+        let (bucket, _) = split_url(url)?;
+        let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+        let region = var("AWS_REGION")
+            .or_else(|| var("AWS_DEFAULT_REGION"))
+            .unwrap_or_else(|| String::from("us-east-1"));
+        let (endpoint, path_style) = match var("AWS_ENDPOINT_URL") {
+            Some(endpoint) => (endpoint.trim_end_matches('/').to_string(), true),
+            None => (format!("https://s3.{}.amazonaws.com", region), false),
+        };
+        Ok(S3 {
+            bucket,
+            access_key: var("AWS_ACCESS_KEY_ID").context("AWS_ACCESS_KEY_ID is not set")?,
+            secret_key: var("AWS_SECRET_ACCESS_KEY").context("AWS_SECRET_ACCESS_KEY is not set")?,
+            session_token: var("AWS_SESSION_TOKEN"),
+            region,
+            endpoint,
+            path_style,
+        })
+    }
+
+    fn key_of(&self, path: &str) -> anyhow::Result<String> {
+        let (bucket, key) = split_url(path)?;
+        if bucket != self.bucket {
+            bail!("{} is not in the bucket {}", path, self.bucket);
+        }
+        Ok(key)
+    }
+
+    // a signed request for `key` with the query parameters already sorted
+    fn request(
+        &self,
+        method: &str,
+        key: &str,
+        query: &[(&str, &str)],
+    ) -> anyhow::Result<ureq::Request> {
+        let (scheme, host) = self
+            .endpoint
+            .split_once("://")
+            .ok_or_else(|| anyhow!("invalid endpoint {}", self.endpoint))?;
+        let (host, uri) = if self.path_style && key.is_empty() {
+            (host.to_string(), format!("/{}", self.bucket))
+        } else if self.path_style {
+            (host.to_string(), format!("/{}/{}", self.bucket, key))
+        } else {
+            (format!("{}.{}", self.bucket, host), format!("/{}", key))
+        };
+        let uri = uri_encode(&uri, false);
+        let mut query: Vec<(String, String)> = query
+            .iter()
+            .map(|(name, value)| (uri_encode(name, true), uri_encode(value, true)))
+            .collect();
+        query.sort();
+        let query = query
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let (date, timestamp) = amz_date(SystemTime::now());
+        let mut headers = vec![
+            ("host", host.clone()),
+            ("x-amz-content-sha256", String::from("UNSIGNED-PAYLOAD")),
+            ("x-amz-date", timestamp.clone()),
+        ];
+        if let Some(token) = &self.session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+        let signed_headers = headers
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(";");
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+            .collect();
+        let canonical_request = format!(
+            "{}\n{}\n{}\n{}\n{}\nUNSIGNED-PAYLOAD",
+            method, uri, query, canonical_headers, signed_headers
+        );
+
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            timestamp,
+            scope,
+            hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+        let mut key = hmac(
+            format!("AWS4{}", self.secret_key).as_bytes(),
+            date.as_bytes(),
+        );
+        for part in [self.region.as_str(), "s3", "aws4_request"] {
+            key = hmac(&key, part.as_bytes());
+        }
+        let signature = hex(&hmac(&key, string_to_sign.as_bytes()));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key, scope, signed_headers, signature
+        );
+
+        let url = if query.is_empty() {
+            format!("{}://{}{}", scheme, host, uri)
+        } else {
+            format!("{}://{}{}?{}", scheme, host, uri, query)
+        };
+        let mut request = ureq::request(method, &url).set("authorization", &authorization);
+        for (name, value) in headers.iter().filter(|(name, _)| *name != "host") {
+            request = request.set(name, value);
+        }
+        Ok(request)
+    }
+}
+
+impl Vfs for S3 {
+    // ListObjectsV2 with a `/` delimiter, common prefixes are the directories
+    fn list(&self, dir: &str) -> anyhow::Result<Vec<VfsEntry>> {
+        let prefix = self.key_of(dir)?;
+        let mut entries = Vec::new();
+        let mut token: Option<String> = None;
+        loop {
+            let mut query = vec![
+                ("delimiter", "/"),
+                ("list-type", "2"),
+                ("prefix", prefix.as_str()),
+            ];
+            if let Some(token) = &token {
+                query.push(("continuation-token", token.as_str()));
+            }
+            let body = self
+                .request("GET", "", &query)?
+                .call()
+                .map_err(request_error)?
+                .into_string()?;
+            let page = parse_listing(&body)?;
+            for (key, size, is_dir) in page.entries {
+                let name = key[prefix.len()..].trim_end_matches('/').to_string();
+                if name.is_empty() {
+                    continue;
+                }
+                entries.push(VfsEntry {
+                    path: format!("s3://{}/{}", self.bucket, key),
+                    name,
+                    is_dir,
+                    size,
+                });
+            }
+            match page.next_token {
+                Some(next) => token = Some(next),
+                None => break,
+            }
+        }
+        entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then(a.name.cmp(&b.name)));
+        Ok(entries)
+    }
+
+    fn read(&self, path: &str, limit: u64) -> anyhow::Result<Vec<u8>> {
+        let key = self.key_of(path)?;
+        let response = self
+            .request("GET", &key, &[])?
+            .set("range", &format!("bytes=0-{}", limit.saturating_sub(1)))
+            .call()
+            .map_err(request_error)?;
+        let mut content = Vec::new();
+        response
+            .into_reader()
+            .take(limit)
+            .read_to_end(&mut content)?;
+        Ok(content)
+    }
+
+    fn download(&self, path: &str, dest: &Path, progress: Progress) -> anyhow::Result<()> {
+        let key = self.key_of(path)?;
+        let response = self
+            .request("GET", &key, &[])?
+            .call()
+            .map_err(request_error)?;
+        let total = response
+            .header("content-length")
+            .and_then(|length| length.parse().ok())
+            .unwrap_or(0);
+
+        let mut reader = response.into_reader();
+        let mut output = File::create(dest)?;
+        let mut buffer = vec![0u8; CHUNK_SIZE];
+        let mut copied = 0;
+        loop {
+            let read = reader.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            output.write_all(&buffer[..read])?;
+            copied += read as u64;
+            progress(copied, total);
+        }
+        Ok(())
+    }
+
+    // a single PUT, objects over 5 GB would need a multipart upload
+    fn upload(&self, source: &Path, path: &str, progress: Progress) -> anyhow::Result<()> {
+        let key = self.key_of(path)?;
+        let file = File::open(source)?;
+        let total = file.metadata()?.len();
+        let reader = ProgressReader {
+            inner: file,
+            read: 0,
+            total,
+            progress,
+        };
+        self.request("PUT", &key, &[])?
+            .set("content-length", &total.to_string())
+            .send(reader)
+            .map_err(request_error)?;
+        Ok(())
+    }
+}
+
+struct ProgressReader<'a, R> {
+    inner: R,
+    read: u64,
+    total: u64,
+    progress: Progress<'a>,
+}
+
+impl<R: Read> Read for ProgressReader<'_, R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buffer)?;
+        self.read += read as u64;
+        (self.progress)(self.read, self.total);
+        Ok(read)
+    }
+}
+
+#[derive(Default)]
+struct ListingPage {
+    // key, size and whether it is a common prefix
+    entries: Vec<(String, u64, bool)>,
+    next_token: Option<String>,
+}
+
+fn parse_listing(xml: &str) -> anyhow::Result<ListingPage> {
+    let mut reader = Reader::from_str(xml);
+    let mut page = ListingPage::default();
+    let mut element = Vec::new();
+    let mut key = String::new();
+    let mut size = 0;
+    let mut truncated = false;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => element = e.local_name().as_ref().to_vec(),
+            Event::Text(e) => {
+                let text = e.unescape()?.to_string();
+                match element.as_slice() {
+                    b"Key" => key = text,
+                    b"Size" => size = text.parse().unwrap_or(0),
+                    b"Prefix" => key = text,
+                    b"IsTruncated" => truncated = text == "true",
+                    b"NextContinuationToken" => page.next_token = Some(text),
+                    _ => {}
+                }
+            }
+            Event::End(e) => {
+                match e.local_name().as_ref() {
+                    b"Contents" => page.entries.push((std::mem::take(&mut key), size, false)),
+                    b"CommonPrefixes" => page.entries.push((std::mem::take(&mut key), 0, true)),
+                    _ => {}
+                }
+                element.clear();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    if !truncated {
+        page.next_token = None;
+    }
+    Ok(page)
+}
+
+// s3://bucket/some/key -> (bucket, some/key)
+fn split_url(url: &str) -> anyhow::Result<(String, String)> {
+    let rest = url
+        .strip_prefix("s3://")
+        .ok_or_else(|| anyhow!("{} is not an s3:// url", url))?;
+    let (bucket, key) = rest.split_once('/').unwrap_or((rest, ""));
+    if bucket.is_empty() {
+        bail!("{} has no bucket", url);
+    }
+    Ok((bucket.to_string(), key.to_string()))
+}
+
+// the error body of S3 says what went wrong, e.g. SignatureDoesNotMatch
+fn request_error(error: ureq::Error) -> anyhow::Error {
+    match error {
+        ureq::Error::Status(code, response) => {
+            let body = response.into_string().unwrap_or_default();
+            let message = body
+                .split("<Message>")
+                .nth(1)
+                .and_then(|rest| rest.split("</Message>").next())
+                .unwrap_or("");
+            anyhow!("request failed with {}: {}", code, message)
+        }
+        error => anyhow!(error),
+    }
+}
+
+// every byte but the unreserved characters is percent encoded, `/` too in
+// query values
+fn uri_encode(text: &str, encode_slash: bool) -> String {
+    let mut encoded = String::new();
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+// 20240131 and 20240131T235959Z
+fn amz_date(now: SystemTime) -> (String, String) {
+    let digits: String = format_timestamp(now)
+        .chars()
+        .filter(|c| c.is_ascii_digit())
+        .collect();
+    (
+        digits[..8].to_string(),
+        format!("{}T{}Z", &digits[..8], &digits[8..]),
+    )
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("hmac takes keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
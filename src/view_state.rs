@@ -0,0 +1,156 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::app::App;
+use crate::nav::Dir;
+use crate::{SortBy, SortType};
+
+// how a directory was last looked at
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ViewState {
+    // name, size, real-size, modified or default
+    pub sort_by: String,
+    pub ascending: bool,
+    pub show_hidden: bool,
+    pub detail_view: bool,
+}
+
+impl Default for ViewState {
+    fn default() -> Self {
+        ViewState {
+            sort_by: String::from("default"),
+            ascending: true,
+            show_hidden: false,
+            detail_view: false,
+        }
+    }
+}
+
+impl ViewState {
+    pub fn new(
+        sort_by: &SortBy,
+        sort_type: &SortType,
+        show_hidden: bool,
+        detail_view: bool,
+    ) -> ViewState {
+        let sort_by = match sort_by {
+            SortBy::Name => "name",
+            SortBy::Size => "size",
+            SortBy::RealSize => "real-size",
+            SortBy::DateAdded => "modified",
+            SortBy::Default => "default",
+        };
+        ViewState {
+            sort_by: sort_by.to_string(),
+            ascending: matches!(sort_type, SortType::ASC),
+            show_hidden,
+            detail_view,
+        }
+    }
+
+    pub fn sort_by(&self) -> SortBy {
+        match self.sort_by.as_str() {
+            "name" => SortBy::Name,
+            "size" => SortBy::Size,
+            "real-size" => SortBy::RealSize,
+            "modified" => SortBy::DateAdded,
+            _ => SortBy::Default,
+        }
+    }
+
+    pub fn sort_type(&self) -> SortType {
+        if self.ascending {
+            SortType::ASC
+        } else {
+            SortType::DESC
+        }
+    }
+}
+
+// directory -> view state, only directories whose view was changed are kept
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct ViewStateStore {
+    states: HashMap<String, ViewState>,
+    #[serde(skip)]
+    path: String,
+}
+
+impl ViewStateStore {
+    // a missing or unreadable file starts empty
+    pub fn load(path: &str) -> ViewStateStore {
+        let mut store: Self = File::open(path)
+            .ok()
+            .and_then(|file| serde_json::from_reader(BufReader::new(file)).ok())
+            .unwrap_or_default();
+        store.path = path.to_string();
+        store
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let writer = BufWriter::new(File::create(&self.path)?);
+        serde_json::to_writer(writer, self)?;
+        Ok(())
+    }
+
+    pub fn get(&self, dir: &Path) -> ViewState {
+        self.states
+            .get(dir.to_string_lossy().as_ref())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    // views matching the default are dropped to keep the file small
+    pub fn remember(&mut self, dir: &Path, state: ViewState) {
+        let key = dir.to_string_lossy().to_string();
+        if state == ViewState::default() {
+            self.states.remove(&key);
+        } else {
+            self.states.insert(key, state);
+        }
+    }
+}
+
+// reads `path` the way it was last viewed there, directories without a
+// saved view get the defaults
+pub fn read_with_view_state(
+    path: impl Into<PathBuf>,
+    app: &mut App,
+    sort_type: &mut SortType,
+    view_states: &ViewStateStore,
+) -> anyhow::Result<Dir> {
+    let path = path.into();
+    let view = view_states.get(&path);
+    let dir = Dir::read(
+        path,
+        view.show_hidden,
+        app.listing_ignore(),
+        view.sort_by(),
+        &view.sort_type(),
+        &app.sort_options,
+        &app.dir_sizes,
+    )?;
+
+    app.sort_by = view.sort_by();
+    *sort_type = view.sort_type();
+    app.show_hidden_files = view.show_hidden;
+    app.detail_view = view.detail_view;
+    Ok(dir)
+}
+
+// saves how the current directory is viewed now
+pub fn remember_view(app: &App, sort_type: &SortType, view_states: &mut ViewStateStore) {
+    let view = ViewState::new(
+        &app.sort_by,
+        sort_type,
+        app.show_hidden_files,
+        app.detail_view,
+    );
+    view_states.remember(&app.current_dir, view);
+    let _ = view_states.save();
+}
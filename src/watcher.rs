@@ -0,0 +1,162 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver},
+    time::{Duration, Instant},
+};
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use walkdir::WalkDir;
+
+use crate::events::Waker;
+use crate::ignore_rules::IgnorePatterns;
+
+// changes closer together than this are one batch
+const DEBOUNCE: Duration = Duration::from_millis(200);
+// a directory that never goes quiet, like node_modules during an install,
+// still gets refreshed this often
+const MAX_WAIT: Duration = Duration::from_secs(1);
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+// every watched directory costs an inotify watch, deep trees are cut off
+const MAX_WATCHED_DIRS: usize = 1000;
+
+pub struct WatchBatch {
+    pub changes: usize,
+}
+
+// watches a directory, and its subdirectories up to `depth` levels down,
+// and hands out the changes in batches, so a build writing thousands of
+// files refreshes the listing a few times instead of once per file
+pub struct FileSystemWatcher {
+    watcher: RecommendedWatcher,
+    receiver: Receiver<notify::Result<Event>>,
+    root: Option<PathBuf>,
+    depth: usize,
+    ignore: IgnorePatterns,
+    watched: HashSet<PathBuf>,
+    changes: usize,
+    first_change: Option<Instant>,
+    last_change: Option<Instant>,
+    last_batch: Option<Instant>,
+}
+
+impl FileSystemWatcher {
+    pub fn new(
+        depth: usize,
+        ignore: IgnorePatterns,
+        waker: Waker,
+    ) -> notify::Result<FileSystemWatcher> {
+        let (sender, receiver) = mpsc::channel();
+        let watcher = notify::recommended_watcher(move |event| {
+            let _ = sender.send(event);
+            waker.wake();
+        })?;
+        Ok(FileSystemWatcher {
+            watcher,
+            receiver,
+            root: None,
+            depth,
+            ignore,
+            watched: HashSet::new(),
+            changes: 0,
+            first_change: None,
+            last_change: None,
+            last_batch: None,
+        })
+    }
+
+    // moves the watch to `dir`, pending changes of the old one are dropped
+    pub fn watch(&mut self, dir: &Path) -> notify::Result<()> {
+        if self.root.as_deref() == Some(dir) {
+            return Ok(());
+        }
+        while self.receiver.try_recv().is_ok() {}
+        self.reset();
+        self.root = Some(dir.to_path_buf());
+        self.sync_watches()
+    }
+
+    // watches the directories currently under the root and drops the ones
+    // that are gone or moved out of reach
+    fn sync_watches(&mut self) -> notify::Result<()> {
+        let Some(root) = self.root.clone() else {
+            return Ok(());
+        };
+        let ignore = &self.ignore;
+        let wanted: HashSet<PathBuf> = WalkDir::new(&root)
+            .max_depth(self.depth)
+            .into_iter()
+            .filter_entry(|entry| {
+                entry.depth() == 0 || !ignore.is_ignored(entry.path(), entry.file_type().is_dir())
+            })
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_dir())
+            .map(|entry| entry.into_path())
+            .take(MAX_WATCHED_DIRS)
+            .collect();
+
+        for gone in self.watched.difference(&wanted) {
+            let _ = self.watcher.unwatch(gone);
+        }
+        let new: Vec<PathBuf> = wanted.difference(&self.watched).cloned().collect();
+        self.watched = wanted;
+        for dir in new {
+            self.watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+        }
+        Ok(())
+    }
+
+    pub fn poll(&mut self) -> Option<WatchBatch> {
+        let now = Instant::now();
+        while let Ok(event) = self.receiver.try_recv() {
+            let Ok(event) = event else {
+                continue;
+            };
+            if matches!(event.kind, EventKind::Access(_)) {
+                continue;
+            }
+            self.changes += 1;
+            self.first_change.get_or_insert(now);
+            self.last_change = Some(now);
+        }
+
+        let (first, last) = (self.first_change?, self.last_change?);
+        let settled = now.duration_since(last) >= DEBOUNCE;
+        let waited_enough = now.duration_since(first) >= MAX_WAIT;
+        let rate_limited = self
+            .last_batch
+            .is_some_and(|batch| now.duration_since(batch) < MIN_REFRESH_INTERVAL);
+        if !(settled || waited_enough) || rate_limited {
+            return None;
+        }
+
+        let batch = WatchBatch {
+            changes: self.changes,
+        };
+        self.reset();
+        self.last_batch = Some(now);
+        // directories created or removed below the root change what is
+        // watched
+        if self.depth > 0 {
+            let _ = self.sync_watches();
+        }
+        Some(batch)
+    }
+
+    // when the changes seen so far are handed out as a batch, the caller
+    // polls again then even if nothing else happens
+    pub fn deadline(&self) -> Option<Instant> {
+        let (first, last) = (self.first_change?, self.last_change?);
+        let due = (last + DEBOUNCE).min(first + MAX_WAIT);
+        Some(match self.last_batch {
+            Some(batch) => due.max(batch + MIN_REFRESH_INTERVAL),
+            None => due,
+        })
+    }
+
+    fn reset(&mut self) {
+        self.changes = 0;
+        self.first_change = None;
+        self.last_change = None;
+    }
+}